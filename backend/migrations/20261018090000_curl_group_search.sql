@@ -0,0 +1,56 @@
+-- Full-text index over curl groups. Rows share their rowid with curl_group.id and are kept in
+-- sync by the triggers below. The raw curl text is pulled out of the JSON encoded `curls` column,
+-- falling back to the column as-is when it does not hold a JSON array.
+CREATE VIRTUAL TABLE IF NOT EXISTS curl_group_search USING fts5(
+    name,
+    description,
+    labels,
+    curls
+);
+
+CREATE TRIGGER IF NOT EXISTS curl_group_search_insert AFTER INSERT ON curl_group BEGIN
+    INSERT INTO curl_group_search (rowid, name, description, labels, curls) VALUES (
+        new.id,
+        new.name,
+        new.description,
+        new.labels,
+        CASE WHEN json_valid(new.curls) AND json_type(new.curls) = 'array' THEN (
+            SELECT group_concat(
+                coalesce(json_extract(value, '$.name'), '') || char(10) || coalesce(json_extract(value, '$.description'), '') || char(10) || coalesce(json_extract(value, '$.rawQuery'), ''),
+                char(10)
+            ) FROM json_each(new.curls)
+        ) ELSE new.curls END
+    );
+END;
+
+CREATE TRIGGER IF NOT EXISTS curl_group_search_update AFTER UPDATE ON curl_group BEGIN
+    UPDATE curl_group_search SET
+        name = new.name,
+        description = new.description,
+        labels = new.labels,
+        curls = CASE WHEN json_valid(new.curls) AND json_type(new.curls) = 'array' THEN (
+            SELECT group_concat(
+                coalesce(json_extract(value, '$.name'), '') || char(10) || coalesce(json_extract(value, '$.description'), '') || char(10) || coalesce(json_extract(value, '$.rawQuery'), ''),
+                char(10)
+            ) FROM json_each(new.curls)
+        ) ELSE new.curls END
+    WHERE rowid = old.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS curl_group_search_delete AFTER DELETE ON curl_group BEGIN
+    DELETE FROM curl_group_search WHERE rowid = old.id;
+END;
+
+INSERT INTO curl_group_search (rowid, name, description, labels, curls)
+SELECT
+    id,
+    name,
+    description,
+    labels,
+    CASE WHEN json_valid(curls) AND json_type(curls) = 'array' THEN (
+        SELECT group_concat(
+            coalesce(json_extract(value, '$.name'), '') || char(10) || coalesce(json_extract(value, '$.description'), '') || char(10) || coalesce(json_extract(value, '$.rawQuery'), ''),
+            char(10)
+        ) FROM json_each(curls)
+    ) ELSE curls END
+FROM curl_group;
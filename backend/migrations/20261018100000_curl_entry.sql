@@ -0,0 +1,73 @@
+-- Curl entries move out of the JSON encoded `curl_group.curls` column into their own table.
+CREATE TABLE IF NOT EXISTS curl_entry (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    group_id INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    description TEXT NOT NULL,
+    raw_query TEXT NOT NULL,
+    FOREIGN KEY (group_id) REFERENCES curl_group(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS curl_entry_group_position ON curl_entry (group_id, position);
+
+-- Well-formed blobs are split into one row per array element, keeping their order. Anything else
+-- is kept as a single entry so no data is lost.
+INSERT INTO curl_entry (group_id, position, name, description, raw_query)
+SELECT
+    curl_group.id,
+    entry.key,
+    coalesce(json_extract(entry.value, '$.name'), 'cURL #' || (entry.key + 1)),
+    coalesce(json_extract(entry.value, '$.description'), ''),
+    coalesce(json_extract(entry.value, '$.rawQuery'), '')
+FROM curl_group, json_each(
+    CASE WHEN json_valid(curl_group.curls) AND json_type(curl_group.curls) = 'array'
+    THEN curl_group.curls ELSE '[]' END
+) AS entry
+ORDER BY curl_group.id, entry.key;
+
+INSERT INTO curl_entry (group_id, position, name, description, raw_query)
+SELECT id, 0, 'cURL #1', '', curls FROM curl_group
+WHERE NOT (json_valid(curls) AND json_type(curls) = 'array') AND trim(curls) != '';
+
+-- The search index now reads curl text from the entries rather than the blob.
+DROP TRIGGER IF EXISTS curl_group_search_insert;
+DROP TRIGGER IF EXISTS curl_group_search_update;
+
+ALTER TABLE curl_group DROP COLUMN curls;
+
+CREATE TRIGGER IF NOT EXISTS curl_group_search_insert AFTER INSERT ON curl_group BEGIN
+    INSERT INTO curl_group_search (rowid, name, description, labels, curls)
+    VALUES (new.id, new.name, new.description, new.labels, '');
+END;
+
+CREATE TRIGGER IF NOT EXISTS curl_group_search_update AFTER UPDATE ON curl_group BEGIN
+    UPDATE curl_group_search SET name = new.name, description = new.description, labels = new.labels
+    WHERE rowid = old.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS curl_entry_search_insert AFTER INSERT ON curl_entry BEGIN
+    UPDATE curl_group_search SET curls = (
+        SELECT coalesce(group_concat(name || char(10) || description || char(10) || raw_query, char(10)), '')
+        FROM curl_entry WHERE group_id = new.group_id
+    ) WHERE rowid = new.group_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS curl_entry_search_update AFTER UPDATE OF name, description, raw_query ON curl_entry BEGIN
+    UPDATE curl_group_search SET curls = (
+        SELECT coalesce(group_concat(name || char(10) || description || char(10) || raw_query, char(10)), '')
+        FROM curl_entry WHERE group_id = new.group_id
+    ) WHERE rowid = new.group_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS curl_entry_search_delete AFTER DELETE ON curl_entry BEGIN
+    UPDATE curl_group_search SET curls = (
+        SELECT coalesce(group_concat(name || char(10) || description || char(10) || raw_query, char(10)), '')
+        FROM curl_entry WHERE group_id = old.group_id
+    ) WHERE rowid = old.group_id;
+END;
+
+UPDATE curl_group_search SET curls = (
+    SELECT coalesce(group_concat(name || char(10) || description || char(10) || raw_query, char(10)), '')
+    FROM curl_entry WHERE group_id = curl_group_search.rowid
+);
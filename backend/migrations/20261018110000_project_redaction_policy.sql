@@ -0,0 +1,3 @@
+-- What happens when secrets are found in a curl being saved: 'Redact' replaces them with
+-- placeholders, 'Reject' refuses the save.
+ALTER TABLE project ADD COLUMN redaction_policy TEXT NOT NULL DEFAULT 'Redact';
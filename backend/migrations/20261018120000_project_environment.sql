@@ -0,0 +1,31 @@
+-- Named environments per project, each holding the variables `{{placeholders}}` in curls render to.
+CREATE TABLE IF NOT EXISTS project_environment (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    project_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    UNIQUE (project_id, name),
+    FOREIGN KEY (project_id) REFERENCES project(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS project_environment_variable (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    environment_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    value TEXT NOT NULL,
+    UNIQUE (environment_id, name),
+    FOREIGN KEY (environment_id) REFERENCES project_environment(id) ON DELETE CASCADE
+);
+
+-- Every base URL in the old comma separated `project.environments` becomes an environment of the
+-- same name with a `base_url` variable.
+WITH RECURSIVE split (project_id, item, rest) AS (
+    SELECT id, '', environments || ',' FROM project
+    UNION ALL
+    SELECT project_id, trim(substr(rest, 1, instr(rest, ',') - 1)), substr(rest, instr(rest, ',') + 1)
+    FROM split WHERE rest <> ''
+)
+INSERT OR IGNORE INTO project_environment (project_id, name)
+SELECT project_id, item FROM split WHERE item <> '';
+
+INSERT INTO project_environment_variable (environment_id, name, value)
+SELECT id, 'base_url', name FROM project_environment;
@@ -0,0 +1,2 @@
+-- Secret variables hold a value encrypted with the server key instead of plaintext.
+ALTER TABLE project_environment_variable ADD COLUMN secret INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,8 @@
+-- Hosts the request runner may call and its limits, per project
+CREATE TABLE IF NOT EXISTS project_runner_settings (
+    project_id INTEGER PRIMARY KEY NOT NULL,
+    allowed_hosts TEXT NOT NULL, -- comma separated host patterns
+    timeout_ms INTEGER NOT NULL,
+    max_response_bytes INTEGER NOT NULL,
+    FOREIGN KEY (project_id) REFERENCES project(id) ON DELETE CASCADE
+);
@@ -0,0 +1,16 @@
+-- Groups and entries generated from an OpenAPI document, so importing the document again updates
+-- them in place rather than adding copies
+CREATE TABLE IF NOT EXISTS curl_group_source (
+    group_id INTEGER PRIMARY KEY NOT NULL,
+    project_id INTEGER NOT NULL,
+    source TEXT NOT NULL, -- document title and tag
+    FOREIGN KEY (group_id) REFERENCES curl_group(id) ON DELETE CASCADE,
+    FOREIGN KEY (project_id) REFERENCES project(id) ON DELETE CASCADE,
+    UNIQUE (project_id, source)
+);
+
+CREATE TABLE IF NOT EXISTS curl_entry_source (
+    entry_id INTEGER PRIMARY KEY NOT NULL,
+    operation TEXT NOT NULL, -- method and path template, e.g. `GET /pets/{petId}`
+    FOREIGN KEY (entry_id) REFERENCES curl_entry(id) ON DELETE CASCADE
+);
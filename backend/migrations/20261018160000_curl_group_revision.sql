@@ -0,0 +1,24 @@
+-- Every saved state of a curl group, so edits can be compared and undone
+CREATE TABLE IF NOT EXISTS curl_group_revision (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    group_id INTEGER NOT NULL,
+    number INTEGER NOT NULL, -- counts up from 1 within the group
+    user_id INTEGER, -- the author, unknown for revisions recorded by this migration
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    content TEXT NOT NULL, -- name, description, labels and curls as JSON
+    FOREIGN KEY (group_id) REFERENCES curl_group(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES user(id) ON DELETE SET NULL,
+    UNIQUE (group_id, number)
+);
+
+INSERT INTO curl_group_revision (group_id, number, user_id, content)
+SELECT id, 1, NULL, json_object(
+    'name', name,
+    'description', description,
+    'labels', labels,
+    'curls', json((
+        SELECT json_group_array(json_object('id', id, 'name', name, 'description', description, 'rawQuery', raw_query))
+        FROM (SELECT * FROM curl_entry WHERE curl_entry.group_id = curl_group.id ORDER BY position)
+    ))
+)
+FROM curl_group;
@@ -0,0 +1,17 @@
+-- Who changed what within a project. Events outlive the project and the acting user, so neither
+-- is a cascading foreign key.
+CREATE TABLE IF NOT EXISTS audit_event (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    project_id INTEGER NOT NULL,
+    actor_id INTEGER,
+    actor_name TEXT NOT NULL, -- as it was when the event was recorded
+    action TEXT NOT NULL, -- e.g. `curl_group.update`
+    target TEXT NOT NULL, -- e.g. `curl_group:3`
+    before TEXT, -- JSON summary of the target before the change
+    after TEXT, -- and after it
+    request_id TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    FOREIGN KEY (actor_id) REFERENCES user(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS audit_event_project ON audit_event (project_id, id);
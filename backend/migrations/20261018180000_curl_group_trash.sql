@@ -0,0 +1,6 @@
+-- Deleted groups stay in the project's trash until they are restored or purged
+ALTER TABLE curl_group ADD COLUMN deleted_at TEXT;
+ALTER TABLE curl_group ADD COLUMN deleted_by INTEGER REFERENCES user(id) ON DELETE SET NULL;
+
+-- Admins can always delete groups, collaborators only where this is set
+ALTER TABLE project ADD COLUMN collaborators_can_delete_groups BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,16 @@
+-- Saving a project used to insert its members again, so keep one row per member, and drop the
+-- collaborator row of anyone who is also an admin
+DELETE FROM project_admin WHERE rowid NOT IN (
+    SELECT MIN(rowid) FROM project_admin GROUP BY project_id, user_id
+);
+DELETE FROM project_collaborator WHERE rowid NOT IN (
+    SELECT MIN(rowid) FROM project_collaborator GROUP BY project_id, user_id
+);
+DELETE FROM project_collaborator WHERE EXISTS (
+    SELECT 1 FROM project_admin
+    WHERE project_admin.project_id = project_collaborator.project_id
+    AND project_admin.user_id = project_collaborator.user_id
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS project_admin_member ON project_admin (project_id, user_id);
+CREATE UNIQUE INDEX IF NOT EXISTS project_collaborator_member ON project_collaborator (project_id, user_id);
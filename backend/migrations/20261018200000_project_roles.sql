@@ -0,0 +1,62 @@
+-- Project roles and what each of them may do. Replaces the admin and collaborator tables with a
+-- single membership table holding each member's role.
+CREATE TABLE IF NOT EXISTS project_role (
+    name TEXT PRIMARY KEY NOT NULL,
+    rank INTEGER UNIQUE NOT NULL -- lower ranks come first
+);
+
+INSERT INTO project_role (name, rank) VALUES
+    ('owner', 0),
+    ('admin', 1),
+    ('editor', 2),
+    ('commenter', 3),
+    ('viewer', 4);
+
+CREATE TABLE IF NOT EXISTS project_role_permission (
+    role TEXT NOT NULL,
+    permission TEXT NOT NULL, -- e.g. `edit_group`
+    PRIMARY KEY (role, permission),
+    FOREIGN KEY (role) REFERENCES project_role(name) ON DELETE CASCADE
+);
+
+INSERT INTO project_role_permission (role, permission) VALUES
+    ('owner', 'read'),
+    ('owner', 'create_group'),
+    ('owner', 'edit_group'),
+    ('owner', 'delete_group'),
+    ('owner', 'manage_members'),
+    ('owner', 'manage_environments'),
+    ('owner', 'delete_project'),
+    ('admin', 'read'),
+    ('admin', 'create_group'),
+    ('admin', 'edit_group'),
+    ('admin', 'delete_group'),
+    ('admin', 'manage_members'),
+    ('admin', 'manage_environments'),
+    ('editor', 'read'),
+    ('editor', 'create_group'),
+    ('editor', 'edit_group'),
+    ('commenter', 'read'),
+    ('viewer', 'read');
+
+CREATE TABLE IF NOT EXISTS project_member (
+    project_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    role TEXT NOT NULL,
+    PRIMARY KEY (project_id, user_id),
+    FOREIGN KEY (project_id) REFERENCES project(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES user(id) ON DELETE CASCADE,
+    FOREIGN KEY (role) REFERENCES project_role(name)
+);
+
+-- the earliest admin of each project owns it, collaborators become editors
+INSERT INTO project_member (project_id, user_id, role)
+SELECT project_id, user_id, CASE
+    WHEN rowid = (SELECT MIN(rowid) FROM project_admin AS first WHERE first.project_id = project_admin.project_id)
+    THEN 'owner' ELSE 'admin'
+END FROM project_admin ORDER BY rowid;
+INSERT OR IGNORE INTO project_member (project_id, user_id, role)
+SELECT project_id, user_id, 'editor' FROM project_collaborator ORDER BY rowid;
+
+DROP TABLE project_admin;
+DROP TABLE project_collaborator;
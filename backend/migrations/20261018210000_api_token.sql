@@ -0,0 +1,17 @@
+-- Personal API tokens, for scripts and CI calling the API without a session. Only a hash of each
+-- token is kept, the token itself is shown once when it is created.
+CREATE TABLE IF NOT EXISTS api_token (
+    id INTEGER PRIMARY KEY NOT NULL,
+    user_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    token_hash TEXT UNIQUE NOT NULL, -- hex encoded SHA-256
+    scope TEXT NOT NULL, -- `read` or `write`
+    project_id INTEGER, -- set when the token only works for one project
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    expires_at TEXT,
+    last_used_at TEXT,
+    FOREIGN KEY (user_id) REFERENCES user(id) ON DELETE CASCADE,
+    FOREIGN KEY (project_id) REFERENCES project(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS api_token_user ON api_token (user_id);
@@ -0,0 +1,11 @@
+-- Single sign-on identities, keyed on the provider's issuer and the `sub` claim it gives the user.
+-- Accounts created through single sign-on have an empty password hash, so only the provider can
+-- be used to sign in to them.
+CREATE TABLE IF NOT EXISTS user_identity (
+    issuer TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    user_id INTEGER NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    PRIMARY KEY (issuer, subject),
+    FOREIGN KEY (user_id) REFERENCES user(id) ON DELETE CASCADE
+);
@@ -0,0 +1,3 @@
+-- Instance wide roles, as opposed to the roles members have in a project. Users who sign in
+-- through the directory get theirs from their groups each time they do.
+ALTER TABLE user ADD COLUMN global_role TEXT NOT NULL DEFAULT 'user';
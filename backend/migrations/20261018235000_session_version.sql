@@ -0,0 +1,3 @@
+-- Sessions remember the version they were signed in at, and stop counting once it moves on. It is
+-- moved on to sign a user out everywhere else, such as when they change their password.
+ALTER TABLE user ADD COLUMN session_version INTEGER NOT NULL DEFAULT 0;
@@ -1,4 +1,4 @@
-use std::{net::TcpListener, env};
+use std::{env, net::TcpListener};
 
 use actix_cors::Cors;
 use actix_session::{config::PersistentSession, storage::CookieSessionStore, SessionMiddleware};
@@ -10,13 +10,43 @@ use actix_web::{
 use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
 use tracing_actix_web::TracingLogger;
 
-use crate::routes::{health_check, project_routes, user_routes};
+use crate::ldap::{LdapAuthenticator, LdapConfig};
+use crate::oidc::OidcConfig;
+use crate::password::PasswordPolicy;
+use crate::rate_limit::RateLimiter;
+use crate::routes::{
+    audit_routes, curl_routes, entry_routes, environment_routes, export_routes, health_check,
+    import_routes, member_routes, project_routes, revision_routes, runner_routes, search_routes,
+    token_routes, user_routes,
+};
+use crate::secrets::SecretCipher;
 
 pub enum ApplicationPoolSettings {
     Standard,
     Test,
 }
 
+/// How users sign in. Single sign-on is off when `oidc` is `None`, and directory sign in when
+/// `ldap` is.
+#[derive(Default)]
+pub struct AuthSettings {
+    pub oidc: Option<OidcConfig>,
+    pub ldap: Option<LdapConfig>,
+    pub password_policy: PasswordPolicy,
+    pub rate_limiter: RateLimiter,
+}
+
+impl AuthSettings {
+    pub fn from_env() -> Self {
+        Self {
+            oidc: OidcConfig::from_env(),
+            ldap: LdapConfig::from_env(),
+            password_policy: PasswordPolicy::from_env(),
+            rate_limiter: RateLimiter::from_env(),
+        }
+    }
+}
+
 pub struct Application {
     port: u16,
     server: Server,
@@ -26,16 +56,27 @@ impl Application {
     pub async fn build(
         port: u16,
         pool_settings: ApplicationPoolSettings,
+    ) -> Result<Self, std::io::Error> {
+        Self::build_with_auth(port, pool_settings, AuthSettings::from_env()).await
+    }
+
+    pub async fn build_with_auth(
+        port: u16,
+        pool_settings: ApplicationPoolSettings,
+        auth: AuthSettings,
     ) -> Result<Self, std::io::Error> {
         let base_url = env::var("BASE_URL").unwrap_or_else(|_| "localhost".to_string());
         let address = format!("{}:{}", base_url, port);
         let listener = TcpListener::bind(&address)?;
 
-        let db_pool = match pool_settings {
-            ApplicationPoolSettings::Standard => {
-                SqlitePool::connect("sqlite:data.db").await.unwrap()
+        let (db_pool, cipher) = match pool_settings {
+            ApplicationPoolSettings::Standard => (
+                SqlitePool::connect("sqlite:data.db").await.unwrap(),
+                SecretCipher::from_env("secret.key").map_err(std::io::Error::other)?,
+            ),
+            ApplicationPoolSettings::Test => {
+                (Self::get_sqlite_test_pool().await, SecretCipher::generate())
             }
-            ApplicationPoolSettings::Test => Self::get_sqlite_test_pool().await,
         };
 
         sqlx::migrate!("./migrations")
@@ -44,7 +85,7 @@ impl Application {
             .expect("Failed to migrate database");
 
         let port = listener.local_addr()?.port();
-        let server = run(db_pool, listener)?;
+        let server = run(db_pool, cipher, auth, listener)?;
         Ok(Self { port, server })
     }
 
@@ -69,9 +110,19 @@ impl Application {
     }
 }
 
-fn run(db_pool: SqlitePool, listener: TcpListener) -> Result<Server, std::io::Error> {
+fn run(
+    db_pool: SqlitePool,
+    cipher: SecretCipher,
+    auth: AuthSettings,
+    listener: TcpListener,
+) -> Result<Server, std::io::Error> {
     tracing::info!("Starting server at {}", listener.local_addr()?);
 
+    let cipher = web::Data::new(cipher);
+    let oidc = web::Data::new(auth.oidc);
+    let ldap = web::Data::new(auth.ldap.map(LdapAuthenticator::new));
+    let password_policy = web::Data::new(auth.password_policy);
+    let rate_limiter = web::Data::new(auth.rate_limiter);
     let private_key = Key::generate();
     let server = HttpServer::new(move || {
         App::new()
@@ -87,10 +138,26 @@ fn run(db_pool: SqlitePool, listener: TcpListener) -> Result<Server, std::io::Er
                     .build(),
             )
             .app_data(web::Data::new(db_pool.clone()))
+            .app_data(cipher.clone())
+            .app_data(oidc.clone())
+            .app_data(ldap.clone())
+            .app_data(password_policy.clone())
+            .app_data(rate_limiter.clone())
             .service(
                 web::scope("/api/v1")
                     .route("/health-check", web::get().to(health_check))
+                    .configure(audit_routes)
+                    .configure(curl_routes)
+                    .configure(entry_routes)
+                    .configure(environment_routes)
+                    .configure(export_routes)
+                    .configure(import_routes)
+                    .configure(member_routes)
                     .configure(project_routes)
+                    .configure(revision_routes)
+                    .configure(runner_routes)
+                    .configure(search_routes)
+                    .configure(token_routes)
                     .configure(user_routes),
             )
     })
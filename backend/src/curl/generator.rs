@@ -0,0 +1,147 @@
+use super::types::{CurlRequest, FormPart, RequestBody};
+
+/// Writes a request back out as a curl command that parses to the same request. Arguments go on
+/// their own lines, the way browsers copy requests.
+pub fn to_command(request: &CurlRequest) -> String {
+    let mut arguments = vec![];
+
+    let implied_method = match (&request.body, request.method.as_str()) {
+        (_, "HEAD") => {
+            arguments.push("-I".to_string());
+            true
+        }
+        (None, "GET") | (Some(_), "POST") => true,
+        _ => false,
+    };
+    if !implied_method {
+        arguments.push(format!("-X {}", quote(&request.method)));
+    }
+    arguments.push(quote(&request.url));
+
+    for header in &request.headers {
+        let header = if header.value.is_empty() {
+            format!("{};", header.name)
+        } else {
+            format!("{}: {}", header.name, header.value)
+        };
+        arguments.push(format!("-H {}", quote(&header)));
+    }
+
+    if !request.cookies.is_empty() {
+        let cookies: Vec<String> = request
+            .cookies
+            .iter()
+            .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+            .collect();
+        arguments.push(format!("-b {}", quote(&cookies.join("; "))));
+    }
+
+    if let Some(auth) = &request.basic_auth {
+        let user = match &auth.password {
+            Some(password) => format!("{}:{}", auth.username, password),
+            None => auth.username.clone(),
+        };
+        arguments.push(format!("-u {}", quote(&user)));
+    }
+
+    match &request.body {
+        Some(RequestBody::Raw { content }) => {
+            arguments.push(format!("--data-raw {}", quote(content)));
+        }
+        Some(RequestBody::Json { content }) => arguments.push(format!("--json {}", quote(content))),
+        Some(RequestBody::Multipart { parts }) => {
+            arguments.extend(parts.iter().map(form_argument));
+        }
+        None => {}
+    }
+
+    if request.insecure {
+        arguments.push("-k".to_string());
+    }
+    if request.follow_redirects {
+        arguments.push("-L".to_string());
+    }
+    if request.compressed {
+        arguments.push("--compressed".to_string());
+    }
+
+    format!("curl {}", arguments.join(" \\\n  "))
+}
+
+fn form_argument(part: &FormPart) -> String {
+    // --form-string sends the value as is, -F is needed for files and content types
+    if !part.is_file && part.content_type.is_none() {
+        return format!(
+            "--form-string {}",
+            quote(&format!("{}={}", part.name, part.value))
+        );
+    }
+
+    let mut value = format!(
+        "{}={}{}",
+        part.name,
+        if part.is_file { "@" } else { "" },
+        part.value
+    );
+    if let Some(content_type) = &part.content_type {
+        value.push_str(&format!(";type={}", content_type));
+    }
+    if let Some(filename) = &part.filename {
+        value.push_str(&format!(";filename={}", filename));
+    }
+    format!("-F {}", quote(&value))
+}
+
+/// Single quotes everything but plain words, closing the quotes around any `'` in the value.
+pub(crate) fn quote(value: &str) -> String {
+    let is_plain = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:@=,+%".contains(c));
+    if is_plain {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+#[cfg(test)]
+mod generator_test {
+    use super::to_command;
+    use crate::curl::parse;
+
+    fn assert_round_trips(command: &str) {
+        let request = parse(command).unwrap();
+        let generated = to_command(&request);
+        assert_eq!(parse(&generated).unwrap(), request, "{}", generated);
+    }
+
+    #[test]
+    fn generates_minimal_commands() {
+        let request = parse("curl https://example.com/users").unwrap();
+        assert_eq!(to_command(&request), "curl https://example.com/users");
+
+        let request = parse("curl -X DELETE 'https://example.com/users?id=1&force'").unwrap();
+        assert_eq!(
+            to_command(&request),
+            "curl -X DELETE \\\n  'https://example.com/users?id=1&force'"
+        );
+    }
+
+    #[test]
+    fn round_trips_parsed_commands() {
+        assert_round_trips("curl -I example.com");
+        assert_round_trips("curl -X PUT -d 'a=1' -d 'b=2' example.com");
+        assert_round_trips("curl -X GET --json '{\"it'\\''s\": true}' example.com");
+        assert_round_trips(
+            "curl -sSkL --compressed -u admin:s3cret -H 'Accept: */*' -H 'X-Empty;' \
+            -H 'Cookie: a=1; b=2' -A agent '{{base_url}}/users?page=2'",
+        );
+        assert_round_trips("curl -u admin example.com");
+        assert_round_trips(
+            "curl -F 'text=hello;world' -F 'file=@photo.png;type=image/png;filename=me.png' \
+            -F 'typed=abc;type=text/plain' example.com",
+        );
+        assert_round_trips("curl --data-raw '@not-a-file' example.com");
+    }
+}
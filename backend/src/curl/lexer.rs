@@ -0,0 +1,236 @@
+use super::types::ParseError;
+
+/// A shell word along with the character offset it started at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Token {
+    pub value: String,
+    pub offset: usize,
+}
+
+/// Splits a command into words the way a POSIX shell would, handling single, double and `$'...'`
+/// quoting, backslash escapes, line continuations and comments. No expansion is performed.
+pub(super) fn tokenize(input: &str) -> Result<Vec<Token>, ParseError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = vec![];
+    let mut current: Option<Token> = None;
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            ' ' | '\t' | '\n' | '\r' => {
+                tokens.extend(current.take());
+                i += 1;
+            }
+            '\\' => {
+                i = match (chars.get(i + 1), chars.get(i + 2)) {
+                    (Some('\n'), _) => i + 2,
+                    (Some('\r'), Some('\n')) => i + 3,
+                    (Some(c), _) => {
+                        start_token(&mut current, i).value.push(*c);
+                        i + 2
+                    }
+                    (None, _) => i + 1,
+                };
+            }
+            '\'' => {
+                let end = find_closing_quote(&chars, i + 1)
+                    .ok_or_else(|| ParseError::new("Unterminated single quote", i, input))?;
+                let value: String = chars[i + 1..end].iter().collect();
+                start_token(&mut current, i).value.push_str(&value);
+                i = end + 1;
+            }
+            '"' => {
+                let (value, end) = read_double_quoted(&chars, i + 1)
+                    .ok_or_else(|| ParseError::new("Unterminated double quote", i, input))?;
+                start_token(&mut current, i).value.push_str(&value);
+                i = end + 1;
+            }
+            '$' if chars.get(i + 1) == Some(&'\'') => {
+                let (value, end) = read_ansi_c_quoted(&chars, i + 2)
+                    .ok_or_else(|| ParseError::new("Unterminated $'...' string", i, input))?;
+                start_token(&mut current, i).value.push_str(&value);
+                i = end + 1;
+            }
+            '#' if current.is_none() => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            c => {
+                start_token(&mut current, i).value.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    tokens.extend(current);
+    Ok(tokens)
+}
+
+fn start_token(current: &mut Option<Token>, offset: usize) -> &mut Token {
+    current.get_or_insert_with(|| Token {
+        value: String::new(),
+        offset,
+    })
+}
+
+fn find_closing_quote(chars: &[char], start: usize) -> Option<usize> {
+    (start..chars.len()).find(|&i| chars[i] == '\'')
+}
+
+/// Within double quotes a backslash only escapes `$`, `` ` ``, `"`, `\` and newlines.
+fn read_double_quoted(chars: &[char], start: usize) -> Option<(String, usize)> {
+    let mut value = String::new();
+    let mut i = start;
+
+    while i < chars.len() {
+        match (chars[i], chars.get(i + 1)) {
+            ('"', _) => return Some((value, i)),
+            ('\\', Some('\n')) => i += 2,
+            ('\\', Some(c @ ('$' | '`' | '"' | '\\'))) => {
+                value.push(*c);
+                i += 2;
+            }
+            (c, _) => {
+                value.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    None
+}
+
+fn read_ansi_c_quoted(chars: &[char], start: usize) -> Option<(String, usize)> {
+    let mut value = String::new();
+    let mut i = start;
+
+    while i < chars.len() {
+        match chars[i] {
+            '\'' => return Some((value, i)),
+            '\\' => {
+                let escape = *chars.get(i + 1)?;
+                i += 2;
+                match escape {
+                    'a' => value.push('\x07'),
+                    'b' => value.push('\x08'),
+                    'e' | 'E' => value.push('\x1b'),
+                    'f' => value.push('\x0c'),
+                    'n' => value.push('\n'),
+                    'r' => value.push('\r'),
+                    't' => value.push('\t'),
+                    'v' => value.push('\x0b'),
+                    '\\' | '\'' | '"' | '?' => value.push(escape),
+                    'x' | 'u' | 'U' | '0'..='7' => {
+                        let (radix, max_digits, digits_start) = match escape {
+                            'x' => (16, 2, i),
+                            'u' => (16, 4, i),
+                            'U' => (16, 8, i),
+                            // the first octal digit is the escape character itself
+                            _ => (8, 3, i - 1),
+                        };
+                        let digits: String = chars[digits_start..]
+                            .iter()
+                            .take(max_digits)
+                            .take_while(|c| c.is_digit(radix))
+                            .collect();
+
+                        match u32::from_str_radix(&digits, radix)
+                            .ok()
+                            .and_then(char::from_u32)
+                        {
+                            Some(c) => {
+                                value.push(c);
+                                i = digits_start + digits.chars().count();
+                            }
+                            None => {
+                                value.push('\\');
+                                value.push(escape);
+                            }
+                        }
+                    }
+                    _ => {
+                        value.push('\\');
+                        value.push(escape);
+                    }
+                }
+            }
+            c => {
+                value.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod lexer_test {
+    use super::{tokenize, Token};
+
+    fn values(input: &str) -> Vec<String> {
+        tokenize(input)
+            .unwrap()
+            .into_iter()
+            .map(|token| token.value)
+            .collect()
+    }
+
+    #[test]
+    fn splits_on_whitespace_and_records_offsets() {
+        assert_eq!(
+            tokenize("curl  -k\turl").unwrap(),
+            vec![
+                Token {
+                    value: "curl".to_string(),
+                    offset: 0
+                },
+                Token {
+                    value: "-k".to_string(),
+                    offset: 6
+                },
+                Token {
+                    value: "url".to_string(),
+                    offset: 9
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn handles_quoting_and_escapes() {
+        assert_eq!(
+            values(r#"curl 'a "b"' "c \"d\" \n $e" f\ g '' h'i'"j""#),
+            vec!["curl", "a \"b\"", "c \"d\" \\n $e", "f g", "", "hij"]
+        );
+    }
+
+    #[test]
+    fn handles_ansi_c_strings() {
+        assert_eq!(
+            values(r"curl $'line\none\t\x41é\101 \'q\''"),
+            vec!["curl", "line\none\tAé\u{41} 'q'"]
+        );
+    }
+
+    #[test]
+    fn joins_line_continuations_and_skips_comments() {
+        assert_eq!(
+            values("curl \\\n  -H 'a: b' \\\r\n  url # trailing comment\n"),
+            vec!["curl", "-H", "a: b", "url"]
+        );
+        assert_eq!(values("cu\\\nrl"), vec!["curl"]);
+    }
+
+    #[test]
+    fn reports_unterminated_quotes() {
+        let error = tokenize("curl \\\n  -d 'open").unwrap_err();
+        assert_eq!(error.message, "Unterminated single quote");
+        assert_eq!((error.offset, error.line, error.column), (12, 2, 6));
+
+        let error = tokenize("curl \"open").unwrap_err();
+        assert_eq!(error.message, "Unterminated double quote");
+        assert_eq!(error.column, 6);
+    }
+}
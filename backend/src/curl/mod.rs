@@ -0,0 +1,13 @@
+//! Parsing of raw cURL commands, as stored in curl entries, into a structured request, and
+//! generating commands from one.
+
+mod generator;
+mod lexer;
+mod parser;
+pub mod types;
+
+pub(crate) use generator::quote;
+pub use generator::to_command;
+pub use parser::parse;
+pub(crate) use parser::{percent_decode, percent_encode};
+pub use types::{BasicAuth, CurlRequest, FormPart, NameValue, ParseError, RequestBody};
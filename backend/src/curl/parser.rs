@@ -0,0 +1,724 @@
+use super::{
+    lexer::{tokenize, Token},
+    types::{BasicAuth, CurlRequest, FormPart, NameValue, ParseError, RequestBody},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Flag {
+    Compressed,
+    Cookie,
+    Data,
+    DataBinary,
+    DataRaw,
+    DataUrlencode,
+    Form,
+    FormString,
+    Get,
+    Head,
+    Header,
+    Insecure,
+    Json,
+    Location,
+    Referer,
+    Request,
+    Url,
+    User,
+    UserAgent,
+    /// Accepted for compatibility but has no bearing on the request itself.
+    Ignored,
+    /// As `Ignored`, but consumes an argument.
+    IgnoredWithArgument,
+}
+
+impl Flag {
+    fn from_long(name: &str) -> Option<Self> {
+        let flag = match name {
+            "compressed" => Flag::Compressed,
+            "cookie" => Flag::Cookie,
+            "data" | "data-ascii" => Flag::Data,
+            "data-binary" => Flag::DataBinary,
+            "data-raw" => Flag::DataRaw,
+            "data-urlencode" => Flag::DataUrlencode,
+            "form" => Flag::Form,
+            "form-string" => Flag::FormString,
+            "get" => Flag::Get,
+            "head" => Flag::Head,
+            "header" => Flag::Header,
+            "insecure" => Flag::Insecure,
+            "json" => Flag::Json,
+            "location" | "location-trusted" => Flag::Location,
+            "referer" => Flag::Referer,
+            "request" => Flag::Request,
+            "url" => Flag::Url,
+            "user" => Flag::User,
+            "user-agent" => Flag::UserAgent,
+            "silent"
+            | "show-error"
+            | "verbose"
+            | "include"
+            | "fail"
+            | "fail-with-body"
+            | "progress-bar"
+            | "no-buffer"
+            | "globoff"
+            | "http1.0"
+            | "http1.1"
+            | "http2"
+            | "http2-prior-knowledge"
+            | "http3"
+            | "remote-name"
+            | "remote-header-name"
+            | "no-keepalive"
+            | "path-as-is"
+            | "tcp-nodelay"
+            | "ipv4"
+            | "ipv6"
+            | "trace-time" => Flag::Ignored,
+            "output" | "max-time" | "connect-timeout" | "retry" | "retry-delay"
+            | "retry-max-time" | "write-out" | "proxy" | "proxy-user" | "cookie-jar" | "cacert"
+            | "capath" | "cert" | "cert-type" | "key" | "key-type" | "resolve" | "connect-to"
+            | "max-redirs" | "dump-header" | "limit-rate" | "interface" | "trace"
+            | "trace-ascii" | "stderr" | "max-filesize" => Flag::IgnoredWithArgument,
+            _ => return None,
+        };
+        Some(flag)
+    }
+
+    fn from_short(name: char) -> Option<Self> {
+        let flag = match name {
+            'A' => Flag::UserAgent,
+            'b' => Flag::Cookie,
+            'd' => Flag::Data,
+            'e' => Flag::Referer,
+            'F' => Flag::Form,
+            'G' => Flag::Get,
+            'H' => Flag::Header,
+            'I' => Flag::Head,
+            'k' => Flag::Insecure,
+            'L' => Flag::Location,
+            'u' => Flag::User,
+            'X' => Flag::Request,
+            's' | 'S' | 'v' | 'i' | 'f' | '#' | 'N' | 'g' | 'O' | 'J' | '4' | '6' => Flag::Ignored,
+            'o' | 'm' | 'w' | 'x' | 'U' | 'c' | 'E' | 'D' | 'Y' | 'y' => Flag::IgnoredWithArgument,
+            _ => return None,
+        };
+        Some(flag)
+    }
+
+    fn takes_argument(self) -> bool {
+        !matches!(
+            self,
+            Flag::Compressed
+                | Flag::Get
+                | Flag::Head
+                | Flag::Insecure
+                | Flag::Location
+                | Flag::Ignored
+        )
+    }
+}
+
+/// Everything collected while walking the arguments, turned into a `CurlRequest` at the end.
+#[derive(Default)]
+struct State {
+    request: CurlRequest,
+    explicit_method: Option<String>,
+    url: Option<Token>,
+    data: Vec<String>,
+    json: Vec<String>,
+    form: Vec<FormPart>,
+    get: bool,
+    head: bool,
+    /// Whether the previous argument was an unsupported option, which may take the next one.
+    after_unsupported_option: bool,
+    /// Arguments following unsupported options, which are only taken as the URL if there is no
+    /// other.
+    possible_option_arguments: Vec<Token>,
+}
+
+/// Parses a raw cURL command into a structured request.
+pub fn parse(input: &str) -> Result<CurlRequest, ParseError> {
+    let tokens = tokenize(input)?;
+    let mut tokens = tokens.into_iter();
+
+    match tokens.next() {
+        Some(token) if is_curl_executable(&token.value) => {}
+        Some(token) => {
+            return Err(ParseError::new(
+                "Command must start with curl",
+                token.offset,
+                input,
+            ))
+        }
+        None => return Err(ParseError::new("Command is empty", 0, input)),
+    }
+
+    let mut state = State::default();
+    while let Some(token) = tokens.next() {
+        let value = token.value.as_str();
+        let after_unsupported_option = std::mem::take(&mut state.after_unsupported_option);
+
+        if let Some(name) = value.strip_prefix("--").filter(|name| !name.is_empty()) {
+            // curl has far more options than are understood here, which are left out
+            let Some(flag) = Flag::from_long(name) else {
+                skip_unsupported_option(&mut state, value.to_string(), true);
+                continue;
+            };
+            let argument = if flag.takes_argument() {
+                Some(next_argument(&mut tokens, &token, input)?)
+            } else {
+                None
+            };
+            apply_flag(&mut state, flag, &token, argument, input)?;
+        } else if value.len() > 1 && value.starts_with('-') {
+            // short flags can be clustered (-sSL) and take their argument attached (-XPOST)
+            for (index, name) in value.char_indices().skip(1) {
+                let Some(flag) = Flag::from_short(name) else {
+                    let last = index + name.len_utf8() == value.len();
+                    skip_unsupported_option(&mut state, format!("-{}", name), last);
+                    continue;
+                };
+
+                if flag.takes_argument() {
+                    let attached = &value[index + name.len_utf8()..];
+                    let argument = if attached.is_empty() {
+                        next_argument(&mut tokens, &token, input)?
+                    } else {
+                        Token {
+                            value: attached.to_string(),
+                            offset: token.offset + value[..index].chars().count() + 1,
+                        }
+                    };
+                    apply_flag(&mut state, flag, &token, Some(argument), input)?;
+                    break;
+                }
+                apply_flag(&mut state, flag, &token, None, input)?;
+            }
+        } else if after_unsupported_option {
+            state.possible_option_arguments.push(token);
+        } else {
+            set_url(&mut state, token, input)?;
+        }
+    }
+
+    finish(state, input)
+}
+
+fn skip_unsupported_option(state: &mut State, option: String, may_take_argument: bool) {
+    state.request.unsupported_options.push(option);
+    state.after_unsupported_option = may_take_argument;
+}
+
+fn is_curl_executable(value: &str) -> bool {
+    let executable = value.rsplit(['/', '\\']).next().unwrap_or(value);
+    executable == "curl" || executable.eq_ignore_ascii_case("curl.exe")
+}
+
+fn next_argument(
+    tokens: &mut impl Iterator<Item = Token>,
+    flag: &Token,
+    input: &str,
+) -> Result<Token, ParseError> {
+    tokens.next().ok_or_else(|| {
+        ParseError::new(
+            format!("Option {} requires an argument", flag.value),
+            flag.offset,
+            input,
+        )
+    })
+}
+
+fn set_url(state: &mut State, token: Token, input: &str) -> Result<(), ParseError> {
+    if state.url.is_some() {
+        return Err(ParseError::new(
+            "Only a single URL is supported",
+            token.offset,
+            input,
+        ));
+    }
+    state.url = Some(token);
+    Ok(())
+}
+
+fn apply_flag(
+    state: &mut State,
+    flag: Flag,
+    token: &Token,
+    argument: Option<Token>,
+    input: &str,
+) -> Result<(), ParseError> {
+    let argument_value = argument
+        .as_ref()
+        .map(|argument| argument.value.clone())
+        .unwrap_or_default();
+
+    match flag {
+        Flag::Compressed => state.request.compressed = true,
+        Flag::Get => state.get = true,
+        Flag::Head => state.head = true,
+        Flag::Insecure => state.request.insecure = true,
+        Flag::Location => state.request.follow_redirects = true,
+        Flag::Request => state.explicit_method = Some(argument_value),
+        Flag::Url => {
+            if let Some(argument) = argument {
+                set_url(state, argument, input)?;
+            }
+        }
+        Flag::Header => {
+            let argument = argument.expect("header flag takes an argument");
+            add_header(state, &argument, input)?;
+        }
+        Flag::UserAgent => state
+            .request
+            .headers
+            .push(NameValue::new("User-Agent", argument_value)),
+        Flag::Referer => state
+            .request
+            .headers
+            .push(NameValue::new("Referer", argument_value)),
+        Flag::Cookie => {
+            // without an `=` the argument names a cookie file, which cannot be read here
+            if argument_value.contains('=') {
+                state
+                    .request
+                    .cookies
+                    .extend(parse_cookie_string(&argument_value));
+            }
+        }
+        Flag::User => {
+            state.request.basic_auth = Some(match argument_value.split_once(':') {
+                Some((username, password)) => BasicAuth {
+                    username: username.to_string(),
+                    password: Some(password.to_string()),
+                },
+                None => BasicAuth {
+                    username: argument_value,
+                    password: None,
+                },
+            })
+        }
+        Flag::Data | Flag::DataBinary | Flag::DataRaw | Flag::DataUrlencode | Flag::Json => {
+            if !state.form.is_empty() {
+                return Err(mixed_body_error(token, input));
+            }
+            match flag {
+                Flag::Json => state.json.push(argument_value),
+                Flag::DataUrlencode => state.data.push(encode_data(&argument_value)),
+                _ => state.data.push(argument_value),
+            }
+        }
+        Flag::Form | Flag::FormString => {
+            if !state.data.is_empty() || !state.json.is_empty() {
+                return Err(mixed_body_error(token, input));
+            }
+            let argument = argument.expect("form flag takes an argument");
+            state
+                .form
+                .push(parse_form_part(&argument, flag == Flag::Form, input)?);
+        }
+        Flag::Ignored | Flag::IgnoredWithArgument => {}
+    }
+
+    Ok(())
+}
+
+fn mixed_body_error(token: &Token, input: &str) -> ParseError {
+    ParseError::new(
+        "Form data (-F) cannot be combined with other request bodies",
+        token.offset,
+        input,
+    )
+}
+
+fn add_header(state: &mut State, argument: &Token, input: &str) -> Result<(), ParseError> {
+    let value = argument.value.as_str();
+
+    // `Name;` sends an empty header, `Name:` without a value removes one curl would add
+    let (name, value) = match value.split_once(':') {
+        Some((name, value)) => (name.trim(), value.trim()),
+        None => match value.trim().strip_suffix(';') {
+            Some(name) => (name.trim(), ""),
+            None => {
+                return Err(ParseError::new(
+                    format!("Header \"{}\" is missing a colon", value),
+                    argument.offset,
+                    input,
+                ))
+            }
+        },
+    };
+
+    if name.is_empty() {
+        return Err(ParseError::new(
+            "Header name is empty",
+            argument.offset,
+            input,
+        ));
+    }
+
+    if name.eq_ignore_ascii_case("cookie") {
+        state.request.cookies.extend(parse_cookie_string(value));
+    } else if !(value.is_empty() && argument.value.contains(':')) {
+        state.request.headers.push(NameValue::new(name, value));
+    }
+
+    Ok(())
+}
+
+fn parse_cookie_string(value: &str) -> Vec<NameValue> {
+    value
+        .split(';')
+        .filter_map(|pair| pair.trim().split_once('='))
+        .map(|(name, value)| NameValue::new(name.trim(), value.trim()))
+        .collect()
+}
+
+/// Mirrors curl's `--data-urlencode` forms: `content`, `=content` and `name=content` encode the
+/// content, while `@file` and `name@file` are kept as given since files cannot be read here.
+fn encode_data(value: &str) -> String {
+    if let Some((name, content)) = value.split_once('=') {
+        if name.is_empty() {
+            return percent_encode(content);
+        }
+        if !name.contains('@') {
+            return format!("{}={}", name, percent_encode(content));
+        }
+    }
+
+    if value.contains('@') {
+        value.to_string()
+    } else {
+        percent_encode(value)
+    }
+}
+
+fn parse_form_part(argument: &Token, interpret: bool, input: &str) -> Result<FormPart, ParseError> {
+    let (name, value) = argument.value.split_once('=').ok_or_else(|| {
+        ParseError::new(
+            format!("Form field \"{}\" is missing an =", argument.value),
+            argument.offset,
+            input,
+        )
+    })?;
+
+    let mut part = FormPart {
+        name: name.to_string(),
+        value: value.to_string(),
+        is_file: false,
+        content_type: None,
+        filename: None,
+    };
+    if !interpret {
+        return Ok(part);
+    }
+
+    let (value, is_file) = match value.strip_prefix('@').or_else(|| value.strip_prefix('<')) {
+        Some(path) => (path, true),
+        None => (value, false),
+    };
+
+    let mut segments = value.split(';');
+    part.value = segments.next().unwrap_or_default().to_string();
+    part.is_file = is_file;
+    for segment in segments {
+        match segment.split_once('=') {
+            Some(("type", content_type)) => part.content_type = Some(content_type.to_string()),
+            Some(("filename", filename)) => part.filename = Some(filename.to_string()),
+            _ => {
+                // not a modifier, so the semicolon belonged to the value
+                part.value.push(';');
+                part.value.push_str(segment);
+            }
+        }
+    }
+
+    Ok(part)
+}
+
+fn finish(state: State, input: &str) -> Result<CurlRequest, ParseError> {
+    let mut request = state.request;
+    let url = state
+        .url
+        .or_else(|| state.possible_option_arguments.into_iter().next())
+        .ok_or_else(|| ParseError::new("No URL was given", input.chars().count(), input))?;
+    request.url = url.value;
+
+    let body = if !state.form.is_empty() {
+        Some(RequestBody::Multipart { parts: state.form })
+    } else if !state.json.is_empty() {
+        Some(RequestBody::Json {
+            content: state.json.concat(),
+        })
+    } else if !state.data.is_empty() {
+        Some(RequestBody::Raw {
+            content: state.data.join("&"),
+        })
+    } else {
+        None
+    };
+
+    // -G sends the data as the query string instead of the body
+    let body = match body {
+        Some(RequestBody::Raw { content }) if state.get => {
+            let separator = if request.url.contains('?') { '&' } else { '?' };
+            request.url = match request.url.split_once('#') {
+                Some((url, fragment)) => format!("{}{}{}#{}", url, separator, content, fragment),
+                None => format!("{}{}{}", request.url, separator, content),
+            };
+            None
+        }
+        body => body,
+    };
+
+    request.query = parse_query(&request.url);
+    request.method = match state.explicit_method {
+        Some(method) => method,
+        None if state.head => "HEAD".to_string(),
+        None if body.is_some() => "POST".to_string(),
+        None => "GET".to_string(),
+    };
+    request.body = body;
+
+    Ok(request)
+}
+
+fn parse_query(url: &str) -> Vec<NameValue> {
+    let url = url.split('#').next().unwrap_or_default();
+    let query = match url.split_once('?') {
+        Some((_, query)) => query,
+        None => return vec![],
+    };
+
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((name, value)) => NameValue::new(percent_decode(name), percent_decode(value)),
+            None => NameValue::new(percent_decode(pair), ""),
+        })
+        .collect()
+}
+
+pub(crate) fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+pub(crate) fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => match value
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                Some(byte) => {
+                    decoded.push(byte);
+                    i += 2;
+                }
+                None => decoded.push(b'%'),
+            },
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod parser_test {
+    use super::parse;
+    use crate::curl::types::{BasicAuth, FormPart, NameValue, RequestBody};
+
+    #[test]
+    fn parses_a_simple_get() {
+        let request = parse("curl https://example.com/users?page=2&q=a%20b+c").unwrap();
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.url, "https://example.com/users?page=2&q=a%20b+c");
+        assert_eq!(
+            request.query,
+            vec![NameValue::new("page", "2"), NameValue::new("q", "a b c")]
+        );
+        assert_eq!(request.body, None);
+    }
+
+    #[test]
+    fn parses_headers_cookies_auth_and_flags() {
+        let request = parse(
+            "curl -sSkL --compressed -u admin:s3cret -H 'Accept: application/json' \
+            -H 'Cookie: a=1; b=2' -b 'c=3' -A agent --url https://example.com",
+        )
+        .unwrap();
+        assert_eq!(
+            request.headers,
+            vec![
+                NameValue::new("Accept", "application/json"),
+                NameValue::new("User-Agent", "agent"),
+            ]
+        );
+        assert_eq!(
+            request.cookies,
+            vec![
+                NameValue::new("a", "1"),
+                NameValue::new("b", "2"),
+                NameValue::new("c", "3"),
+            ]
+        );
+        assert_eq!(
+            request.basic_auth,
+            Some(BasicAuth {
+                username: "admin".to_string(),
+                password: Some("s3cret".to_string()),
+            })
+        );
+        assert!(request.insecure && request.follow_redirects && request.compressed);
+    }
+
+    #[test]
+    fn parses_data_bodies() {
+        let request = parse("curl -XPUT https://example.com -d a=1 --data-raw '@b=2'").unwrap();
+        assert_eq!(request.method, "PUT");
+        assert_eq!(
+            request.body,
+            Some(RequestBody::Raw {
+                content: "a=1&@b=2".to_string()
+            })
+        );
+
+        let request = parse("curl https://example.com --data-urlencode 'q=a b&c'").unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(
+            request.body,
+            Some(RequestBody::Raw {
+                content: "q=a%20b%26c".to_string()
+            })
+        );
+
+        let request = parse(r#"curl https://example.com --json '{"a":' --json '1}'"#).unwrap();
+        assert_eq!(
+            request.body,
+            Some(RequestBody::Json {
+                content: r#"{"a":1}"#.to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn moves_data_into_query_with_get_flag() {
+        let request = parse("curl -G https://example.com/search?x=1 -d q=curl").unwrap();
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.url, "https://example.com/search?x=1&q=curl");
+        assert_eq!(request.query[1], NameValue::new("q", "curl"));
+        assert_eq!(request.body, None);
+    }
+
+    #[test]
+    fn parses_multipart_forms() {
+        let request = parse(
+            "curl https://example.com -F name=value -F 'file=@photo.png;type=image/png' \
+            --form-string 'raw=@literal'",
+        )
+        .unwrap();
+        assert_eq!(
+            request.body,
+            Some(RequestBody::Multipart {
+                parts: vec![
+                    FormPart {
+                        name: "name".to_string(),
+                        value: "value".to_string(),
+                        is_file: false,
+                        content_type: None,
+                        filename: None,
+                    },
+                    FormPart {
+                        name: "file".to_string(),
+                        value: "photo.png".to_string(),
+                        is_file: true,
+                        content_type: Some("image/png".to_string()),
+                        filename: None,
+                    },
+                    FormPart {
+                        name: "raw".to_string(),
+                        value: "@literal".to_string(),
+                        is_file: false,
+                        content_type: None,
+                        filename: None,
+                    },
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn parses_multi_line_commands_copied_from_browsers() {
+        let request = parse(
+            "curl 'https://example.com/api' \\\n  -H $'x-note: it\\'s' \\\n  --data-binary $'{\"a\":\"b\\n\"}' \\\n  --compressed",
+        )
+        .unwrap();
+        assert_eq!(request.headers, vec![NameValue::new("x-note", "it's")]);
+        assert_eq!(
+            request.body,
+            Some(RequestBody::Raw {
+                content: "{\"a\":\"b\n\"}".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn skips_unsupported_options() {
+        let request = parse("curl --digest -u admin:pw -sZ https://example.com").unwrap();
+        assert_eq!(request.url, "https://example.com");
+        assert_eq!(request.unsupported_options, vec!["--digest", "-Z"]);
+        assert!(request.basic_auth.is_some());
+
+        let request = parse("curl --oauth2-bearer TOKEN https://example.com").unwrap();
+        assert_eq!(request.url, "https://example.com");
+
+        let request = parse("curl -T upload.txt https://example.com -n").unwrap();
+        assert_eq!(request.url, "https://example.com");
+        assert_eq!(request.unsupported_options, vec!["-T", "-n"]);
+
+        let request = parse("curl --bogus https://example.com").unwrap();
+        assert_eq!(request.url, "https://example.com");
+
+        let error = parse("curl --compressed foo bar baz").unwrap_err();
+        assert_eq!(error.message, "Only a single URL is supported");
+    }
+
+    #[test]
+    fn reports_errors_with_positions() {
+        let error = parse("wget https://example.com").unwrap_err();
+        assert_eq!(error.message, "Command must start with curl");
+
+        let error = parse("curl https://example.com \\\n  other.com").unwrap_err();
+        assert_eq!(error.message, "Only a single URL is supported");
+        assert_eq!((error.line, error.column), (2, 3));
+
+        let error = parse("curl https://example.com -H").unwrap_err();
+        assert_eq!(error.message, "Option -H requires an argument");
+        assert_eq!(error.offset, 25);
+
+        let error = parse("curl -H 'Accept' https://example.com").unwrap_err();
+        assert_eq!(error.message, "Header \"Accept\" is missing a colon");
+        assert_eq!(error.column, 9);
+
+        let error = parse("curl -k").unwrap_err();
+        assert_eq!(error.message, "No URL was given");
+        assert_eq!(error.offset, 7);
+
+        let error = parse("curl https://example.com -d a=1 -F b=2").unwrap_err();
+        assert_eq!(error.column, 33);
+    }
+}
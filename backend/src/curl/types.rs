@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct CurlRequest {
+    pub method: String,
+    pub url: String,
+    pub query: Vec<NameValue>,
+    /// Headers given explicitly in the command - those curl adds implicitly (e.g. the content type
+    /// of a body) are not included.
+    pub headers: Vec<NameValue>,
+    pub cookies: Vec<NameValue>,
+    pub body: Option<RequestBody>,
+    pub basic_auth: Option<BasicAuth>,
+    pub insecure: bool,
+    pub follow_redirects: bool,
+    pub compressed: bool,
+    /// Options that are not supported here and were left out of the request, e.g. `--digest`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unsupported_options: Vec<String>,
+}
+
+impl CurlRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|header| header.name.eq_ignore_ascii_case(name))
+            .map(|header| header.value.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct NameValue {
+    pub name: String,
+    pub value: String,
+}
+
+impl NameValue {
+    pub fn new(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RequestBody {
+    /// Sent via `-d`, `--data-raw`, `--data-binary` or `--data-urlencode`.
+    Raw { content: String },
+    /// Sent via `--json`.
+    Json { content: String },
+    /// Sent via `-F` or `--form-string`.
+    Multipart { parts: Vec<FormPart> },
+}
+
+impl RequestBody {
+    /// The content type curl sends for the body when none is given explicitly.
+    pub fn default_content_type(&self) -> &'static str {
+        match self {
+            RequestBody::Raw { .. } => "application/x-www-form-urlencoded",
+            RequestBody::Json { .. } => "application/json",
+            RequestBody::Multipart { .. } => "multipart/form-data",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct FormPart {
+    pub name: String,
+    /// The literal value, or the path of the file to upload when `is_file` is set.
+    pub value: String,
+    pub is_file: bool,
+    pub content_type: Option<String>,
+    pub filename: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct BasicAuth {
+    pub username: String,
+    pub password: Option<String>,
+}
+
+/// A parse failure. `offset` is the character offset into the command, `line` and `column` are
+/// both 1-based.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ParseError {
+    pub message: String,
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl ParseError {
+    pub(crate) fn new(message: impl Into<String>, offset: usize, input: &str) -> Self {
+        let preceding: Vec<char> = input.chars().take(offset).collect();
+        let line = preceding.iter().filter(|c| **c == '\n').count() + 1;
+        let column = offset
+            - preceding
+                .iter()
+                .rposition(|c| *c == '\n')
+                .map_or(0, |position| position + 1)
+            + 1;
+
+        Self {
+            message: message.into(),
+            offset,
+            line,
+            column,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (line {}, column {})",
+            self.message, self.line, self.column
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
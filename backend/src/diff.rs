@@ -0,0 +1,132 @@
+//! Line based diffs, as shown when comparing two versions of a curl command.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LineChange {
+    Unchanged,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct DiffLine {
+    pub change: LineChange,
+    pub text: String,
+}
+
+impl DiffLine {
+    fn new(change: LineChange, text: &str) -> Self {
+        Self {
+            change,
+            text: text.to_string(),
+        }
+    }
+}
+
+/// Every line of both texts in order, with removed lines coming before the lines added in their
+/// place. Unchanged lines follow the longest common subsequence of the two.
+pub fn diff_lines(from: &str, to: &str) -> Vec<DiffLine> {
+    let from: Vec<&str> = from.lines().collect();
+    let to: Vec<&str> = to.lines().collect();
+
+    let mut lines = vec![];
+    let (mut i, mut j) = (0, 0);
+    // the end of both texts closes the gap after the last common line
+    for (next_i, next_j) in common_subsequence(&from, &to)
+        .into_iter()
+        .chain([(from.len(), to.len())])
+    {
+        lines.extend(
+            from[i..next_i]
+                .iter()
+                .map(|line| DiffLine::new(LineChange::Removed, line)),
+        );
+        lines.extend(
+            to[j..next_j]
+                .iter()
+                .map(|line| DiffLine::new(LineChange::Added, line)),
+        );
+        if next_i < from.len() {
+            lines.push(DiffLine::new(LineChange::Unchanged, from[next_i]));
+        }
+        (i, j) = (next_i + 1, next_j + 1);
+    }
+    lines
+}
+
+/// The indices into `from` and `to` of the items making up their longest common subsequence.
+pub fn common_subsequence<T: PartialEq>(from: &[T], to: &[T]) -> Vec<(usize, usize)> {
+    // common[i][j] is the length of the longest common subsequence of from[i..] and to[j..]
+    let mut common = vec![vec![0usize; to.len() + 1]; from.len() + 1];
+    for i in (0..from.len()).rev() {
+        for j in (0..to.len()).rev() {
+            common[i][j] = if from[i] == to[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut pairs = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < from.len() && j < to.len() {
+        if from[i] == to[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if common[i + 1][j] >= common[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod diff_test {
+    use super::{diff_lines, LineChange};
+
+    fn render(from: &str, to: &str) -> Vec<String> {
+        diff_lines(from, to)
+            .into_iter()
+            .map(|line| {
+                let marker = match line.change {
+                    LineChange::Unchanged => ' ',
+                    LineChange::Added => '+',
+                    LineChange::Removed => '-',
+                };
+                format!("{}{}", marker, line.text)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn marks_added_and_removed_lines() {
+        assert_eq!(
+            render(
+                "curl example.com \\\n  -H 'Accept: */*' \\\n  -k",
+                "curl example.com \\\n  -H 'Accept: application/json' \\\n  -k \\\n  -L"
+            ),
+            vec![
+                " curl example.com \\",
+                "-  -H 'Accept: */*' \\",
+                "-  -k",
+                "+  -H 'Accept: application/json' \\",
+                "+  -k \\",
+                "+  -L"
+            ]
+        );
+    }
+
+    #[test]
+    fn handles_empty_texts() {
+        assert!(diff_lines("", "").is_empty());
+        assert_eq!(render("", "curl a.com"), vec!["+curl a.com"]);
+        assert_eq!(render("curl a.com", ""), vec!["-curl a.com"]);
+        assert_eq!(render("curl a.com", "curl a.com"), vec![" curl a.com"]);
+    }
+}
@@ -0,0 +1,582 @@
+//! HAR 1.2 files, as saved from the network panel of browser devtools. Entries are turned into
+//! curl commands with their credentials redacted, and curl groups are written back out as HAR.
+
+use crate::{
+    curl::{self, CurlRequest, FormPart, NameValue, RequestBody},
+    importer::ImportedEntry,
+    redaction::REDACTED,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+pub const VERSION: &str = "1.2";
+
+/// Devtools resource types of requests that only load the page itself.
+const STATIC_RESOURCE_TYPES: [&str; 7] = [
+    "image",
+    "font",
+    "stylesheet",
+    "script",
+    "media",
+    "manifest",
+    "texttrack",
+];
+
+const STATIC_EXTENSIONS: [&str; 19] = [
+    "js", "mjs", "css", "map", "png", "jpg", "jpeg", "gif", "svg", "ico", "webp", "avif", "woff",
+    "woff2", "ttf", "otf", "eot", "mp4", "webm",
+];
+
+/// Headers the browser or curl sets by itself, which only get in the way of a replay.
+const DROPPED_HEADERS: [&str; 3] = ["host", "content-length", "connection"];
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct Har {
+    pub log: Log,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct Log {
+    pub version: String,
+    pub creator: Creator,
+    #[serde(default)]
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Creator {
+    pub name: String,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Entry {
+    #[serde(default)]
+    pub started_date_time: String,
+    #[serde(default)]
+    pub time: f64,
+    pub request: Request,
+    #[serde(default)]
+    pub response: Response,
+    #[serde(default)]
+    pub cache: Map<String, Value>,
+    #[serde(default)]
+    pub timings: Timings,
+    /// Chromium's devtools record what the request was for, e.g. `script` or `fetch`.
+    #[serde(
+        rename = "_resourceType",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub resource_type: Option<String>,
+    /// Holds the entry's name when exported.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Request {
+    pub method: String,
+    pub url: String,
+    #[serde(default)]
+    pub http_version: String,
+    #[serde(default)]
+    pub cookies: Vec<Pair>,
+    #[serde(default)]
+    pub headers: Vec<Pair>,
+    #[serde(default)]
+    pub query_string: Vec<Pair>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_data: Option<PostData>,
+    #[serde(default)]
+    pub headers_size: i64,
+    #[serde(default)]
+    pub body_size: i64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Response {
+    pub status: i64,
+    pub status_text: String,
+    pub http_version: String,
+    pub cookies: Vec<Pair>,
+    pub headers: Vec<Pair>,
+    pub content: Content,
+    #[serde(rename = "redirectURL")]
+    pub redirect_url: String,
+    pub headers_size: i64,
+    pub body_size: i64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Content {
+    pub size: i64,
+    pub mime_type: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct Timings {
+    pub send: f64,
+    pub wait: f64,
+    pub receive: f64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Pair {
+    pub name: String,
+    #[serde(default)]
+    pub value: String,
+}
+
+impl Pair {
+    pub fn new(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PostData {
+    #[serde(default)]
+    pub mime_type: String,
+    #[serde(default)]
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub params: Vec<Param>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Param {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+}
+
+/// Whether the entry loaded a script, style sheet, image, font or other asset of the page rather
+/// than calling an API.
+pub fn is_static(entry: &Entry) -> bool {
+    let url = &entry.request.url;
+    if url.starts_with("data:") || url.starts_with("blob:") {
+        return true;
+    }
+    if let Some(resource_type) = &entry.resource_type {
+        return STATIC_RESOURCE_TYPES.contains(&resource_type.as_str());
+    }
+
+    let mime_type = entry.response.content.mime_type.to_lowercase();
+    let static_mime_type = ["image/", "font/", "audio/", "video/", "text/css"]
+        .iter()
+        .any(|prefix| mime_type.starts_with(prefix))
+        || mime_type.contains("javascript");
+
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    let last_segment = path.rsplit('/').next().unwrap_or_default();
+    let static_extension = last_segment
+        .rsplit_once('.')
+        .map(|(_, extension)| STATIC_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+        .unwrap_or(false);
+
+    static_mime_type || static_extension
+}
+
+/// Cookie values and credentials in authorization headers are replaced with [`REDACTED`] so a
+/// captured session never ends up in a curl group.
+pub fn import_entry(entry: &Entry) -> Result<ImportedEntry, String> {
+    let request = &entry.request;
+    // files exported from a curl group may start with a placeholder such as `{{base_url}}`
+    let is_http = ["http://", "https://", "{{"]
+        .iter()
+        .any(|prefix| request.url.starts_with(prefix));
+    if !is_http {
+        return Err("Only http and https requests can be converted".to_string());
+    }
+
+    let mut curl_request = CurlRequest {
+        method: request.method.to_uppercase(),
+        url: request.url.clone(),
+        cookies: request
+            .cookies
+            .iter()
+            .map(|cookie| NameValue::new(&cookie.name, redact(&cookie.value)))
+            .collect(),
+        ..Default::default()
+    };
+
+    for header in request.headers.iter() {
+        let name = header.name.to_lowercase();
+        // HTTP/2 pseudo headers such as `:authority` are not headers curl can send
+        if name.starts_with(':') || DROPPED_HEADERS.contains(&name.as_str()) {
+            continue;
+        }
+        match name.as_str() {
+            "cookie" => {
+                if request.cookies.is_empty() {
+                    curl_request.cookies.extend(
+                        header
+                            .value
+                            .split(';')
+                            .filter_map(|pair| pair.split_once('='))
+                            .map(|(name, value)| NameValue::new(name.trim(), redact(value))),
+                    );
+                }
+            }
+            "accept-encoding" => curl_request.compressed = true,
+            "authorization" | "proxy-authorization" => {
+                let value = match header.value.split_once(' ') {
+                    Some((scheme, credentials)) => format!("{} {}", scheme, redact(credentials)),
+                    None => redact(&header.value),
+                };
+                curl_request
+                    .headers
+                    .push(NameValue::new(&header.name, value));
+            }
+            _ => curl_request
+                .headers
+                .push(NameValue::new(&header.name, &header.value)),
+        }
+    }
+
+    if let Some(post_data) = &request.post_data {
+        curl_request.body = import_post_data(post_data);
+        // the boundary in the captured content type belongs to the captured body, curl sets its own
+        if let Some(RequestBody::Multipart { .. }) = curl_request.body {
+            curl_request
+                .headers
+                .retain(|header| !header.name.eq_ignore_ascii_case("content-type"));
+        }
+    }
+
+    Ok(ImportedEntry {
+        name: match &entry.comment {
+            Some(comment) if !comment.trim().is_empty() => comment.clone(),
+            _ => default_name(&curl_request.method, &request.url),
+        },
+        description: String::new(),
+        raw_query: curl::to_command(&curl_request),
+        source: None,
+    })
+}
+
+/// Placeholders, as found in HAR files exported from a curl group, are kept.
+fn redact(value: &str) -> String {
+    let value = value.trim();
+    if value.starts_with("{{") && value.ends_with("}}") {
+        value.to_string()
+    } else {
+        REDACTED.to_string()
+    }
+}
+
+fn import_post_data(post_data: &PostData) -> Option<RequestBody> {
+    let is_multipart = post_data.mime_type.starts_with("multipart/form-data");
+    if is_multipart && !post_data.params.is_empty() {
+        let parts = post_data
+            .params
+            .iter()
+            .map(|param| FormPart {
+                name: param.name.clone(),
+                value: param
+                    .file_name
+                    .clone()
+                    .or_else(|| param.value.clone())
+                    .unwrap_or_default(),
+                is_file: param.file_name.is_some(),
+                content_type: param.content_type.clone(),
+                filename: None,
+            })
+            .collect();
+        return Some(RequestBody::Multipart { parts });
+    }
+
+    if !post_data.text.is_empty() {
+        return Some(RequestBody::Raw {
+            content: post_data.text.clone(),
+        });
+    }
+    let pairs: Vec<String> = post_data
+        .params
+        .iter()
+        .map(|param| format!("{}={}", param.name, param.value.as_deref().unwrap_or("")))
+        .collect();
+    (!pairs.is_empty()).then(|| RequestBody::Raw {
+        content: pairs.join("&"),
+    })
+}
+
+/// `GET /api/users` for `GET https://example.com/api/users?page=2`.
+fn default_name(method: &str, url: &str) -> String {
+    let without_scheme = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url);
+    let path = without_scheme
+        .find('/')
+        .map(|start| &without_scheme[start..])
+        .unwrap_or("/");
+    let path = path.split(['?', '#']).next().unwrap_or(path);
+    format!("{} {}", method, path)
+}
+
+pub fn export_log(entries: Vec<Entry>) -> Har {
+    Har {
+        log: Log {
+            version: VERSION.to_string(),
+            creator: Creator {
+                name: "cURL Share".to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+            },
+            entries,
+        },
+    }
+}
+
+/// An entry for a request that was never sent, so its response is left empty.
+pub fn export_entry(name: &str, request: &CurlRequest, started_date_time: &str) -> Entry {
+    let mut headers: Vec<Pair> = request
+        .headers
+        .iter()
+        .map(|header| Pair::new(&header.name, &header.value))
+        .collect();
+    if !request.cookies.is_empty() {
+        let cookies: Vec<String> = request
+            .cookies
+            .iter()
+            .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+            .collect();
+        headers.push(Pair::new("Cookie", cookies.join("; ")));
+    }
+    if let Some(auth) = &request.basic_auth {
+        let credentials = format!(
+            "{}:{}",
+            auth.username,
+            auth.password.as_deref().unwrap_or_default()
+        );
+        headers.push(Pair::new(
+            "Authorization",
+            format!("Basic {}", STANDARD.encode(credentials)),
+        ));
+    }
+
+    let post_data = request
+        .body
+        .as_ref()
+        .map(|body| export_post_data(request, body));
+    let body_size = post_data
+        .as_ref()
+        .map(|post_data| post_data.text.len() as i64)
+        .unwrap_or(0);
+
+    Entry {
+        started_date_time: started_date_time.to_string(),
+        time: 0.0,
+        request: Request {
+            method: request.method.clone(),
+            url: request.url.clone(),
+            http_version: "HTTP/1.1".to_string(),
+            cookies: request
+                .cookies
+                .iter()
+                .map(|cookie| Pair::new(&cookie.name, &cookie.value))
+                .collect(),
+            headers,
+            query_string: request
+                .query
+                .iter()
+                .map(|pair| Pair::new(&pair.name, &pair.value))
+                .collect(),
+            post_data,
+            headers_size: -1,
+            body_size,
+        },
+        response: Response {
+            content: Content {
+                size: 0,
+                mime_type: "x-unknown".to_string(),
+            },
+            headers_size: -1,
+            body_size: -1,
+            ..Default::default()
+        },
+        cache: Map::new(),
+        timings: Timings::default(),
+        resource_type: None,
+        comment: Some(name.to_string()),
+    }
+}
+
+fn export_post_data(request: &CurlRequest, body: &RequestBody) -> PostData {
+    let mime_type = request
+        .header("content-type")
+        .unwrap_or_else(|| body.default_content_type())
+        .to_string();
+
+    match body {
+        RequestBody::Raw { content } | RequestBody::Json { content } => PostData {
+            mime_type,
+            text: content.clone(),
+            params: vec![],
+        },
+        RequestBody::Multipart { parts } => PostData {
+            mime_type,
+            text: String::new(),
+            params: parts
+                .iter()
+                .map(|part| Param {
+                    name: part.name.clone(),
+                    value: (!part.is_file).then(|| part.value.clone()),
+                    file_name: part
+                        .is_file
+                        .then(|| part.filename.clone().unwrap_or_else(|| part.value.clone())),
+                    content_type: part.content_type.clone(),
+                })
+                .collect(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod har_test {
+    use super::{default_name, export_entry, import_entry, is_static, Entry};
+    use crate::{
+        curl::{parse, NameValue, RequestBody},
+        redaction::REDACTED,
+    };
+    use serde_json::json;
+
+    fn entry(value: serde_json::Value) -> Entry {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn recognises_static_assets() {
+        let asset = |url: &str, mime_type: &str| {
+            entry(json!({
+                "request": {"method": "GET", "url": url},
+                "response": {"content": {"mimeType": mime_type}}
+            }))
+        };
+        assert!(is_static(&asset("https://example.com/app.js?v=3", "")));
+        assert!(is_static(&asset(
+            "https://cdn.example.com/logo",
+            "image/png"
+        )));
+        assert!(is_static(&asset("data:image/png;base64,AAAA", "")));
+        assert!(!is_static(&asset(
+            "https://example.com/api/users",
+            "application/json"
+        )));
+
+        let mut fetch = asset("https://example.com/bundle.js", "application/javascript");
+        fetch.resource_type = Some("fetch".to_string());
+        assert!(!is_static(&fetch));
+    }
+
+    #[test]
+    fn redacts_cookies_and_credentials() {
+        let imported = import_entry(&entry(json!({
+            "request": {
+                "method": "post",
+                "url": "https://example.com/api/orders?draft=1",
+                "headers": [
+                    {"name": ":authority", "value": "example.com"},
+                    {"name": "Content-Length", "value": "11"},
+                    {"name": "Authorization", "value": "Bearer abc.def.ghi"},
+                    {"name": "Cookie", "value": "session=s3cret; theme=dark"},
+                    {"name": "Accept-Encoding", "value": "gzip, br"},
+                    {"name": "Content-Type", "value": "application/json"}
+                ],
+                "postData": {"mimeType": "application/json", "text": "{\"id\": 1}"}
+            }
+        })))
+        .unwrap();
+        assert_eq!(imported.name, "POST /api/orders");
+
+        let request = parse(&imported.raw_query).unwrap();
+        assert_eq!(
+            request.headers,
+            vec![
+                NameValue::new("Authorization", format!("Bearer {}", REDACTED)),
+                NameValue::new("Content-Type", "application/json")
+            ]
+        );
+        assert_eq!(
+            request.cookies,
+            vec![
+                NameValue::new("session", REDACTED),
+                NameValue::new("theme", REDACTED)
+            ]
+        );
+        assert!(request.compressed);
+        assert!(!request.follow_redirects);
+        assert!(!imported.raw_query.contains("s3cret"));
+    }
+
+    #[test]
+    fn rebuilds_multipart_bodies_from_their_params() {
+        let imported = import_entry(&entry(json!({
+            "request": {
+                "method": "POST",
+                "url": "https://example.com/upload",
+                "headers": [{"name": "Content-Type", "value": "multipart/form-data; boundary=----abc"}],
+                "postData": {
+                    "mimeType": "multipart/form-data; boundary=----abc",
+                    "params": [
+                        {"name": "title", "value": "Receipt"},
+                        {"name": "file", "fileName": "receipt.pdf", "contentType": "application/pdf"}
+                    ]
+                }
+            }
+        })))
+        .unwrap();
+
+        let request = parse(&imported.raw_query).unwrap();
+        assert!(request.headers.is_empty());
+        match request.body {
+            Some(RequestBody::Multipart { parts }) => {
+                assert_eq!(parts[0].value, "Receipt");
+                assert!(parts[1].is_file);
+                assert_eq!(parts[1].value, "receipt.pdf");
+            }
+            body => panic!("Expected a multipart body, got {:?}", body),
+        }
+    }
+
+    #[test]
+    fn exported_entries_import_as_the_same_request() {
+        let request = parse(
+            "curl -X PUT -H 'Content-Type: application/json' -d '{\"a\": 1}' 'https://example.com/items/1?force=true'",
+        )
+        .unwrap();
+        let exported = export_entry("Update item", &request, "2026-10-18T10:00:00.000Z");
+        assert_eq!(
+            exported.request.post_data.as_ref().unwrap().text,
+            "{\"a\": 1}"
+        );
+        assert_eq!(exported.request.query_string.len(), 1);
+
+        let imported = import_entry(&exported).unwrap();
+        assert_eq!(imported.name, "Update item");
+        assert_eq!(parse(&imported.raw_query).unwrap(), request);
+    }
+
+    #[test]
+    fn names_entries_after_their_path() {
+        assert_eq!(
+            default_name("GET", "https://example.com/api/users?page=2"),
+            "GET /api/users"
+        );
+        assert_eq!(default_name("GET", "https://example.com"), "GET /");
+    }
+}
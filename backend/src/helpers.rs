@@ -1,10 +1,116 @@
-use crate::routes::user::types::UserError;
+use crate::routes::{
+    token::{
+        authenticate_api_token,
+        types::{TokenAccess, TokenScope},
+    },
+    user::types::UserError,
+};
 use actix_session::Session;
+use actix_web::{
+    dev::Payload,
+    error,
+    http::{header, Method},
+    web, FromRequest, HttpRequest,
+};
+use sqlx::SqlitePool;
+use std::{future::Future, pin::Pin};
 
-pub async fn get_user_id(session: &Session) -> Result<i64, UserError> {
+/// Who a request is made by: the user logged in to the session, or the owner of the API token sent
+/// as `Authorization: Bearer <token>` instead. A token that is sent has to be valid, and read-only
+/// tokens are refused for anything but `GET` requests. Sessions that were signed out elsewhere are
+/// cleared.
+pub struct Identity {
+    session: Session,
+    token: Option<TokenAccess>,
+}
+
+impl Identity {
+    /// The project the request's API token is limited to, if any.
+    pub fn project_scope(&self) -> Option<i64> {
+        self.token.as_ref().and_then(|token| token.project_id)
+    }
+}
+
+impl FromRequest for Identity {
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let session = Session::from_request(req, payload);
+        let token = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(|token| token.trim().to_string());
+        let pool = req.app_data::<web::Data<SqlitePool>>().cloned();
+        let is_read = matches!(*req.method(), Method::GET | Method::HEAD);
+
+        Box::pin(async move {
+            let session = session.await?;
+            let pool = pool.ok_or_else(|| error::ErrorInternalServerError("No database pool."))?;
+            let Some(token) = token else {
+                if let Ok(user_id) = read_session_user_id(&session) {
+                    check_session_version(&session, user_id, &pool)
+                        .await
+                        .map_err(|e| error::ErrorInternalServerError(format!("{:?}", e)))?;
+                }
+                return Ok(Identity {
+                    session,
+                    token: None,
+                });
+            };
+
+            let access = authenticate_api_token(&token, &pool)
+                .await
+                .map_err(error::ErrorInternalServerError)?
+                .ok_or_else(|| error::ErrorUnauthorized("Invalid or expired API token."))?;
+            if access.scope == TokenScope::Read && !is_read {
+                return Err(error::ErrorForbidden("This API token is read-only."));
+            }
+            Ok(Identity {
+                session,
+                token: Some(access),
+            })
+        })
+    }
+}
+
+pub async fn get_user_id(identity: &Identity) -> Result<i64, UserError> {
+    match &identity.token {
+        Some(token) => Ok(token.user_id),
+        None => read_session_user_id(&identity.session),
+    }
+}
+
+/// Only the session counts, for what API tokens must not be used for.
+pub async fn get_session_user_id(session: &Session, pool: &SqlitePool) -> Result<i64, UserError> {
+    let id = read_session_user_id(session)?;
+    check_session_version(session, id, pool).await?;
+    read_session_user_id(session)
+}
+
+fn read_session_user_id(session: &Session) -> Result<i64, UserError> {
     let maybe_user_id = session.get::<i64>("user_id")?;
     let id = maybe_user_id.ok_or_else(|| {
         UserError::SessionGetError("User ID not found within session".to_string())
     })?;
     Ok(id)
 }
+
+/// Purges the session once the user's session version has moved on from the one it was signed in
+/// at, e.g. because they changed their password on another device.
+async fn check_session_version(
+    session: &Session,
+    user_id: i64,
+    pool: &SqlitePool,
+) -> Result<(), UserError> {
+    let session_version = session.get::<i64>("session_version")?.unwrap_or_default();
+    let current = sqlx::query!(r#"SELECT session_version FROM user WHERE id = ?"#, user_id)
+        .fetch_optional(pool)
+        .await?;
+    if current.is_none_or(|user| user.session_version != session_version) {
+        session.purge();
+    }
+    Ok(())
+}
@@ -0,0 +1,148 @@
+use crate::{insomnia, postman};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::BTreeMap, fmt};
+
+/// Groups, environments and skipped items read from another tool's export file.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Imported {
+    pub groups: Vec<ImportedGroup>,
+    pub environments: Vec<ImportedEnvironment>,
+    pub skipped: Vec<Skipped>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ImportedGroup {
+    pub name: String,
+    pub description: String,
+    pub entries: Vec<ImportedEntry>,
+    /// Identifies groups generated from a document that can be imported again, such as an OpenAPI
+    /// specification. Importing it again updates the group rather than adding another.
+    pub source: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ImportedEntry {
+    pub name: String,
+    pub description: String,
+    /// The generated curl command.
+    pub raw_query: String,
+    /// Identifies the entry within a group with a source, e.g. the operation it was generated from.
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ImportedEnvironment {
+    pub name: String,
+    pub variables: BTreeMap<String, String>,
+    pub secrets: BTreeMap<String, String>,
+}
+
+/// A request that could not be turned into a curl command, named by its folder path, or an
+/// environment variable whose name cannot be used in a placeholder.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Skipped {
+    pub name: String,
+    pub reason: String,
+}
+
+impl Skipped {
+    pub fn new(name: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            reason: reason.into(),
+        }
+    }
+
+    pub fn invalid_variable(environment: &str, variable: &str) -> Self {
+        Self::new(
+            folder_path(&[environment.to_string()], variable),
+            "Variable names may only contain letters, digits and underscores",
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ImportError(pub String);
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+/// Reads a Postman v2.1 collection, with its environment files, or an Insomnia v4 export, which
+/// carries its environments itself.
+pub fn import(content: &Value, environments: &[Value]) -> Result<Imported, ImportError> {
+    if let Some(format) = content.get("__export_format") {
+        if format != 4 {
+            return Err(ImportError(format!(
+                "Insomnia export format {} is not supported, export in format 4",
+                format
+            )));
+        }
+        let export: insomnia::Export = serde_json::from_value(content.clone())
+            .map_err(|e| ImportError(format!("Invalid Insomnia export: {}", e)))?;
+        return Ok(insomnia::import_export(&export));
+    }
+
+    let schema = content
+        .pointer("/info/schema")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    if !schema.contains("/collection/v2.1") {
+        return Err(ImportError(
+            "Expected a Postman v2.1 collection or an Insomnia v4 export".to_string(),
+        ));
+    }
+
+    let collection: postman::Collection = serde_json::from_value(content.clone())
+        .map_err(|e| ImportError(format!("Invalid Postman collection: {}", e)))?;
+    let mut imported = postman::import_collection(&collection);
+    for environment in environments {
+        let environment: postman::PostmanEnvironment = serde_json::from_value(environment.clone())
+            .map_err(|e| ImportError(format!("Invalid Postman environment: {}", e)))?;
+        let (environment, skipped) = postman::import_environment(&environment);
+        imported.environments.push(environment);
+        imported.skipped.extend(skipped);
+    }
+    Ok(imported)
+}
+
+/// Joins folder names into the name of the group their requests land in.
+pub(crate) fn folder_path(parents: &[String], name: &str) -> String {
+    parents
+        .iter()
+        .map(String::as_str)
+        .chain(std::iter::once(name))
+        .collect::<Vec<_>>()
+        .join(" / ")
+}
+
+#[cfg(test)]
+mod importer_test {
+    use super::{import, ImportError};
+    use serde_json::json;
+
+    #[test]
+    fn rejects_unknown_files() {
+        assert!(matches!(
+            import(&json!({"item": []}), &[]),
+            Err(ImportError(_))
+        ));
+        assert!(matches!(
+            import(
+                &json!({"info": {"name": "a", "schema": "https://schema.getpostman.com/json/collection/v2.0.0/collection.json"}, "item": []}),
+                &[]
+            ),
+            Err(ImportError(_))
+        ));
+        assert!(matches!(
+            import(&json!({"__export_format": 3, "resources": []}), &[]),
+            Err(ImportError(_))
+        ));
+    }
+}
@@ -0,0 +1,582 @@
+//! Reading Insomnia v4 exports. Folders become groups, requests become curl commands and the
+//! environments of each workspace are mapped onto project environments.
+
+use crate::{
+    curl::{self, percent_encode, BasicAuth, CurlRequest, FormPart, NameValue, RequestBody},
+    importer::{folder_path, Imported, ImportedEntry, ImportedEnvironment, ImportedGroup, Skipped},
+    template,
+};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+
+/// Insomnia references variables as `{{ _.name }}`, older versions as `{{ name }}`.
+static VARIABLE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\{\{\s*(?:_\.)?([A-Za-z_][A-Za-z0-9_]*)\s*\}\}")
+        .expect("variable pattern is valid")
+});
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Export {
+    pub __export_format: u32,
+    pub resources: Vec<Resource>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "_type", rename_all = "snake_case")]
+pub enum Resource {
+    Workspace(Workspace),
+    RequestGroup(RequestGroup),
+    Request(Box<Request>),
+    Environment(Environment),
+    /// Cookie jars, API specs, unit tests and the like.
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Workspace {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestGroup {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub parent_id: Option<String>,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub meta_sort_key: f64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Request {
+    pub parent_id: Option<String>,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub method: String,
+    pub url: String,
+    #[serde(default)]
+    pub body: Body,
+    #[serde(default)]
+    pub parameters: Vec<Pair>,
+    #[serde(default)]
+    pub headers: Vec<Pair>,
+    #[serde(default)]
+    pub authentication: Authentication,
+    #[serde(default)]
+    pub meta_sort_key: f64,
+    /// `on`, `off` or `global`, which follows redirects by default.
+    #[serde(default)]
+    pub setting_follow_redirects: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Body {
+    pub mime_type: Option<String>,
+    pub text: Option<String>,
+    #[serde(default)]
+    pub params: Vec<Pair>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Pair {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub value: String,
+    #[serde(default)]
+    pub disabled: bool,
+    /// `file` for form fields sending a file.
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    pub file_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Authentication {
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    #[serde(default)]
+    pub disabled: bool,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+    #[serde(default)]
+    pub token: String,
+    pub prefix: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Environment {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub parent_id: Option<String>,
+    pub name: String,
+    #[serde(default)]
+    pub data: Map<String, Value>,
+    /// Private environments are not shared by Insomnia, their values are imported as secrets.
+    #[serde(default)]
+    pub is_private: bool,
+}
+
+pub fn import_export(export: &Export) -> Imported {
+    let mut imported = Imported::default();
+
+    for resource in export.resources.iter() {
+        if let Resource::Workspace(workspace) = resource {
+            import_folder(
+                export,
+                &workspace.id,
+                &workspace.name,
+                &workspace.description,
+                None,
+                &mut imported,
+            );
+            import_environments(export, &workspace.id, &mut imported);
+        }
+    }
+
+    imported
+}
+
+/// `parents` is `None` for the workspace, whose name is left out of the folder paths.
+fn import_folder(
+    export: &Export,
+    id: &str,
+    name: &str,
+    description: &str,
+    parents: Option<&[String]>,
+    imported: &mut Imported,
+) {
+    let group_name = match parents {
+        Some(parents) => folder_path(parents, name),
+        None => name.to_string(),
+    };
+
+    let mut requests: Vec<&Request> = children(export, id)
+        .filter_map(|resource| match resource {
+            Resource::Request(request) => Some(request.as_ref()),
+            _ => None,
+        })
+        .collect();
+    requests.sort_by(|a, b| by_sort_key(a.meta_sort_key, b.meta_sort_key));
+
+    let mut entries = vec![];
+    for request in requests {
+        match import_request(request) {
+            Ok(entry) => entries.push(entry),
+            Err(reason) => imported.skipped.push(Skipped::new(
+                folder_path(std::slice::from_ref(&group_name), &request.name),
+                reason,
+            )),
+        }
+    }
+    if !entries.is_empty() {
+        imported.groups.push(ImportedGroup {
+            name: group_name,
+            description: description.to_string(),
+            entries,
+            source: None,
+        });
+    }
+
+    let mut folders: Vec<&RequestGroup> = children(export, id)
+        .filter_map(|resource| match resource {
+            Resource::RequestGroup(folder) => Some(folder),
+            _ => None,
+        })
+        .collect();
+    folders.sort_by(|a, b| by_sort_key(a.meta_sort_key, b.meta_sort_key));
+
+    let mut path = parents.map(<[String]>::to_vec).unwrap_or_default();
+    if parents.is_some() {
+        path.push(name.to_string());
+    }
+    for folder in folders {
+        import_folder(
+            export,
+            &folder.id,
+            &folder.name,
+            &folder.description,
+            Some(&path),
+            imported,
+        );
+    }
+}
+
+fn children<'a>(export: &'a Export, parent_id: &'a str) -> impl Iterator<Item = &'a Resource> {
+    export.resources.iter().filter(move |resource| {
+        let resource_parent_id = match resource {
+            Resource::RequestGroup(folder) => folder.parent_id.as_deref(),
+            Resource::Request(request) => request.parent_id.as_deref(),
+            _ => None,
+        };
+        resource_parent_id == Some(parent_id)
+    })
+}
+
+fn by_sort_key(a: f64, b: f64) -> Ordering {
+    a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+}
+
+fn import_request(request: &Request) -> Result<ImportedEntry, String> {
+    let mut url = convert_template(&request.url)?;
+    if url.trim().is_empty() {
+        return Err("The request has no URL".to_string());
+    }
+    let parameters: Vec<String> = request
+        .parameters
+        .iter()
+        .filter(|parameter| !parameter.disabled && !parameter.name.is_empty())
+        .map(|parameter| {
+            Ok(format!(
+                "{}={}",
+                convert_template(&parameter.name)?,
+                convert_template(&parameter.value)?
+            ))
+        })
+        .collect::<Result<_, String>>()?;
+    if !parameters.is_empty() {
+        url.push(if url.contains('?') { '&' } else { '?' });
+        url.push_str(&parameters.join("&"));
+    }
+
+    let mut curl_request = CurlRequest {
+        method: request.method.to_uppercase(),
+        url,
+        follow_redirects: request.setting_follow_redirects != "off",
+        ..Default::default()
+    };
+    for header in request
+        .headers
+        .iter()
+        .filter(|header| !header.disabled && !header.name.is_empty())
+    {
+        curl_request.headers.push(NameValue::new(
+            convert_template(&header.name)?,
+            convert_template(&header.value)?,
+        ));
+    }
+
+    let authentication = &request.authentication;
+    match authentication.kind.as_deref() {
+        _ if authentication.disabled => {}
+        Some("basic") => {
+            curl_request.basic_auth = Some(BasicAuth {
+                username: convert_template(&authentication.username)?,
+                password: Some(convert_template(&authentication.password)?),
+            })
+        }
+        Some("bearer") => {
+            let prefix = match authentication.prefix.as_deref() {
+                Some(prefix) if !prefix.is_empty() => prefix,
+                _ => "Bearer",
+            };
+            curl_request.headers.push(NameValue::new(
+                "Authorization",
+                format!("{} {}", prefix, convert_template(&authentication.token)?),
+            ));
+        }
+        Some("none") | None => {}
+        Some(_) => return Err("Only basic and bearer authentication can be converted".to_string()),
+    }
+
+    curl_request.body = import_body(&request.body)?;
+    match (&curl_request.body, &request.body.mime_type) {
+        // curl sets the multipart content type itself, with the boundary
+        (Some(RequestBody::Multipart { .. }), _) => curl_request
+            .headers
+            .retain(|header| !header.name.eq_ignore_ascii_case("content-type")),
+        (Some(RequestBody::Raw { .. }), Some(mime_type))
+            if curl_request.header("content-type").is_none() =>
+        {
+            curl_request
+                .headers
+                .push(NameValue::new("Content-Type", mime_type))
+        }
+        _ => {}
+    }
+
+    Ok(ImportedEntry {
+        name: request.name.clone(),
+        description: request.description.clone(),
+        raw_query: curl::to_command(&curl_request),
+        source: None,
+    })
+}
+
+fn import_body(body: &Body) -> Result<Option<RequestBody>, String> {
+    let enabled_params = || body.params.iter().filter(|param| !param.disabled);
+
+    let body = match body.mime_type.as_deref() {
+        Some("application/graphql") => return Err("GraphQL bodies cannot be converted".to_string()),
+        Some("application/x-www-form-urlencoded") => {
+            let pairs = enabled_params()
+                .map(|param| {
+                    Ok(format!(
+                        "{}={}",
+                        percent_encode(&convert_template(&param.name)?),
+                        percent_encode(&convert_template(&param.value)?)
+                    ))
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            (!pairs.is_empty()).then(|| RequestBody::Raw {
+                content: pairs.join("&"),
+            })
+        }
+        Some("multipart/form-data") => {
+            let mut parts = vec![];
+            for param in enabled_params() {
+                let is_file = param.kind.as_deref() == Some("file");
+                let value = if is_file {
+                    match param.file_name.as_deref() {
+                        Some(file_name) if !file_name.is_empty() => file_name.to_string(),
+                        _ => {
+                            return Err(format!(
+                                "No file is selected for the form field \"{}\"",
+                                param.name
+                            ))
+                        }
+                    }
+                } else {
+                    convert_template(&param.value)?
+                };
+                parts.push(FormPart {
+                    name: convert_template(&param.name)?,
+                    value,
+                    is_file,
+                    content_type: None,
+                    filename: None,
+                });
+            }
+            (!parts.is_empty()).then_some(RequestBody::Multipart { parts })
+        }
+        _ => match body.text.as_deref() {
+            Some(text) if !text.is_empty() => Some(RequestBody::Raw {
+                content: convert_template(text)?,
+            }),
+            _ => None,
+        },
+    };
+    Ok(body)
+}
+
+/// Rewrites Insomnia variables as placeholders. Template tags, such as chained responses, have
+/// no equivalent.
+fn convert_template(value: &str) -> Result<String, String> {
+    if value.contains("{%") {
+        return Err("Template tags such as {% response %} cannot be converted".to_string());
+    }
+    Ok(VARIABLE.replace_all(value, "{{$1}}").into_owned())
+}
+
+/// The base environment of a workspace is merged into each of its sub environments. Without any
+/// sub environment, the base environment is imported on its own.
+fn import_environments(export: &Export, workspace_id: &str, imported: &mut Imported) {
+    let environments: Vec<&Environment> = export
+        .resources
+        .iter()
+        .filter_map(|resource| match resource {
+            Resource::Environment(environment) => Some(environment),
+            _ => None,
+        })
+        .collect();
+
+    let Some(base) = environments
+        .iter()
+        .copied()
+        .find(|environment| environment.parent_id.as_deref() == Some(workspace_id))
+    else {
+        return;
+    };
+    let sub_environments: Vec<&Environment> = environments
+        .iter()
+        .copied()
+        .filter(|environment| environment.parent_id.as_deref() == Some(base.id.as_str()))
+        .collect();
+
+    if sub_environments.is_empty() {
+        if !base.data.is_empty() {
+            let environment = import_environment(&base.name, &[base], imported);
+            imported.environments.push(environment);
+        }
+        return;
+    }
+    for environment in sub_environments {
+        let environment = import_environment(&environment.name, &[base, environment], imported);
+        imported.environments.push(environment);
+    }
+}
+
+/// Later layers override the values of earlier ones.
+fn import_environment(
+    name: &str,
+    layers: &[&Environment],
+    imported: &mut Imported,
+) -> ImportedEnvironment {
+    let mut environment = ImportedEnvironment {
+        name: name.to_string(),
+        ..Default::default()
+    };
+
+    for layer in layers {
+        for (key, value) in layer.data.iter() {
+            let value = match value {
+                Value::String(text) => convert_template(text),
+                Value::Number(number) => Ok(number.to_string()),
+                Value::Bool(boolean) => Ok(boolean.to_string()),
+                _ => Err("Only text, number and boolean values can be imported".to_string()),
+            };
+            let value = match value {
+                Ok(value) if template::is_valid_variable_name(key) => value,
+                Ok(_) => {
+                    imported.skipped.push(Skipped::invalid_variable(name, key));
+                    continue;
+                }
+                Err(reason) => {
+                    imported
+                        .skipped
+                        .push(Skipped::new(folder_path(&[name.to_string()], key), reason));
+                    continue;
+                }
+            };
+
+            environment.variables.remove(key);
+            environment.secrets.remove(key);
+            if layer.is_private {
+                environment.secrets.insert(key.clone(), value);
+            } else {
+                environment.variables.insert(key.clone(), value);
+            }
+        }
+    }
+
+    environment
+}
+
+#[cfg(test)]
+mod insomnia_test {
+    use super::{import_export, Export};
+    use crate::curl::{parse, NameValue, RequestBody};
+    use serde_json::json;
+
+    fn export(resources: serde_json::Value) -> Export {
+        serde_json::from_value(json!({"__export_format": 4, "resources": resources})).unwrap()
+    }
+
+    #[test]
+    fn converts_requests_into_curl_commands() {
+        let imported = import_export(&export(json!([
+            {"_id": "wrk_1", "_type": "workspace", "name": "Shop"},
+            {"_id": "fld_1", "_type": "request_group", "parentId": "wrk_1", "name": "Orders"},
+            {
+                "_id": "req_2", "_type": "request", "parentId": "fld_1", "name": "Create order",
+                "method": "POST", "url": "{{ _.base_url }}/orders", "metaSortKey": 2,
+                "body": {"mimeType": "application/json", "text": "{\"item\": \"{{ _.item }}\"}"},
+                "headers": [{"name": "X-Disabled", "value": "1", "disabled": true}],
+                "authentication": {"type": "bearer", "token": "{{ _.token }}", "prefix": ""},
+                "settingFollowRedirects": "off"
+            },
+            {
+                "_id": "req_1", "_type": "request", "parentId": "fld_1", "name": "List orders",
+                "method": "GET", "url": "{{ base_url }}/orders",
+                "parameters": [{"name": "page", "value": "2"}], "metaSortKey": 1,
+                "authentication": {"type": "basic", "username": "{{ _.user }}", "password": "{{ _.password }}"}
+            }
+        ])));
+
+        assert!(imported.skipped.is_empty());
+        assert_eq!(imported.groups.len(), 1);
+        let group = &imported.groups[0];
+        assert_eq!(group.name, "Orders");
+        assert_eq!(group.entries[0].name, "List orders");
+
+        let list = parse(&group.entries[0].raw_query).unwrap();
+        assert_eq!(list.url, "{{base_url}}/orders?page=2");
+        assert_eq!(list.basic_auth.unwrap().username, "{{user}}");
+        assert!(list.follow_redirects);
+
+        let create = parse(&group.entries[1].raw_query).unwrap();
+        assert_eq!(create.method, "POST");
+        assert_eq!(
+            create.headers,
+            vec![
+                NameValue::new("Authorization", "Bearer {{token}}"),
+                NameValue::new("Content-Type", "application/json")
+            ]
+        );
+        assert_eq!(
+            create.body,
+            Some(RequestBody::Raw {
+                content: "{\"item\": \"{{item}}\"}".to_string()
+            })
+        );
+        assert!(!create.follow_redirects);
+    }
+
+    #[test]
+    fn skips_requests_that_cannot_be_converted() {
+        let imported = import_export(&export(json!([
+            {"_id": "wrk_1", "_type": "workspace", "name": "Shop"},
+            {
+                "_id": "req_1", "_type": "request", "parentId": "wrk_1", "name": "Chained",
+                "method": "GET", "url": "{% response 'body', 'req_2', '$.url' %}"
+            },
+            {
+                "_id": "req_2", "_type": "request", "parentId": "wrk_1", "name": "Signed",
+                "method": "GET", "url": "https://example.com",
+                "authentication": {"type": "hawk", "id": "a", "key": "b"}
+            },
+            {"_id": "jar_1", "_type": "cookie_jar", "parentId": "wrk_1"}
+        ])));
+
+        assert!(imported.groups.is_empty());
+        let skipped: Vec<&str> = imported.skipped.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(skipped, vec!["Shop / Chained", "Shop / Signed"]);
+    }
+
+    #[test]
+    fn merges_the_base_environment_into_sub_environments() {
+        let imported = import_export(&export(json!([
+            {"_id": "wrk_1", "_type": "workspace", "name": "Shop"},
+            {
+                "_id": "env_1", "_type": "environment", "parentId": "wrk_1", "name": "Base Environment",
+                "data": {"base_url": "https://shop.test", "retries": 3, "nested": {"a": 1}}
+            },
+            {
+                "_id": "env_2", "_type": "environment", "parentId": "env_1", "name": "Production",
+                "data": {"base_url": "https://shop.example.com", "token": "{{ _.base_url }}"},
+                "isPrivate": true
+            }
+        ])));
+
+        assert_eq!(imported.environments.len(), 1);
+        let environment = &imported.environments[0];
+        assert_eq!(environment.name, "Production");
+        assert_eq!(
+            environment.variables.keys().collect::<Vec<_>>(),
+            vec!["retries"]
+        );
+        assert_eq!(environment.secrets["base_url"], "https://shop.example.com");
+        assert_eq!(environment.secrets["token"], "{{base_url}}");
+        assert_eq!(imported.skipped.len(), 1);
+        assert_eq!(imported.skipped[0].name, "Production / nested");
+    }
+}
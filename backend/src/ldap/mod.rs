@@ -0,0 +1,479 @@
+//! Signing in against an LDAP directory, such as OpenLDAP or Active Directory. Users are looked up
+//! with a search and then bound as, so their password never leaves the directory.
+
+pub mod protocol;
+
+use rand_core::{OsRng, RngCore};
+use reqwest::Url;
+use secrecy::{ExposeSecret, Secret};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    env, fmt, io,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpStream,
+};
+use tokio_rustls::{
+    rustls::{Certificate, ClientConfig, OwnedTrustAnchor, RootCertStore, ServerName},
+    TlsConnector,
+};
+
+use protocol::{
+    read_message, write_message, LdapMessage, ProtocolError, ProtocolOp, INVALID_CREDENTIALS,
+    SUCCESS,
+};
+
+pub struct LdapConfig {
+    /// An `ldap://` or `ldaps://` URL.
+    pub url: String,
+    /// Where users are searched for.
+    pub base_dn: String,
+    /// The account users are searched for with. The search is anonymous when it is not set.
+    pub bind_dn: Option<String>,
+    pub bind_password: Secret<String>,
+    /// What users sign in with: `uid` by default, `sAMAccountName` on Active Directory.
+    pub username_attribute: String,
+    /// Lists the groups of a user.
+    pub group_attribute: String,
+    /// Members of these groups are made admins.
+    pub admin_groups: Vec<String>,
+    /// When set, only members of these groups, or of `admin_groups`, can sign in.
+    pub user_groups: Vec<String>,
+    /// The certificates `ldaps://` servers are checked against, in place of the well known ones.
+    pub ca_file: Option<String>,
+    /// How long a successful sign in is remembered for. Nothing is remembered when it is zero.
+    pub cache_ttl: Duration,
+    pub timeout: Duration,
+}
+
+impl LdapConfig {
+    pub fn new(url: &str, base_dn: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            base_dn: base_dn.to_string(),
+            bind_dn: None,
+            bind_password: Secret::new(String::new()),
+            username_attribute: "uid".to_string(),
+            group_attribute: "memberOf".to_string(),
+            admin_groups: Vec::new(),
+            user_groups: Vec::new(),
+            ca_file: None,
+            cache_ttl: Duration::from_secs(300),
+            timeout: Duration::from_secs(10),
+        }
+    }
+
+    /// Directory sign in is on once `LDAP_URL` and `LDAP_BASE_DN` are set. `LDAP_BIND_DN`,
+    /// `LDAP_BIND_PASSWORD`, `LDAP_USERNAME_ATTRIBUTE`, `LDAP_GROUP_ATTRIBUTE`, `LDAP_CA_FILE`,
+    /// `LDAP_CACHE_SECONDS` and `LDAP_TIMEOUT_SECONDS` override the defaults, while
+    /// `LDAP_ADMIN_GROUPS` and `LDAP_USER_GROUPS` take group DNs separated by `;`.
+    pub fn from_env() -> Option<Self> {
+        let mut config = Self::new(&env::var("LDAP_URL").ok()?, &env::var("LDAP_BASE_DN").ok()?);
+        config.bind_dn = env::var("LDAP_BIND_DN").ok();
+        if let Ok(password) = env::var("LDAP_BIND_PASSWORD") {
+            config.bind_password = Secret::new(password);
+        }
+        if let Ok(attribute) = env::var("LDAP_USERNAME_ATTRIBUTE") {
+            config.username_attribute = attribute;
+        }
+        if let Ok(attribute) = env::var("LDAP_GROUP_ATTRIBUTE") {
+            config.group_attribute = attribute;
+        }
+        let groups = |name: &str| {
+            env::var(name)
+                .map(|groups| {
+                    groups
+                        .split(';')
+                        .map(str::trim)
+                        .filter(|group| !group.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        config.admin_groups = groups("LDAP_ADMIN_GROUPS");
+        config.user_groups = groups("LDAP_USER_GROUPS");
+        config.ca_file = env::var("LDAP_CA_FILE").ok();
+        let seconds = |name: &str| env::var(name).ok()?.parse().ok().map(Duration::from_secs);
+        if let Some(ttl) = seconds("LDAP_CACHE_SECONDS") {
+            config.cache_ttl = ttl;
+        }
+        if let Some(timeout) = seconds("LDAP_TIMEOUT_SECONDS") {
+            config.timeout = timeout;
+        }
+        Some(config)
+    }
+}
+
+/// A user the directory vouched for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DirectoryUser {
+    /// Normalised, so that it can be compared as is.
+    pub dn: String,
+    pub is_admin: bool,
+}
+
+#[derive(Debug)]
+pub enum LdapError {
+    /// The directory could not be reached or answered with something unexpected.
+    Unavailable(String),
+    InvalidCredentials,
+    /// The user is in none of the groups allowed to sign in.
+    NotPermitted,
+}
+
+impl fmt::Display for LdapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LdapError::Unavailable(e) => write!(f, "The directory failed: {}", e),
+            LdapError::InvalidCredentials => write!(f, "The directory rejected the password"),
+            LdapError::NotPermitted => write!(f, "The user is not allowed to sign in"),
+        }
+    }
+}
+
+impl std::error::Error for LdapError {}
+
+impl From<io::Error> for LdapError {
+    fn from(e: io::Error) -> Self {
+        LdapError::Unavailable(e.to_string())
+    }
+}
+
+impl From<ProtocolError> for LdapError {
+    fn from(e: ProtocolError) -> Self {
+        LdapError::Unavailable(e.to_string())
+    }
+}
+
+struct CachedLogin {
+    password_digest: Vec<u8>,
+    user: DirectoryUser,
+    expires_at: Instant,
+}
+
+/// Checks passwords against the directory, remembering the ones that worked for a while.
+pub struct LdapAuthenticator {
+    config: LdapConfig,
+    /// Keeps the digests of remembered passwords from being looked up in precomputed tables.
+    salt: [u8; 32],
+    cache: Mutex<HashMap<String, CachedLogin>>,
+}
+
+impl LdapAuthenticator {
+    pub fn new(config: LdapConfig) -> Self {
+        let mut salt = [0u8; 32];
+        OsRng.fill_bytes(&mut salt);
+        Self {
+            config,
+            salt,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn config(&self) -> &LdapConfig {
+        &self.config
+    }
+
+    /// `None` when the directory has no such user, leaving them to the local accounts.
+    pub async fn authenticate(
+        &self,
+        username: &str,
+        password: &Secret<String>,
+    ) -> Result<Option<DirectoryUser>, LdapError> {
+        // directories treat a bind with an empty password as anonymous, and let it through
+        if password.expose_secret().is_empty() {
+            return Err(LdapError::InvalidCredentials);
+        }
+        let password_digest = self.digest(username, password);
+        if let Some(user) = self.cached(username, &password_digest) {
+            return Ok(Some(user));
+        }
+
+        let result = tokio::time::timeout(self.config.timeout, self.bind_user(username, password))
+            .await
+            .unwrap_or_else(|_| Err(LdapError::Unavailable("timed out".to_string())));
+
+        let mut cache = self.cache.lock().unwrap();
+        match &result {
+            Ok(Some(user)) if !self.config.cache_ttl.is_zero() => {
+                cache.insert(
+                    username.to_string(),
+                    CachedLogin {
+                        password_digest,
+                        user: user.clone(),
+                        expires_at: Instant::now() + self.config.cache_ttl,
+                    },
+                );
+            }
+            _ => {
+                cache.remove(username);
+            }
+        }
+        result
+    }
+
+    fn digest(&self, username: &str, password: &Secret<String>) -> Vec<u8> {
+        Sha256::new()
+            .chain_update(self.salt)
+            .chain_update(username.as_bytes())
+            .chain_update([0])
+            .chain_update(password.expose_secret().as_bytes())
+            .finalize()
+            .to_vec()
+    }
+
+    fn cached(&self, username: &str, password_digest: &[u8]) -> Option<DirectoryUser> {
+        let mut cache = self.cache.lock().unwrap();
+        let now = Instant::now();
+        cache.retain(|_, login| login.expires_at > now);
+        cache
+            .get(username)
+            .filter(|login| login.password_digest == password_digest)
+            .map(|login| login.user.clone())
+    }
+
+    async fn bind_user(
+        &self,
+        username: &str,
+        password: &Secret<String>,
+    ) -> Result<Option<DirectoryUser>, LdapError> {
+        let config = &self.config;
+        let mut connection = Connection::open(config).await?;
+        connection
+            .bind(
+                config.bind_dn.as_deref().unwrap_or_default(),
+                config.bind_password.expose_secret(),
+            )
+            .await
+            .map_err(|e| match e {
+                LdapError::InvalidCredentials => {
+                    LdapError::Unavailable("the directory rejected the bind account".to_string())
+                }
+                e => e,
+            })?;
+
+        let mut entries = connection
+            .search(
+                &config.base_dn,
+                &config.username_attribute,
+                username,
+                &config.group_attribute,
+            )
+            .await?;
+        if entries.len() > 1 {
+            return Err(LdapError::Unavailable(format!(
+                "more than one entry has the {} {}",
+                config.username_attribute, username
+            )));
+        }
+        let Some((dn, groups)) = entries.pop() else {
+            return Ok(None);
+        };
+        connection.bind(&dn, password.expose_secret()).await?;
+        connection.unbind().await;
+
+        let groups: Vec<String> = groups.iter().map(|group| normalize_dn(group)).collect();
+        let is_member = |wanted: &[String]| {
+            wanted
+                .iter()
+                .any(|group| groups.contains(&normalize_dn(group)))
+        };
+        let is_admin = is_member(&config.admin_groups);
+        if !is_admin && !config.user_groups.is_empty() && !is_member(&config.user_groups) {
+            return Err(LdapError::NotPermitted);
+        }
+        Ok(Some(DirectoryUser {
+            dn: normalize_dn(&dn),
+            is_admin,
+        }))
+    }
+}
+
+/// Directories compare DNs without regard to case or the spaces between their parts.
+pub fn normalize_dn(dn: &str) -> String {
+    dn.split(',')
+        .map(|part| part.trim().to_lowercase())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Stream for T {}
+
+struct Connection {
+    stream: Box<dyn Stream>,
+    last_id: i64,
+}
+
+impl Connection {
+    async fn open(config: &LdapConfig) -> Result<Self, LdapError> {
+        let url = Url::parse(&config.url)
+            .map_err(|e| LdapError::Unavailable(format!("invalid URL: {}", e)))?;
+        let host = url
+            .host_str()
+            .ok_or_else(|| LdapError::Unavailable("the URL has no host".to_string()))?;
+        let stream: Box<dyn Stream> = match url.scheme() {
+            "ldap" => Box::new(TcpStream::connect((host, url.port().unwrap_or(389))).await?),
+            "ldaps" => {
+                let tcp = TcpStream::connect((host, url.port().unwrap_or(636))).await?;
+                let server_name = ServerName::try_from(host)
+                    .map_err(|e| LdapError::Unavailable(format!("invalid host: {}", e)))?;
+                Box::new(
+                    tls_connector(config.ca_file.as_deref())?
+                        .connect(server_name, tcp)
+                        .await?,
+                )
+            }
+            scheme => {
+                return Err(LdapError::Unavailable(format!(
+                    "unsupported scheme {}",
+                    scheme
+                )))
+            }
+        };
+        Ok(Self { stream, last_id: 0 })
+    }
+
+    async fn request(&mut self, op: ProtocolOp) -> Result<i64, LdapError> {
+        self.last_id += 1;
+        write_message(&mut self.stream, &LdapMessage::new(self.last_id, op)).await?;
+        Ok(self.last_id)
+    }
+
+    async fn response(&mut self, id: i64) -> Result<ProtocolOp, LdapError> {
+        loop {
+            match read_message(&mut self.stream).await? {
+                Some(message) if message.id == id => return Ok(message.op),
+                // unsolicited notifications, such as the server going away, have no ID
+                Some(message) if message.id == 0 => {
+                    return Err(LdapError::Unavailable(format!(
+                        "the directory sent {:?}",
+                        message.op
+                    )))
+                }
+                Some(_) => continue,
+                None => {
+                    return Err(LdapError::Unavailable(
+                        "the directory closed the connection".to_string(),
+                    ))
+                }
+            }
+        }
+    }
+
+    async fn bind(&mut self, dn: &str, password: &str) -> Result<(), LdapError> {
+        let id = self
+            .request(ProtocolOp::BindRequest {
+                name: dn.to_string(),
+                password: password.to_string(),
+            })
+            .await?;
+        match self.response(id).await? {
+            ProtocolOp::BindResponse(result) if result.code == SUCCESS => Ok(()),
+            ProtocolOp::BindResponse(result) if result.code == INVALID_CREDENTIALS => {
+                Err(LdapError::InvalidCredentials)
+            }
+            ProtocolOp::BindResponse(result) => Err(LdapError::Unavailable(format!(
+                "bind failed with {}: {}",
+                result.code, result.message
+            ))),
+            op => Err(LdapError::Unavailable(format!("unexpected {:?}", op))),
+        }
+    }
+
+    /// The DN of each entry whose `attribute` equals `value`, along with its groups.
+    async fn search(
+        &mut self,
+        base: &str,
+        attribute: &str,
+        value: &str,
+        group_attribute: &str,
+    ) -> Result<Vec<(String, Vec<String>)>, LdapError> {
+        let id = self
+            .request(ProtocolOp::SearchRequest {
+                base: base.to_string(),
+                attribute: attribute.to_string(),
+                value: value.to_string(),
+                attributes: vec![group_attribute.to_string()],
+                size_limit: 2,
+            })
+            .await?;
+
+        let mut entries = Vec::new();
+        loop {
+            match self.response(id).await? {
+                ProtocolOp::SearchResultEntry { dn, attributes } => {
+                    let groups = attributes
+                        .into_iter()
+                        .filter(|(name, _)| name.eq_ignore_ascii_case(group_attribute))
+                        .flat_map(|(_, values)| values)
+                        .collect();
+                    entries.push((dn, groups));
+                }
+                ProtocolOp::SearchResultReference => {}
+                ProtocolOp::SearchResultDone(result) if result.code == SUCCESS => {
+                    return Ok(entries)
+                }
+                ProtocolOp::SearchResultDone(result) => {
+                    return Err(LdapError::Unavailable(format!(
+                        "search failed with {}: {}",
+                        result.code, result.message
+                    )))
+                }
+                op => return Err(LdapError::Unavailable(format!("unexpected {:?}", op))),
+            }
+        }
+    }
+
+    async fn unbind(mut self) {
+        // the server closes the connection without answering
+        let _ = self.request(ProtocolOp::UnbindRequest).await;
+    }
+}
+
+fn tls_connector(ca_file: Option<&str>) -> Result<TlsConnector, LdapError> {
+    let mut roots = RootCertStore::empty();
+    match ca_file {
+        Some(path) => {
+            let pem = std::fs::read(path)?;
+            for certificate in rustls_pemfile::certs(&mut pem.as_slice())? {
+                roots
+                    .add(&Certificate(certificate))
+                    .map_err(|e| LdapError::Unavailable(format!("invalid CA file: {}", e)))?;
+            }
+        }
+        None => {
+            roots.add_server_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.0.iter().map(|anchor| {
+                OwnedTrustAnchor::from_subject_spki_name_constraints(
+                    anchor.subject,
+                    anchor.spki,
+                    anchor.name_constraints,
+                )
+            }))
+        }
+    }
+    let config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    Ok(TlsConnector::from(Arc::new(config)))
+}
+
+#[cfg(test)]
+mod ldap_test {
+    use super::normalize_dn;
+
+    #[test]
+    fn dns_are_compared_without_case_or_spaces() {
+        assert_eq!(
+            normalize_dn("CN=Admins, OU=Groups,dc=Example, DC=com"),
+            "cn=admins,ou=groups,dc=example,dc=com"
+        );
+    }
+}
@@ -0,0 +1,490 @@
+//! Just enough of LDAPv3 (RFC 4511) to bind and look users up, encoded in BER. Both ends of each
+//! exchange are here, so a stand-in directory can be built from the same messages.
+
+use std::{fmt, io};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Directories have no reason to send anything near this large while signing someone in.
+const MAX_MESSAGE_LENGTH: usize = 1 << 20;
+
+pub const SUCCESS: u32 = 0;
+pub const INVALID_CREDENTIALS: u32 = 49;
+
+mod tag {
+    pub const BOOLEAN: u8 = 0x01;
+    pub const INTEGER: u8 = 0x02;
+    pub const OCTET_STRING: u8 = 0x04;
+    pub const ENUMERATED: u8 = 0x0a;
+    pub const SEQUENCE: u8 = 0x30;
+    pub const SET: u8 = 0x31;
+    pub const BIND_REQUEST: u8 = 0x60;
+    pub const BIND_RESPONSE: u8 = 0x61;
+    pub const UNBIND_REQUEST: u8 = 0x42;
+    pub const SEARCH_REQUEST: u8 = 0x63;
+    pub const SEARCH_RESULT_ENTRY: u8 = 0x64;
+    pub const SEARCH_RESULT_DONE: u8 = 0x65;
+    pub const SEARCH_RESULT_REFERENCE: u8 = 0x73;
+    pub const SIMPLE_AUTHENTICATION: u8 = 0x80;
+    pub const EQUALITY_MATCH: u8 = 0xa3;
+}
+
+/// Searches always cover the whole subtree under their base.
+const WHOLE_SUBTREE: i64 = 2;
+const NEVER_DEREFERENCE_ALIASES: i64 = 0;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LdapMessage {
+    pub id: i64,
+    pub op: ProtocolOp,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProtocolOp {
+    /// A simple bind. Binding with an empty name and password is anonymous.
+    BindRequest {
+        name: String,
+        password: String,
+    },
+    BindResponse(LdapResult),
+    UnbindRequest,
+    /// Looks for entries under `base` whose `attribute` equals `value`.
+    SearchRequest {
+        base: String,
+        attribute: String,
+        value: String,
+        attributes: Vec<String>,
+        size_limit: i64,
+    },
+    SearchResultEntry {
+        dn: String,
+        attributes: Vec<(String, Vec<String>)>,
+    },
+    SearchResultDone(LdapResult),
+    /// Points to another server, which is not followed.
+    SearchResultReference,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LdapResult {
+    pub code: u32,
+    pub message: String,
+}
+
+impl LdapResult {
+    pub fn new(code: u32, message: &str) -> Self {
+        Self {
+            code,
+            message: message.to_string(),
+        }
+    }
+
+    fn encode(&self, tag: u8) -> Vec<u8> {
+        constructed(
+            tag,
+            &[
+                integer(tag::ENUMERATED, self.code.into()),
+                octet_string(""),
+                octet_string(&self.message),
+            ],
+        )
+    }
+
+    fn decode(reader: &mut Reader) -> Result<Self, ProtocolError> {
+        let code = reader.read_integer(tag::ENUMERATED)?;
+        let _matched_dn = reader.read_string()?;
+        Ok(Self {
+            code: u32::try_from(code).map_err(|_| ProtocolError::new("invalid result code"))?,
+            message: reader.read_string()?,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct ProtocolError(pub String);
+
+impl ProtocolError {
+    fn new(reason: &str) -> Self {
+        Self(reason.to_string())
+    }
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Malformed LDAP message: {}", self.0)
+    }
+}
+
+impl std::error::Error for ProtocolError {}
+
+impl From<io::Error> for ProtocolError {
+    fn from(e: io::Error) -> Self {
+        ProtocolError(e.to_string())
+    }
+}
+
+impl LdapMessage {
+    pub fn new(id: i64, op: ProtocolOp) -> Self {
+        Self { id, op }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let op = match &self.op {
+            ProtocolOp::BindRequest { name, password } => constructed(
+                tag::BIND_REQUEST,
+                &[
+                    integer(tag::INTEGER, 3),
+                    octet_string(name),
+                    tlv(tag::SIMPLE_AUTHENTICATION, password.as_bytes()),
+                ],
+            ),
+            ProtocolOp::BindResponse(result) => result.encode(tag::BIND_RESPONSE),
+            ProtocolOp::UnbindRequest => tlv(tag::UNBIND_REQUEST, &[]),
+            ProtocolOp::SearchRequest {
+                base,
+                attribute,
+                value,
+                attributes,
+                size_limit,
+            } => constructed(
+                tag::SEARCH_REQUEST,
+                &[
+                    octet_string(base),
+                    integer(tag::ENUMERATED, WHOLE_SUBTREE),
+                    integer(tag::ENUMERATED, NEVER_DEREFERENCE_ALIASES),
+                    integer(tag::INTEGER, *size_limit),
+                    integer(tag::INTEGER, 0),
+                    tlv(tag::BOOLEAN, &[0]),
+                    constructed(
+                        tag::EQUALITY_MATCH,
+                        &[octet_string(attribute), octet_string(value)],
+                    ),
+                    constructed(
+                        tag::SEQUENCE,
+                        &attributes
+                            .iter()
+                            .map(|attribute| octet_string(attribute))
+                            .collect::<Vec<_>>(),
+                    ),
+                ],
+            ),
+            ProtocolOp::SearchResultEntry { dn, attributes } => constructed(
+                tag::SEARCH_RESULT_ENTRY,
+                &[
+                    octet_string(dn),
+                    constructed(
+                        tag::SEQUENCE,
+                        &attributes
+                            .iter()
+                            .map(|(name, values)| {
+                                constructed(
+                                    tag::SEQUENCE,
+                                    &[
+                                        octet_string(name),
+                                        constructed(
+                                            tag::SET,
+                                            &values
+                                                .iter()
+                                                .map(|value| octet_string(value))
+                                                .collect::<Vec<_>>(),
+                                        ),
+                                    ],
+                                )
+                            })
+                            .collect::<Vec<_>>(),
+                    ),
+                ],
+            ),
+            ProtocolOp::SearchResultDone(result) => result.encode(tag::SEARCH_RESULT_DONE),
+            ProtocolOp::SearchResultReference => tlv(tag::SEARCH_RESULT_REFERENCE, &[]),
+        };
+        constructed(tag::SEQUENCE, &[integer(tag::INTEGER, self.id), op])
+    }
+
+    /// Controls and anything else this client does not ask for are left out.
+    pub fn decode(data: &[u8]) -> Result<Self, ProtocolError> {
+        let mut message = Reader::new(data).read_nested(tag::SEQUENCE)?;
+        let id = message.read_integer(tag::INTEGER)?;
+        let (op_tag, contents) = message.read_any()?;
+        let mut op = Reader::new(contents);
+
+        let op = match op_tag {
+            tag::BIND_REQUEST => {
+                let _version = op.read_integer(tag::INTEGER)?;
+                let name = op.read_string()?;
+                let password = utf8(op.read(tag::SIMPLE_AUTHENTICATION)?)?;
+                ProtocolOp::BindRequest { name, password }
+            }
+            tag::BIND_RESPONSE => ProtocolOp::BindResponse(LdapResult::decode(&mut op)?),
+            tag::UNBIND_REQUEST => ProtocolOp::UnbindRequest,
+            tag::SEARCH_REQUEST => {
+                let base = op.read_string()?;
+                let _scope = op.read_integer(tag::ENUMERATED)?;
+                let _deref_aliases = op.read_integer(tag::ENUMERATED)?;
+                let size_limit = op.read_integer(tag::INTEGER)?;
+                let _time_limit = op.read_integer(tag::INTEGER)?;
+                let _types_only = op.read(tag::BOOLEAN)?;
+                let mut filter = op.read_nested(tag::EQUALITY_MATCH)?;
+                let attribute = filter.read_string()?;
+                let value = filter.read_string()?;
+                let mut list = op.read_nested(tag::SEQUENCE)?;
+                let mut attributes = Vec::new();
+                while !list.is_empty() {
+                    attributes.push(list.read_string()?);
+                }
+                ProtocolOp::SearchRequest {
+                    base,
+                    attribute,
+                    value,
+                    attributes,
+                    size_limit,
+                }
+            }
+            tag::SEARCH_RESULT_ENTRY => {
+                let dn = op.read_string()?;
+                let mut list = op.read_nested(tag::SEQUENCE)?;
+                let mut attributes = Vec::new();
+                while !list.is_empty() {
+                    let mut attribute = list.read_nested(tag::SEQUENCE)?;
+                    let name = attribute.read_string()?;
+                    let mut set = attribute.read_nested(tag::SET)?;
+                    let mut values = Vec::new();
+                    while !set.is_empty() {
+                        values.push(set.read_string()?);
+                    }
+                    attributes.push((name, values));
+                }
+                ProtocolOp::SearchResultEntry { dn, attributes }
+            }
+            tag::SEARCH_RESULT_DONE => ProtocolOp::SearchResultDone(LdapResult::decode(&mut op)?),
+            tag::SEARCH_RESULT_REFERENCE => ProtocolOp::SearchResultReference,
+            other => {
+                return Err(ProtocolError(format!(
+                    "unsupported operation {:#04x}",
+                    other
+                )))
+            }
+        };
+        Ok(Self { id, op })
+    }
+}
+
+/// `None` once the other end has closed the connection.
+pub async fn read_message<R: AsyncRead + Unpin>(
+    reader: &mut R,
+) -> Result<Option<LdapMessage>, ProtocolError> {
+    let mut header = [0u8; 2];
+    match reader.read_exact(&mut header).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+
+    let mut data = header.to_vec();
+    let length = match header[1] {
+        length if length < 0x80 => usize::from(length),
+        0x81..=0x84 => {
+            let mut length_bytes = vec![0u8; usize::from(header[1] & 0x7f)];
+            reader.read_exact(&mut length_bytes).await?;
+            data.extend(&length_bytes);
+            length_bytes
+                .iter()
+                .fold(0, |length, byte| (length << 8) | usize::from(*byte))
+        }
+        _ => return Err(ProtocolError::new("unsupported length")),
+    };
+    if length > MAX_MESSAGE_LENGTH {
+        return Err(ProtocolError::new("the message is too large"));
+    }
+
+    let mut contents = vec![0u8; length];
+    reader.read_exact(&mut contents).await?;
+    data.extend(contents);
+    LdapMessage::decode(&data).map(Some)
+}
+
+pub async fn write_message<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    message: &LdapMessage,
+) -> Result<(), ProtocolError> {
+    writer.write_all(&message.encode()).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+fn tlv(tag: u8, contents: &[u8]) -> Vec<u8> {
+    let mut encoded = vec![tag];
+    match u8::try_from(contents.len()) {
+        Ok(length) if length < 0x80 => encoded.push(length),
+        _ => {
+            let length = contents.len().to_be_bytes();
+            let skip = length.iter().take_while(|byte| **byte == 0).count();
+            encoded.push(0x80 | (length.len() - skip) as u8);
+            encoded.extend(&length[skip..]);
+        }
+    }
+    encoded.extend(contents);
+    encoded
+}
+
+fn constructed(tag: u8, parts: &[Vec<u8>]) -> Vec<u8> {
+    tlv(tag, &parts.concat())
+}
+
+/// In the fewest bytes that keep its sign.
+fn integer(tag: u8, value: i64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let mut start = 0;
+    while start < bytes.len() - 1
+        && ((bytes[start] == 0x00 && bytes[start + 1] & 0x80 == 0)
+            || (bytes[start] == 0xff && bytes[start + 1] & 0x80 != 0))
+    {
+        start += 1;
+    }
+    tlv(tag, &bytes[start..])
+}
+
+fn octet_string(value: &str) -> Vec<u8> {
+    tlv(tag::OCTET_STRING, value.as_bytes())
+}
+
+fn utf8(bytes: &[u8]) -> Result<String, ProtocolError> {
+    String::from_utf8(bytes.to_vec()).map_err(|_| ProtocolError::new("a string is not UTF-8"))
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn read_any(&mut self) -> Result<(u8, &'a [u8]), ProtocolError> {
+        let truncated = || ProtocolError::new("the message is truncated");
+        let (&tag, rest) = self.data.split_first().ok_or_else(truncated)?;
+        let (&first, mut rest) = rest.split_first().ok_or_else(truncated)?;
+        let length = match first {
+            length if length < 0x80 => usize::from(length),
+            0x81..=0x84 => {
+                let count = usize::from(first & 0x7f);
+                if rest.len() < count {
+                    return Err(truncated());
+                }
+                let (length_bytes, after) = rest.split_at(count);
+                rest = after;
+                length_bytes
+                    .iter()
+                    .fold(0, |length, byte| (length << 8) | usize::from(*byte))
+            }
+            _ => return Err(ProtocolError::new("unsupported length")),
+        };
+        if rest.len() < length {
+            return Err(truncated());
+        }
+        let (contents, rest) = rest.split_at(length);
+        self.data = rest;
+        Ok((tag, contents))
+    }
+
+    fn read(&mut self, expected: u8) -> Result<&'a [u8], ProtocolError> {
+        match self.read_any()? {
+            (tag, contents) if tag == expected => Ok(contents),
+            (tag, _) => Err(ProtocolError(format!(
+                "expected {:#04x}, found {:#04x}",
+                expected, tag
+            ))),
+        }
+    }
+
+    fn read_nested(&mut self, tag: u8) -> Result<Reader<'a>, ProtocolError> {
+        self.read(tag).map(Reader::new)
+    }
+
+    fn read_integer(&mut self, tag: u8) -> Result<i64, ProtocolError> {
+        let contents = self.read(tag)?;
+        if contents.is_empty() || contents.len() > 8 {
+            return Err(ProtocolError::new("invalid integer"));
+        }
+        let sign = if contents[0] & 0x80 != 0 { -1 } else { 0 };
+        Ok(contents
+            .iter()
+            .fold(sign, |value, byte| (value << 8) | i64::from(*byte)))
+    }
+
+    fn read_string(&mut self) -> Result<String, ProtocolError> {
+        self.read(tag::OCTET_STRING).and_then(utf8)
+    }
+}
+
+#[cfg(test)]
+mod protocol_test {
+    use super::{LdapMessage, LdapResult, ProtocolOp, INVALID_CREDENTIALS};
+
+    fn round_trip(op: ProtocolOp) {
+        let message = LdapMessage::new(7, op);
+        assert_eq!(LdapMessage::decode(&message.encode()).unwrap(), message);
+    }
+
+    #[test]
+    fn anonymous_bind_matches_the_wire_format() {
+        let message = LdapMessage::new(
+            1,
+            ProtocolOp::BindRequest {
+                name: String::new(),
+                password: String::new(),
+            },
+        );
+        assert_eq!(
+            message.encode(),
+            [0x30, 0x0c, 0x02, 0x01, 0x01, 0x60, 0x07, 0x02, 0x01, 0x03, 0x04, 0x00, 0x80, 0x00]
+        );
+    }
+
+    #[test]
+    fn messages_survive_a_round_trip() {
+        round_trip(ProtocolOp::BindRequest {
+            name: "uid=alice,ou=people,dc=example,dc=com".to_string(),
+            password: "secret".to_string(),
+        });
+        round_trip(ProtocolOp::BindResponse(LdapResult::new(
+            INVALID_CREDENTIALS,
+            "invalid credentials",
+        )));
+        round_trip(ProtocolOp::UnbindRequest);
+        round_trip(ProtocolOp::SearchRequest {
+            base: "dc=example,dc=com".to_string(),
+            attribute: "uid".to_string(),
+            value: "alice".to_string(),
+            attributes: vec!["memberOf".to_string()],
+            size_limit: 2,
+        });
+        round_trip(ProtocolOp::SearchResultEntry {
+            dn: "uid=alice,ou=people,dc=example,dc=com".to_string(),
+            attributes: vec![(
+                "memberOf".to_string(),
+                vec!["x".repeat(300), "cn=admins".to_string()],
+            )],
+        });
+        round_trip(ProtocolOp::SearchResultDone(LdapResult::new(0, "")));
+    }
+
+    #[test]
+    fn integers_keep_their_sign() {
+        for id in [0, 127, 128, 255, 256, -1, -128, -129, i64::MAX, i64::MIN] {
+            let message = LdapMessage::new(id, ProtocolOp::UnbindRequest);
+            assert_eq!(LdapMessage::decode(&message.encode()).unwrap().id, id);
+        }
+    }
+
+    #[test]
+    fn truncated_messages_are_rejected() {
+        let encoded = LdapMessage::new(1, ProtocolOp::UnbindRequest).encode();
+        assert!(LdapMessage::decode(&encoded[..encoded.len() - 1]).is_err());
+        assert!(LdapMessage::decode(&[0x30, 0x80]).is_err());
+    }
+}
@@ -1,5 +1,22 @@
 pub mod application;
+pub mod curl;
+pub mod diff;
+pub mod har;
 pub mod helpers;
+pub mod importer;
+pub mod insomnia;
+pub mod ldap;
 pub mod models;
 pub mod observability;
+pub mod oidc;
+pub mod openapi;
+pub mod password;
+pub mod postman;
+pub mod rate_limit;
+pub mod redaction;
 pub mod routes;
+pub mod runner;
+pub mod secrets;
+pub mod snippet;
+pub mod ssrf;
+pub mod template;
@@ -1,13 +1,24 @@
 use backend::{
     application::{Application, ApplicationPoolSettings},
     observability::{get_subscriber, init_subscriber},
+    secrets::{rotate_secret_key, SecretCipher},
 };
+use sqlx::SqlitePool;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let subscriber = get_subscriber("backend", "info", std::io::stdout);
     init_subscriber(subscriber);
 
+    // `backend rotate-secret-key` re-encrypts secret variables with the current SECRET_KEY
+    if std::env::args().nth(1).as_deref() == Some("rotate-secret-key") {
+        let cipher = SecretCipher::from_env("secret.key")?;
+        let pool = SqlitePool::connect("sqlite:data.db").await?;
+        let rotated = rotate_secret_key(&cipher, &pool).await?;
+        tracing::info!("Re-encrypted {} secret variables", rotated);
+        return Ok(());
+    }
+
     // TODO: turn sqlite address into enum
     let application = Application::build(8080, ApplicationPoolSettings::Standard).await?;
     application.run_until_stopped().await?;
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Deserialize, Serialize)]
 pub struct Status {
@@ -15,6 +16,27 @@ pub struct CurlGroup {
     pub project_id: i64,
 }
 
+/// A curl entry in the shape the frontend embeds within `CurlGroup::curls`.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Curl {
+    #[serde(default)]
+    pub id: i64,
+    pub name: String,
+    pub description: String,
+    #[serde(rename = "rawQuery")]
+    pub raw_query: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct CurlEntry {
+    pub id: i64,
+    pub group_id: i64,
+    pub position: i64,
+    pub name: String,
+    pub description: String,
+    pub raw_query: String,
+}
+
 #[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
 pub struct ProjectInfo {
     pub id: i64,
@@ -22,6 +44,12 @@ pub struct ProjectInfo {
     pub description: String,
     pub name: String,
     pub visibility: String,
+    /// `Redact` or `Reject`; left unchanged on update when omitted.
+    #[serde(default)]
+    pub redaction_policy: Option<String>,
+    /// Left unchanged on update when omitted.
+    #[serde(default)]
+    pub collaborators_can_delete_groups: Option<bool>,
 }
 
 #[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
@@ -32,6 +60,17 @@ pub struct Project {
     pub groups: Vec<CurlGroup>,
 }
 
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Environment {
+    pub id: i64,
+    pub project_id: i64,
+    pub name: String,
+    pub variables: BTreeMap<String, String>,
+    /// Names of the secret variables, whose values are never sent out.
+    #[serde(default)]
+    pub secrets: Vec<String>,
+}
+
 #[allow(dead_code)]
 pub struct ProjectCollaborator {
     pub project_id: i64,
@@ -0,0 +1,716 @@
+//! Generating curl groups from OpenAPI 3.0 and 3.1 documents. Each tag becomes a group with one
+//! entry per operation, and each server becomes an environment setting `base_url`.
+
+use crate::{
+    curl::{self, percent_encode, BasicAuth, CurlRequest, FormPart, NameValue, RequestBody},
+    importer::{ImportError, Imported, ImportedEntry, ImportedEnvironment, ImportedGroup},
+};
+
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// How deep example bodies are generated, which also stops at recursive schemas.
+const MAX_SCHEMA_DEPTH: usize = 8;
+
+/// Following more references than this means they form a cycle.
+const MAX_REFERENCES: usize = 32;
+
+/// Reads a JSON or YAML document, checking that it is OpenAPI 3.
+pub fn parse_document(text: &str) -> Result<Value, ImportError> {
+    let document: Value = if text.trim_start().starts_with('{') {
+        serde_json::from_str(text).map_err(|e| ImportError(format!("Invalid JSON: {}", e)))?
+    } else {
+        serde_yaml::from_str(text).map_err(|e| ImportError(format!("Invalid YAML: {}", e)))?
+    };
+
+    let version = document
+        .get("openapi")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    if !version.starts_with("3.0") && !version.starts_with("3.1") {
+        return Err(ImportError(
+            "Only OpenAPI 3.0 and 3.1 documents can be imported".to_string(),
+        ));
+    }
+    Ok(document)
+}
+
+/// Groups are identified by the document's title and their tag, entries by their operation's
+/// method and path, so a later version of the document updates what an earlier one generated.
+pub fn import_document(document: &Value) -> Imported {
+    let title = document
+        .pointer("/info/title")
+        .and_then(Value::as_str)
+        .unwrap_or("API");
+
+    // groups follow the order of the document's tags, then the order tags are first used in
+    let mut groups: Vec<ImportedGroup> = document
+        .get("tags")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|tag| {
+            let name = tag.get("name").and_then(Value::as_str)?;
+            Some(group(title, name, text(tag, "description")))
+        })
+        .collect();
+
+    let paths = document.get("paths").and_then(Value::as_object);
+    for (path, path_item) in paths.into_iter().flatten() {
+        let path_item = resolve(path_item, document);
+        for method in METHODS {
+            let Some(operation) = path_item.get(method) else {
+                continue;
+            };
+            let tag = operation
+                .pointer("/tags/0")
+                .and_then(Value::as_str)
+                .unwrap_or(title);
+            let entry = import_operation(document, path, method, path_item, operation);
+
+            match groups.iter_mut().find(|group| group.name == tag) {
+                Some(group) => group.entries.push(entry),
+                None => {
+                    let mut group = group(title, tag, String::new());
+                    group.entries.push(entry);
+                    groups.push(group);
+                }
+            }
+        }
+    }
+    groups.retain(|group| !group.entries.is_empty());
+
+    Imported {
+        groups,
+        environments: import_servers(document),
+        skipped: vec![],
+    }
+}
+
+fn group(title: &str, tag: &str, description: String) -> ImportedGroup {
+    ImportedGroup {
+        name: tag.to_string(),
+        description,
+        entries: vec![],
+        source: Some(format!("{} / {}", title, tag)),
+    }
+}
+
+fn import_operation(
+    document: &Value,
+    path: &str,
+    method: &str,
+    path_item: &Value,
+    operation: &Value,
+) -> ImportedEntry {
+    let mut request = CurlRequest {
+        method: method.to_uppercase(),
+        ..Default::default()
+    };
+
+    let mut url = path.to_string();
+    let mut query = vec![];
+    for parameter in parameters(document, path_item, operation) {
+        let name = text(parameter, "name");
+        let value = parameter_value(document, parameter);
+        match parameter.get("in").and_then(Value::as_str) {
+            Some("path") => url = url.replace(&format!("{{{}}}", name), &value),
+            Some("query") => {
+                let required = parameter.get("required") == Some(&Value::Bool(true));
+                if required || example(document, parameter).is_some() {
+                    query.push(format!("{}={}", percent_encode(&name), value));
+                }
+            }
+            Some("header") => request.headers.push(NameValue::new(name, value)),
+            Some("cookie") => request.cookies.push(NameValue::new(name, value)),
+            _ => {}
+        }
+    }
+
+    apply_security(document, operation, &mut request, &mut query);
+
+    if let Some(request_body) = operation.get("requestBody") {
+        let request_body = resolve(request_body, document);
+        if let Some((media_type, body)) = import_body(document, request_body) {
+            if !matches!(body, RequestBody::Multipart { .. }) {
+                request
+                    .headers
+                    .push(NameValue::new("Content-Type", media_type));
+            }
+            request.body = Some(body);
+        }
+    }
+
+    request.url = format!("{{{{base_url}}}}{}", url);
+    if !query.is_empty() {
+        request.url = format!("{}?{}", request.url, query.join("&"));
+    }
+
+    let operation_key = format!("{} {}", request.method, path);
+    let name = [text(operation, "summary"), text(operation, "operationId")]
+        .into_iter()
+        .find(|name| !name.trim().is_empty())
+        .unwrap_or_else(|| operation_key.clone());
+
+    ImportedEntry {
+        name,
+        description: text(operation, "description"),
+        raw_query: curl::to_command(&request),
+        source: Some(operation_key),
+    }
+}
+
+/// Path level parameters apply to every operation, which may override them.
+fn parameters<'a>(
+    document: &'a Value,
+    path_item: &'a Value,
+    operation: &'a Value,
+) -> Vec<&'a Value> {
+    let list = |item: &'a Value| -> Vec<&'a Value> {
+        item.get("parameters")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .map(|parameter| resolve(parameter, document))
+            .collect()
+    };
+
+    let operation_parameters = list(operation);
+    let mut parameters: Vec<&Value> = list(path_item)
+        .into_iter()
+        .filter(|parameter| {
+            !operation_parameters.iter().any(|overriding| {
+                overriding.get("name") == parameter.get("name")
+                    && overriding.get("in") == parameter.get("in")
+            })
+        })
+        .collect();
+    parameters.extend(operation_parameters);
+    parameters
+}
+
+/// The parameter's example, or a placeholder named after it.
+fn parameter_value(document: &Value, parameter: &Value) -> String {
+    match example(document, parameter) {
+        Some(Value::String(value)) => percent_encode(&value),
+        Some(value) => percent_encode(&value.to_string()),
+        None => placeholder(&text(parameter, "name")),
+    }
+}
+
+fn example(document: &Value, parameter: &Value) -> Option<Value> {
+    if let Some(example) = parameter.get("example") {
+        return Some(example.clone());
+    }
+    if let Some(example) = first_example(document, parameter) {
+        return Some(example);
+    }
+    let schema = resolve(parameter.get("schema")?, document);
+    ["example", "default"]
+        .iter()
+        .find_map(|key| schema.get(*key).cloned())
+        .or_else(|| schema.pointer("/enum/0").cloned())
+}
+
+/// The value of the first entry of an `examples` map.
+fn first_example(document: &Value, item: &Value) -> Option<Value> {
+    let (_, example) = item.get("examples")?.as_object()?.iter().next()?;
+    resolve(example, document).get("value").cloned()
+}
+
+/// Uses the first security requirement of the operation, or of the document.
+fn apply_security(
+    document: &Value,
+    operation: &Value,
+    request: &mut CurlRequest,
+    query: &mut Vec<String>,
+) {
+    let requirement = operation
+        .get("security")
+        .or_else(|| document.get("security"))
+        .and_then(|security| security.get(0))
+        .and_then(Value::as_object);
+
+    for scheme_name in requirement.into_iter().flat_map(Map::keys) {
+        let Some(scheme) = document
+            .pointer(&format!(
+                "/components/securitySchemes/{}",
+                escape_pointer(scheme_name)
+            ))
+            .map(|scheme| resolve(scheme, document))
+        else {
+            continue;
+        };
+        let name = text(scheme, "name");
+        match (
+            scheme.get("type").and_then(Value::as_str),
+            text(scheme, "scheme").to_lowercase().as_str(),
+            scheme.get("in").and_then(Value::as_str),
+        ) {
+            (Some("http"), "basic", _) => {
+                request.basic_auth = Some(BasicAuth {
+                    username: placeholder("username"),
+                    password: Some(placeholder("password")),
+                })
+            }
+            (Some("http"), _, _) | (Some("oauth2"), _, _) | (Some("openIdConnect"), _, _) => {
+                request.headers.push(NameValue::new(
+                    "Authorization",
+                    format!("Bearer {}", placeholder("token")),
+                ))
+            }
+            (Some("apiKey"), _, Some("header")) => request
+                .headers
+                .push(NameValue::new(&name, placeholder(&name))),
+            (Some("apiKey"), _, Some("query")) => {
+                query.push(format!("{}={}", percent_encode(&name), placeholder(&name)))
+            }
+            (Some("apiKey"), _, Some("cookie")) => request
+                .cookies
+                .push(NameValue::new(&name, placeholder(&name))),
+            _ => {}
+        }
+    }
+}
+
+/// Prefers JSON, then forms, then whatever the operation accepts first.
+fn import_body(document: &Value, request_body: &Value) -> Option<(String, RequestBody)> {
+    let content = request_body.get("content")?.as_object()?;
+    let media_type = content
+        .keys()
+        .find(|media_type| is_json(media_type))
+        .or_else(|| {
+            content
+                .keys()
+                .find(|media_type| media_type.as_str() == "application/x-www-form-urlencoded")
+        })
+        .or_else(|| {
+            content
+                .keys()
+                .find(|media_type| media_type.as_str() == "multipart/form-data")
+        })
+        .or_else(|| content.keys().next())?;
+    let media = &content[media_type];
+    let schema = media.get("schema").map(|schema| resolve(schema, document));
+
+    let example = media
+        .get("example")
+        .cloned()
+        .or_else(|| first_example(document, media))
+        .or_else(|| schema.map(|schema| example_from_schema(document, schema, 0)));
+
+    let body = match (media_type.as_str(), example) {
+        (media_type, Some(example)) if is_json(media_type) => RequestBody::Raw {
+            content: serde_json::to_string_pretty(&example).ok()?,
+        },
+        ("application/x-www-form-urlencoded", Some(Value::Object(fields))) => RequestBody::Raw {
+            content: fields
+                .iter()
+                .map(|(name, value)| {
+                    format!(
+                        "{}={}",
+                        percent_encode(name),
+                        percent_encode(&scalar(value))
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("&"),
+        },
+        ("multipart/form-data", Some(Value::Object(fields))) => RequestBody::Multipart {
+            parts: fields
+                .iter()
+                .map(|(name, value)| {
+                    let is_file = schema
+                        .and_then(|schema| {
+                            schema.pointer(&format!("/properties/{}", escape_pointer(name)))
+                        })
+                        .map(|property| {
+                            let format = resolve(property, document).get("format");
+                            format == Some(&Value::from("binary"))
+                                || format == Some(&Value::from("base64"))
+                        })
+                        .unwrap_or(false);
+                    FormPart {
+                        name: name.clone(),
+                        value: if is_file {
+                            placeholder(name)
+                        } else {
+                            scalar(value)
+                        },
+                        is_file,
+                        content_type: None,
+                        filename: None,
+                    }
+                })
+                .collect(),
+        },
+        (_, Some(Value::String(text))) => RequestBody::Raw { content: text },
+        _ => RequestBody::Raw {
+            content: placeholder("body"),
+        },
+    };
+    Some((media_type.clone(), body))
+}
+
+fn is_json(media_type: &str) -> bool {
+    media_type == "application/json" || media_type.ends_with("+json")
+}
+
+/// Builds an example value from the examples, defaults and types given in the schema.
+fn example_from_schema(document: &Value, schema: &Value, depth: usize) -> Value {
+    let schema = resolve(schema, document);
+    if depth > MAX_SCHEMA_DEPTH {
+        return Value::Null;
+    }
+    if let Some(example) = ["example", "default"]
+        .iter()
+        .find_map(|key| schema.get(*key))
+        .or_else(|| schema.pointer("/examples/0"))
+        .or_else(|| schema.pointer("/enum/0"))
+    {
+        return example.clone();
+    }
+
+    if let Some(all_of) = schema.get("allOf").and_then(Value::as_array) {
+        let mut merged = Map::new();
+        for part in all_of {
+            if let Value::Object(fields) = example_from_schema(document, part, depth + 1) {
+                merged.extend(fields);
+            }
+        }
+        return Value::Object(merged);
+    }
+    if let Some(first) = ["oneOf", "anyOf"]
+        .iter()
+        .find_map(|key| schema.get(*key).and_then(|options| options.get(0)))
+    {
+        return example_from_schema(document, first, depth + 1);
+    }
+
+    // 3.1 allows a list of types, such as `[string, "null"]`
+    let kind = match schema.get("type") {
+        Some(Value::String(kind)) => Some(kind.as_str()),
+        Some(Value::Array(kinds)) => kinds
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|kind| *kind != "null"),
+        _ => None,
+    };
+    match kind {
+        Some("object") | None if schema.get("properties").is_some() => Value::Object(
+            schema
+                .get("properties")
+                .and_then(Value::as_object)
+                .into_iter()
+                .flatten()
+                .map(|(name, property)| {
+                    (
+                        name.clone(),
+                        example_from_schema(document, property, depth + 1),
+                    )
+                })
+                .collect(),
+        ),
+        Some("object") => Value::Object(Map::new()),
+        Some("array") => match schema.get("items") {
+            Some(items) => Value::Array(vec![example_from_schema(document, items, depth + 1)]),
+            None => Value::Array(vec![]),
+        },
+        Some("string") => Value::from(match schema.get("format").and_then(Value::as_str) {
+            Some("date-time") => "2024-01-01T00:00:00Z",
+            Some("date") => "2024-01-01",
+            Some("email") => "user@example.com",
+            Some("uuid") => "00000000-0000-0000-0000-000000000000",
+            Some("uri") | Some("url") => "https://example.com",
+            _ => "string",
+        }),
+        Some("integer") | Some("number") => Value::from(0),
+        Some("boolean") => Value::Bool(false),
+        _ => Value::Null,
+    }
+}
+
+/// Every server becomes an environment, with its variables set to their defaults.
+fn import_servers(document: &Value) -> Vec<ImportedEnvironment> {
+    let mut environments: Vec<ImportedEnvironment> = vec![];
+    for server in document
+        .get("servers")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        let mut url = text(server, "url");
+        let variables = server.get("variables").and_then(Value::as_object);
+        for (name, variable) in variables.into_iter().flatten() {
+            url = url.replace(&format!("{{{}}}", name), &text(variable, "default"));
+        }
+        if url.is_empty() {
+            continue;
+        }
+
+        let description = text(server, "description");
+        let name = if description.trim().is_empty() {
+            url.clone()
+        } else {
+            description
+        };
+        if environments
+            .iter()
+            .any(|environment| environment.name == name)
+        {
+            continue;
+        }
+        environments.push(ImportedEnvironment {
+            name,
+            variables: BTreeMap::from([("base_url".to_string(), url)]),
+            secrets: BTreeMap::new(),
+        });
+    }
+    environments
+}
+
+/// Follows `$ref`s within the document. References to other files are left unresolved.
+fn resolve<'a>(mut value: &'a Value, document: &'a Value) -> &'a Value {
+    for _ in 0..MAX_REFERENCES {
+        let Some(reference) = value.get("$ref").and_then(Value::as_str) else {
+            return value;
+        };
+        let Some(target) = reference
+            .strip_prefix('#')
+            .and_then(|pointer| document.pointer(pointer))
+        else {
+            return value;
+        };
+        value = target;
+    }
+    value
+}
+
+fn escape_pointer(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+fn text(value: &Value, key: &str) -> String {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// A `{{placeholder}}` for a variable named after `name`, which may hold characters that are not
+/// allowed in variable names.
+fn placeholder(name: &str) -> String {
+    let mut variable: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if variable.is_empty() || variable.starts_with(|c: char| c.is_ascii_digit()) {
+        variable.insert(0, '_');
+    }
+    format!("{{{{{}}}}}", variable)
+}
+
+#[cfg(test)]
+mod openapi_test {
+    use super::{example_from_schema, import_document, parse_document, placeholder};
+    use crate::curl::{parse, NameValue, RequestBody};
+    use serde_json::json;
+
+    const PETSTORE: &str = r#"
+openapi: 3.0.3
+info:
+  title: Petstore
+  version: 1.0.0
+servers:
+  - url: https://{region}.petstore.example.com/v1
+    description: Production
+    variables:
+      region:
+        default: eu
+  - url: http://localhost:8080/v1
+security:
+  - bearerAuth: []
+tags:
+  - name: pets
+    description: Everything about pets
+paths:
+  /pets:
+    get:
+      tags: [pets]
+      summary: List pets
+      parameters:
+        - name: limit
+          in: query
+          schema: { type: integer, example: 20 }
+        - name: cursor
+          in: query
+          schema: { type: string }
+    post:
+      tags: [pets]
+      operationId: createPet
+      requestBody:
+        content:
+          application/json:
+            schema: { $ref: '#/components/schemas/Pet' }
+  /pets/{petId}:
+    parameters:
+      - $ref: '#/components/parameters/PetId'
+    get:
+      tags: [pets]
+      summary: Show pet
+      security:
+        - apiKey: []
+  /health:
+    get:
+      security: []
+components:
+  parameters:
+    PetId:
+      name: petId
+      in: path
+      required: true
+      schema: { type: string }
+  securitySchemes:
+    bearerAuth: { type: http, scheme: bearer }
+    apiKey: { type: apiKey, in: header, name: X-API-Key }
+  schemas:
+    Pet:
+      type: object
+      required: [name]
+      properties:
+        name: { type: string, example: Rex }
+        tags: { type: array, items: { type: string } }
+        owner: { $ref: '#/components/schemas/Owner' }
+    Owner:
+      type: object
+      properties:
+        email: { type: string, format: email }
+        pets: { type: array, items: { $ref: '#/components/schemas/Pet' } }
+"#;
+
+    #[test]
+    fn parses_json_and_yaml_documents() {
+        assert!(parse_document(PETSTORE).is_ok());
+        assert!(parse_document(r#"{"openapi": "3.1.0", "info": {"title": "A"}}"#).is_ok());
+        assert!(parse_document(r#"{"swagger": "2.0"}"#).is_err());
+        assert!(parse_document("openapi: [3.0").is_err());
+    }
+
+    #[test]
+    fn generates_a_group_per_tag_and_an_entry_per_operation() {
+        let imported = import_document(&parse_document(PETSTORE).unwrap());
+
+        let groups: Vec<(&str, Option<&str>)> = imported
+            .groups
+            .iter()
+            .map(|group| (group.name.as_str(), group.source.as_deref()))
+            .collect();
+        assert_eq!(
+            groups,
+            vec![
+                ("pets", Some("Petstore / pets")),
+                ("Petstore", Some("Petstore / Petstore"))
+            ]
+        );
+        let pets = &imported.groups[0];
+        assert_eq!(pets.description, "Everything about pets");
+        let entries: Vec<(&str, Option<&str>)> = pets
+            .entries
+            .iter()
+            .map(|entry| (entry.name.as_str(), entry.source.as_deref()))
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                ("List pets", Some("GET /pets")),
+                ("createPet", Some("POST /pets")),
+                ("Show pet", Some("GET /pets/{petId}"))
+            ]
+        );
+
+        let list = parse(&pets.entries[0].raw_query).unwrap();
+        assert_eq!(list.url, "{{base_url}}/pets?limit=20");
+        assert_eq!(
+            list.headers,
+            vec![NameValue::new("Authorization", "Bearer {{token}}")]
+        );
+
+        let show = parse(&pets.entries[2].raw_query).unwrap();
+        assert_eq!(show.url, "{{base_url}}/pets/{{petId}}");
+        assert_eq!(
+            show.headers,
+            vec![NameValue::new("X-API-Key", "{{X_API_Key}}")]
+        );
+
+        let health = parse(&imported.groups[1].entries[0].raw_query).unwrap();
+        assert!(health.headers.is_empty());
+        assert_eq!(imported.groups[1].entries[0].name, "GET /health");
+    }
+
+    #[test]
+    fn builds_request_bodies_from_schemas() {
+        let imported = import_document(&parse_document(PETSTORE).unwrap());
+        let create = parse(&imported.groups[0].entries[1].raw_query).unwrap();
+        assert_eq!(create.header("content-type"), Some("application/json"));
+        let Some(RequestBody::Raw { content }) = create.body else {
+            panic!("Expected a raw body");
+        };
+        let body: serde_json::Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(body["name"], "Rex");
+        assert_eq!(body["tags"], json!(["string"]));
+        assert_eq!(body["owner"]["email"], "user@example.com");
+    }
+
+    #[test]
+    fn stops_at_recursive_schemas() {
+        let document = json!({
+            "components": {"schemas": {"Node": {
+                "type": "object",
+                "properties": {"child": {"$ref": "#/components/schemas/Node"}}
+            }}}
+        });
+        let example =
+            example_from_schema(&document, &json!({"$ref": "#/components/schemas/Node"}), 0);
+        assert!(example.pointer("/child/child/child").is_some());
+    }
+
+    #[test]
+    fn maps_servers_onto_environments() {
+        let imported = import_document(&parse_document(PETSTORE).unwrap());
+        let environments: Vec<(&str, &str)> = imported
+            .environments
+            .iter()
+            .map(|environment| {
+                (
+                    environment.name.as_str(),
+                    environment.variables["base_url"].as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            environments,
+            vec![
+                ("Production", "https://eu.petstore.example.com/v1"),
+                ("http://localhost:8080/v1", "http://localhost:8080/v1")
+            ]
+        );
+    }
+
+    #[test]
+    fn names_placeholders_after_valid_variables() {
+        assert_eq!(placeholder("X-API-Key"), "{{X_API_Key}}");
+        assert_eq!(placeholder("2fa"), "{{_2fa}}");
+    }
+}
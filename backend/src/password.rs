@@ -0,0 +1,237 @@
+//! What passwords have to look like before they are accepted.
+
+use sha1::{Digest, Sha1};
+use std::{
+    env,
+    fs::File,
+    io::{self, BufRead, BufReader, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    /// A local copy of the Pwned Passwords list, sorted by hash, that passwords must not be on.
+    pub breached_list: Option<PathBuf>,
+}
+
+/// At least 8 characters with a capital letter, as the user stories ask for.
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            require_uppercase: true,
+            require_lowercase: false,
+            require_digit: false,
+            require_symbol: false,
+            breached_list: None,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// `PASSWORD_MIN_LENGTH`, `PASSWORD_REQUIRE_UPPERCASE`, `PASSWORD_REQUIRE_LOWERCASE`,
+    /// `PASSWORD_REQUIRE_DIGIT`, `PASSWORD_REQUIRE_SYMBOL` and `PASSWORD_BREACHED_LIST` override
+    /// the defaults.
+    pub fn from_env() -> Self {
+        let mut policy = Self::default();
+        if let Some(length) = env::var("PASSWORD_MIN_LENGTH")
+            .ok()
+            .and_then(|length| length.parse().ok())
+        {
+            policy.min_length = length;
+        }
+        let flag = |name: &str, default: bool| {
+            env::var(name)
+                .ok()
+                .and_then(|flag| flag.parse().ok())
+                .unwrap_or(default)
+        };
+        policy.require_uppercase = flag("PASSWORD_REQUIRE_UPPERCASE", policy.require_uppercase);
+        policy.require_lowercase = flag("PASSWORD_REQUIRE_LOWERCASE", policy.require_lowercase);
+        policy.require_digit = flag("PASSWORD_REQUIRE_DIGIT", policy.require_digit);
+        policy.require_symbol = flag("PASSWORD_REQUIRE_SYMBOL", policy.require_symbol);
+        policy.breached_list = env::var("PASSWORD_BREACHED_LIST").ok().map(PathBuf::from);
+        policy
+    }
+
+    /// Everything wrong with the password, which is fine when there is nothing.
+    pub fn check(&self, password: &str) -> io::Result<Vec<String>> {
+        let mut problems = vec![];
+        if password.chars().count() < self.min_length {
+            problems.push(format!(
+                "Password must be at least {} characters long.",
+                self.min_length
+            ));
+        }
+        let has = |is_class: fn(char) -> bool| password.chars().any(is_class);
+        let classes = [
+            (
+                self.require_uppercase,
+                has(char::is_uppercase),
+                "an uppercase letter",
+            ),
+            (
+                self.require_lowercase,
+                has(char::is_lowercase),
+                "a lowercase letter",
+            ),
+            (self.require_digit, has(|c| c.is_ascii_digit()), "a digit"),
+            (self.require_symbol, has(is_symbol), "a symbol"),
+        ];
+        for (required, present, description) in classes {
+            if required && !present {
+                problems.push(format!("Password must contain {}.", description));
+            }
+        }
+        if let Some(list) = &self.breached_list {
+            if is_breached(list, password)? {
+                problems.push("Password has appeared in a data breach.".to_string());
+            }
+        }
+        Ok(problems)
+    }
+}
+
+fn is_symbol(c: char) -> bool {
+    !c.is_alphanumeric() && !c.is_whitespace()
+}
+
+/// Each line of the list is the uppercase SHA-1 of a password, a colon and how often it was seen.
+/// As with the Pwned Passwords range API, only the range of hashes sharing the first five
+/// characters is looked up, and the rest of the hash is compared within it.
+fn is_breached(list: &Path, password: &str) -> io::Result<bool> {
+    let hash = format!("{:X}", Sha1::digest(password.as_bytes()));
+    let (prefix, suffix) = hash.split_at(5);
+    Ok(hash_range(list, prefix)?
+        .iter()
+        .any(|candidate| candidate == suffix))
+}
+
+/// The suffixes of the hashes starting with `prefix`, found with a binary search over the file so
+/// that the full list does not have to be read.
+fn hash_range(list: &Path, prefix: &str) -> io::Result<Vec<String>> {
+    let mut reader = BufReader::new(File::open(list)?);
+    let key = |line: &str| line.get(..prefix.len()).map(str::to_ascii_uppercase);
+
+    let (mut low, mut high) = (0, reader.get_ref().metadata()?.len());
+    while low < high {
+        let middle = low + (high - low) / 2;
+        match line_from(&mut reader, middle)? {
+            Some(line) if key(&line).is_some_and(|key| key.as_str() < prefix) => low = middle + 1,
+            _ => high = middle,
+        }
+    }
+
+    let mut suffixes = vec![];
+    let mut next = line_from(&mut reader, low)?;
+    while let Some(line) = next.filter(|line| key(line).as_deref() == Some(prefix)) {
+        let hash = line.split(':').next().unwrap_or_default().trim();
+        suffixes.push(hash[prefix.len()..].to_ascii_uppercase());
+        let mut following = String::new();
+        next = match reader.read_line(&mut following)? {
+            0 => None,
+            _ => Some(following),
+        };
+    }
+    Ok(suffixes)
+}
+
+/// The first line starting at or after `position`.
+fn line_from(reader: &mut BufReader<File>, position: u64) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    if position > 0 {
+        reader.seek(SeekFrom::Start(position - 1))?;
+        // the rest of the line `position` may be in the middle of
+        reader.read_line(&mut line)?;
+        line.clear();
+    } else {
+        reader.rewind()?;
+    }
+    match reader.read_line(&mut line)? {
+        0 => Ok(None),
+        _ => Ok(Some(line)),
+    }
+}
+
+#[cfg(test)]
+mod password_test {
+    use super::PasswordPolicy;
+    use sha1::{Digest, Sha1};
+    use std::{env, fs, path::PathBuf};
+
+    fn breached_list(name: &str, passwords: &[&str]) -> PathBuf {
+        let mut lines: Vec<String> = passwords
+            .iter()
+            .map(|password| format!("{:X}:12", Sha1::digest(password.as_bytes())))
+            .collect();
+        lines.extend((0..200).map(|n| format!("{:X}:1", Sha1::digest(format!("filler-{}", n)))));
+        lines.sort();
+        let path = env::temp_dir().join(format!("{}-{}.txt", name, std::process::id()));
+        fs::write(&path, lines.join("\r\n")).unwrap();
+        path
+    }
+
+    #[test]
+    fn default_policy_asks_for_8_characters_with_a_capital_letter() {
+        let policy = PasswordPolicy::default();
+        assert!(policy.check("Password").unwrap().is_empty());
+        assert_eq!(
+            policy.check("short").unwrap(),
+            [
+                "Password must be at least 8 characters long.",
+                "Password must contain an uppercase letter."
+            ]
+        );
+    }
+
+    #[test]
+    fn required_character_classes_are_checked() {
+        let policy = PasswordPolicy {
+            require_uppercase: false,
+            require_lowercase: true,
+            require_digit: true,
+            require_symbol: true,
+            ..PasswordPolicy::default()
+        };
+        assert!(policy.check("correct horse 1!").unwrap().is_empty());
+        assert_eq!(
+            policy.check("CORRECT HORSE").unwrap(),
+            [
+                "Password must contain a lowercase letter.",
+                "Password must contain a digit.",
+                "Password must contain a symbol."
+            ]
+        );
+    }
+
+    #[test]
+    fn passwords_on_the_breached_list_are_refused() {
+        let breached = ["Password1", "Summer2026", "Zzzzzzzz"];
+        let policy = PasswordPolicy {
+            breached_list: Some(breached_list("breached-passwords", &breached)),
+            ..PasswordPolicy::default()
+        };
+        for password in breached {
+            assert_eq!(
+                policy.check(password).unwrap(),
+                ["Password has appeared in a data breach."],
+                "{} was not found",
+                password
+            );
+        }
+        assert!(policy.check("Unbreached-password").unwrap().is_empty());
+    }
+
+    #[test]
+    fn missing_breached_list_is_an_error() {
+        let policy = PasswordPolicy {
+            breached_list: Some(PathBuf::from("/nonexistent/breached-passwords.txt")),
+            ..PasswordPolicy::default()
+        };
+        assert!(policy.check("Password").is_err());
+    }
+}
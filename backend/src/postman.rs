@@ -0,0 +1,820 @@
+use crate::{
+    curl::{
+        self, percent_decode, percent_encode, BasicAuth, CurlRequest, FormPart, NameValue,
+        RequestBody,
+    },
+    importer::{folder_path, Imported, ImportedEntry, ImportedEnvironment, ImportedGroup, Skipped},
+    models::Environment,
+    template,
+};
+
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+pub const SCHEMA: &str = "https://schema.getpostman.com/json/collection/v2.1.0/collection.json";
+
+/// A Postman v2.1 collection. Only the parts curl commands can express are modelled.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Collection {
+    pub info: Info,
+    pub item: Vec<Item>,
+    /// Used by every request that does not set its own.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<Auth>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Info {
+    pub name: String,
+    #[serde(
+        default,
+        deserialize_with = "description",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub description: Option<String>,
+    pub schema: String,
+}
+
+/// A request, or a folder of further items when `request` is missing.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Item {
+    pub name: String,
+    #[serde(
+        default,
+        deserialize_with = "description",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request: Option<Request>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub item: Vec<Item>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<Auth>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protocol_profile_behavior: Option<ProtocolProfileBehavior>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Request {
+    pub method: String,
+    #[serde(default)]
+    pub header: Vec<KeyValue>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Body>,
+    pub url: Url,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<Auth>,
+    #[serde(
+        default,
+        deserialize_with = "description",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct KeyValue {
+    pub key: String,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub value: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub disabled: bool,
+}
+
+impl KeyValue {
+    pub fn new(key: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            value: value.into(),
+            disabled: false,
+        }
+    }
+}
+
+/// Postman accepts both a plain string and the url split into its parts.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum Url {
+    Raw(String),
+    Detailed(UrlDetails),
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct UrlDetails {
+    pub raw: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub host: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub path: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub query: Vec<KeyValue>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "mode", rename_all = "lowercase")]
+pub enum Body {
+    Raw {
+        raw: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        options: Option<BodyOptions>,
+    },
+    Urlencoded {
+        urlencoded: Vec<KeyValue>,
+    },
+    Formdata {
+        formdata: Vec<FormParam>,
+    },
+    /// File and GraphQL bodies, which are not converted.
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct BodyOptions {
+    pub raw: RawOptions,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct RawOptions {
+    pub language: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum FormParam {
+    Text {
+        key: String,
+        #[serde(default, deserialize_with = "lenient_string")]
+        value: String,
+        #[serde(
+            default,
+            rename = "contentType",
+            skip_serializing_if = "Option::is_none"
+        )]
+        content_type: Option<String>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        disabled: bool,
+    },
+    File {
+        key: String,
+        /// The path of the file; Postman writes an empty list when none is selected.
+        #[serde(default, deserialize_with = "lenient_string")]
+        src: String,
+        #[serde(
+            default,
+            rename = "contentType",
+            skip_serializing_if = "Option::is_none"
+        )]
+        content_type: Option<String>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        disabled: bool,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Auth {
+    Basic {
+        basic: Vec<AuthParam>,
+    },
+    Bearer {
+        bearer: Vec<AuthParam>,
+    },
+    Noauth,
+    /// API key, OAuth and the other schemes, which are not converted.
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct AuthParam {
+    pub key: String,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub value: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+}
+
+impl AuthParam {
+    fn string(key: &str, value: impl Into<String>) -> Self {
+        Self {
+            key: key.to_string(),
+            value: value.into(),
+            kind: "string".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ProtocolProfileBehavior {
+    #[serde(
+        default,
+        rename = "followRedirects",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub follow_redirects: Option<bool>,
+    #[serde(default, rename = "strictSSL", skip_serializing_if = "Option::is_none")]
+    pub strict_ssl: Option<bool>,
+}
+
+/// A Postman environment file, imported alongside the collection.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct PostmanEnvironment {
+    pub name: String,
+    pub values: Vec<EnvironmentValue>,
+    #[serde(rename = "_postman_variable_scope", default)]
+    pub scope: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct EnvironmentValue {
+    pub key: String,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub value: String,
+    /// `default` or `secret`.
+    #[serde(rename = "type", default)]
+    pub kind: String,
+    #[serde(default = "enabled")]
+    pub enabled: bool,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+/// Postman writes descriptions either as text or as `{"content": ..., "type": ...}`.
+fn description<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    Ok(match Option::<Value>::deserialize(deserializer)? {
+        Some(Value::String(text)) => Some(text),
+        Some(Value::Object(object)) => object
+            .get("content")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        _ => None,
+    })
+}
+
+/// Values are usually strings, but numbers, booleans, `null` and lists of paths turn up too.
+fn lenient_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    Ok(match Option::<Value>::deserialize(deserializer)? {
+        Some(Value::String(text)) => text,
+        Some(Value::Array(values)) => values
+            .iter()
+            .find_map(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        Some(Value::Null) | None => String::new(),
+        Some(value) => value.to_string(),
+    })
+}
+
+pub fn export_collection(name: &str, description: &str, item: Vec<Item>) -> Collection {
+    Collection {
+        info: Info {
+            name: name.to_string(),
+            description: non_empty(description),
+            schema: SCHEMA.to_string(),
+        },
+        item,
+        auth: None,
+    }
+}
+
+/// Converts a parsed curl command. Both tools write variables as `{{name}}`, so placeholders carry
+/// over unchanged.
+pub fn export_request(name: &str, description: &str, request: &CurlRequest) -> Item {
+    let mut header: Vec<KeyValue> = vec![];
+    let mut auth = request.basic_auth.as_ref().map(|basic_auth| Auth::Basic {
+        basic: vec![
+            AuthParam::string("username", &basic_auth.username),
+            AuthParam::string("password", basic_auth.password.clone().unwrap_or_default()),
+        ],
+    });
+
+    for NameValue { name, value } in &request.headers {
+        match value.strip_prefix("Bearer ") {
+            Some(token) if auth.is_none() && name.eq_ignore_ascii_case("authorization") => {
+                auth = Some(Auth::Bearer {
+                    bearer: vec![AuthParam::string("token", token)],
+                });
+            }
+            _ => header.push(KeyValue::new(name, value)),
+        }
+    }
+
+    if !request.cookies.is_empty() {
+        let cookies: Vec<String> = request
+            .cookies
+            .iter()
+            .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+            .collect();
+        header.push(KeyValue::new("Cookie", cookies.join("; ")));
+    }
+
+    let body = request
+        .body
+        .as_ref()
+        .map(|body| export_body(body, request.header("content-type")));
+
+    Item {
+        name: name.to_string(),
+        description: None,
+        request: Some(Request {
+            method: request.method.clone(),
+            header,
+            body,
+            url: Url::Detailed(export_url(&request.url)),
+            auth,
+            description: non_empty(description),
+        }),
+        item: vec![],
+        auth: None,
+        protocol_profile_behavior: Some(ProtocolProfileBehavior {
+            // curl stays put unless told otherwise, Postman follows by default
+            follow_redirects: Some(request.follow_redirects),
+            strict_ssl: request.insecure.then_some(false),
+        }),
+    }
+}
+
+/// Secret values never leave the server, so secrets are exported by name for the user to fill in.
+pub fn export_environment(environment: &Environment) -> PostmanEnvironment {
+    let variables = environment
+        .variables
+        .iter()
+        .map(|(key, value)| (key.clone(), value.clone(), "default"));
+    let secrets = environment
+        .secrets
+        .iter()
+        .map(|key| (key.clone(), String::new(), "secret"));
+
+    PostmanEnvironment {
+        name: environment.name.clone(),
+        values: variables
+            .chain(secrets)
+            .map(|(key, value, kind)| EnvironmentValue {
+                key,
+                value,
+                kind: kind.to_string(),
+                enabled: true,
+            })
+            .collect(),
+        scope: "environment".to_string(),
+    }
+}
+
+fn export_body(body: &RequestBody, content_type: Option<&str>) -> Body {
+    // curl sends -d data as a form unless another content type is given
+    let is_form = content_type
+        .map(|content_type| content_type.starts_with("application/x-www-form-urlencoded"))
+        .unwrap_or(true);
+
+    match body {
+        RequestBody::Json { content } => raw_body(content, "json"),
+        RequestBody::Raw { content } if is_form => Body::Urlencoded {
+            urlencoded: split_pairs(content, true),
+        },
+        RequestBody::Raw { content } => {
+            let content_type = content_type.unwrap_or_default();
+            let language = if content_type.contains("json") {
+                "json"
+            } else if content_type.contains("xml") {
+                "xml"
+            } else {
+                "text"
+            };
+            raw_body(content, language)
+        }
+        RequestBody::Multipart { parts } => Body::Formdata {
+            formdata: parts.iter().map(export_form_part).collect(),
+        },
+    }
+}
+
+fn raw_body(content: &str, language: &str) -> Body {
+    Body::Raw {
+        raw: content.to_string(),
+        options: Some(BodyOptions {
+            raw: RawOptions {
+                language: language.to_string(),
+            },
+        }),
+    }
+}
+
+fn export_form_part(part: &FormPart) -> FormParam {
+    if part.is_file {
+        FormParam::File {
+            key: part.name.clone(),
+            src: part.value.clone(),
+            content_type: part.content_type.clone(),
+            disabled: false,
+        }
+    } else {
+        FormParam::Text {
+            key: part.name.clone(),
+            value: part.value.clone(),
+            content_type: part.content_type.clone(),
+            disabled: false,
+        }
+    }
+}
+
+/// Splits the url the way Postman does, leaving query values encoded as written. Urls may start
+/// with a placeholder, so they are split by hand rather than parsed.
+fn export_url(raw: &str) -> UrlDetails {
+    let without_fragment = raw.split('#').next().unwrap_or_default();
+    let (without_query, query) = match without_fragment.split_once('?') {
+        Some((url, query)) => (url, split_pairs(query, false)),
+        None => (without_fragment, vec![]),
+    };
+    let (protocol, rest) = match without_query.split_once("://") {
+        Some((protocol, rest)) => (Some(protocol.to_string()), rest),
+        None => (None, without_query),
+    };
+    let (authority, path) = match rest.split_once('/') {
+        Some((authority, path)) => (authority, Some(path)),
+        None => (rest, None),
+    };
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) if !port.is_empty() && !port.contains(']') => {
+            (host, Some(port.to_string()))
+        }
+        _ => (authority, None),
+    };
+
+    UrlDetails {
+        raw: raw.to_string(),
+        protocol,
+        host: host.split('.').map(str::to_string).collect(),
+        port,
+        path: path
+            .map(|path| path.split('/').map(str::to_string).collect())
+            .unwrap_or_default(),
+        query,
+    }
+}
+
+/// Every folder holding requests becomes a group; requests outside any folder are grouped under the
+/// collection's name.
+pub fn import_collection(collection: &Collection) -> Imported {
+    let mut imported = Imported::default();
+    let root = Item {
+        name: collection.info.name.clone(),
+        description: collection.info.description.clone(),
+        request: None,
+        item: collection.item.clone(),
+        auth: collection.auth.clone(),
+        protocol_profile_behavior: None,
+    };
+    import_folder(&root, None, None, &mut imported);
+    imported
+}
+
+/// `parents` is `None` for the collection itself, whose name is left out of the folder paths.
+fn import_folder(
+    folder: &Item,
+    parents: Option<&[String]>,
+    inherited_auth: Option<&Auth>,
+    imported: &mut Imported,
+) {
+    let name = match parents {
+        Some(parents) => folder_path(parents, &folder.name),
+        None => folder.name.clone(),
+    };
+    let auth = folder.auth.as_ref().or(inherited_auth);
+
+    let mut entries = vec![];
+    for item in folder.item.iter() {
+        let Some(request) = &item.request else {
+            continue;
+        };
+        match import_request(item, request, auth) {
+            Ok(entry) => entries.push(entry),
+            Err(reason) => imported.skipped.push(Skipped::new(
+                folder_path(std::slice::from_ref(&name), &item.name),
+                reason,
+            )),
+        }
+    }
+    if !entries.is_empty() {
+        imported.groups.push(ImportedGroup {
+            name: name.clone(),
+            description: folder.description.clone().unwrap_or_default(),
+            entries,
+            source: None,
+        });
+    }
+
+    let mut path = parents.map(<[String]>::to_vec).unwrap_or_default();
+    if parents.is_some() {
+        path.push(folder.name.clone());
+    }
+    for subfolder in folder.item.iter().filter(|item| item.request.is_none()) {
+        import_folder(subfolder, Some(&path), auth, imported);
+    }
+}
+
+fn import_request(
+    item: &Item,
+    request: &Request,
+    inherited_auth: Option<&Auth>,
+) -> Result<ImportedEntry, String> {
+    let url = match &request.url {
+        Url::Raw(raw) => raw.clone(),
+        Url::Detailed(url) if !url.raw.is_empty() => url.raw.clone(),
+        Url::Detailed(url) => join_url(url),
+    };
+    if url.trim().is_empty() {
+        return Err("The request has no URL".to_string());
+    }
+
+    let mut curl_request = CurlRequest {
+        method: request.method.to_uppercase(),
+        url,
+        headers: request
+            .header
+            .iter()
+            .filter(|header| !header.disabled)
+            .map(|header| NameValue::new(&header.key, &header.value))
+            .collect(),
+        ..Default::default()
+    };
+
+    match request.auth.as_ref().or(inherited_auth) {
+        Some(Auth::Basic { basic }) => {
+            curl_request.basic_auth = Some(BasicAuth {
+                username: auth_param(basic, "username"),
+                password: Some(auth_param(basic, "password")),
+            })
+        }
+        Some(Auth::Bearer { bearer }) => curl_request.headers.push(NameValue::new(
+            "Authorization",
+            format!("Bearer {}", auth_param(bearer, "token")),
+        )),
+        Some(Auth::Other) => {
+            return Err("Only basic and bearer authentication can be converted".to_string())
+        }
+        Some(Auth::Noauth) | None => {}
+    }
+
+    if let Some(body) = &request.body {
+        curl_request.body = import_body(body)?;
+        if let (Some(RequestBody::Raw { .. }), Body::Raw { options, .. }) =
+            (&curl_request.body, body)
+        {
+            if curl_request.header("content-type").is_none() {
+                let language = options
+                    .as_ref()
+                    .map(|options| options.raw.language.as_str());
+                curl_request.headers.push(NameValue::new(
+                    "Content-Type",
+                    raw_content_type(language.unwrap_or("text")),
+                ));
+            }
+        }
+    }
+
+    // Postman follows redirects and checks certificates unless told otherwise
+    let behavior = item.protocol_profile_behavior.as_ref();
+    curl_request.follow_redirects = behavior
+        .and_then(|behavior| behavior.follow_redirects)
+        .unwrap_or(true);
+    curl_request.insecure = behavior.and_then(|behavior| behavior.strict_ssl) == Some(false);
+
+    Ok(ImportedEntry {
+        name: item.name.clone(),
+        description: request.description.clone().unwrap_or_default(),
+        raw_query: curl::to_command(&curl_request),
+        source: None,
+    })
+}
+
+fn import_body(body: &Body) -> Result<Option<RequestBody>, String> {
+    let body = match body {
+        Body::Raw { raw, .. } if raw.is_empty() => None,
+        Body::Raw { raw, .. } => Some(RequestBody::Raw {
+            content: raw.clone(),
+        }),
+        Body::Urlencoded { urlencoded } => {
+            let pairs: Vec<String> = urlencoded
+                .iter()
+                .filter(|pair| !pair.disabled)
+                .map(|pair| {
+                    format!(
+                        "{}={}",
+                        percent_encode(&pair.key),
+                        percent_encode(&pair.value)
+                    )
+                })
+                .collect();
+            (!pairs.is_empty()).then(|| RequestBody::Raw {
+                content: pairs.join("&"),
+            })
+        }
+        Body::Formdata { formdata } => {
+            let mut parts = vec![];
+            for param in formdata {
+                match param {
+                    FormParam::Text { disabled: true, .. }
+                    | FormParam::File { disabled: true, .. } => {}
+                    FormParam::Text {
+                        key,
+                        value,
+                        content_type,
+                        ..
+                    } => parts.push(FormPart {
+                        name: key.clone(),
+                        value: value.clone(),
+                        is_file: false,
+                        content_type: content_type.clone(),
+                        filename: None,
+                    }),
+                    FormParam::File { key, src, .. } if src.is_empty() => {
+                        return Err(format!(
+                            "No file is selected for the form field \"{}\"",
+                            key
+                        ))
+                    }
+                    FormParam::File {
+                        key,
+                        src,
+                        content_type,
+                        ..
+                    } => parts.push(FormPart {
+                        name: key.clone(),
+                        value: src.clone(),
+                        is_file: true,
+                        content_type: content_type.clone(),
+                        filename: None,
+                    }),
+                }
+            }
+            (!parts.is_empty()).then_some(RequestBody::Multipart { parts })
+        }
+        Body::Other => {
+            return Err("Only raw, urlencoded and form-data bodies can be converted".to_string())
+        }
+    };
+    Ok(body)
+}
+
+/// The content type Postman sends for a raw body written in the given language.
+fn raw_content_type(language: &str) -> &'static str {
+    match language {
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "html" => "text/html",
+        "javascript" => "application/javascript",
+        _ => "text/plain",
+    }
+}
+
+fn auth_param(params: &[AuthParam], key: &str) -> String {
+    params
+        .iter()
+        .find(|param| param.key == key)
+        .map(|param| param.value.clone())
+        .unwrap_or_default()
+}
+
+fn join_url(url: &UrlDetails) -> String {
+    let mut joined = String::new();
+    if let Some(protocol) = &url.protocol {
+        joined.push_str(&format!("{}://", protocol));
+    }
+    joined.push_str(&url.host.join("."));
+    if let Some(port) = &url.port {
+        joined.push_str(&format!(":{}", port));
+    }
+    if !url.path.is_empty() {
+        joined.push_str(&format!("/{}", url.path.join("/")));
+    }
+    let query: Vec<String> = url
+        .query
+        .iter()
+        .filter(|pair| !pair.disabled)
+        .map(|pair| format!("{}={}", pair.key, pair.value))
+        .collect();
+    if !query.is_empty() {
+        joined.push_str(&format!("?{}", query.join("&")));
+    }
+    joined
+}
+
+/// Postman's secret values become secrets of the environment, which keep a value only when the
+/// file holds one.
+pub fn import_environment(environment: &PostmanEnvironment) -> (ImportedEnvironment, Vec<Skipped>) {
+    let mut imported = ImportedEnvironment {
+        name: environment.name.clone(),
+        ..Default::default()
+    };
+    let mut skipped = vec![];
+
+    for value in environment.values.iter().filter(|value| value.enabled) {
+        if !template::is_valid_variable_name(&value.key) {
+            skipped.push(Skipped::invalid_variable(&environment.name, &value.key));
+        } else if value.kind == "secret" {
+            if !value.value.is_empty() {
+                imported
+                    .secrets
+                    .insert(value.key.clone(), value.value.clone());
+            }
+        } else {
+            imported
+                .variables
+                .insert(value.key.clone(), value.value.clone());
+        }
+    }
+
+    (imported, skipped)
+}
+
+fn split_pairs(content: &str, decode: bool) -> Vec<KeyValue> {
+    let decode = |value: &str| {
+        if decode {
+            percent_decode(value)
+        } else {
+            value.to_string()
+        }
+    };
+
+    content
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((key, value)) => KeyValue::new(decode(key), decode(value)),
+            None => KeyValue::new(decode(pair), ""),
+        })
+        .collect()
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    (!value.trim().is_empty()).then(|| value.to_string())
+}
+
+#[cfg(test)]
+mod postman_test {
+    use super::{export_request, export_url, Auth, AuthParam, Body, KeyValue, Url};
+    use crate::curl::parse;
+
+    fn export(command: &str) -> super::Request {
+        export_request("Test", "", &parse(command).unwrap())
+            .request
+            .unwrap()
+    }
+
+    #[test]
+    fn splits_urls_with_placeholders() {
+        let url = export_url("{{base_url}}:8080/users/{{id}}?expand=a%20b&flag");
+        assert_eq!(url.protocol, None);
+        assert_eq!(url.host, vec!["{{base_url}}"]);
+        assert_eq!(url.port.as_deref(), Some("8080"));
+        assert_eq!(url.path, vec!["users", "{{id}}"]);
+        assert_eq!(
+            url.query,
+            vec![KeyValue::new("expand", "a%20b"), KeyValue::new("flag", "")]
+        );
+
+        let url = export_url("https://api.example.com");
+        assert_eq!(url.protocol.as_deref(), Some("https"));
+        assert_eq!(url.host, vec!["api", "example", "com"]);
+        assert!(url.path.is_empty());
+    }
+
+    #[test]
+    fn moves_bearer_tokens_into_auth() {
+        let request = export("curl -H 'Authorization: Bearer {{token}}' -H 'Accept: */*' a.com");
+        assert_eq!(
+            request.auth,
+            Some(Auth::Bearer {
+                bearer: vec![AuthParam::string("token", "{{token}}")]
+            })
+        );
+        assert_eq!(request.header, vec![KeyValue::new("Accept", "*/*")]);
+        assert!(matches!(request.url, Url::Detailed(_)));
+    }
+
+    #[test]
+    fn picks_the_body_mode_from_the_content_type() {
+        let request = export("curl -d 'a=1&b=x%20y' a.com");
+        assert_eq!(
+            request.body,
+            Some(Body::Urlencoded {
+                urlencoded: vec![KeyValue::new("a", "1"), KeyValue::new("b", "x y")]
+            })
+        );
+
+        let request = export("curl -H 'Content-Type: application/xml' -d '<a/>' a.com");
+        assert!(matches!(
+            request.body,
+            Some(Body::Raw { options: Some(options), .. }) if options.raw.language == "xml"
+        ));
+    }
+}
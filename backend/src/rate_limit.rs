@@ -0,0 +1,474 @@
+//! Throttling for logging in and signing up: token buckets per client IP and per username, and
+//! progressive delays followed by a lockout for usernames whose password keeps being wrong.
+
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header,
+    web, Error, HttpResponse,
+};
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    env,
+    future::{ready, Future, Ready},
+    pin::Pin,
+    rc::Rc,
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+pub struct RateLimitConfig {
+    /// How many attempts a client IP can make in a burst.
+    pub ip_burst: u32,
+    /// How often a client IP gets an attempt back.
+    pub ip_refill: Duration,
+    pub username_burst: u32,
+    pub username_refill: Duration,
+    /// Wrong passwords in a row before each further one makes the next attempt wait.
+    pub free_failures: u32,
+    /// The first wait, which doubles with each wrong password after it.
+    pub base_delay: Duration,
+    /// Wrong passwords in a row after which the username is locked out.
+    pub lockout_failures: u32,
+    pub lockout_duration: Duration,
+    /// Takes the client IP from `Forwarded` or `X-Forwarded-For`, which only a proxy in front of
+    /// the app can be trusted to set.
+    pub trust_proxy: bool,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            ip_burst: 20,
+            ip_refill: Duration::from_secs(3),
+            username_burst: 10,
+            username_refill: Duration::from_secs(6),
+            free_failures: 3,
+            base_delay: Duration::from_secs(1),
+            lockout_failures: 10,
+            lockout_duration: Duration::from_secs(15 * 60),
+            trust_proxy: false,
+        }
+    }
+}
+
+impl RateLimitConfig {
+    /// `RATE_LIMIT_IP_BURST`, `RATE_LIMIT_IP_REFILL_SECONDS`, `RATE_LIMIT_USERNAME_BURST`,
+    /// `RATE_LIMIT_USERNAME_REFILL_SECONDS`, `RATE_LIMIT_FREE_FAILURES`,
+    /// `RATE_LIMIT_BASE_DELAY_SECONDS`, `RATE_LIMIT_LOCKOUT_FAILURES`,
+    /// `RATE_LIMIT_LOCKOUT_SECONDS` and `RATE_LIMIT_TRUST_PROXY` override the defaults.
+    pub fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(name: &str) -> Option<T> {
+            env::var(name).ok()?.parse().ok()
+        }
+        let seconds = |name: &str| var(name).map(Duration::from_secs);
+
+        let default = Self::default();
+        Self {
+            ip_burst: var("RATE_LIMIT_IP_BURST").unwrap_or(default.ip_burst),
+            ip_refill: seconds("RATE_LIMIT_IP_REFILL_SECONDS").unwrap_or(default.ip_refill),
+            username_burst: var("RATE_LIMIT_USERNAME_BURST").unwrap_or(default.username_burst),
+            username_refill: seconds("RATE_LIMIT_USERNAME_REFILL_SECONDS")
+                .unwrap_or(default.username_refill),
+            free_failures: var("RATE_LIMIT_FREE_FAILURES").unwrap_or(default.free_failures),
+            base_delay: seconds("RATE_LIMIT_BASE_DELAY_SECONDS").unwrap_or(default.base_delay),
+            lockout_failures: var("RATE_LIMIT_LOCKOUT_FAILURES")
+                .unwrap_or(default.lockout_failures),
+            lockout_duration: seconds("RATE_LIMIT_LOCKOUT_SECONDS")
+                .unwrap_or(default.lockout_duration),
+            trust_proxy: var("RATE_LIMIT_TRUST_PROXY").unwrap_or(default.trust_proxy),
+        }
+    }
+}
+
+/// Wrong passwords in a row for a username.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FailureRecord {
+    pub count: u32,
+    pub last_failure: SystemTime,
+}
+
+/// Where buckets and failures are kept. The in-memory store is enough for a single instance, while
+/// instances sharing the load need to share a store as well. Times are wall clock times so that
+/// they mean the same to every instance.
+pub trait RateLimitStore: Send + Sync {
+    /// Takes a token from the bucket under `key`, which holds up to `burst` of them and gets one
+    /// back every `refill`. When it is empty, says how long until it is not.
+    fn take_token(
+        &self,
+        key: &str,
+        burst: u32,
+        refill: Duration,
+        now: SystemTime,
+    ) -> Result<(), Duration>;
+    /// Counts another failure, to be forgotten once `forget_after` has passed without one.
+    fn record_failure(&self, key: &str, now: SystemTime, forget_after: Duration) -> FailureRecord;
+    fn failures(&self, key: &str, now: SystemTime) -> Option<FailureRecord>;
+    fn clear_failures(&self, key: &str);
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: SystemTime,
+    /// When it will be full again, after which there is no need to keep it.
+    full_at: SystemTime,
+}
+
+#[derive(Default)]
+struct MemoryState {
+    buckets: HashMap<String, Bucket>,
+    failures: HashMap<String, (FailureRecord, SystemTime)>,
+    pruned: Option<SystemTime>,
+}
+
+#[derive(Default)]
+pub struct MemoryStore {
+    state: Mutex<MemoryState>,
+}
+
+impl MemoryStore {
+    const PRUNE_EVERY: Duration = Duration::from_secs(60);
+
+    fn prune(state: &mut MemoryState, now: SystemTime) {
+        if state
+            .pruned
+            .is_some_and(|pruned| elapsed(pruned, now) < Self::PRUNE_EVERY)
+        {
+            return;
+        }
+        state.buckets.retain(|_, bucket| bucket.full_at > now);
+        state.failures.retain(|_, (_, forget_at)| *forget_at > now);
+        state.pruned = Some(now);
+    }
+}
+
+impl RateLimitStore for MemoryStore {
+    fn take_token(
+        &self,
+        key: &str,
+        burst: u32,
+        refill: Duration,
+        now: SystemTime,
+    ) -> Result<(), Duration> {
+        let mut state = self.state.lock().unwrap();
+        Self::prune(&mut state, now);
+
+        let burst = f64::from(burst.max(1));
+        let bucket = state.buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: burst,
+            updated: now,
+            full_at: now,
+        });
+        let refilled = elapsed(bucket.updated, now).as_secs_f64() / refill.as_secs_f64();
+        bucket.tokens = (bucket.tokens + refilled).min(burst);
+        bucket.updated = now;
+        if bucket.tokens < 1.0 {
+            return Err(refill.mul_f64(1.0 - bucket.tokens));
+        }
+        bucket.tokens -= 1.0;
+        bucket.full_at = now + refill.mul_f64(burst - bucket.tokens);
+        Ok(())
+    }
+
+    fn record_failure(&self, key: &str, now: SystemTime, forget_after: Duration) -> FailureRecord {
+        let mut state = self.state.lock().unwrap();
+        Self::prune(&mut state, now);
+
+        let count = match state.failures.get(key) {
+            Some((record, forget_at)) if *forget_at > now => record.count,
+            _ => 0,
+        };
+        let record = FailureRecord {
+            count: count + 1,
+            last_failure: now,
+        };
+        state
+            .failures
+            .insert(key.to_string(), (record, now + forget_after));
+        record
+    }
+
+    fn failures(&self, key: &str, now: SystemTime) -> Option<FailureRecord> {
+        let state = self.state.lock().unwrap();
+        state
+            .failures
+            .get(key)
+            .filter(|(_, forget_at)| *forget_at > now)
+            .map(|(record, _)| *record)
+    }
+
+    fn clear_failures(&self, key: &str) {
+        self.state.lock().unwrap().failures.remove(key);
+    }
+}
+
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    store: Box<dyn RateLimitStore>,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(RateLimitConfig::default())
+    }
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self::with_store(config, MemoryStore::default())
+    }
+
+    pub fn with_store(config: RateLimitConfig, store: impl RateLimitStore + 'static) -> Self {
+        Self {
+            config,
+            store: Box::new(store),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(RateLimitConfig::from_env())
+    }
+
+    /// Takes an attempt from the client IP and the username, or says how long until they may try
+    /// again. Usernames that have to wait after wrong passwords do not use up any attempts.
+    pub fn check(
+        &self,
+        ip: Option<&str>,
+        username: Option<&str>,
+        now: SystemTime,
+    ) -> Result<(), Duration> {
+        if let Some(username) = username {
+            if let Some(wait) = self.penalty(username, now) {
+                return Err(wait);
+            }
+        }
+        let config = &self.config;
+        if let Some(ip) = ip {
+            self.store.take_token(
+                &format!("ip:{}", ip),
+                config.ip_burst,
+                config.ip_refill,
+                now,
+            )?;
+        }
+        if let Some(username) = username {
+            self.store.take_token(
+                &format!("username:{}", username),
+                config.username_burst,
+                config.username_refill,
+                now,
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn record_failure(&self, username: &str, now: SystemTime) {
+        self.store
+            .record_failure(username, now, self.config.lockout_duration);
+    }
+
+    pub fn record_success(&self, username: &str) {
+        self.store.clear_failures(username);
+    }
+
+    /// How much longer the username has to wait after its last wrong password, if at all.
+    fn penalty(&self, username: &str, now: SystemTime) -> Option<Duration> {
+        let config = &self.config;
+        let record = self.store.failures(username, now)?;
+        let wait = if record.count >= config.lockout_failures {
+            config.lockout_duration
+        } else if record.count > config.free_failures {
+            let doublings = (record.count - config.free_failures - 1).min(16);
+            config
+                .base_delay
+                .saturating_mul(1 << doublings)
+                .min(config.lockout_duration)
+        } else {
+            return None;
+        };
+        wait.checked_sub(elapsed(record.last_failure, now))
+            .filter(|wait| !wait.is_zero())
+    }
+}
+
+fn elapsed(since: SystemTime, now: SystemTime) -> Duration {
+    now.duration_since(since).unwrap_or_default()
+}
+
+#[derive(Deserialize)]
+struct UsernameBody {
+    username: String,
+}
+
+/// Throttles the requests it wraps with the app's [`RateLimiter`], answering `429 Too Many
+/// Requests` with a `Retry-After` once they are over the limit. The username is read from the JSON
+/// body, which is put back for the handler.
+pub struct RateLimit;
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = RateLimitMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimitMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct RateLimitMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+
+        Box::pin(async move {
+            let Some(limiter) = req.app_data::<web::Data<RateLimiter>>().cloned() else {
+                return service
+                    .call(req)
+                    .await
+                    .map(ServiceResponse::map_into_left_body);
+            };
+
+            let body = req.extract::<web::Bytes>().await?;
+            let username = serde_json::from_slice::<UsernameBody>(&body)
+                .ok()
+                .map(|body| body.username);
+            let (_, mut payload) = actix_http::h1::Payload::create(true);
+            payload.unread_data(body);
+            req.set_payload(payload.into());
+            let ip = match limiter.config.trust_proxy {
+                true => req.connection_info().realip_remote_addr().map(strip_port),
+                false => req.peer_addr().map(|address| address.ip().to_string()),
+            };
+
+            match limiter.check(ip.as_deref(), username.as_deref(), SystemTime::now()) {
+                Ok(_) => service
+                    .call(req)
+                    .await
+                    .map(ServiceResponse::map_into_left_body),
+                Err(wait) => {
+                    tracing::warn!("Rate limited {:?} as {:?} for {:?}", ip, username, wait);
+                    let response = HttpResponse::TooManyRequests()
+                        .insert_header((header::RETRY_AFTER, retry_after_seconds(wait)))
+                        .finish();
+                    Ok(req.into_response(response).map_into_right_body())
+                }
+            }
+        })
+    }
+}
+
+/// Whole seconds, rounded up so that clients do not come back too early.
+fn retry_after_seconds(wait: Duration) -> u64 {
+    wait.as_secs() + u64::from(wait.subsec_nanos() > 0)
+}
+
+fn strip_port(address: &str) -> String {
+    match address.parse::<std::net::SocketAddr>() {
+        Ok(address) => address.ip().to_string(),
+        Err(_) => address.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod rate_limit_test {
+    use super::{retry_after_seconds, RateLimitConfig, RateLimiter};
+    use std::time::{Duration, SystemTime};
+
+    fn limiter() -> RateLimiter {
+        RateLimiter::new(RateLimitConfig {
+            ip_burst: 2,
+            ip_refill: Duration::from_secs(10),
+            username_burst: 100,
+            free_failures: 1,
+            base_delay: Duration::from_secs(4),
+            lockout_failures: 4,
+            lockout_duration: Duration::from_secs(600),
+            ..RateLimitConfig::default()
+        })
+    }
+
+    #[test]
+    fn buckets_refill_over_time() {
+        let limiter = limiter();
+        let start = SystemTime::UNIX_EPOCH;
+        assert!(limiter.check(Some("10.0.0.1"), None, start).is_ok());
+        assert!(limiter.check(Some("10.0.0.1"), None, start).is_ok());
+        assert_eq!(
+            limiter.check(Some("10.0.0.1"), None, start),
+            Err(Duration::from_secs(10))
+        );
+        assert!(limiter.check(Some("10.0.0.2"), None, start).is_ok());
+
+        let later = start + Duration::from_secs(5);
+        assert_eq!(
+            limiter.check(Some("10.0.0.1"), None, later),
+            Err(Duration::from_secs(5))
+        );
+        let later = start + Duration::from_secs(10);
+        assert!(limiter.check(Some("10.0.0.1"), None, later).is_ok());
+    }
+
+    #[test]
+    fn wrong_passwords_delay_and_then_lock_out_the_username() {
+        let limiter = limiter();
+        let start = SystemTime::UNIX_EPOCH;
+        let waits: Vec<Option<Duration>> = (0..4)
+            .map(|_| {
+                limiter.record_failure("alice", start);
+                limiter.check(None, Some("alice"), start).err()
+            })
+            .collect();
+        assert_eq!(
+            waits,
+            [
+                None,
+                Some(Duration::from_secs(4)),
+                Some(Duration::from_secs(8)),
+                Some(Duration::from_secs(600)),
+            ]
+        );
+        assert!(limiter.check(None, Some("bob"), start).is_ok());
+
+        let later = start + Duration::from_secs(600);
+        assert!(limiter.check(None, Some("alice"), later).is_ok());
+    }
+
+    #[test]
+    fn success_forgives_earlier_failures() {
+        let limiter = limiter();
+        let start = SystemTime::UNIX_EPOCH;
+        limiter.record_failure("alice", start);
+        limiter.record_failure("alice", start);
+        limiter.record_success("alice");
+        limiter.record_failure("alice", start);
+        assert!(limiter.check(None, Some("alice"), start).is_ok());
+    }
+
+    #[test]
+    fn retry_after_rounds_up() {
+        assert_eq!(retry_after_seconds(Duration::from_millis(1500)), 2);
+        assert_eq!(retry_after_seconds(Duration::from_secs(3)), 3);
+    }
+}
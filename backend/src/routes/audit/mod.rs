@@ -0,0 +1,12 @@
+mod routes;
+pub mod types;
+
+use actix_web::web;
+use routes::*;
+pub(crate) use routes::{
+    get_curl_entry_summary, get_curl_group_summary, record_audit_event, AuditRecord,
+};
+
+pub fn audit_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_audit_events);
+}
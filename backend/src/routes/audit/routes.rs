@@ -0,0 +1,173 @@
+use crate::{
+    helpers::Identity,
+    routes::project::{
+        check_project_permission,
+        types::{Permission, ProjectError},
+    },
+};
+
+use actix_web::{get, web, HttpResponse, Responder};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use sqlx::{Executor, Sqlite, SqlitePool, Transaction};
+use tracing_actix_web::RequestId;
+
+use super::types::{AuditEvent, AuditPage, AuditParams};
+
+#[get("/project/{project_id}/audit")]
+#[tracing::instrument(name = "Getting audit events.", skip(pool, identity))]
+async fn get_audit_events(
+    params: web::Path<i64>,
+    query: web::Query<AuditParams>,
+    pool: web::Data<SqlitePool>,
+    identity: Identity,
+) -> impl Responder {
+    match get_audit_events_from_db(*params, &query, &pool, &identity).await {
+        Ok(page) => HttpResponse::Ok().json(page),
+        Err(e) => e.into(),
+    }
+}
+
+/// A change to record. `before` and `after` summarise the target around the change, and are left
+/// out for targets that did not exist before or no longer exist after it.
+pub(crate) struct AuditRecord {
+    pub project_id: i64,
+    pub actor_id: i64,
+    pub action: &'static str,
+    pub target: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+/// Records the event as part of whatever the executor is doing, so it is only kept when the
+/// change is.
+pub(crate) async fn record_audit_event<'e, E>(
+    record: AuditRecord,
+    request_id: &RequestId,
+    executor: E,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let before = record.before.map(|before| before.to_string());
+    let after = record.after.map(|after| after.to_string());
+    let request_id = request_id.to_string();
+    sqlx::query!(
+        r#"INSERT INTO audit_event (project_id, actor_id, actor_name, action, target, before, after, request_id)
+        VALUES (?, ?, (SELECT name FROM user WHERE id = ?), ?, ?, ?, ?, ?)"#,
+        record.project_id,
+        record.actor_id,
+        record.actor_id,
+        record.action,
+        record.target,
+        before,
+        after,
+        request_id
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+async fn get_audit_events_from_db(
+    project_id: i64,
+    params: &AuditParams,
+    pool: &SqlitePool,
+    identity: &Identity,
+) -> Result<AuditPage, ProjectError> {
+    check_project_permission(identity, project_id, Permission::ManageMembers, pool).await?;
+
+    // one more than asked for tells whether there is another page
+    let limit = params.limit() + 1;
+    let records = sqlx::query!(
+        r#"SELECT id, actor_name, action, target, before, after, request_id, created_at FROM audit_event
+        WHERE project_id = ?
+        AND (? IS NULL OR actor_name = ?)
+        AND (? IS NULL OR action = ?)
+        AND (? IS NULL OR id < ?)
+        ORDER BY id DESC LIMIT ?"#,
+        project_id,
+        params.actor,
+        params.actor,
+        params.action,
+        params.action,
+        params.before,
+        params.before,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut events: Vec<AuditEvent> = records
+        .into_iter()
+        .map(|record| AuditEvent {
+            id: record.id,
+            actor: record.actor_name,
+            action: record.action,
+            target: record.target,
+            before: record
+                .before
+                .and_then(|before| serde_json::from_str(&before).ok()),
+            after: record
+                .after
+                .and_then(|after| serde_json::from_str(&after).ok()),
+            request_id: record.request_id,
+            created_at: record.created_at,
+        })
+        .collect();
+
+    let next = if events.len() as i64 > params.limit() {
+        events.truncate(params.limit() as usize);
+        events.last().map(|event| event.id)
+    } else {
+        None
+    };
+    Ok(AuditPage { events, next })
+}
+
+/// The group's fields and the names of its entries.
+pub(crate) async fn get_curl_group_summary(
+    group_id: i64,
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> Result<Value, sqlx::Error> {
+    let group = sqlx::query!(
+        r#"SELECT name, description, labels, (
+            SELECT json_group_array(name) FROM (SELECT name FROM curl_entry WHERE group_id = curl_group.id ORDER BY position)
+        ) AS "entries!: String"
+        FROM curl_group WHERE id = ?"#,
+        group_id
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+
+    Ok(json!({
+        "name": group.name,
+        "description": group.description,
+        "labels": group.labels,
+        "entries": serde_json::from_str::<Value>(&group.entries).unwrap_or_default(),
+    }))
+}
+
+/// The command itself is left out, as whatever credentials it holds would outlive the entry in the
+/// log. Its hash still shows whether it changed.
+pub(crate) async fn get_curl_entry_summary(
+    entry_id: i64,
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> Result<Value, sqlx::Error> {
+    let entry = sqlx::query!(
+        r#"SELECT group_id, position, name, description, raw_query FROM curl_entry WHERE id = ?"#,
+        entry_id
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+
+    Ok(json!({
+        "group_id": entry.group_id,
+        "position": entry.position,
+        "name": entry.name,
+        "description": entry.description,
+        "raw_query_sha256": format!("{:x}", Sha256::digest(entry.raw_query.as_bytes())),
+        "raw_query_length": entry.raw_query.chars().count(),
+    }))
+}
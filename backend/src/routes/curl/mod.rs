@@ -0,0 +1,9 @@
+mod routes;
+pub mod types;
+
+use actix_web::web;
+use routes::*;
+
+pub fn curl_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(parse_curl);
+}
@@ -0,0 +1,14 @@
+use crate::curl;
+
+use actix_web::{post, web, HttpResponse, Responder};
+
+use super::types::CurlParseRequest;
+
+#[post("/curl/parse")]
+#[tracing::instrument(name = "Parsing curl command.", skip(body))]
+async fn parse_curl(body: web::Json<CurlParseRequest>) -> impl Responder {
+    match curl::parse(&body.command) {
+        Ok(request) => HttpResponse::Ok().json(request),
+        Err(e) => HttpResponse::UnprocessableEntity().json(e),
+    }
+}
@@ -0,0 +1,16 @@
+mod routes;
+pub mod types;
+
+use actix_web::web;
+pub(crate) use routes::replace_curl_entries;
+use routes::*;
+
+pub fn entry_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_curl_entries)
+        .service(create_curl_entry)
+        .service(get_curl_entry)
+        .service(get_curl_entry_snippet)
+        .service(update_curl_entry)
+        .service(move_curl_entry)
+        .service(delete_curl_entry);
+}
@@ -0,0 +1,501 @@
+use crate::{
+    curl,
+    helpers::{get_user_id, Identity},
+    models::{Curl, CurlEntry},
+    routes::{
+        audit::{get_curl_entry_summary, record_audit_event, AuditRecord},
+        project::{
+            apply_redaction_policy, check_project_permission, curl_group_check_user_permission,
+            get_redaction_policy,
+            types::{FieldError, Permission, ProjectError, SavedWithRedactions},
+            validate_curl_entry,
+        },
+        revision::record_curl_group_revision,
+    },
+    snippet::{self, Language},
+};
+
+use actix_web::{delete, get, post, web, HttpResponse, Responder};
+use sqlx::{Sqlite, SqlitePool, Transaction};
+use tracing_actix_web::RequestId;
+
+use super::types::{CurlEntryPosition, CurlEntryRequest, Snippet, SnippetParams};
+
+#[get("/group/{group_id}/entry")]
+#[tracing::instrument(name = "Getting curl entries.", skip(pool, identity))]
+async fn get_curl_entries(
+    params: web::Path<i64>,
+    pool: web::Data<SqlitePool>,
+    identity: Identity,
+) -> impl Responder {
+    match get_curl_entries_from_db(*params, &pool, &identity).await {
+        Ok(entries) => HttpResponse::Ok().json(entries),
+        Err(e) => e.into(),
+    }
+}
+
+#[post("/group/{group_id}/entry")]
+#[tracing::instrument(name = "Creating curl entry.", skip(body, pool, identity))]
+async fn create_curl_entry(
+    params: web::Path<i64>,
+    body: web::Json<CurlEntryRequest>,
+    pool: web::Data<SqlitePool>,
+    identity: Identity,
+    request_id: RequestId,
+) -> impl Responder {
+    match insert_curl_entry_into_db(*params, body.into_inner(), &pool, &identity, &request_id).await
+    {
+        Ok(saved) => HttpResponse::Ok().json(saved),
+        Err(e) => e.into(),
+    }
+}
+
+#[get("/group/{group_id}/entry/{entry_id}")]
+#[tracing::instrument(name = "Getting curl entry.", skip(pool, identity))]
+async fn get_curl_entry(
+    params: web::Path<(i64, i64)>,
+    pool: web::Data<SqlitePool>,
+    identity: Identity,
+) -> impl Responder {
+    let (group_id, entry_id) = params.into_inner();
+    match get_curl_entry_from_db(group_id, entry_id, &pool, &identity).await {
+        Ok(entry) => HttpResponse::Ok().json(entry),
+        Err(e) => e.into(),
+    }
+}
+
+#[get("/group/{group_id}/entry/{entry_id}/snippet")]
+#[tracing::instrument(name = "Getting curl entry snippet.", skip(pool, identity))]
+async fn get_curl_entry_snippet(
+    params: web::Path<(i64, i64)>,
+    query: web::Query<SnippetParams>,
+    pool: web::Data<SqlitePool>,
+    identity: Identity,
+) -> impl Responder {
+    let (group_id, entry_id) = params.into_inner();
+    match get_curl_entry_snippet_from_db(group_id, entry_id, query.lang, &pool, &identity).await {
+        Ok(snippet) => HttpResponse::Ok().json(snippet),
+        Err(e) => e.into(),
+    }
+}
+
+#[post("/group/{group_id}/entry/{entry_id}")]
+#[tracing::instrument(name = "Updating curl entry.", skip(body, pool, identity))]
+async fn update_curl_entry(
+    params: web::Path<(i64, i64)>,
+    body: web::Json<CurlEntryRequest>,
+    pool: web::Data<SqlitePool>,
+    identity: Identity,
+    request_id: RequestId,
+) -> impl Responder {
+    let (group_id, entry_id) = params.into_inner();
+    match update_curl_entry_in_db(
+        group_id,
+        entry_id,
+        body.into_inner(),
+        &pool,
+        &identity,
+        &request_id,
+    )
+    .await
+    {
+        Ok(saved) if !saved.redactions.is_empty() => HttpResponse::Ok().json(saved),
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) => e.into(),
+    }
+}
+
+#[post("/group/{group_id}/entry/{entry_id}/move")]
+#[tracing::instrument(name = "Moving curl entry.", skip(pool, identity))]
+async fn move_curl_entry(
+    params: web::Path<(i64, i64)>,
+    body: web::Json<CurlEntryPosition>,
+    pool: web::Data<SqlitePool>,
+    identity: Identity,
+    request_id: RequestId,
+) -> impl Responder {
+    let (group_id, entry_id) = params.into_inner();
+    match move_curl_entry_in_db(
+        group_id,
+        entry_id,
+        body.position,
+        &pool,
+        &identity,
+        &request_id,
+    )
+    .await
+    {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) => e.into(),
+    }
+}
+
+#[delete("/group/{group_id}/entry/{entry_id}")]
+#[tracing::instrument(name = "Deleting curl entry.", skip(pool, identity))]
+async fn delete_curl_entry(
+    params: web::Path<(i64, i64)>,
+    pool: web::Data<SqlitePool>,
+    identity: Identity,
+    request_id: RequestId,
+) -> impl Responder {
+    let (group_id, entry_id) = params.into_inner();
+    match delete_curl_entry_from_db(group_id, entry_id, &pool, &identity, &request_id).await {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) => e.into(),
+    }
+}
+
+async fn get_curl_entries_from_db(
+    group_id: i64,
+    pool: &SqlitePool,
+    identity: &Identity,
+) -> Result<Vec<CurlEntry>, ProjectError> {
+    curl_group_check_user_permission(group_id, pool, identity).await?;
+
+    let entries = sqlx::query_as!(
+        CurlEntry,
+        r#"SELECT * FROM curl_entry WHERE group_id = ? ORDER BY position"#,
+        group_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(entries)
+}
+
+async fn get_curl_entry_from_db(
+    group_id: i64,
+    entry_id: i64,
+    pool: &SqlitePool,
+    identity: &Identity,
+) -> Result<CurlEntry, ProjectError> {
+    curl_group_check_user_permission(group_id, pool, identity).await?;
+
+    let entry = sqlx::query_as!(
+        CurlEntry,
+        r#"SELECT * FROM curl_entry WHERE id = ? AND group_id = ?"#,
+        entry_id,
+        group_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(entry)
+}
+
+async fn get_curl_entry_snippet_from_db(
+    group_id: i64,
+    entry_id: i64,
+    language: Language,
+    pool: &SqlitePool,
+    identity: &Identity,
+) -> Result<Snippet, ProjectError> {
+    let entry = get_curl_entry_from_db(group_id, entry_id, pool, identity).await?;
+    let request = curl::parse(&entry.raw_query).map_err(|e| {
+        ProjectError::ValidationError(vec![FieldError::new(
+            "raw_query",
+            format!("\"{}\" cannot be translated: {}", entry.name, e),
+        )])
+    })?;
+
+    Ok(Snippet {
+        language,
+        code: snippet::generate(&request, language),
+    })
+}
+
+async fn insert_curl_entry_into_db(
+    group_id: i64,
+    mut entry: CurlEntryRequest,
+    pool: &SqlitePool,
+    identity: &Identity,
+    request_id: &RequestId,
+) -> Result<SavedWithRedactions, ProjectError> {
+    let user_id = get_user_id(identity).await?;
+    let project_id = curl_group_check_user_permission(group_id, pool, identity).await?;
+    check_project_permission(identity, project_id, Permission::EditGroup, pool).await?;
+    validate_curl_entry(&entry.name, &entry.raw_query)?;
+    let policy = get_redaction_policy(project_id, pool).await?;
+    let redactions =
+        apply_redaction_policy(policy, [("raw_query".to_string(), &mut entry.raw_query)])?;
+
+    let mut transaction = pool.begin().await?;
+    // new entries are always appended to the end of the group
+    let entry_id = sqlx::query!(
+        r#"INSERT INTO curl_entry (group_id, position, name, description, raw_query)
+        VALUES (?, (SELECT COUNT(*) FROM curl_entry WHERE group_id = ?), ?, ?, ?) RETURNING id"#,
+        group_id,
+        group_id,
+        entry.name,
+        entry.description,
+        entry.raw_query
+    )
+    .fetch_one(&mut transaction)
+    .await?
+    .id;
+    record_curl_group_revision(group_id, user_id, &mut transaction).await?;
+    let after = get_curl_entry_summary(entry_id, &mut transaction).await?;
+    record_audit_event(
+        AuditRecord {
+            project_id,
+            actor_id: user_id,
+            action: "curl_entry.create",
+            target: format!("curl_entry:{}", entry_id),
+            before: None,
+            after: Some(after),
+        },
+        request_id,
+        &mut transaction,
+    )
+    .await?;
+    transaction.commit().await?;
+
+    Ok(SavedWithRedactions {
+        id: entry_id,
+        redactions,
+    })
+}
+
+async fn update_curl_entry_in_db(
+    group_id: i64,
+    entry_id: i64,
+    mut entry: CurlEntryRequest,
+    pool: &SqlitePool,
+    identity: &Identity,
+    request_id: &RequestId,
+) -> Result<SavedWithRedactions, ProjectError> {
+    let user_id = get_user_id(identity).await?;
+    let project_id = curl_group_check_user_permission(group_id, pool, identity).await?;
+    check_project_permission(identity, project_id, Permission::EditGroup, pool).await?;
+    validate_curl_entry(&entry.name, &entry.raw_query)?;
+    let policy = get_redaction_policy(project_id, pool).await?;
+    let redactions =
+        apply_redaction_policy(policy, [("raw_query".to_string(), &mut entry.raw_query)])?;
+
+    let mut transaction = pool.begin().await?;
+    let before = get_curl_entry_summary(entry_id, &mut transaction).await?;
+    let result = sqlx::query!(
+        r#"UPDATE curl_entry SET name = ?, description = ?, raw_query = ? WHERE id = ? AND group_id = ?"#,
+        entry.name,
+        entry.description,
+        entry.raw_query,
+        entry_id,
+        group_id
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound.into());
+    }
+    record_curl_group_revision(group_id, user_id, &mut transaction).await?;
+    let after = get_curl_entry_summary(entry_id, &mut transaction).await?;
+    record_audit_event(
+        AuditRecord {
+            project_id,
+            actor_id: user_id,
+            action: "curl_entry.update",
+            target: format!("curl_entry:{}", entry_id),
+            before: Some(before),
+            after: Some(after),
+        },
+        request_id,
+        &mut transaction,
+    )
+    .await?;
+    transaction.commit().await?;
+
+    Ok(SavedWithRedactions {
+        id: entry_id,
+        redactions,
+    })
+}
+
+async fn move_curl_entry_in_db(
+    group_id: i64,
+    entry_id: i64,
+    position: i64,
+    pool: &SqlitePool,
+    identity: &Identity,
+    request_id: &RequestId,
+) -> Result<(), ProjectError> {
+    let user_id = get_user_id(identity).await?;
+    let project_id = curl_group_check_user_permission(group_id, pool, identity).await?;
+    check_project_permission(identity, project_id, Permission::EditGroup, pool).await?;
+
+    let mut transaction = pool.begin().await?;
+    let before = get_curl_entry_summary(entry_id, &mut transaction).await?;
+
+    let current_position = sqlx::query!(
+        r#"SELECT position FROM curl_entry WHERE id = ? AND group_id = ?"#,
+        entry_id,
+        group_id
+    )
+    .fetch_one(&mut transaction)
+    .await?
+    .position;
+
+    let entry_count = sqlx::query!(
+        r#"SELECT COUNT(*) AS "count!: i64" FROM curl_entry WHERE group_id = ?"#,
+        group_id
+    )
+    .fetch_one(&mut transaction)
+    .await?
+    .count;
+
+    // shift the entries between the old and new position to close the gap left behind
+    let position = position.clamp(0, entry_count - 1);
+    if position < current_position {
+        sqlx::query!(
+            r#"UPDATE curl_entry SET position = position + 1 WHERE group_id = ? AND position >= ? AND position < ?"#,
+            group_id,
+            position,
+            current_position
+        )
+        .execute(&mut transaction)
+        .await?;
+    } else if position > current_position {
+        sqlx::query!(
+            r#"UPDATE curl_entry SET position = position - 1 WHERE group_id = ? AND position > ? AND position <= ?"#,
+            group_id,
+            current_position,
+            position
+        )
+        .execute(&mut transaction)
+        .await?;
+    }
+
+    sqlx::query!(
+        r#"UPDATE curl_entry SET position = ? WHERE id = ?"#,
+        position,
+        entry_id
+    )
+    .execute(&mut transaction)
+    .await?;
+    record_curl_group_revision(group_id, user_id, &mut transaction).await?;
+    let after = get_curl_entry_summary(entry_id, &mut transaction).await?;
+    record_audit_event(
+        AuditRecord {
+            project_id,
+            actor_id: user_id,
+            action: "curl_entry.move",
+            target: format!("curl_entry:{}", entry_id),
+            before: Some(before),
+            after: Some(after),
+        },
+        request_id,
+        &mut transaction,
+    )
+    .await?;
+
+    transaction.commit().await?;
+    Ok(())
+}
+
+async fn delete_curl_entry_from_db(
+    group_id: i64,
+    entry_id: i64,
+    pool: &SqlitePool,
+    identity: &Identity,
+    request_id: &RequestId,
+) -> Result<(), ProjectError> {
+    let user_id = get_user_id(identity).await?;
+    let project_id = curl_group_check_user_permission(group_id, pool, identity).await?;
+    check_project_permission(identity, project_id, Permission::EditGroup, pool).await?;
+
+    let mut transaction = pool.begin().await?;
+    let before = get_curl_entry_summary(entry_id, &mut transaction).await?;
+
+    let position = sqlx::query!(
+        r#"DELETE FROM curl_entry WHERE id = ? AND group_id = ? RETURNING position"#,
+        entry_id,
+        group_id
+    )
+    .fetch_one(&mut transaction)
+    .await?
+    .position;
+
+    sqlx::query!(
+        r#"UPDATE curl_entry SET position = position - 1 WHERE group_id = ? AND position > ?"#,
+        group_id,
+        position
+    )
+    .execute(&mut transaction)
+    .await?;
+    record_curl_group_revision(group_id, user_id, &mut transaction).await?;
+    record_audit_event(
+        AuditRecord {
+            project_id,
+            actor_id: user_id,
+            action: "curl_entry.delete",
+            target: format!("curl_entry:{}", entry_id),
+            before: Some(before),
+            after: None,
+        },
+        request_id,
+        &mut transaction,
+    )
+    .await?;
+
+    transaction.commit().await?;
+    Ok(())
+}
+
+/// Brings the entries of a group in line with the curls submitted as part of the group. Curls
+/// carrying the id of one of the group's entries update it in place, the rest are inserted, and
+/// entries that are no longer present are removed. Positions follow the order of `curls`, as do
+/// the returned entry ids.
+pub(crate) async fn replace_curl_entries(
+    group_id: i64,
+    curls: &[Curl],
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> Result<Vec<i64>, ProjectError> {
+    let existing_ids: Vec<i64> =
+        sqlx::query!(r#"SELECT id FROM curl_entry WHERE group_id = ?"#, group_id)
+            .fetch_all(&mut *transaction)
+            .await?
+            .iter()
+            .map(|record| record.id)
+            .collect();
+
+    let mut kept_ids = vec![];
+    let mut ids = vec![];
+    for (position, curl) in curls.iter().enumerate() {
+        let position = position as i64;
+        if existing_ids.contains(&curl.id) && !kept_ids.contains(&curl.id) {
+            sqlx::query!(
+                r#"UPDATE curl_entry SET position = ?, name = ?, description = ?, raw_query = ? WHERE id = ?"#,
+                position,
+                curl.name,
+                curl.description,
+                curl.raw_query,
+                curl.id
+            )
+            .execute(&mut *transaction)
+            .await?;
+            kept_ids.push(curl.id);
+            ids.push(curl.id);
+        } else {
+            let id = sqlx::query!(
+                r#"INSERT INTO curl_entry (group_id, position, name, description, raw_query) VALUES (?, ?, ?, ?, ?) RETURNING id"#,
+                group_id,
+                position,
+                curl.name,
+                curl.description,
+                curl.raw_query
+            )
+            .fetch_one(&mut *transaction)
+            .await?
+            .id;
+            ids.push(id);
+        }
+    }
+
+    for id in existing_ids.iter().filter(|id| !kept_ids.contains(id)) {
+        sqlx::query!(r#"DELETE FROM curl_entry WHERE id = ?"#, id)
+            .execute(&mut *transaction)
+            .await?;
+    }
+
+    Ok(ids)
+}
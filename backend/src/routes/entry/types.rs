@@ -0,0 +1,26 @@
+use crate::snippet::Language;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CurlEntryRequest {
+    pub name: String,
+    pub description: String,
+    pub raw_query: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CurlEntryPosition {
+    pub position: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SnippetParams {
+    pub lang: Language,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Snippet {
+    pub language: Language,
+    pub code: String,
+}
@@ -0,0 +1,14 @@
+mod routes;
+pub mod types;
+
+use actix_web::web;
+use routes::*;
+pub(crate) use routes::{get_environment_variables, get_environments_from_db, import_environment};
+
+pub fn environment_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_environments)
+        .service(create_environment)
+        .service(update_environment)
+        .service(delete_environment)
+        .service(render_curl_group);
+}
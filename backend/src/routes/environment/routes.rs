@@ -0,0 +1,493 @@
+use crate::{
+    helpers::Identity,
+    importer::ImportedEnvironment,
+    models::Environment,
+    routes::project::{
+        check_project_permission, curl_group_check_user_permission, project_check_user_permission,
+        types::{FieldError, Id, Permission, ProjectError},
+    },
+    secrets::SecretCipher,
+    template,
+};
+
+use actix_web::{delete, get, post, web, HttpResponse, Responder};
+use sqlx::{Sqlite, SqlitePool, Transaction};
+use std::collections::BTreeMap;
+
+use super::types::{EnvironmentRequest, RenderParams, RenderedCurl};
+
+#[get("/project/{project_id}/environment")]
+#[tracing::instrument(name = "Getting environments.", skip(pool, identity))]
+async fn get_environments(
+    params: web::Path<i64>,
+    pool: web::Data<SqlitePool>,
+    identity: Identity,
+) -> impl Responder {
+    match get_environments_from_db(*params, &pool, &identity).await {
+        Ok(environments) => HttpResponse::Ok().json(environments),
+        Err(e) => e.into(),
+    }
+}
+
+#[post("/project/{project_id}/environment")]
+#[tracing::instrument(name = "Creating environment.", skip(body, pool, cipher, identity))]
+async fn create_environment(
+    params: web::Path<i64>,
+    body: web::Json<EnvironmentRequest>,
+    pool: web::Data<SqlitePool>,
+    cipher: web::Data<SecretCipher>,
+    identity: Identity,
+) -> impl Responder {
+    match insert_environment_into_db(*params, &body, &pool, &cipher, &identity).await {
+        Ok(id) => HttpResponse::Ok().json(Id { id }),
+        Err(e) => e.into(),
+    }
+}
+
+#[post("/project/{project_id}/environment/{environment_id}")]
+#[tracing::instrument(name = "Updating environment.", skip(body, pool, cipher, identity))]
+async fn update_environment(
+    params: web::Path<(i64, i64)>,
+    body: web::Json<EnvironmentRequest>,
+    pool: web::Data<SqlitePool>,
+    cipher: web::Data<SecretCipher>,
+    identity: Identity,
+) -> impl Responder {
+    let (project_id, environment_id) = params.into_inner();
+    match update_environment_in_db(project_id, environment_id, &body, &pool, &cipher, &identity)
+        .await
+    {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) => e.into(),
+    }
+}
+
+#[delete("/project/{project_id}/environment/{environment_id}")]
+#[tracing::instrument(name = "Deleting environment.", skip(pool, identity))]
+async fn delete_environment(
+    params: web::Path<(i64, i64)>,
+    pool: web::Data<SqlitePool>,
+    identity: Identity,
+) -> impl Responder {
+    let (project_id, environment_id) = params.into_inner();
+    match delete_environment_from_db(project_id, environment_id, &pool, &identity).await {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) => e.into(),
+    }
+}
+
+#[get("/group/{group_id}/render")]
+#[tracing::instrument(name = "Rendering curl group.", skip(pool, cipher, identity))]
+async fn render_curl_group(
+    params: web::Path<i64>,
+    query: web::Query<RenderParams>,
+    pool: web::Data<SqlitePool>,
+    cipher: web::Data<SecretCipher>,
+    identity: Identity,
+) -> impl Responder {
+    match render_curl_group_from_db(*params, &query, &pool, &cipher, &identity).await {
+        Ok(rendered) => HttpResponse::Ok().json(rendered),
+        Err(e) => e.into(),
+    }
+}
+
+pub(crate) async fn get_environments_from_db(
+    project_id: i64,
+    pool: &SqlitePool,
+    identity: &Identity,
+) -> Result<Vec<Environment>, ProjectError> {
+    project_check_user_permission(project_id, pool, identity).await?;
+
+    let mut environments: Vec<Environment> = sqlx::query!(
+        r#"SELECT id, name FROM project_environment WHERE project_id = ? ORDER BY name"#,
+        project_id
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|record| Environment {
+        id: record.id,
+        project_id,
+        name: record.name,
+        variables: BTreeMap::new(),
+        secrets: vec![],
+    })
+    .collect();
+
+    let variables = sqlx::query!(
+        r#"SELECT environment_id, project_environment_variable.name, value, secret AS "secret: bool"
+        FROM project_environment_variable
+        INNER JOIN project_environment ON project_environment.id = project_environment_variable.environment_id
+        WHERE project_environment.project_id = ? ORDER BY project_environment_variable.name"#,
+        project_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for variable in variables {
+        let Some(environment) = environments
+            .iter_mut()
+            .find(|environment| environment.id == variable.environment_id)
+        else {
+            continue;
+        };
+        // secret values never leave the server, only their names do
+        if variable.secret {
+            environment.secrets.push(variable.name);
+        } else {
+            environment.variables.insert(variable.name, variable.value);
+        }
+    }
+
+    Ok(environments)
+}
+
+async fn insert_environment_into_db(
+    project_id: i64,
+    environment: &EnvironmentRequest,
+    pool: &SqlitePool,
+    cipher: &SecretCipher,
+    identity: &Identity,
+) -> Result<i64, ProjectError> {
+    check_project_permission(identity, project_id, Permission::ManageEnvironments, pool).await?;
+    validate_environment(project_id, None, environment, pool).await?;
+
+    let mut transaction = pool.begin().await?;
+    let environment_id = sqlx::query!(
+        r#"INSERT INTO project_environment (project_id, name) VALUES (?, ?) RETURNING id"#,
+        project_id,
+        environment.name
+    )
+    .fetch_one(&mut transaction)
+    .await?
+    .id;
+    replace_variables(environment_id, environment, cipher, &mut transaction).await?;
+    transaction.commit().await?;
+
+    Ok(environment_id)
+}
+
+async fn update_environment_in_db(
+    project_id: i64,
+    environment_id: i64,
+    environment: &EnvironmentRequest,
+    pool: &SqlitePool,
+    cipher: &SecretCipher,
+    identity: &Identity,
+) -> Result<(), ProjectError> {
+    check_project_permission(identity, project_id, Permission::ManageEnvironments, pool).await?;
+    validate_environment(project_id, Some(environment_id), environment, pool).await?;
+
+    let mut transaction = pool.begin().await?;
+    let result = sqlx::query!(
+        r#"UPDATE project_environment SET name = ? WHERE id = ? AND project_id = ?"#,
+        environment.name,
+        environment_id,
+        project_id
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound.into());
+    }
+
+    replace_variables(environment_id, environment, cipher, &mut transaction).await?;
+    transaction.commit().await?;
+
+    Ok(())
+}
+
+async fn delete_environment_from_db(
+    project_id: i64,
+    environment_id: i64,
+    pool: &SqlitePool,
+    identity: &Identity,
+) -> Result<(), ProjectError> {
+    check_project_permission(identity, project_id, Permission::ManageEnvironments, pool).await?;
+
+    let result = sqlx::query!(
+        r#"DELETE FROM project_environment WHERE id = ? AND project_id = ?"#,
+        environment_id,
+        project_id
+    )
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound.into());
+    }
+
+    Ok(())
+}
+
+async fn render_curl_group_from_db(
+    group_id: i64,
+    params: &RenderParams,
+    pool: &SqlitePool,
+    cipher: &SecretCipher,
+    identity: &Identity,
+) -> Result<Vec<RenderedCurl>, ProjectError> {
+    let project_id = curl_group_check_user_permission(group_id, pool, identity).await?;
+    let (mut variables, secrets) = match &params.environment {
+        Some(name) => get_environment_variables(project_id, name, pool).await?,
+        None => (BTreeMap::new(), BTreeMap::new()),
+    };
+
+    // anyone who can read the group may render it, but secrets are only filled in for
+    // members who may edit its groups when they ask for them
+    if params.include_secrets && !secrets.is_empty() {
+        check_project_permission(identity, project_id, Permission::EditGroup, pool).await?;
+        for (name, value) in &secrets {
+            variables.insert(name.clone(), cipher.decrypt(value)?);
+        }
+    }
+
+    let rendered = sqlx::query!(
+        r#"SELECT id, name, raw_query FROM curl_entry WHERE group_id = ? ORDER BY position"#,
+        group_id
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|entry| {
+        let rendered = template::render(&entry.raw_query, &variables);
+        let (withheld, missing) = rendered
+            .missing
+            .into_iter()
+            .partition(|name| secrets.contains_key(name));
+        RenderedCurl {
+            id: entry.id,
+            name: entry.name,
+            raw_query: rendered.text,
+            missing_variables: missing,
+            withheld_variables: withheld,
+        }
+    })
+    .collect();
+
+    Ok(rendered)
+}
+
+/// Plain variables and still encrypted secrets of the named environment of a project, failing
+/// with a 404 when there is no such environment.
+pub(crate) async fn get_environment_variables(
+    project_id: i64,
+    name: &str,
+    pool: &SqlitePool,
+) -> Result<(BTreeMap<String, String>, BTreeMap<String, String>), ProjectError> {
+    let environment_id = sqlx::query!(
+        r#"SELECT id FROM project_environment WHERE project_id = ? AND name = ?"#,
+        project_id,
+        name
+    )
+    .fetch_one(pool)
+    .await?
+    .id;
+
+    let mut variables = BTreeMap::new();
+    let mut secrets = BTreeMap::new();
+    let records = sqlx::query!(
+        r#"SELECT name, value, secret AS "secret: bool" FROM project_environment_variable WHERE environment_id = ?"#,
+        environment_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for record in records {
+        if record.secret {
+            secrets.insert(record.name, record.value);
+        } else {
+            variables.insert(record.name, record.value);
+        }
+    }
+
+    Ok((variables, secrets))
+}
+
+/// Creates the environment, or merges its variables into the project's environment of the same
+/// name, replacing any variable already there with the same name.
+pub(crate) async fn import_environment(
+    project_id: i64,
+    imported: &ImportedEnvironment,
+    cipher: &SecretCipher,
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> Result<(), ProjectError> {
+    if imported.name.trim().is_empty() {
+        return Err(ProjectError::ValidationError(vec![FieldError::new(
+            "environments",
+            "Environments must have a name.",
+        )]));
+    }
+
+    let existing_id = sqlx::query!(
+        r#"SELECT id FROM project_environment WHERE project_id = ? AND name = ?"#,
+        project_id,
+        imported.name
+    )
+    .fetch_optional(&mut *transaction)
+    .await?
+    .map(|record| record.id);
+    let environment_id =
+        match existing_id {
+            Some(id) => id,
+            None => sqlx::query!(
+                r#"INSERT INTO project_environment (project_id, name) VALUES (?, ?) RETURNING id"#,
+                project_id,
+                imported.name
+            )
+            .fetch_one(&mut *transaction)
+            .await?
+            .id,
+        };
+
+    let mut environment = EnvironmentRequest {
+        name: imported.name.clone(),
+        variables: BTreeMap::new(),
+        secrets: BTreeMap::new(),
+    };
+    let existing = sqlx::query!(
+        r#"SELECT name, value, secret AS "secret: bool" FROM project_environment_variable WHERE environment_id = ?"#,
+        environment_id
+    )
+    .fetch_all(&mut *transaction)
+    .await?;
+    for record in existing {
+        if imported.variables.contains_key(&record.name)
+            || imported.secrets.contains_key(&record.name)
+        {
+            continue;
+        }
+        if record.secret {
+            environment.secrets.insert(record.name, None);
+        } else {
+            environment.variables.insert(record.name, record.value);
+        }
+    }
+    environment.variables.extend(imported.variables.clone());
+    environment.secrets.extend(
+        imported
+            .secrets
+            .iter()
+            .map(|(name, value)| (name.clone(), Some(value.clone()))),
+    );
+
+    replace_variables(environment_id, &environment, cipher, transaction).await
+}
+
+/// Secrets submitted without a value keep the one already stored, so clients never need to know
+/// the value to save an environment.
+async fn replace_variables(
+    environment_id: i64,
+    environment: &EnvironmentRequest,
+    cipher: &SecretCipher,
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> Result<(), ProjectError> {
+    let existing_secrets: BTreeMap<String, String> = sqlx::query!(
+        r#"SELECT name, value FROM project_environment_variable WHERE environment_id = ? AND secret = 1"#,
+        environment_id
+    )
+    .fetch_all(&mut *transaction)
+    .await?
+    .into_iter()
+    .map(|record| (record.name, record.value))
+    .collect();
+
+    sqlx::query!(
+        r#"DELETE FROM project_environment_variable WHERE environment_id = ?"#,
+        environment_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    for (name, value) in &environment.variables {
+        sqlx::query!(
+            r#"INSERT INTO project_environment_variable (environment_id, name, value) VALUES (?, ?, ?)"#,
+            environment_id,
+            name,
+            value
+        )
+        .execute(&mut *transaction)
+        .await?;
+    }
+
+    for (name, value) in &environment.secrets {
+        let value = match value {
+            Some(value) => cipher.encrypt(value),
+            None => existing_secrets.get(name).cloned().ok_or_else(|| {
+                ProjectError::ValidationError(vec![FieldError::new(
+                    format!("secrets.{}", name),
+                    "New secrets need a value.",
+                )])
+            })?,
+        };
+        sqlx::query!(
+            r#"INSERT INTO project_environment_variable (environment_id, name, value, secret) VALUES (?, ?, ?, 1)"#,
+            environment_id,
+            name,
+            value
+        )
+        .execute(&mut *transaction)
+        .await?;
+    }
+
+    Ok(())
+}
+
+async fn validate_environment(
+    project_id: i64,
+    environment_id: Option<i64>,
+    environment: &EnvironmentRequest,
+    pool: &SqlitePool,
+) -> Result<(), ProjectError> {
+    let mut errors = vec![];
+
+    if environment.name.trim().is_empty() {
+        errors.push(FieldError::new("name", "Name must not be empty."));
+    }
+
+    let name_taken = sqlx::query!(
+        r#"SELECT id FROM project_environment WHERE project_id = ? AND name = ? AND id IS NOT ?"#,
+        project_id,
+        environment.name,
+        environment_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .is_some();
+    if name_taken {
+        errors.push(FieldError::new(
+            "name",
+            "An environment with this name already exists.",
+        ));
+    }
+
+    for name in environment.variables.keys() {
+        if !template::is_valid_variable_name(name) {
+            errors.push(FieldError::new(
+                format!("variables.{}", name),
+                "Variable names may only contain letters, digits and underscores, and must not start with a digit.",
+            ));
+        }
+    }
+
+    for name in environment.secrets.keys() {
+        if !template::is_valid_variable_name(name) {
+            errors.push(FieldError::new(
+                format!("secrets.{}", name),
+                "Variable names may only contain letters, digits and underscores, and must not start with a digit.",
+            ));
+        } else if environment.variables.contains_key(name) {
+            errors.push(FieldError::new(
+                format!("secrets.{}", name),
+                "A variable with this name already exists.",
+            ));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ProjectError::ValidationError(errors))
+    }
+}
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct EnvironmentRequest {
+    pub name: String,
+    #[serde(default)]
+    pub variables: BTreeMap<String, String>,
+    /// Secret values are write only; `null` keeps the value already stored.
+    #[serde(default)]
+    pub secrets: BTreeMap<String, Option<String>>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RenderParams {
+    /// Name of the environment to render with; without one only the missing variables are reported.
+    pub environment: Option<String>,
+    pub include_secrets: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct RenderedCurl {
+    pub id: i64,
+    pub name: String,
+    pub raw_query: String,
+    pub missing_variables: Vec<String>,
+    /// Secrets the command uses that were left as placeholders.
+    pub withheld_variables: Vec<String>,
+}
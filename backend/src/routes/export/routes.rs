@@ -0,0 +1,140 @@
+use crate::{
+    curl,
+    har::{self, Har},
+    helpers::Identity,
+    postman::{self, Item},
+    routes::{
+        environment::{get_environment_variables, get_environments_from_db},
+        project::{
+            curl_group_check_user_permission,
+            types::{FieldError, ProjectError},
+        },
+    },
+    template,
+};
+
+use actix_web::{get, web, HttpResponse, Responder};
+use sqlx::SqlitePool;
+use std::collections::BTreeMap;
+
+use super::types::{ExportFormat, ExportParams, PostmanExport};
+
+#[get("/group/{group_id}/export")]
+#[tracing::instrument(name = "Exporting curl group.", skip(pool, identity))]
+async fn export_curl_group(
+    params: web::Path<i64>,
+    query: web::Query<ExportParams>,
+    pool: web::Data<SqlitePool>,
+    identity: Identity,
+) -> impl Responder {
+    let export = match query.format {
+        ExportFormat::Postman => export_postman_from_db(*params, &pool, &identity)
+            .await
+            .map(|export| HttpResponse::Ok().json(export)),
+        ExportFormat::Har => {
+            export_har_from_db(*params, query.environment.as_deref(), &pool, &identity)
+                .await
+                .map(|export| HttpResponse::Ok().json(export))
+        }
+    };
+
+    match export {
+        Ok(response) => response,
+        Err(e) => e.into(),
+    }
+}
+
+async fn export_postman_from_db(
+    group_id: i64,
+    pool: &SqlitePool,
+    identity: &Identity,
+) -> Result<PostmanExport, ProjectError> {
+    let project_id = curl_group_check_user_permission(group_id, pool, identity).await?;
+
+    let group = sqlx::query!(
+        r#"SELECT name, description FROM curl_group WHERE id = ?"#,
+        group_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let entries = sqlx::query!(
+        r#"SELECT name, description, raw_query FROM curl_entry WHERE group_id = ? ORDER BY position"#,
+        group_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut items: Vec<Item> = vec![];
+    let mut errors = vec![];
+    for (index, entry) in entries.iter().enumerate() {
+        match curl::parse(&entry.raw_query) {
+            Ok(request) => items.push(postman::export_request(
+                &entry.name,
+                &entry.description,
+                &request,
+            )),
+            Err(e) => errors.push(FieldError::new(
+                format!("entries[{}].raw_query", index),
+                format!("\"{}\" cannot be exported: {}", entry.name, e),
+            )),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(ProjectError::ValidationError(errors));
+    }
+
+    let environments = get_environments_from_db(project_id, pool, identity)
+        .await?
+        .iter()
+        .map(postman::export_environment)
+        .collect();
+
+    Ok(PostmanExport {
+        collection: postman::export_collection(&group.name, &group.description, items),
+        environments,
+    })
+}
+
+async fn export_har_from_db(
+    group_id: i64,
+    environment: Option<&str>,
+    pool: &SqlitePool,
+    identity: &Identity,
+) -> Result<Har, ProjectError> {
+    let project_id = curl_group_check_user_permission(group_id, pool, identity).await?;
+    let variables = match environment {
+        Some(name) => get_environment_variables(project_id, name, pool).await?.0,
+        None => BTreeMap::new(),
+    };
+
+    let entries = sqlx::query!(
+        r#"SELECT name, raw_query, strftime('%Y-%m-%dT%H:%M:%fZ', 'now') AS "started_date_time!: String"
+        FROM curl_entry WHERE group_id = ? ORDER BY position"#,
+        group_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut har_entries = vec![];
+    let mut errors = vec![];
+    for (index, entry) in entries.iter().enumerate() {
+        let raw_query = template::render(&entry.raw_query, &variables).text;
+        match curl::parse(&raw_query) {
+            Ok(request) => har_entries.push(har::export_entry(
+                &entry.name,
+                &request,
+                &entry.started_date_time,
+            )),
+            Err(e) => errors.push(FieldError::new(
+                format!("entries[{}].raw_query", index),
+                format!("\"{}\" cannot be exported: {}", entry.name, e),
+            )),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(ProjectError::ValidationError(errors));
+    }
+
+    Ok(har::export_log(har_entries))
+}
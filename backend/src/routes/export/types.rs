@@ -0,0 +1,26 @@
+use crate::postman::{Collection, PostmanEnvironment};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Postman,
+    Har,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportParams {
+    pub format: ExportFormat,
+    /// Fills the HAR file's requests in with the variables of this environment, as HAR has no
+    /// placeholders of its own. Secrets are never filled in.
+    pub environment: Option<String>,
+}
+
+/// The collection with one environment file per project environment, each to be imported on its
+/// own.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct PostmanExport {
+    pub collection: Collection,
+    pub environments: Vec<PostmanEnvironment>,
+}
@@ -0,0 +1,28 @@
+mod routes;
+pub mod types;
+
+use actix_web::web;
+use routes::*;
+
+/// Browsers include response bodies in HAR files, which quickly outgrow the default JSON limit, as
+/// do the OpenAPI documents of larger APIs.
+const MAX_IMPORT_SIZE: usize = 50 * 1024 * 1024;
+
+pub fn import_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(import_collection)
+        .service(
+            web::resource("/project/{project_id}/import/har")
+                .app_data(web::JsonConfig::default().limit(MAX_IMPORT_SIZE))
+                .route(web::post().to(import_har)),
+        )
+        .service(
+            web::resource("/project/{project_id}/import/har/entries")
+                .app_data(web::JsonConfig::default().limit(MAX_IMPORT_SIZE))
+                .route(web::post().to(get_har_entries)),
+        )
+        .service(
+            web::resource("/project/{project_id}/import/openapi")
+                .app_data(web::JsonConfig::default().limit(MAX_IMPORT_SIZE))
+                .route(web::post().to(import_openapi)),
+        );
+}
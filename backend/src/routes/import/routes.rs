@@ -0,0 +1,489 @@
+use crate::{
+    har::{self, Har},
+    helpers::{get_user_id, Identity},
+    importer::{self, Imported, ImportedGroup, Skipped},
+    models::Curl,
+    openapi,
+    routes::{
+        audit::{record_audit_event, AuditRecord},
+        entry::replace_curl_entries,
+        environment::import_environment,
+        project::{
+            apply_redaction_policy, check_project_permission, get_redaction_policy,
+            types::{FieldError, Permission, ProjectError, Redaction},
+        },
+        revision::record_curl_group_revision,
+    },
+    secrets::SecretCipher,
+};
+
+use actix_web::{post, web, HttpResponse, Responder};
+use serde_json::{json, Value};
+use sqlx::{Sqlite, SqlitePool, Transaction};
+use tracing_actix_web::RequestId;
+
+use super::types::{
+    HarEntrySummary, HarFile, HarImportRequest, ImportRequest, ImportResult, ImportedGroupSummary,
+    OpenApiImportRequest,
+};
+
+#[post("/project/{project_id}/import")]
+#[tracing::instrument(name = "Importing collection.", skip(body, pool, cipher, identity))]
+async fn import_collection(
+    params: web::Path<i64>,
+    body: web::Json<ImportRequest>,
+    pool: web::Data<SqlitePool>,
+    cipher: web::Data<SecretCipher>,
+    identity: Identity,
+    request_id: RequestId,
+) -> impl Responder {
+    match import_collection_into_db(*params, &body, &pool, &cipher, &identity, &request_id).await {
+        Ok(result) => HttpResponse::Ok().json(result),
+        Err(e) => e.into(),
+    }
+}
+
+#[tracing::instrument(name = "Importing HAR file.", skip(body, pool, identity))]
+pub(super) async fn import_har(
+    params: web::Path<i64>,
+    body: web::Json<HarImportRequest>,
+    pool: web::Data<SqlitePool>,
+    identity: Identity,
+    request_id: RequestId,
+) -> impl Responder {
+    match import_har_into_db(*params, &body, &pool, &identity, &request_id).await {
+        Ok(result) => HttpResponse::Ok().json(result),
+        Err(e) => e.into(),
+    }
+}
+
+#[tracing::instrument(name = "Listing HAR entries.", skip(body, pool, identity))]
+pub(super) async fn get_har_entries(
+    params: web::Path<i64>,
+    body: web::Json<HarFile>,
+    pool: web::Data<SqlitePool>,
+    identity: Identity,
+) -> impl Responder {
+    match summarize_har_entries(*params, &body, &pool, &identity).await {
+        Ok(entries) => HttpResponse::Ok().json(entries),
+        Err(e) => e.into(),
+    }
+}
+
+#[tracing::instrument(
+    name = "Importing OpenAPI document.",
+    skip(body, pool, cipher, identity)
+)]
+pub(super) async fn import_openapi(
+    params: web::Path<i64>,
+    body: web::Json<OpenApiImportRequest>,
+    pool: web::Data<SqlitePool>,
+    cipher: web::Data<SecretCipher>,
+    identity: Identity,
+    request_id: RequestId,
+) -> impl Responder {
+    match import_openapi_into_db(*params, &body, &pool, &cipher, &identity, &request_id).await {
+        Ok(result) => HttpResponse::Ok().json(result),
+        Err(e) => e.into(),
+    }
+}
+
+/// Everything is imported in one transaction, so a failed import leaves the project untouched.
+async fn import_collection_into_db(
+    project_id: i64,
+    request: &ImportRequest,
+    pool: &SqlitePool,
+    cipher: &SecretCipher,
+    identity: &Identity,
+    request_id: &RequestId,
+) -> Result<ImportResult, ProjectError> {
+    let user_id = get_user_id(identity).await?;
+    check_project_permission(identity, project_id, Permission::CreateGroup, pool).await?;
+
+    let imported = importer::import(&request.content, &request.environments).map_err(|e| {
+        ProjectError::ValidationError(vec![FieldError::new("content", e.to_string())])
+    })?;
+    store_imported(project_id, user_id, imported, pool, cipher, request_id).await
+}
+
+async fn import_openapi_into_db(
+    project_id: i64,
+    request: &OpenApiImportRequest,
+    pool: &SqlitePool,
+    cipher: &SecretCipher,
+    identity: &Identity,
+    request_id: &RequestId,
+) -> Result<ImportResult, ProjectError> {
+    let user_id = get_user_id(identity).await?;
+    check_project_permission(identity, project_id, Permission::CreateGroup, pool).await?;
+
+    let document = openapi::parse_document(&request.spec)
+        .map_err(|e| ProjectError::ValidationError(vec![FieldError::new("spec", e.to_string())]))?;
+    let imported = openapi::import_document(&document);
+    if imported.groups.is_empty() {
+        return Err(ProjectError::ValidationError(vec![FieldError::new(
+            "spec",
+            "The document defines no operations.",
+        )]));
+    }
+    store_imported(project_id, user_id, imported, pool, cipher, request_id).await
+}
+
+async fn store_imported(
+    project_id: i64,
+    user_id: i64,
+    imported: Imported,
+    pool: &SqlitePool,
+    cipher: &SecretCipher,
+    request_id: &RequestId,
+) -> Result<ImportResult, ProjectError> {
+    let Imported {
+        mut groups,
+        environments,
+        skipped,
+    } = imported;
+
+    let redactions = redact_imported_groups(project_id, &mut groups, pool).await?;
+
+    let mut transaction = pool.begin().await?;
+    let summaries = insert_imported_groups(project_id, user_id, &groups, &mut transaction).await?;
+    for environment in environments.iter() {
+        import_environment(project_id, environment, cipher, &mut transaction).await?;
+    }
+    let environments: Vec<String> = environments
+        .into_iter()
+        .map(|environment| environment.name)
+        .collect();
+    record_import(
+        project_id,
+        user_id,
+        &summaries,
+        &environments,
+        request_id,
+        &mut transaction,
+    )
+    .await?;
+    transaction.commit().await?;
+
+    Ok(ImportResult {
+        groups: summaries,
+        environments,
+        skipped,
+        redactions,
+    })
+}
+
+async fn import_har_into_db(
+    project_id: i64,
+    request: &HarImportRequest,
+    pool: &SqlitePool,
+    identity: &Identity,
+    request_id: &RequestId,
+) -> Result<ImportResult, ProjectError> {
+    let user_id = get_user_id(identity).await?;
+    check_project_permission(identity, project_id, Permission::CreateGroup, pool).await?;
+    let har = parse_har(&request.har)?;
+    let har_entries = &har.log.entries;
+
+    let mut errors = vec![];
+    if request.name.trim().is_empty() {
+        errors.push(FieldError::new("name", "Name must not be empty."));
+    }
+    let indices: Vec<usize> = match &request.entries {
+        Some(indices) => {
+            for (position, index) in indices.iter().enumerate() {
+                if *index >= har_entries.len() {
+                    errors.push(FieldError::new(
+                        format!("entries[{}]", position),
+                        "The HAR file has no such entry.",
+                    ));
+                }
+            }
+            indices.clone()
+        }
+        None => (0..har_entries.len())
+            .filter(|&index| request.include_static || !har::is_static(&har_entries[index]))
+            .collect(),
+    };
+    if !errors.is_empty() {
+        return Err(ProjectError::ValidationError(errors));
+    }
+
+    let mut entries = vec![];
+    let mut skipped = vec![];
+    for index in indices {
+        let entry = &har_entries[index];
+        match har::import_entry(entry) {
+            Ok(imported) => entries.push(imported),
+            Err(reason) => skipped.push(Skipped::new(
+                format!("{} {}", entry.request.method, entry.request.url),
+                reason,
+            )),
+        }
+    }
+    if entries.is_empty() {
+        return Err(ProjectError::ValidationError(vec![FieldError::new(
+            "entries",
+            "None of the entries can be imported.",
+        )]));
+    }
+
+    let mut groups = vec![ImportedGroup {
+        name: request.name.clone(),
+        description: request.description.clone(),
+        entries,
+        source: None,
+    }];
+    let redactions = redact_imported_groups(project_id, &mut groups, pool).await?;
+
+    let mut transaction = pool.begin().await?;
+    let summaries = insert_imported_groups(project_id, user_id, &groups, &mut transaction).await?;
+    record_import(
+        project_id,
+        user_id,
+        &summaries,
+        &[],
+        request_id,
+        &mut transaction,
+    )
+    .await?;
+    transaction.commit().await?;
+
+    Ok(ImportResult {
+        groups: summaries,
+        environments: vec![],
+        skipped,
+        redactions,
+    })
+}
+
+async fn summarize_har_entries(
+    project_id: i64,
+    file: &HarFile,
+    pool: &SqlitePool,
+    identity: &Identity,
+) -> Result<Vec<HarEntrySummary>, ProjectError> {
+    check_project_permission(identity, project_id, Permission::CreateGroup, pool).await?;
+    let har = parse_har(&file.har)?;
+
+    Ok(har
+        .log
+        .entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| HarEntrySummary {
+            index,
+            method: entry.request.method.clone(),
+            url: entry.request.url.clone(),
+            status: entry.response.status,
+            mime_type: entry.response.content.mime_type.clone(),
+            is_static: har::is_static(entry),
+        })
+        .collect())
+}
+
+fn parse_har(har: &Value) -> Result<Har, ProjectError> {
+    serde_json::from_value(har.clone()).map_err(|e| {
+        ProjectError::ValidationError(vec![FieldError::new(
+            "har",
+            format!("Not a valid HAR file: {}", e),
+        )])
+    })
+}
+
+/// Applies the project's redaction policy to the generated commands.
+async fn redact_imported_groups(
+    project_id: i64,
+    groups: &mut [ImportedGroup],
+    pool: &SqlitePool,
+) -> Result<Vec<Redaction>, ProjectError> {
+    let policy = get_redaction_policy(project_id, pool).await?;
+    apply_redaction_policy(
+        policy,
+        groups
+            .iter_mut()
+            .enumerate()
+            .flat_map(|(group_index, group)| {
+                group
+                    .entries
+                    .iter_mut()
+                    .enumerate()
+                    .map(move |(index, entry)| {
+                        (
+                            format!("groups[{}].entries[{}].raw_query", group_index, index),
+                            &mut entry.raw_query,
+                        )
+                    })
+            }),
+    )
+}
+
+/// One event covers the whole import, listing the groups it created or updated.
+async fn record_import(
+    project_id: i64,
+    user_id: i64,
+    groups: &[ImportedGroupSummary],
+    environments: &[String],
+    request_id: &RequestId,
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> Result<(), ProjectError> {
+    record_audit_event(
+        AuditRecord {
+            project_id,
+            actor_id: user_id,
+            action: "project.import",
+            target: format!("project:{}", project_id),
+            before: None,
+            after: Some(json!({ "groups": groups, "environments": environments })),
+        },
+        request_id,
+        &mut *transaction,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Groups generated from a document before are updated rather than inserted again.
+async fn insert_imported_groups(
+    project_id: i64,
+    user_id: i64,
+    groups: &[ImportedGroup],
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> Result<Vec<ImportedGroupSummary>, ProjectError> {
+    let mut summaries = vec![];
+    for group in groups {
+        if let Some(source) = &group.source {
+            summaries
+                .push(sync_generated_group(project_id, user_id, source, group, transaction).await?);
+            continue;
+        }
+
+        let curls: Vec<Curl> = group
+            .entries
+            .iter()
+            .map(|entry| Curl {
+                id: 0,
+                name: entry.name.clone(),
+                description: entry.description.clone(),
+                raw_query: entry.raw_query.clone(),
+            })
+            .collect();
+        let curl_group_id = sqlx::query!(r#"INSERT INTO curl_group (description, labels, name, project_id) VALUES (?, '', ?, ?) RETURNING id"#,
+            group.description, group.name, project_id)
+            .fetch_one(&mut *transaction)
+            .await?.id;
+        replace_curl_entries(curl_group_id, &curls, &mut *transaction).await?;
+        record_curl_group_revision(curl_group_id, user_id, &mut *transaction).await?;
+        summaries.push(ImportedGroupSummary {
+            id: curl_group_id,
+            name: group.name.clone(),
+            entries: curls.len(),
+        });
+    }
+    Ok(summaries)
+}
+
+/// Entries added to the group by hand are kept. Generated entries are replaced by the
+/// operations they were generated from, and removed along with them.
+async fn sync_generated_group(
+    project_id: i64,
+    user_id: i64,
+    source: &str,
+    group: &ImportedGroup,
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> Result<ImportedGroupSummary, ProjectError> {
+    let existing = sqlx::query!(
+        r#"SELECT curl_group.id, curl_group.name FROM curl_group_source JOIN curl_group ON curl_group.id = curl_group_source.group_id WHERE curl_group_source.project_id = ? AND curl_group_source.source = ? AND curl_group.deleted_at IS NULL"#,
+        project_id,
+        source
+    )
+    .fetch_optional(&mut *transaction)
+    .await?;
+
+    let (curl_group_id, name) = match existing {
+        Some(record) => (record.id, record.name),
+        None => {
+            let curl_group_id = sqlx::query!(r#"INSERT INTO curl_group (description, labels, name, project_id) VALUES (?, '', ?, ?) RETURNING id"#,
+                group.description, group.name, project_id)
+                .fetch_one(&mut *transaction)
+                .await?.id;
+            // takes the source over from a group of the same source sitting in the trash
+            sqlx::query!(
+                r#"INSERT OR REPLACE INTO curl_group_source (group_id, project_id, source) VALUES (?, ?, ?)"#,
+                curl_group_id,
+                project_id,
+                source
+            )
+            .execute(&mut *transaction)
+            .await?;
+            (curl_group_id, group.name.clone())
+        }
+    };
+
+    let existing_entries = sqlx::query!(
+        r#"SELECT curl_entry.id, curl_entry.name, curl_entry.description, curl_entry.raw_query, curl_entry_source.operation AS "operation?" FROM curl_entry LEFT JOIN curl_entry_source ON curl_entry_source.entry_id = curl_entry.id WHERE curl_entry.group_id = ? ORDER BY curl_entry.position"#,
+        curl_group_id
+    )
+    .fetch_all(&mut *transaction)
+    .await?;
+
+    let mut curls = vec![];
+    let mut operations = vec![];
+    for record in existing_entries {
+        let Some(operation) = record.operation else {
+            curls.push(Curl {
+                id: record.id,
+                name: record.name,
+                description: record.description,
+                raw_query: record.raw_query,
+            });
+            operations.push(None);
+            continue;
+        };
+        if let Some(entry) = group
+            .entries
+            .iter()
+            .find(|entry| entry.source.as_ref() == Some(&operation))
+        {
+            curls.push(Curl {
+                id: record.id,
+                name: entry.name.clone(),
+                description: entry.description.clone(),
+                raw_query: entry.raw_query.clone(),
+            });
+            operations.push(Some(operation));
+        }
+    }
+    for entry in group.entries.iter() {
+        if !operations.contains(&entry.source) {
+            curls.push(Curl {
+                id: 0,
+                name: entry.name.clone(),
+                description: entry.description.clone(),
+                raw_query: entry.raw_query.clone(),
+            });
+            operations.push(entry.source.clone());
+        }
+    }
+
+    let ids = replace_curl_entries(curl_group_id, &curls, &mut *transaction).await?;
+    for (id, operation) in ids.iter().zip(operations.iter()) {
+        if let Some(operation) = operation {
+            sqlx::query!(
+                r#"INSERT OR REPLACE INTO curl_entry_source (entry_id, operation) VALUES (?, ?)"#,
+                id,
+                operation
+            )
+            .execute(&mut *transaction)
+            .await?;
+        }
+    }
+
+    record_curl_group_revision(curl_group_id, user_id, &mut *transaction).await?;
+
+    Ok(ImportedGroupSummary {
+        id: curl_group_id,
+        name,
+        entries: curls.len(),
+    })
+}
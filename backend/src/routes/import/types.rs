@@ -0,0 +1,64 @@
+use crate::{importer::Skipped, routes::project::types::Redaction};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A Postman v2.1 collection with its environment files, or an Insomnia v4 export in `content`.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ImportRequest {
+    pub content: Value,
+    #[serde(default)]
+    pub environments: Vec<Value>,
+}
+
+/// Names the group the picked entries of a HAR file are imported into.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct HarImportRequest {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub har: Value,
+    /// Indices into `log.entries` to import. Without them, every entry but the page's static
+    /// assets is imported.
+    pub entries: Option<Vec<usize>>,
+    #[serde(default)]
+    pub include_static: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct HarFile {
+    pub har: Value,
+}
+
+/// An OpenAPI 3.0 or 3.1 document, in JSON or YAML.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct OpenApiImportRequest {
+    pub spec: String,
+}
+
+/// What the entries of a HAR file are, so some can be picked for import.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct HarEntrySummary {
+    pub index: usize,
+    pub method: String,
+    pub url: String,
+    pub status: i64,
+    pub mime_type: String,
+    pub is_static: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ImportResult {
+    pub groups: Vec<ImportedGroupSummary>,
+    /// Names of the environments created or merged into.
+    pub environments: Vec<String>,
+    pub skipped: Vec<Skipped>,
+    pub redactions: Vec<Redaction>,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ImportedGroupSummary {
+    pub id: i64,
+    pub name: String,
+    pub entries: usize,
+}
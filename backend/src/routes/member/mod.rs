@@ -0,0 +1,14 @@
+mod routes;
+pub mod types;
+
+use actix_web::web;
+use routes::*;
+pub(crate) use routes::{get_admins_and_collaborators, set_project_members};
+
+pub fn member_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_roles)
+        .service(get_members)
+        .service(get_member)
+        .service(set_member_role)
+        .service(remove_member);
+}
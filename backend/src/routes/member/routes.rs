@@ -0,0 +1,488 @@
+use crate::{
+    helpers::{get_user_id, Identity},
+    routes::{
+        audit::{record_audit_event, AuditRecord},
+        project::{
+            check_project_permission, project_check_user_permission,
+            types::{FieldError, Permission, ProjectError, Role},
+        },
+    },
+};
+
+use actix_web::{delete, get, put, web, HttpResponse, Responder};
+use serde_json::json;
+use sqlx::{Executor, Sqlite, SqlitePool, Transaction};
+use tracing_actix_web::RequestId;
+
+use super::types::{Member, MemberRoleRequest, RoleInfo};
+
+#[get("/roles")]
+#[tracing::instrument(name = "Getting project roles.", skip(pool))]
+async fn get_roles(pool: web::Data<SqlitePool>) -> impl Responder {
+    match get_roles_from_db(&pool).await {
+        Ok(roles) => HttpResponse::Ok().json(roles),
+        Err(e) => e.into(),
+    }
+}
+
+#[get("/project/{project_id}/members")]
+#[tracing::instrument(name = "Getting project members.", skip(pool, identity))]
+async fn get_members(
+    params: web::Path<i64>,
+    pool: web::Data<SqlitePool>,
+    identity: Identity,
+) -> impl Responder {
+    let members = match project_check_user_permission(*params, &pool, &identity).await {
+        Ok(_) => get_members_from_db(*params, pool.get_ref()).await,
+        Err(e) => Err(e),
+    };
+
+    match members {
+        Ok(members) => HttpResponse::Ok().json(members),
+        Err(e) => e.into(),
+    }
+}
+
+#[get("/project/{project_id}/members/{username}")]
+#[tracing::instrument(name = "Getting project member.", skip(pool, identity))]
+async fn get_member(
+    params: web::Path<(i64, String)>,
+    pool: web::Data<SqlitePool>,
+    identity: Identity,
+) -> impl Responder {
+    let (project_id, username) = params.into_inner();
+    let members = match project_check_user_permission(project_id, &pool, &identity).await {
+        Ok(_) => get_members_from_db(project_id, pool.get_ref()).await,
+        Err(e) => Err(e),
+    };
+
+    match members.map(|members| {
+        members
+            .into_iter()
+            .find(|member| member.username == username)
+    }) {
+        Ok(Some(member)) => HttpResponse::Ok().json(member),
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(e) => e.into(),
+    }
+}
+
+#[put("/project/{project_id}/members/{username}")]
+#[tracing::instrument(name = "Setting project member role.", skip(body, pool, identity))]
+async fn set_member_role(
+    params: web::Path<(i64, String)>,
+    body: web::Json<MemberRoleRequest>,
+    pool: web::Data<SqlitePool>,
+    identity: Identity,
+    request_id: RequestId,
+) -> impl Responder {
+    let (project_id, username) = params.into_inner();
+    match set_member_role_in_db(
+        project_id,
+        &username,
+        body.role,
+        &pool,
+        &identity,
+        &request_id,
+    )
+    .await
+    {
+        Ok(member) => HttpResponse::Ok().json(member),
+        Err(e) => e.into(),
+    }
+}
+
+#[delete("/project/{project_id}/members/{username}")]
+#[tracing::instrument(name = "Removing project member.", skip(pool, identity))]
+async fn remove_member(
+    params: web::Path<(i64, String)>,
+    pool: web::Data<SqlitePool>,
+    identity: Identity,
+    request_id: RequestId,
+) -> impl Responder {
+    let (project_id, username) = params.into_inner();
+    match remove_member_from_db(project_id, &username, &pool, &identity, &request_id).await {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) => e.into(),
+    }
+}
+
+/// Owners and admins count as a project's admins, every other role as a collaborator.
+pub(crate) async fn get_admins_and_collaborators<'e, E>(
+    project_id: i64,
+    executor: E,
+) -> Result<(Vec<String>, Vec<String>), ProjectError>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let (admins, collaborators): (Vec<Member>, Vec<Member>) =
+        get_members_from_db(project_id, executor)
+            .await?
+            .into_iter()
+            .partition(|member| member.role.is_admin());
+
+    Ok((
+        admins.into_iter().map(|member| member.username).collect(),
+        collaborators
+            .into_iter()
+            .map(|member| member.username)
+            .collect(),
+    ))
+}
+
+/// Highest role first, each role in the order its members joined.
+async fn get_members_from_db<'e, E>(
+    project_id: i64,
+    executor: E,
+) -> Result<Vec<Member>, ProjectError>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let records = sqlx::query!(
+        r#"SELECT user.name, project_member.role FROM project_member
+        JOIN user ON user.id = project_member.user_id
+        JOIN project_role ON project_role.name = project_member.role
+        WHERE project_member.project_id = ? ORDER BY project_role.rank, project_member.rowid"#,
+        project_id
+    )
+    .fetch_all(executor)
+    .await?;
+
+    records
+        .into_iter()
+        .map(|record| {
+            Ok(Member {
+                username: record.name,
+                role: parse_role(&record.role)?,
+            })
+        })
+        .collect()
+}
+
+async fn get_roles_from_db(pool: &SqlitePool) -> Result<Vec<RoleInfo>, ProjectError> {
+    let records = sqlx::query!(
+        r#"SELECT project_role.name, project_role_permission.permission AS "permission?" FROM project_role
+        LEFT JOIN project_role_permission ON project_role_permission.role = project_role.name
+        ORDER BY project_role.rank"#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut roles: Vec<RoleInfo> = vec![];
+    for record in records {
+        let name = parse_role(&record.name)?;
+        if roles.last().map(|role| role.name) != Some(name) {
+            roles.push(RoleInfo {
+                name,
+                permissions: vec![],
+            });
+        }
+        if let Some(permission) = record.permission.as_deref().and_then(Permission::from_name) {
+            roles.last_mut().unwrap().permissions.push(permission);
+        }
+    }
+    for role in &mut roles {
+        role.permissions
+            .sort_by_key(|permission| Permission::ALL.iter().position(|other| other == permission));
+    }
+
+    Ok(roles)
+}
+
+/// Replaces the project's members with the given lists in one go, as saving a whole project does.
+/// Every name has to belong to a user, and nobody can be listed twice. Members keep their role
+/// while they stay on the same list; anyone new becomes an admin or an editor.
+pub(crate) async fn set_project_members(
+    project_id: i64,
+    actor_id: i64,
+    admins: &[String],
+    collaborators: &[String],
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> Result<(), ProjectError> {
+    let mut errors = vec![];
+    if admins.is_empty() {
+        errors.push(last_owner_error("admins"));
+    }
+    let mut admin_ids = vec![];
+    let mut collaborator_ids = vec![];
+    for (field, names, ids) in [
+        ("admins", admins, &mut admin_ids),
+        ("collaborators", collaborators, &mut collaborator_ids),
+    ] {
+        for name in names {
+            match find_user_id(name, &mut *transaction).await? {
+                Some(id) => ids.push(id),
+                None => errors.push(unknown_user_error(field, name)),
+            }
+        }
+    }
+    for name in collaborators.iter().filter(|name| admins.contains(name)) {
+        errors.push(FieldError::new(
+            "collaborators",
+            format!("\"{}\" is already an admin.", name),
+        ));
+    }
+    if !errors.is_empty() {
+        return Err(ProjectError::ValidationError(errors));
+    }
+
+    let mut current = vec![];
+    for record in sqlx::query!(
+        r#"SELECT user_id, role FROM project_member WHERE project_id = ?"#,
+        project_id
+    )
+    .fetch_all(&mut *transaction)
+    .await?
+    {
+        current.push((record.user_id, parse_role(&record.role)?));
+    }
+    let role_of = |user_id: i64| {
+        current
+            .iter()
+            .find(|(id, _)| *id == user_id)
+            .map(|(_, role)| *role)
+    };
+
+    let roles: Vec<(i64, Role)> = admin_ids
+        .iter()
+        .map(|&id| match role_of(id) {
+            Some(role) if role.is_admin() => (id, role),
+            _ => (id, Role::Admin),
+        })
+        .chain(collaborator_ids.iter().map(|&id| match role_of(id) {
+            Some(role) if !role.is_admin() => (id, role),
+            _ => (id, Role::Editor),
+        }))
+        .collect();
+    if !roles.iter().any(|(_, role)| *role == Role::Owner) {
+        return Err(ProjectError::ValidationError(vec![last_owner_error(
+            "admins",
+        )]));
+    }
+    let owner_changed = current
+        .iter()
+        .filter(|(_, role)| *role == Role::Owner)
+        .any(|(id, _)| !roles.contains(&(*id, Role::Owner)));
+    if owner_changed && role_of(actor_id) != Some(Role::Owner) {
+        return Err(owner_only_error());
+    }
+
+    let ids = json!(roles.iter().map(|(id, _)| id).collect::<Vec<_>>()).to_string();
+    sqlx::query!(
+        r#"DELETE FROM project_member WHERE project_id = ? AND user_id NOT IN (SELECT value FROM json_each(?))"#,
+        project_id,
+        ids
+    )
+    .execute(&mut *transaction)
+    .await?;
+    for (user_id, role) in roles {
+        set_membership(project_id, user_id, role, &mut *transaction).await?;
+    }
+
+    Ok(())
+}
+
+/// Only owners can make someone an owner or change an owner's role.
+async fn set_member_role_in_db(
+    project_id: i64,
+    username: &str,
+    role: Role,
+    pool: &SqlitePool,
+    identity: &Identity,
+    request_id: &RequestId,
+) -> Result<Member, ProjectError> {
+    let user_id = get_user_id(identity).await?;
+    check_project_permission(identity, project_id, Permission::ManageMembers, pool).await?;
+
+    let mut transaction = pool.begin().await?;
+    let member_id = find_user_id(username, &mut transaction)
+        .await?
+        .ok_or_else(|| {
+            ProjectError::ValidationError(vec![unknown_user_error("username", username)])
+        })?;
+    let before = get_member_role(project_id, member_id, &mut transaction).await?;
+    if before == Some(role) {
+        return Ok(Member {
+            username: username.to_string(),
+            role,
+        });
+    }
+    if (role == Role::Owner || before == Some(Role::Owner))
+        && get_member_role(project_id, user_id, &mut transaction).await? != Some(Role::Owner)
+    {
+        return Err(owner_only_error());
+    }
+    if before == Some(Role::Owner) {
+        check_other_owner_remains(project_id, member_id, "role", &mut transaction).await?;
+    }
+
+    set_membership(project_id, member_id, role, &mut transaction).await?;
+    record_audit_event(
+        AuditRecord {
+            project_id,
+            actor_id: user_id,
+            action: "project.members.update",
+            target: format!("user:{}", username),
+            before: before.map(|role| json!({ "role": role })),
+            after: Some(json!({ "role": role })),
+        },
+        request_id,
+        &mut transaction,
+    )
+    .await?;
+    transaction.commit().await?;
+
+    Ok(Member {
+        username: username.to_string(),
+        role,
+    })
+}
+
+/// Members who manage the project can remove anyone but its owners, and members can always leave
+/// a project themselves.
+async fn remove_member_from_db(
+    project_id: i64,
+    username: &str,
+    pool: &SqlitePool,
+    identity: &Identity,
+    request_id: &RequestId,
+) -> Result<(), ProjectError> {
+    let user_id = get_user_id(identity).await?;
+    if let Err(e) =
+        check_project_permission(identity, project_id, Permission::ManageMembers, pool).await
+    {
+        let member_id = find_user_id(username, pool).await?;
+        if member_id != Some(user_id) {
+            return Err(e);
+        }
+    }
+
+    let mut transaction = pool.begin().await?;
+    let member_id = find_user_id(username, &mut transaction)
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)?;
+    let role = get_member_role(project_id, member_id, &mut transaction)
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)?;
+    if role == Role::Owner {
+        if member_id != user_id
+            && get_member_role(project_id, user_id, &mut transaction).await? != Some(Role::Owner)
+        {
+            return Err(owner_only_error());
+        }
+        check_other_owner_remains(project_id, member_id, "username", &mut transaction).await?;
+    }
+
+    sqlx::query!(
+        r#"DELETE FROM project_member WHERE project_id = ? AND user_id = ?"#,
+        project_id,
+        member_id
+    )
+    .execute(&mut transaction)
+    .await?;
+    record_audit_event(
+        AuditRecord {
+            project_id,
+            actor_id: user_id,
+            action: "project.members.update",
+            target: format!("user:{}", username),
+            before: Some(json!({ "role": role })),
+            after: None,
+        },
+        request_id,
+        &mut transaction,
+    )
+    .await?;
+    transaction.commit().await?;
+
+    Ok(())
+}
+
+async fn find_user_id<'e, E>(username: &str, executor: E) -> Result<Option<i64>, ProjectError>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    let user = sqlx::query!(r#"SELECT id FROM user WHERE name = ?"#, username)
+        .fetch_optional(executor)
+        .await?;
+
+    Ok(user.map(|user| user.id))
+}
+
+async fn get_member_role(
+    project_id: i64,
+    user_id: i64,
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> Result<Option<Role>, ProjectError> {
+    let member = sqlx::query!(
+        r#"SELECT role FROM project_member WHERE project_id = ? AND user_id = ?"#,
+        project_id,
+        user_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await?;
+
+    member.map(|member| parse_role(&member.role)).transpose()
+}
+
+async fn set_membership(
+    project_id: i64,
+    user_id: i64,
+    role: Role,
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> Result<(), ProjectError> {
+    let role = role.name();
+    sqlx::query!(
+        r#"INSERT INTO project_member (project_id, user_id, role) VALUES (?, ?, ?)
+        ON CONFLICT (project_id, user_id) DO UPDATE SET role = excluded.role"#,
+        project_id,
+        user_id,
+        role
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    Ok(())
+}
+
+/// A project can never be left without an owner.
+async fn check_other_owner_remains(
+    project_id: i64,
+    user_id: i64,
+    field: &str,
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> Result<(), ProjectError> {
+    let others = sqlx::query!(
+        r#"SELECT COUNT(*) AS "count!: i64" FROM project_member WHERE project_id = ? AND user_id != ? AND role = 'owner'"#,
+        project_id,
+        user_id
+    )
+    .fetch_one(&mut *transaction)
+    .await?
+    .count;
+
+    if others == 0 {
+        return Err(ProjectError::ValidationError(vec![last_owner_error(field)]));
+    }
+    Ok(())
+}
+
+fn parse_role(name: &str) -> Result<Role, ProjectError> {
+    Role::from_name(name).ok_or_else(|| {
+        ProjectError::SqlxError(sqlx::Error::Decode(
+            format!("unknown project role \"{}\"", name).into(),
+        ))
+    })
+}
+
+fn owner_only_error() -> ProjectError {
+    ProjectError::Forbidden("Only owners can grant or change the owner role.".to_string())
+}
+
+fn last_owner_error(field: &str) -> FieldError {
+    FieldError::new(field, "A project must keep at least one owner.")
+}
+
+fn unknown_user_error(field: &str, username: &str) -> FieldError {
+    FieldError::new(field, format!("No user is named \"{}\".", username))
+}
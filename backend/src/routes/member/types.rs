@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+
+use crate::routes::project::types::{Permission, Role};
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Member {
+    pub username: String,
+    pub role: Role,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MemberRoleRequest {
+    pub role: Role,
+}
+
+/// A role and everything it allows, as laid out in the permission matrix.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct RoleInfo {
+    pub name: Role,
+    pub permissions: Vec<Permission>,
+}
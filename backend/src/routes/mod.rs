@@ -1,9 +1,33 @@
+pub mod audit;
+pub mod curl;
+pub mod entry;
+pub mod environment;
+pub mod export;
+pub mod import;
+pub mod member;
 pub mod project;
+pub mod revision;
+pub mod runner;
+pub mod search;
+pub mod token;
 pub mod user;
 
 use actix_session::Session;
 use actix_web::{HttpResponse, Responder};
+pub use audit::audit_routes;
+pub use curl::curl_routes;
+pub use entry::entry_routes;
+pub use environment::environment_routes;
+pub use export::export_routes;
+pub use import::import_routes;
+pub use member::member_routes;
+// both modules have `types`, which is only ever reached through its own module
+#[allow(ambiguous_glob_reexports)]
 pub use project::*;
+pub use revision::revision_routes;
+pub use runner::runner_routes;
+pub use search::search_routes;
+pub use token::token_routes;
 pub use user::*;
 
 pub async fn health_check(session: Session) -> impl Responder {
@@ -1,7 +1,13 @@
 mod routes;
 pub mod types;
+mod validation;
 use actix_web::web;
 use routes::*;
+pub(crate) use routes::{
+    check_project_permission, curl_group_check_user_permission, get_redaction_policy,
+    project_check_user_permission,
+};
+pub(crate) use validation::{apply_redaction_policy, validate_curl_entry};
 
 pub fn project_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(get_projects)
@@ -12,5 +18,9 @@ pub fn project_routes(cfg: &mut web::ServiceConfig) {
         .service(check_user_admin_permission_for_project)
         .service(create_curl_group)
         .service(get_curl_group)
-        .service(update_curl_group);
+        .service(update_curl_group)
+        .service(delete_curl_group)
+        .service(get_trashed_curl_groups)
+        .service(restore_trashed_curl_group)
+        .service(purge_trashed_curl_group);
 }
@@ -1,134 +1,214 @@
 use crate::{
-    helpers::get_user_id,
+    helpers::{get_user_id, Identity},
     models::{CurlGroup, Project, ProjectInfo},
-    routes::project::types::Id,
+    redaction::RedactionPolicy,
+    routes::{
+        audit::{get_curl_group_summary, record_audit_event, AuditRecord},
+        entry::replace_curl_entries,
+        member::{get_admins_and_collaborators, set_project_members},
+        project::types::Id,
+        revision::record_curl_group_revision,
+        user::types::GlobalRole,
+    },
 };
 
-use actix_session::Session;
 use actix_web::{delete, get, post, web, HttpResponse, Responder};
-use sqlx::SqlitePool;
-
-use super::types::{ProjectError, UserAdminStatus};
+use serde_json::{json, Value};
+use sqlx::{Sqlite, SqlitePool, Transaction};
+use tracing_actix_web::RequestId;
+
+use super::{
+    types::{
+        Permission, ProjectError, ProjectParams, Role, SavedWithRedactions, TrashedCurlGroup,
+        UserAdminStatus, TRASH_RETENTION_DAYS,
+    },
+    validation::{apply_redaction_policy, validate_curl_group, validate_project_info},
+};
 
 #[get("/project")]
-#[tracing::instrument(name = "Getting projects.", skip(pool, session))]
+#[tracing::instrument(name = "Getting projects.", skip(pool, identity))]
 async fn get_projects(
-    // query: web::Query<ProjectParams>,
+    query: web::Query<ProjectParams>,
     pool: web::Data<SqlitePool>,
-    session: Session,
+    identity: Identity,
 ) -> impl Responder {
-    // TODO: handle search query & labels
-    match get_projects_from_db(&pool, &session).await {
+    match get_projects_from_db(&query, &pool, &identity).await {
         Ok(projects) => HttpResponse::Ok().json(projects),
         Err(e) => e.into(),
     }
 }
 
 #[post("/project")]
-#[tracing::instrument(name = "Creating new project.", skip(body, pool, session))]
+#[tracing::instrument(name = "Creating new project.", skip(body, pool, identity))]
 async fn create_project(
     body: web::Json<ProjectInfo>,
     pool: web::Data<SqlitePool>,
-    session: Session,
+    identity: Identity,
+    request_id: RequestId,
 ) -> impl Responder {
-    match insert_project_into_db(&body, &pool, &session).await {
+    match insert_project_into_db(&body, &pool, &identity, &request_id).await {
         Ok(id) => HttpResponse::Ok().json(Id { id }),
         Err(e) => e.into(),
     }
 }
 
 #[delete("/project/{project_id}")]
-#[tracing::instrument(name = "Deleting project.", skip(pool, session))]
+#[tracing::instrument(name = "Deleting project.", skip(pool, identity))]
 async fn delete_project(
     params: web::Path<i64>,
     pool: web::Data<SqlitePool>,
-    session: Session,
+    identity: Identity,
+    request_id: RequestId,
 ) -> impl Responder {
-    match delete_project_from_db(*params, &pool, &session).await {
+    match delete_project_from_db(*params, &pool, &identity, &request_id).await {
         Ok(_) => HttpResponse::NoContent().finish(),
         Err(e) => e.into(),
     }
 }
 
 #[get("/project/{project_id}")]
-#[tracing::instrument(name = "Getting project.", skip(pool, session))]
+#[tracing::instrument(name = "Getting project.", skip(pool, identity))]
 async fn get_project(
     params: web::Path<i64>,
     pool: web::Data<SqlitePool>,
-    session: Session,
+    identity: Identity,
 ) -> impl Responder {
-    match get_project_from_db(*params, &pool, &session).await {
+    match get_project_from_db(*params, &pool, &identity).await {
         Ok(project) => HttpResponse::Ok().json(project),
         Err(e) => e.into(),
     }
 }
 
 #[post("/project/{project_id}")]
-#[tracing::instrument(name = "Updating project.", skip(body, pool, session))]
+#[tracing::instrument(name = "Updating project.", skip(body, pool, identity))]
 async fn update_project(
     params: web::Path<i64>,
     body: web::Json<Project>,
     pool: web::Data<SqlitePool>,
-    session: Session,
+    identity: Identity,
+    request_id: RequestId,
 ) -> impl Responder {
-    match update_project_in_db(*params, &body, &pool, &session).await {
+    match update_project_in_db(*params, &body, &pool, &identity, &request_id).await {
         Ok(_) => HttpResponse::NoContent().finish(),
         Err(e) => e.into(),
     }
 }
 
 #[post("/project/{project_id}/group")]
-#[tracing::instrument(name = "Creating curl group.", skip(body, pool, session))]
+#[tracing::instrument(name = "Creating curl group.", skip(body, pool, identity))]
 async fn create_curl_group(
     params: web::Path<i64>,
     body: web::Json<CurlGroup>,
     pool: web::Data<SqlitePool>,
-    session: Session,
+    identity: Identity,
+    request_id: RequestId,
 ) -> impl Responder {
-    match insert_curl_group_into_db(*params, &body, &pool, &session).await {
-        Ok(curl_group_id) => HttpResponse::Ok().json(Id { id: curl_group_id }),
+    match insert_curl_group_into_db(*params, &body, &pool, &identity, &request_id).await {
+        Ok(saved) => HttpResponse::Ok().json(saved),
         Err(e) => e.into(),
     }
 }
 
 #[get("/project/{project_id}/is-user-admin")]
-#[tracing::instrument(name = "Getting user admin status.", skip(pool, session))]
+#[tracing::instrument(name = "Getting user admin status.", skip(pool, identity))]
 async fn check_user_admin_permission_for_project(
     params: web::Path<i64>,
     pool: web::Data<SqlitePool>,
-    session: Session,
+    identity: Identity,
 ) -> impl Responder {
-    let is_user_admin = match get_user_id(&session).await {
-        Ok(user_id) => check_user_has_project_admin_permission(user_id, *params, &pool)
+    let is_user_admin =
+        check_project_permission(&identity, *params, Permission::ManageMembers, &pool)
             .await
-            .is_ok(),
-        Err(_) => false,
-    };
+            .is_ok();
     HttpResponse::Ok().json(UserAdminStatus { is_user_admin })
 }
 
 #[get("/group/{group_id}")]
-#[tracing::instrument(name = "Getting curl group.", skip(pool, session))]
+#[tracing::instrument(name = "Getting curl group.", skip(pool, identity))]
 async fn get_curl_group(
     params: web::Path<i64>,
     pool: web::Data<SqlitePool>,
-    session: Session,
+    identity: Identity,
 ) -> impl Responder {
-    match get_curl_group_from_db(*params, &pool, &session).await {
+    match get_curl_group_from_db(*params, &pool, &identity).await {
         Ok(curl_group) => HttpResponse::Ok().json(curl_group),
         Err(e) => e.into(),
     }
 }
 
 #[post("/group/{group_id}")]
-#[tracing::instrument(name = "Updating curl group.", skip(body, pool, session))]
+#[tracing::instrument(name = "Updating curl group.", skip(body, pool, identity))]
 async fn update_curl_group(
     params: web::Path<i64>,
     body: web::Json<CurlGroup>,
     pool: web::Data<SqlitePool>,
-    session: Session,
+    identity: Identity,
+    request_id: RequestId,
+) -> impl Responder {
+    match update_curl_group_in_db(*params, &body, &pool, &identity, &request_id).await {
+        // the body is only worth sending when something was changed behind the user's back
+        Ok(saved) if !saved.redactions.is_empty() => HttpResponse::Ok().json(saved),
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) => e.into(),
+    }
+}
+
+#[delete("/group/{group_id}")]
+#[tracing::instrument(name = "Deleting curl group.", skip(pool, identity))]
+async fn delete_curl_group(
+    params: web::Path<i64>,
+    pool: web::Data<SqlitePool>,
+    identity: Identity,
+    request_id: RequestId,
+) -> impl Responder {
+    match delete_curl_group_from_db(*params, &pool, &identity, &request_id).await {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) => e.into(),
+    }
+}
+
+#[get("/project/{project_id}/trash")]
+#[tracing::instrument(name = "Getting trashed curl groups.", skip(pool, identity))]
+async fn get_trashed_curl_groups(
+    params: web::Path<i64>,
+    pool: web::Data<SqlitePool>,
+    identity: Identity,
+) -> impl Responder {
+    match get_trashed_curl_groups_from_db(*params, &pool, &identity).await {
+        Ok(groups) => HttpResponse::Ok().json(groups),
+        Err(e) => e.into(),
+    }
+}
+
+#[post("/project/{project_id}/trash/{group_id}/restore")]
+#[tracing::instrument(name = "Restoring trashed curl group.", skip(pool, identity))]
+async fn restore_trashed_curl_group(
+    params: web::Path<(i64, i64)>,
+    pool: web::Data<SqlitePool>,
+    identity: Identity,
+    request_id: RequestId,
+) -> impl Responder {
+    let (project_id, group_id) = params.into_inner();
+    match restore_trashed_curl_group_in_db(project_id, group_id, &pool, &identity, &request_id)
+        .await
+    {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) => e.into(),
+    }
+}
+
+#[delete("/project/{project_id}/trash/{group_id}")]
+#[tracing::instrument(name = "Purging trashed curl group.", skip(pool, identity))]
+async fn purge_trashed_curl_group(
+    params: web::Path<(i64, i64)>,
+    pool: web::Data<SqlitePool>,
+    identity: Identity,
+    request_id: RequestId,
 ) -> impl Responder {
-    match update_curl_group_in_db(*params, &body, &pool, &session).await {
+    let (project_id, group_id) = params.into_inner();
+    match purge_trashed_curl_group_from_db(project_id, group_id, &pool, &identity, &request_id)
+        .await
+    {
         Ok(_) => HttpResponse::NoContent().finish(),
         Err(e) => e.into(),
     }
@@ -138,134 +218,229 @@ async fn insert_curl_group_into_db(
     project_id: i64,
     curl_group: &CurlGroup,
     pool: &SqlitePool,
-    session: &Session,
-) -> Result<i64, ProjectError> {
-    let user_id = get_user_id(session).await?;
-    check_user_has_project_permission(user_id, project_id, pool).await?;
-
-    let curl_group_id = sqlx::query!(r#"INSERT INTO curl_group (curls, description, labels, name, project_id) VALUES (?, ?, ?, ?, ?) RETURNING id"#, 
-        curl_group.curls, curl_group.description, curl_group.labels, curl_group.name, project_id)
-        .fetch_one(pool)
+    identity: &Identity,
+    request_id: &RequestId,
+) -> Result<SavedWithRedactions, ProjectError> {
+    let user_id = get_user_id(identity).await?;
+    check_project_permission(identity, project_id, Permission::CreateGroup, pool).await?;
+    let mut curls = validate_curl_group(curl_group)?;
+    let policy = get_redaction_policy(project_id, pool).await?;
+    let redactions = apply_redaction_policy(
+        policy,
+        curls
+            .iter_mut()
+            .enumerate()
+            .map(|(index, curl)| (format!("curls[{}].rawQuery", index), &mut curl.raw_query)),
+    )?;
+
+    let mut transaction = pool.begin().await?;
+    let curl_group_id = sqlx::query!(r#"INSERT INTO curl_group (description, labels, name, project_id) VALUES (?, ?, ?, ?) RETURNING id"#,
+        curl_group.description, curl_group.labels, curl_group.name, project_id)
+        .fetch_one(&mut transaction)
         .await?.id;
+    replace_curl_entries(curl_group_id, &curls, &mut transaction).await?;
+    record_curl_group_revision(curl_group_id, user_id, &mut transaction).await?;
+    let after = get_curl_group_summary(curl_group_id, &mut transaction).await?;
+    record_audit_event(
+        AuditRecord {
+            project_id,
+            actor_id: user_id,
+            action: "curl_group.create",
+            target: format!("curl_group:{}", curl_group_id),
+            before: None,
+            after: Some(after),
+        },
+        request_id,
+        &mut transaction,
+    )
+    .await?;
+    transaction.commit().await?;
 
-    Ok(curl_group_id)
+    Ok(SavedWithRedactions {
+        id: curl_group_id,
+        redactions,
+    })
 }
 
 async fn update_curl_group_in_db(
     curl_group_id: i64,
     curl_group: &CurlGroup,
     pool: &SqlitePool,
-    session: &Session,
-) -> Result<(), ProjectError> {
-    get_user_id(session).await?;
-    curl_group_check_user_permission(curl_group_id, pool, session).await?;
-
+    identity: &Identity,
+    request_id: &RequestId,
+) -> Result<SavedWithRedactions, ProjectError> {
+    let user_id = get_user_id(identity).await?;
+    let project_id = curl_group_check_user_permission(curl_group_id, pool, identity).await?;
+    check_project_permission(identity, project_id, Permission::EditGroup, pool).await?;
+    let mut curls = validate_curl_group(curl_group)?;
+    let policy = get_redaction_policy(project_id, pool).await?;
+    let redactions = apply_redaction_policy(
+        policy,
+        curls
+            .iter_mut()
+            .enumerate()
+            .map(|(index, curl)| (format!("curls[{}].rawQuery", index), &mut curl.raw_query)),
+    )?;
+
+    let mut transaction = pool.begin().await?;
+    let before = get_curl_group_summary(curl_group_id, &mut transaction).await?;
     sqlx::query!(
-        r#"UPDATE curl_group SET curls = ?, description = ?, labels = ?, name = ? WHERE id = ?"#,
-        curl_group.curls,
+        r#"UPDATE curl_group SET description = ?, labels = ?, name = ? WHERE id = ?"#,
         curl_group.description,
         curl_group.labels,
         curl_group.name,
         curl_group_id
     )
-    .execute(pool)
+    .execute(&mut transaction)
     .await?;
+    replace_curl_entries(curl_group_id, &curls, &mut transaction).await?;
+    record_curl_group_revision(curl_group_id, user_id, &mut transaction).await?;
+    let after = get_curl_group_summary(curl_group_id, &mut transaction).await?;
+    record_audit_event(
+        AuditRecord {
+            project_id,
+            actor_id: user_id,
+            action: "curl_group.update",
+            target: format!("curl_group:{}", curl_group_id),
+            before: Some(before),
+            after: Some(after),
+        },
+        request_id,
+        &mut transaction,
+    )
+    .await?;
+    transaction.commit().await?;
 
-    Ok(())
+    Ok(SavedWithRedactions {
+        id: curl_group_id,
+        redactions,
+    })
 }
 
-async fn check_user_has_project_permission(
-    user_id: i64,
+pub(crate) async fn get_redaction_policy(
     project_id: i64,
     pool: &SqlitePool,
-) -> Result<(), ProjectError> {
-    let admin_query = check_user_has_project_admin_permission(user_id, project_id, pool).await;
-    let collaborator_query = sqlx::query!(
-        r#"SELECT * FROM project_collaborator WHERE project_id = ? AND user_id = ?"#,
-        project_id,
-        user_id
+) -> Result<RedactionPolicy, ProjectError> {
+    let policy = sqlx::query!(
+        r#"SELECT redaction_policy FROM project WHERE id = ?"#,
+        project_id
     )
     .fetch_one(pool)
-    .await;
-
-    if admin_query.is_ok() || collaborator_query.is_ok() {
-        Ok(())
-    } else {
-        // Return 404 if project not found - else just return forbidden
-        let mut error = ProjectError::Forbidden(
-            "User does not not have permission to perform that action on this project.".to_string(),
-        );
-
-        if let ProjectError::ProjectDoesNotExistError(e) = admin_query.unwrap_err() {
-            error = ProjectError::ProjectDoesNotExistError(e)
-        }
+    .await?
+    .redaction_policy;
 
-        Err(error)
-    }
+    Ok(RedactionPolicy::from_name(&policy))
 }
 
-async fn check_user_has_project_admin_permission(
-    user_id: i64,
+/// The one place project access is decided: the user's role in the project has to grant the
+/// permission. Editors can also delete groups where the project allows collaborators to, instance
+/// admins may do anything, and API tokens limited to a project cannot be used anywhere else.
+pub(crate) async fn check_project_permission(
+    identity: &Identity,
     project_id: i64,
+    permission: Permission,
     pool: &SqlitePool,
 ) -> Result<(), ProjectError> {
-    let project_does_exist_query =
-        sqlx::query!(r#"SELECT id FROM project WHERE id = ?"#, project_id)
-            .fetch_one(pool)
-            .await;
-
-    if project_does_exist_query.is_err() {
-        return Err(ProjectError::ProjectDoesNotExistError(
-            "Requested project does not exist.".to_string(),
-        ));
-    }
-
-    let project_admin_query = sqlx::query!(
-        r#"SELECT * FROM project_admin WHERE project_id = ? AND user_id = ?"#,
-        project_id,
-        user_id
+    let user_id = get_user_id(identity).await?;
+    check_token_scope(identity, project_id)?;
+    let permission_name = permission.name();
+    let global_admin = GlobalRole::Admin.name();
+    let access = sqlx::query!(
+        r#"SELECT
+            (SELECT role FROM project_member WHERE project_id = project.id AND user_id = ?) AS "role?: String",
+            EXISTS (
+                SELECT 1 FROM project_member
+                JOIN project_role_permission ON project_role_permission.role = project_member.role
+                WHERE project_member.project_id = project.id AND project_member.user_id = ?
+                AND project_role_permission.permission = ?
+            ) AS "granted!: bool",
+            collaborators_can_delete_groups AS "collaborators_can_delete_groups: bool",
+            EXISTS (SELECT 1 FROM user WHERE id = ? AND global_role = ?) AS "global_admin!: bool"
+        FROM project WHERE id = ?"#,
+        user_id,
+        user_id,
+        permission_name,
+        user_id,
+        global_admin,
+        project_id
     )
-    .fetch_one(pool)
-    .await;
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| {
+        ProjectError::ProjectDoesNotExistError("Requested project does not exist.".to_string())
+    })?;
+
+    let granted = access.granted
+        || access.global_admin
+        || (permission == Permission::DeleteGroup
+            && access.role.as_deref() == Some(Role::Editor.name())
+            && access.collaborators_can_delete_groups);
+    if !granted {
+        return Err(ProjectError::Forbidden(format!(
+            "User does not have the {} permission for this project.",
+            permission_name
+        )));
+    }
+    Ok(())
+}
 
-    if project_admin_query.is_err() {
+/// API tokens limited to a project cannot be used anywhere else, public projects included.
+fn check_token_scope(identity: &Identity, project_id: i64) -> Result<(), ProjectError> {
+    if identity
+        .project_scope()
+        .is_some_and(|scope| scope != project_id)
+    {
         return Err(ProjectError::Forbidden(
-            "User does not have permission to for this project".to_string(),
+            "This API token is limited to another project.".to_string(),
         ));
     }
+    Ok(())
+}
+
+/// Read access to a project follows its visibility.
+pub(crate) async fn project_check_user_permission(
+    project_id: i64,
+    pool: &SqlitePool,
+    identity: &Identity,
+) -> Result<(), ProjectError> {
+    get_project_from_db(project_id, pool, identity).await?;
 
     Ok(())
 }
 
-async fn curl_group_check_user_permission(
+/// Returns the id of the project the group belongs to once access is confirmed.
+pub(crate) async fn curl_group_check_user_permission(
     group_id: i64,
     pool: &SqlitePool,
-    session: &Session,
-) -> Result<(), ProjectError> {
+    identity: &Identity,
+) -> Result<i64, ProjectError> {
     let project_id = sqlx::query!(
-        r#"SELECT project_id FROM curl_group WHERE id = ?"#,
+        r#"SELECT project_id FROM curl_group WHERE id = ? AND deleted_at IS NULL"#,
         group_id
     )
     .fetch_one(pool)
     .await?
     .project_id;
 
-    // checks project visibility and such things - we just discard the resulting project output
-    get_project_from_db(project_id, pool, session).await?;
+    project_check_user_permission(project_id, pool, identity).await?;
 
-    Ok(())
+    Ok(project_id)
 }
 
 async fn get_curl_group_from_db(
     group_id: i64,
     pool: &SqlitePool,
-    session: &Session,
+    identity: &Identity,
 ) -> Result<CurlGroup, ProjectError> {
-    curl_group_check_user_permission(group_id, pool, session).await?;
+    curl_group_check_user_permission(group_id, pool, identity).await?;
 
     let curl_group = sqlx::query_as!(
         CurlGroup,
-        r#"SELECT * FROM curl_group WHERE id = ?"#,
+        r#"SELECT id, description, labels, name, project_id, (
+            SELECT json_group_array(json_object('id', id, 'name', name, 'description', description, 'rawQuery', raw_query))
+            FROM (SELECT * FROM curl_entry WHERE curl_entry.group_id = curl_group.id ORDER BY position)
+        ) AS "curls!: String"
+        FROM curl_group WHERE id = ?"#,
         group_id
     )
     .fetch_one(pool)
@@ -277,14 +452,31 @@ async fn get_curl_group_from_db(
 async fn delete_project_from_db(
     project_id: i64,
     pool: &SqlitePool,
-    session: &Session,
+    identity: &Identity,
+    request_id: &RequestId,
 ) -> Result<(), ProjectError> {
-    let user_id = get_user_id(session).await?;
-    check_user_has_project_admin_permission(user_id, project_id, pool).await?;
+    let user_id = get_user_id(identity).await?;
+    check_project_permission(identity, project_id, Permission::DeleteProject, pool).await?;
 
+    let mut transaction = pool.begin().await?;
+    let before = get_project_summary(project_id, &mut transaction).await?;
     sqlx::query!(r#"DELETE FROM project WHERE id = ?"#, project_id)
-        .execute(pool)
+        .execute(&mut transaction)
         .await?;
+    record_audit_event(
+        AuditRecord {
+            project_id,
+            actor_id: user_id,
+            action: "project.delete",
+            target: format!("project:{}", project_id),
+            before: Some(before),
+            after: None,
+        },
+        request_id,
+        &mut transaction,
+    )
+    .await?;
+    transaction.commit().await?;
 
     Ok(())
 }
@@ -292,19 +484,19 @@ async fn delete_project_from_db(
 async fn get_project_from_db(
     project_id: i64,
     pool: &SqlitePool,
-    session: &Session,
+    identity: &Identity,
 ) -> Result<Project, ProjectError> {
+    check_token_scope(identity, project_id)?;
     let info = sqlx::query_as!(
         ProjectInfo,
-        r#"SELECT * FROM project WHERE id = ?"#,
+        r#"SELECT id, environments, description, name, visibility, redaction_policy AS "redaction_policy?", collaborators_can_delete_groups AS "collaborators_can_delete_groups?: bool" FROM project WHERE id = ?"#,
         project_id
     )
     .fetch_one(pool)
     .await?;
 
     if info.visibility == "Private" {
-        let user_id = get_user_id(session).await?;
-        check_user_has_project_permission(user_id, project_id, pool).await?;
+        check_project_permission(identity, project_id, Permission::Read, pool).await?;
     }
 
     let (admins, collaborators) = get_admins_and_collaborators(info.id, pool).await?;
@@ -320,29 +512,54 @@ async fn get_project_from_db(
 }
 
 async fn get_projects_from_db(
+    params: &ProjectParams,
     pool: &SqlitePool,
-    session: &Session,
+    identity: &Identity,
 ) -> Result<Vec<ProjectInfo>, ProjectError> {
-    let user_id = get_user_id(session).await;
-
-    let project_infos = match user_id {
-        Ok(user_id) => {
-            sqlx::query_as!(ProjectInfo, r#"
-            SELECT id, environments, description, name, visibility FROM project
-            LEFT JOIN project_admin ON project_admin.project_id = project.id AND project_admin.user_id = ?
-            LEFT JOIN project_collaborator ON project_collaborator.project_id = project.id AND project_collaborator.user_id = ?
-            WHERE visibility = "Public" OR project_admin.user_id = ? OR project_collaborator.user_id = ?;"#, user_id, user_id, user_id, user_id)
-            .fetch_all(pool).await?
-        },
-        Err(_) => {
-            sqlx::query_as!(
-                ProjectInfo,
-                r#"SELECT * FROM project WHERE visibility = "Public""#
+    // anonymous users get an id that can never match, so they only see public projects
+    let user_id = get_user_id(identity).await.unwrap_or(-1);
+    let scope = identity.project_scope();
+    let global_admin = GlobalRole::Admin.name();
+    let search = params.escaped_search();
+    let labels = serde_json::to_string(&params.labels).unwrap_or_else(|_| "[]".to_string());
+
+    // every requested label must be present on at least one of the project's curl groups, whose
+    // comma separated labels are split and trimmed the same way as the requested ones
+    let project_infos = sqlx::query_as!(
+        ProjectInfo,
+        r#"
+        WITH RECURSIVE curl_group_label(project_id, label, rest) AS (
+            SELECT project_id, NULL, labels || ',' FROM curl_group WHERE deleted_at IS NULL
+            UNION ALL
+            SELECT project_id, TRIM(SUBSTR(rest, 1, INSTR(rest, ',') - 1)), SUBSTR(rest, INSTR(rest, ',') + 1)
+            FROM curl_group_label WHERE rest <> ''
+        )
+        SELECT id, environments, description, name, visibility, redaction_policy AS "redaction_policy?", collaborators_can_delete_groups AS "collaborators_can_delete_groups?: bool" FROM project
+        LEFT JOIN project_member ON project_member.project_id = project.id AND project_member.user_id = ?
+        WHERE (
+            visibility = "Public" OR project_member.user_id IS NOT NULL
+            OR EXISTS (SELECT 1 FROM user WHERE user.id = ? AND global_role = ?)
+        )
+        AND (? IS NULL OR project.id = ?)
+        AND (? = '' OR name LIKE '%' || ? || '%' ESCAPE '\' OR description LIKE '%' || ? || '%' ESCAPE '\')
+        AND NOT EXISTS (
+            SELECT 1 FROM json_each(?) AS label WHERE NOT EXISTS (
+                SELECT 1 FROM curl_group_label WHERE curl_group_label.project_id = project.id
+                AND curl_group_label.label = label.value COLLATE NOCASE
             )
-            .fetch_all(pool)
-            .await?
-        }
-    };
+        );"#,
+        user_id,
+        user_id,
+        global_admin,
+        scope,
+        scope,
+        search,
+        search,
+        search,
+        labels
+    )
+    .fetch_all(pool)
+    .await?;
 
     Ok(project_infos)
 }
@@ -350,23 +567,50 @@ async fn get_projects_from_db(
 async fn insert_project_into_db(
     project_info: &ProjectInfo,
     pool: &SqlitePool,
-    session: &Session,
+    identity: &Identity,
+    request_id: &RequestId,
 ) -> Result<i64, ProjectError> {
-    let user_id = get_user_id(session).await?;
+    let user_id = get_user_id(identity).await?;
+    if identity.project_scope().is_some() {
+        return Err(ProjectError::Forbidden(
+            "API tokens limited to a project cannot create projects.".to_string(),
+        ));
+    }
+    validate_project_info(project_info)?;
 
-    let project_id = sqlx::query!(r#"INSERT INTO project (environments, description, name, visibility) VALUES (?, ?, ?, ?) RETURNING id"#, 
-        project_info.environments, project_info.description, project_info.name, project_info.visibility)
-        .fetch_one(pool)
+    let mut transaction = pool.begin().await?;
+    let project_id = sqlx::query!(r#"INSERT INTO project (environments, description, name, visibility, redaction_policy, collaborators_can_delete_groups) VALUES (?, ?, ?, ?, COALESCE(?, 'Redact'), COALESCE(?, FALSE)) RETURNING id"#,
+        project_info.environments, project_info.description, project_info.name, project_info.visibility, project_info.redaction_policy, project_info.collaborators_can_delete_groups)
+        .fetch_one(&mut transaction)
         .await?.id;
+    sync_project_environments(project_id, "", &project_info.environments, &mut transaction).await?;
 
+    let owner = Role::Owner.name();
     sqlx::query!(
-        r#"INSERT INTO project_admin (project_id, user_id) VALUES (?, ?)"#,
+        r#"INSERT INTO project_member (project_id, user_id, role) VALUES (?, ?, ?)"#,
         project_id,
-        user_id
+        user_id,
+        owner
     )
-    .execute(pool)
+    .execute(&mut transaction)
     .await?;
 
+    let after = get_project_summary(project_id, &mut transaction).await?;
+    record_audit_event(
+        AuditRecord {
+            project_id,
+            actor_id: user_id,
+            action: "project.create",
+            target: format!("project:{}", project_id),
+            before: None,
+            after: Some(after),
+        },
+        request_id,
+        &mut transaction,
+    )
+    .await?;
+    transaction.commit().await?;
+
     Ok(project_id)
 }
 
@@ -374,108 +618,333 @@ async fn update_project_in_db(
     project_id: i64,
     project: &Project,
     pool: &SqlitePool,
-    session: &Session,
+    identity: &Identity,
+    request_id: &RequestId,
 ) -> Result<(), ProjectError> {
-    let user_id = get_user_id(session).await?;
-    check_user_has_project_admin_permission(user_id, project_id, pool).await?;
+    let user_id = get_user_id(identity).await?;
+    check_project_permission(identity, project_id, Permission::ManageMembers, pool).await?;
+    validate_project_info(&project.info)?;
 
+    let mut transaction = pool.begin().await?;
+    let before = get_project_summary(project_id, &mut transaction).await?;
     sqlx::query!(
-        r#"UPDATE project SET environments = ?, description = ?, name = ?, visibility = ? WHERE id = ?"#,
+        r#"UPDATE project SET environments = ?, description = ?, name = ?, visibility = ?,
+        redaction_policy = COALESCE(?, redaction_policy),
+        collaborators_can_delete_groups = COALESCE(?, collaborators_can_delete_groups) WHERE id = ?"#,
         project.info.environments,
         project.info.description,
         project.info.name,
         project.info.visibility,
+        project.info.redaction_policy,
+        project.info.collaborators_can_delete_groups,
         project_id
     )
-    .execute(pool)
+    .execute(&mut transaction)
+    .await?;
+    sync_project_environments(
+        project_id,
+        before["environments"].as_str().unwrap_or_default(),
+        &project.info.environments,
+        &mut transaction,
+    )
+    .await?;
+    set_project_members(
+        project_id,
+        user_id,
+        &project.admins,
+        &project.collaborators,
+        &mut transaction,
+    )
     .await?;
 
-    update_project_admins_and_collaborators(project, pool).await?;
+    // settings and membership are recorded apart, so access changes can be looked up on their own
+    let after = get_project_summary(project_id, &mut transaction).await?;
+    for (action, fields) in [
+        (
+            "project.update",
+            &[
+                "name",
+                "description",
+                "environments",
+                "visibility",
+                "redaction_policy",
+                "collaborators_can_delete_groups",
+            ][..],
+        ),
+        ("project.members.update", &["admins", "collaborators"][..]),
+    ] {
+        let before = pick(&before, fields);
+        let after = pick(&after, fields);
+        if before != after {
+            record_audit_event(
+                AuditRecord {
+                    project_id,
+                    actor_id: user_id,
+                    action,
+                    target: format!("project:{}", project_id),
+                    before: Some(before),
+                    after: Some(after),
+                },
+                request_id,
+                &mut transaction,
+            )
+            .await?;
+        }
+    }
+    transaction.commit().await?;
 
     Ok(())
 }
 
-async fn update_project_admins_and_collaborators(
-    project: &Project,
+/// Keeps the project's environments in step with its comma separated base URLs: each added base
+/// URL becomes an environment of the same name with a `base_url` variable, and each removed one
+/// is deleted. Environments managed on their own are left alone.
+async fn sync_project_environments(
+    project_id: i64,
+    previous: &str,
+    environments: &str,
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> Result<(), ProjectError> {
+    let split = |environments: &str| -> Vec<String> {
+        environments
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+    let previous = split(previous);
+    let environments = split(environments);
+
+    for name in previous.iter().filter(|name| !environments.contains(name)) {
+        sqlx::query!(
+            r#"DELETE FROM project_environment WHERE project_id = ? AND name = ?"#,
+            project_id,
+            name
+        )
+        .execute(&mut *transaction)
+        .await?;
+    }
+
+    for name in environments.iter().filter(|name| !previous.contains(name)) {
+        let environment_id = sqlx::query!(
+            r#"INSERT INTO project_environment (project_id, name) VALUES (?, ?)
+            ON CONFLICT (project_id, name) DO NOTHING RETURNING id"#,
+            project_id,
+            name
+        )
+        .fetch_optional(&mut *transaction)
+        .await?;
+        if let Some(record) = environment_id {
+            sqlx::query!(
+                r#"INSERT INTO project_environment_variable (environment_id, name, value) VALUES (?, 'base_url', ?)"#,
+                record.id,
+                name
+            )
+            .execute(&mut *transaction)
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn get_project_summary(
+    project_id: i64,
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> Result<Value, ProjectError> {
+    let info = sqlx::query!(
+        r#"SELECT name, description, environments, visibility, redaction_policy,
+        collaborators_can_delete_groups AS "collaborators_can_delete_groups: bool" FROM project WHERE id = ?"#,
+        project_id
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+    let (admins, collaborators) =
+        get_admins_and_collaborators(project_id, &mut *transaction).await?;
+
+    Ok(json!({
+        "name": info.name,
+        "description": info.description,
+        "environments": info.environments,
+        "visibility": info.visibility,
+        "redaction_policy": info.redaction_policy,
+        "collaborators_can_delete_groups": info.collaborators_can_delete_groups,
+        "admins": admins,
+        "collaborators": collaborators,
+    }))
+}
+
+fn pick(summary: &Value, fields: &[&str]) -> Value {
+    Value::Object(
+        fields
+            .iter()
+            .map(|field| (field.to_string(), summary[*field].clone()))
+            .collect(),
+    )
+}
+
+/// Moves the group to the project's trash.
+async fn delete_curl_group_from_db(
+    group_id: i64,
     pool: &SqlitePool,
+    identity: &Identity,
+    request_id: &RequestId,
 ) -> Result<(), ProjectError> {
-    // TODO: use query builder eventually!
+    let user_id = get_user_id(identity).await?;
+    let project_id = curl_group_check_user_permission(group_id, pool, identity).await?;
+    check_project_permission(identity, project_id, Permission::DeleteGroup, pool).await?;
+    purge_expired_curl_groups(pool).await?;
 
-    let admin_list = generate_user_list(&project.admins).await;
-    let collaborator_list = generate_user_list(&project.admins).await;
+    let mut transaction = pool.begin().await?;
+    let before = get_curl_group_summary(group_id, &mut transaction).await?;
     sqlx::query!(
-        r#"DELETE FROM project_admin WHERE project_id = ? AND user_id in (
-        SELECT user_id FROM project_admin
-        LEFT JOIN user ON user.id = project_admin.user_id
-        WHERE user.name NOT IN (?)
-    )"#,
-        project.info.id,
-        admin_list
+        r#"UPDATE curl_group SET deleted_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now'), deleted_by = ? WHERE id = ?"#,
+        user_id,
+        group_id
+    )
+    .execute(&mut transaction)
+    .await?;
+    record_audit_event(
+        AuditRecord {
+            project_id,
+            actor_id: user_id,
+            action: "curl_group.delete",
+            target: format!("curl_group:{}", group_id),
+            before: Some(before),
+            after: None,
+        },
+        request_id,
+        &mut transaction,
     )
-    .execute(pool)
     .await?;
+    transaction.commit().await?;
 
-    sqlx::query!(
-        r#"DELETE FROM project_collaborator WHERE project_id = ? AND user_id in (
-        SELECT user_id FROM project_collaborator
-        LEFT JOIN user ON user.id = project_collaborator.user_id
-        WHERE user.name NOT IN (?)
-    )"#,
-        project.info.id,
-        collaborator_list
+    Ok(())
+}
+
+/// Most recently deleted first. The trash is for project admins only, even where collaborators can
+/// delete groups.
+async fn get_trashed_curl_groups_from_db(
+    project_id: i64,
+    pool: &SqlitePool,
+    identity: &Identity,
+) -> Result<Vec<TrashedCurlGroup>, ProjectError> {
+    check_project_permission(identity, project_id, Permission::ManageMembers, pool).await?;
+    purge_expired_curl_groups(pool).await?;
+
+    let retention = format!("+{} days", TRASH_RETENTION_DAYS);
+    let groups = sqlx::query_as!(
+        TrashedCurlGroup,
+        r#"SELECT curl_group.id, curl_group.name, description, labels,
+        deleted_at AS "deleted_at!", user.name AS "deleted_by?",
+        strftime('%Y-%m-%dT%H:%M:%fZ', deleted_at, ?) AS "purge_at!: String"
+        FROM curl_group LEFT JOIN user ON user.id = curl_group.deleted_by
+        WHERE project_id = ? AND deleted_at IS NOT NULL ORDER BY deleted_at DESC"#,
+        retention,
+        project_id
     )
-    .execute(pool)
+    .fetch_all(pool)
     .await?;
 
-    for admin in &project.admins {
-        sqlx::query!(
-            r#"INSERT INTO project_admin (project_id, user_id) VALUES (?, (SELECT id FROM user WHERE name = ?))"#,
-            project.info.id, admin,
-        ).execute(pool).await?;
-    }
+    Ok(groups)
+}
 
-    for collaborator in &project.collaborators {
-        sqlx::query!(
-            r#"INSERT INTO project_collaborator (project_id, user_id) VALUES (?, (SELECT id FROM user WHERE name = ?))"#,
-            project.info.id, collaborator,
-        ).execute(pool).await?;
+async fn restore_trashed_curl_group_in_db(
+    project_id: i64,
+    group_id: i64,
+    pool: &SqlitePool,
+    identity: &Identity,
+    request_id: &RequestId,
+) -> Result<(), ProjectError> {
+    let user_id = get_user_id(identity).await?;
+    check_project_permission(identity, project_id, Permission::ManageMembers, pool).await?;
+    purge_expired_curl_groups(pool).await?;
+
+    let mut transaction = pool.begin().await?;
+    let result = sqlx::query!(
+        r#"UPDATE curl_group SET deleted_at = NULL, deleted_by = NULL
+        WHERE id = ? AND project_id = ? AND deleted_at IS NOT NULL"#,
+        group_id,
+        project_id
+    )
+    .execute(&mut transaction)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound.into());
     }
+    let after = get_curl_group_summary(group_id, &mut transaction).await?;
+    record_audit_event(
+        AuditRecord {
+            project_id,
+            actor_id: user_id,
+            action: "curl_group.undelete",
+            target: format!("curl_group:{}", group_id),
+            before: None,
+            after: Some(after),
+        },
+        request_id,
+        &mut transaction,
+    )
+    .await?;
+    transaction.commit().await?;
 
     Ok(())
 }
 
-async fn get_admins_and_collaborators(
+/// Removes the group, its entries and history for good, without waiting for the retention window.
+async fn purge_trashed_curl_group_from_db(
     project_id: i64,
+    group_id: i64,
     pool: &SqlitePool,
-) -> Result<(Vec<String>, Vec<String>), ProjectError> {
-    // TODO: tidy up
-    let admins: Vec<String> = sqlx::query!(
-        r#"SELECT name FROM user 
-        LEFT JOIN project_admin ON project_admin.user_id = user.id
-        WHERE project_admin.project_id = ?
-    "#,
+    identity: &Identity,
+    request_id: &RequestId,
+) -> Result<(), ProjectError> {
+    let user_id = get_user_id(identity).await?;
+    check_project_permission(identity, project_id, Permission::ManageMembers, pool).await?;
+
+    let mut transaction = pool.begin().await?;
+    let before = get_curl_group_summary(group_id, &mut transaction).await?;
+    let result = sqlx::query!(
+        r#"DELETE FROM curl_group WHERE id = ? AND project_id = ? AND deleted_at IS NOT NULL"#,
+        group_id,
         project_id
     )
-    .fetch_all(pool)
-    .await?
-    .iter()
-    .map(|record| record.name.clone())
-    .collect();
-
-    let collaborators: Vec<String> = sqlx::query!(
-        r#"SELECT name FROM user 
-        LEFT JOIN project_collaborator ON project_collaborator.user_id = user.id
-        WHERE project_collaborator.project_id = ?
-    "#,
-        project_id
+    .execute(&mut transaction)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound.into());
+    }
+    record_audit_event(
+        AuditRecord {
+            project_id,
+            actor_id: user_id,
+            action: "curl_group.purge",
+            target: format!("curl_group:{}", group_id),
+            before: Some(before),
+            after: None,
+        },
+        request_id,
+        &mut transaction,
     )
-    .fetch_all(pool)
-    .await?
-    .iter()
-    .map(|record| record.name.clone())
-    .collect();
+    .await?;
+    transaction.commit().await?;
+
+    Ok(())
+}
+
+/// Groups are purged lazily, whenever the trash is worked with, once they outlive the retention
+/// window.
+async fn purge_expired_curl_groups(pool: &SqlitePool) -> Result<(), ProjectError> {
+    let retention = format!("-{} days", TRASH_RETENTION_DAYS);
+    sqlx::query!(
+        r#"DELETE FROM curl_group WHERE deleted_at < strftime('%Y-%m-%dT%H:%M:%fZ', 'now', ?)"#,
+        retention
+    )
+    .execute(pool)
+    .await?;
 
-    Ok((admins, collaborators))
+    Ok(())
 }
 
 async fn get_curl_groups_for_project(
@@ -484,7 +953,11 @@ async fn get_curl_groups_for_project(
 ) -> Result<Vec<CurlGroup>, ProjectError> {
     let curl_groups: Vec<CurlGroup> = sqlx::query_as!(
         CurlGroup,
-        r#"SELECT * FROM curl_group WHERE project_id = ?"#,
+        r#"SELECT id, description, labels, name, project_id, (
+            SELECT json_group_array(json_object('id', id, 'name', name, 'description', description, 'rawQuery', raw_query))
+            FROM (SELECT * FROM curl_entry WHERE curl_entry.group_id = curl_group.id ORDER BY position)
+        ) AS "curls!: String"
+        FROM curl_group WHERE project_id = ? AND deleted_at IS NULL"#,
         project_id
     )
     .fetch_all(pool)
@@ -492,30 +965,3 @@ async fn get_curl_groups_for_project(
 
     Ok(curl_groups)
 }
-
-async fn generate_user_list(users: &Vec<String>) -> String {
-    let mut users = users.clone();
-    for i in 0..users.len() {
-        users[i] = format!("\"{}\"", users[i]);
-    }
-    users.join(", ")
-}
-
-#[cfg(test)]
-mod user_list_test {
-    use super::generate_user_list;
-
-    #[tokio::test]
-    async fn generates_user_list_as_expected() {
-        let users = vec!["user".to_string(), "other_user".to_string()];
-        let user_list = generate_user_list(&users).await;
-        assert_eq!(user_list, "\"user\", \"other_user\"");
-    }
-
-    #[tokio::test]
-    async fn generates_blank_string_as_expected_when_no_users() {
-        let users = vec![];
-        let user_list = generate_user_list(&users).await;
-        assert_eq!(user_list, "");
-    }
-}
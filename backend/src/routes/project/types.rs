@@ -1,19 +1,22 @@
 use actix_session::SessionGetError;
 use actix_web::HttpResponse;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
-use crate::routes::user::types::UserError;
+use crate::{redaction::SecretKind, routes::user::types::UserError, secrets::SecretError};
 
 #[derive(Debug)]
 pub enum ProjectError {
     Forbidden(String),
     ProjectDoesNotExistError(String),
+    SecretError(SecretError),
     SessionGetError(String),
     SqlxError(sqlx::Error),
     UserError(UserError),
+    ValidationError(Vec<FieldError>),
 }
 
 impl From<SessionGetError> for ProjectError {
+    #[allow(clippy::match_single_binding)]
     fn from(e: SessionGetError) -> Self {
         match e {
             // TODO: improve
@@ -22,6 +25,12 @@ impl From<SessionGetError> for ProjectError {
     }
 }
 
+impl From<SecretError> for ProjectError {
+    fn from(e: SecretError) -> Self {
+        ProjectError::SecretError(e)
+    }
+}
+
 impl From<sqlx::Error> for ProjectError {
     fn from(e: sqlx::Error) -> Self {
         match e {
@@ -49,16 +58,150 @@ impl From<ProjectError> for HttpResponse {
             ProjectError::ProjectDoesNotExistError(_) => HttpResponse::NotFound().finish(),
             ProjectError::SessionGetError(_) => HttpResponse::Unauthorized().finish(),
             ProjectError::Forbidden(_) => HttpResponse::Forbidden().finish(),
+            ProjectError::ValidationError(errors) => {
+                HttpResponse::UnprocessableEntity().json(ValidationErrors { errors })
+            }
             _ => HttpResponse::InternalServerError().finish(),
         }
     }
 }
 
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ValidationErrors {
+    pub errors: Vec<FieldError>,
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct Id {
     pub id: i64,
 }
 
+/// A field whose secrets were replaced with placeholders before saving.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Redaction {
+    pub field: String,
+    pub secrets: Vec<SecretKind>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SavedWithRedactions {
+    pub id: i64,
+    pub redactions: Vec<Redaction>,
+}
+
+/// A member's role within a project, from the most to the least privileged. What each role may do
+/// is kept in the `project_role_permission` table.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Owner,
+    Admin,
+    Editor,
+    Commenter,
+    Viewer,
+}
+
+impl Role {
+    pub const ALL: [Role; 5] = [
+        Role::Owner,
+        Role::Admin,
+        Role::Editor,
+        Role::Commenter,
+        Role::Viewer,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Role::Owner => "owner",
+            Role::Admin => "admin",
+            Role::Editor => "editor",
+            Role::Commenter => "commenter",
+            Role::Viewer => "viewer",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Role> {
+        Self::ALL.into_iter().find(|role| role.name() == name)
+    }
+
+    /// Owners and admins are listed as a project's admins, everyone else as its collaborators.
+    pub fn is_admin(self) -> bool {
+        matches!(self, Role::Owner | Role::Admin)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    Read,
+    CreateGroup,
+    EditGroup,
+    DeleteGroup,
+    ManageMembers,
+    ManageEnvironments,
+    DeleteProject,
+}
+
+impl Permission {
+    pub const ALL: [Permission; 7] = [
+        Permission::Read,
+        Permission::CreateGroup,
+        Permission::EditGroup,
+        Permission::DeleteGroup,
+        Permission::ManageMembers,
+        Permission::ManageEnvironments,
+        Permission::DeleteProject,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Permission::Read => "read",
+            Permission::CreateGroup => "create_group",
+            Permission::EditGroup => "edit_group",
+            Permission::DeleteGroup => "delete_group",
+            Permission::ManageMembers => "manage_members",
+            Permission::ManageEnvironments => "manage_environments",
+            Permission::DeleteProject => "delete_project",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Permission> {
+        Self::ALL
+            .into_iter()
+            .find(|permission| permission.name() == name)
+    }
+}
+
+/// Days a deleted group stays in the trash before it is purged for good.
+pub const TRASH_RETENTION_DAYS: i64 = 30;
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct TrashedCurlGroup {
+    pub id: i64,
+    pub name: String,
+    pub description: String,
+    pub labels: String,
+    pub deleted_at: String,
+    /// Unset once the user who deleted the group is gone.
+    pub deleted_by: Option<String>,
+    pub purge_at: String,
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct UserAdminStatus {
     #[serde(rename = "isUserAdmin")]
@@ -66,7 +209,87 @@ pub struct UserAdminStatus {
 }
 
 #[derive(Debug, Default, Deserialize)]
+#[serde(default)]
 pub struct ProjectParams {
     pub search: String,
+    #[serde(deserialize_with = "deserialize_comma_separated")]
     pub labels: Vec<String>,
 }
+
+impl ProjectParams {
+    /// Search term with LIKE wildcards escaped, ready to be wrapped in `%...%`.
+    pub fn escaped_search(&self) -> String {
+        self.search
+            .trim()
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_")
+    }
+}
+
+// Labels are stored comma separated on curl groups, so they are accepted the same way in the query string.
+fn deserialize_comma_separated<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let labels = String::deserialize(deserializer)?;
+    Ok(labels
+        .split(',')
+        .map(|label| label.trim().to_string())
+        .filter(|label| !label.is_empty())
+        .collect())
+}
+
+#[cfg(test)]
+mod project_params_test {
+    use super::ProjectParams;
+    use actix_web::web::Query;
+
+    #[test]
+    fn deserialises_comma_separated_labels() {
+        let params =
+            Query::<ProjectParams>::from_query("search=api&labels=auth,%20billing,,").unwrap();
+        assert_eq!(params.search, "api");
+        assert_eq!(params.labels, vec!["auth", "billing"]);
+    }
+
+    #[test]
+    fn missing_fields_fall_back_to_defaults() {
+        let params = Query::<ProjectParams>::from_query("").unwrap();
+        assert_eq!(params.search, "");
+        assert!(params.labels.is_empty());
+    }
+
+    #[test]
+    fn escapes_like_wildcards_in_search() {
+        let params = ProjectParams {
+            search: " 100%_done ".to_string(),
+            labels: vec![],
+        };
+        assert_eq!(params.escaped_search(), "100\\%\\_done");
+    }
+}
+
+#[cfg(test)]
+mod role_test {
+    use super::{Permission, Role};
+
+    #[test]
+    fn names_match_serialised_values() {
+        for role in Role::ALL {
+            assert_eq!(
+                serde_json::to_value(role).unwrap(),
+                serde_json::json!(role.name())
+            );
+            assert_eq!(Role::from_name(role.name()), Some(role));
+        }
+        for permission in Permission::ALL {
+            assert_eq!(
+                serde_json::to_value(permission).unwrap(),
+                serde_json::json!(permission.name())
+            );
+            assert_eq!(Permission::from_name(permission.name()), Some(permission));
+        }
+        assert_eq!(Role::from_name("collaborator"), None);
+    }
+}
@@ -0,0 +1,260 @@
+use crate::{
+    curl,
+    models::{Curl, CurlGroup, ProjectInfo},
+    redaction::{self, RedactionPolicy},
+};
+
+use super::types::{FieldError, ProjectError, Redaction};
+
+pub const MAX_LABELS: usize = 3;
+
+/// Checks a submitted curl group, returning its parsed curls. All problems are collected so they
+/// can be reported together.
+pub(crate) fn validate_curl_group(curl_group: &CurlGroup) -> Result<Vec<Curl>, ProjectError> {
+    let mut errors = vec![];
+
+    if curl_group.name.trim().is_empty() {
+        errors.push(FieldError::new("name", "Name must not be empty."));
+    }
+
+    let label_count = curl_group
+        .labels
+        .split(',')
+        .filter(|label| !label.trim().is_empty())
+        .count();
+    if label_count > MAX_LABELS {
+        errors.push(FieldError::new(
+            "labels",
+            format!("At most {} labels are allowed.", MAX_LABELS),
+        ));
+    }
+
+    let curls = match serde_json::from_str::<Vec<Curl>>(&curl_group.curls) {
+        Ok(curls) => curls,
+        Err(e) => {
+            errors.push(FieldError::new(
+                "curls",
+                format!("Curls must be a list of curl entries: {}", e),
+            ));
+            vec![]
+        }
+    };
+
+    for (index, curl) in curls.iter().enumerate() {
+        // the group form submits `rawQuery` while single entries use `raw_query`
+        errors.extend(check_curl_entry(
+            &format!("curls[{}].", index),
+            "rawQuery",
+            &curl.name,
+            &curl.raw_query,
+        ));
+    }
+
+    if errors.is_empty() {
+        Ok(curls)
+    } else {
+        Err(ProjectError::ValidationError(errors))
+    }
+}
+
+/// Checks a single curl entry submitted on its own.
+pub(crate) fn validate_curl_entry(name: &str, raw_query: &str) -> Result<(), ProjectError> {
+    // single entries use `raw_query` while the group form submits `rawQuery`
+    let errors = check_curl_entry("", "raw_query", name, raw_query);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ProjectError::ValidationError(errors))
+    }
+}
+
+pub(crate) fn validate_project_info(info: &ProjectInfo) -> Result<(), ProjectError> {
+    match &info.redaction_policy {
+        Some(policy) if !RedactionPolicy::NAMES.contains(&policy.as_str()) => {
+            Err(ProjectError::ValidationError(vec![FieldError::new(
+                "redaction_policy",
+                format!(
+                    "Redaction policy must be one of: {}.",
+                    RedactionPolicy::NAMES.join(", ")
+                ),
+            )]))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Replaces secrets in the given raw queries in place, reporting each field that changed. Projects
+/// with the `Reject` policy fail the save instead.
+pub(crate) fn apply_redaction_policy<'a>(
+    policy: RedactionPolicy,
+    raw_queries: impl IntoIterator<Item = (String, &'a mut String)>,
+) -> Result<Vec<Redaction>, ProjectError> {
+    let mut redactions = vec![];
+
+    for (field, raw_query) in raw_queries {
+        let redacted = redaction::redact(raw_query);
+        if !redacted.secrets.is_empty() {
+            *raw_query = redacted.text;
+            redactions.push(Redaction {
+                field,
+                secrets: redacted.secrets,
+            });
+        }
+    }
+
+    if policy == RedactionPolicy::Reject && !redactions.is_empty() {
+        let errors = redactions
+            .into_iter()
+            .map(|redaction| {
+                let secrets: Vec<&str> = redaction
+                    .secrets
+                    .iter()
+                    .map(|secret| secret.description())
+                    .collect();
+                FieldError::new(
+                    redaction.field,
+                    format!(
+                        "Contains secrets ({}); use {{{{variables}}}} instead.",
+                        secrets.join(", ")
+                    ),
+                )
+            })
+            .collect();
+        return Err(ProjectError::ValidationError(errors));
+    }
+
+    Ok(redactions)
+}
+
+fn check_curl_entry(
+    prefix: &str,
+    raw_query_field: &str,
+    name: &str,
+    raw_query: &str,
+) -> Vec<FieldError> {
+    let mut errors = vec![];
+
+    if name.trim().is_empty() {
+        errors.push(FieldError::new(
+            format!("{}name", prefix),
+            "Name must not be empty.",
+        ));
+    }
+
+    if let Err(e) = curl::parse(raw_query) {
+        errors.push(FieldError::new(
+            format!("{}{}", prefix, raw_query_field),
+            format!("Not a valid curl command: {}", e),
+        ));
+    }
+
+    errors
+}
+
+#[cfg(test)]
+mod validation_test {
+    use super::{apply_redaction_policy, validate_curl_group};
+    use crate::{
+        models::CurlGroup,
+        redaction::{RedactionPolicy, SecretKind, REDACTED},
+        routes::project::types::ProjectError,
+    };
+
+    fn curl_group(name: &str, labels: &str, curls: &str) -> CurlGroup {
+        CurlGroup {
+            id: 1,
+            curls: curls.to_string(),
+            description: "".to_string(),
+            labels: labels.to_string(),
+            name: name.to_string(),
+            project_id: 1,
+        }
+    }
+
+    fn error_fields(curl_group: &CurlGroup) -> Vec<String> {
+        match validate_curl_group(curl_group) {
+            Err(ProjectError::ValidationError(errors)) => {
+                errors.into_iter().map(|error| error.field).collect()
+            }
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn accepts_well_formed_group() {
+        let group = curl_group(
+            "Group",
+            "a, b,c",
+            r#"[{"id":1,"name":"One","description":"","rawQuery":"curl https://example.com"}]"#,
+        );
+        assert_eq!(validate_curl_group(&group).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn collects_every_field_error() {
+        let group = curl_group(
+            " ",
+            "a,b,c,d",
+            r#"[{"name":"","description":"","rawQuery":"curl https://example.com"},{"name":"Two","description":"","rawQuery":"wget example.com"}]"#,
+        );
+        assert_eq!(
+            error_fields(&group),
+            vec!["name", "labels", "curls[0].name", "curls[1].rawQuery"]
+        );
+
+        let group = curl_group("Group", "", r#"[{"name":"No query"}]"#);
+        assert_eq!(error_fields(&group), vec!["curls"]);
+    }
+
+    #[test]
+    fn accepts_curl_options_that_are_not_supported() {
+        let group = curl_group(
+            "Group",
+            "",
+            r#"[{"name":"Digest","description":"","rawQuery":"curl --digest -u admin https://example.com"},{"name":"Upload","description":"","rawQuery":"curl -n -T file.txt https://example.com"}]"#,
+        );
+        assert_eq!(validate_curl_group(&group).unwrap().len(), 2);
+
+        let group = curl_group(
+            "Group",
+            "",
+            r#"[{"name":"Extra","description":"","rawQuery":"curl --compressed foo bar baz"}]"#,
+        );
+        assert_eq!(error_fields(&group), vec!["curls[0].rawQuery"]);
+    }
+
+    #[test]
+    fn redacts_or_rejects_secrets_by_policy() {
+        let mut raw_queries = [
+            "curl https://example.com".to_string(),
+            "curl -u admin:hunter2 https://example.com".to_string(),
+        ];
+        let fields = raw_queries
+            .iter_mut()
+            .enumerate()
+            .map(|(index, raw_query)| (format!("curls[{}].rawQuery", index), raw_query));
+        let redactions = apply_redaction_policy(RedactionPolicy::Redact, fields).unwrap();
+        assert_eq!(redactions.len(), 1);
+        assert_eq!(redactions[0].field, "curls[1].rawQuery");
+        assert_eq!(redactions[0].secrets, vec![SecretKind::BasicAuth]);
+        assert_eq!(
+            raw_queries[1],
+            format!("curl -u admin:{} https://example.com", REDACTED)
+        );
+
+        let mut raw_query = "curl -H 'X-Api-Key: 123' https://example.com".to_string();
+        match apply_redaction_policy(
+            RedactionPolicy::Reject,
+            [("raw_query".to_string(), &mut raw_query)],
+        ) {
+            Err(ProjectError::ValidationError(errors)) => {
+                assert_eq!(errors[0].field, "raw_query");
+                assert_eq!(
+                    errors[0].message,
+                    "Contains secrets (API key); use {{variables}} instead."
+                );
+            }
+            _ => panic!("expected the save to be rejected"),
+        }
+    }
+}
@@ -0,0 +1,14 @@
+mod routes;
+pub mod types;
+
+use actix_web::web;
+pub(crate) use routes::record_curl_group_revision;
+use routes::*;
+
+pub fn revision_routes(cfg: &mut web::ServiceConfig) {
+    // the diff has to be matched before `{number}` would reject it
+    cfg.service(get_curl_group_revision_diff)
+        .service(get_curl_group_revisions)
+        .service(get_curl_group_revision)
+        .service(restore_curl_group_revision);
+}
@@ -0,0 +1,356 @@
+use crate::{
+    diff::{common_subsequence, diff_lines},
+    helpers::{get_user_id, Identity},
+    models::Curl,
+    routes::{
+        audit::{get_curl_group_summary, record_audit_event, AuditRecord},
+        entry::replace_curl_entries,
+        project::{
+            check_project_permission, curl_group_check_user_permission,
+            types::{Permission, ProjectError},
+        },
+    },
+};
+
+use actix_web::{get, post, web, HttpResponse, Responder};
+use sqlx::{Sqlite, SqlitePool, Transaction};
+use tracing_actix_web::RequestId;
+
+use super::types::{
+    EntryChange, EntryDiff, FieldChange, Revision, RevisionContent, RevisionDiff,
+    RevisionDiffParams, RevisionSummary,
+};
+
+#[get("/group/{group_id}/revision")]
+#[tracing::instrument(name = "Getting curl group revisions.", skip(pool, identity))]
+async fn get_curl_group_revisions(
+    params: web::Path<i64>,
+    pool: web::Data<SqlitePool>,
+    identity: Identity,
+) -> impl Responder {
+    match get_revisions_from_db(*params, &pool, &identity).await {
+        Ok(revisions) => HttpResponse::Ok().json(revisions),
+        Err(e) => e.into(),
+    }
+}
+
+#[get("/group/{group_id}/revision/diff")]
+#[tracing::instrument(name = "Comparing curl group revisions.", skip(pool, identity))]
+async fn get_curl_group_revision_diff(
+    params: web::Path<i64>,
+    query: web::Query<RevisionDiffParams>,
+    pool: web::Data<SqlitePool>,
+    identity: Identity,
+) -> impl Responder {
+    match diff_revisions_from_db(*params, query.from, query.to, &pool, &identity).await {
+        Ok(diff) => HttpResponse::Ok().json(diff),
+        Err(e) => e.into(),
+    }
+}
+
+#[get("/group/{group_id}/revision/{number}")]
+#[tracing::instrument(name = "Getting curl group revision.", skip(pool, identity))]
+async fn get_curl_group_revision(
+    params: web::Path<(i64, i64)>,
+    pool: web::Data<SqlitePool>,
+    identity: Identity,
+) -> impl Responder {
+    let (group_id, number) = params.into_inner();
+    let revision = match curl_group_check_user_permission(group_id, &pool, &identity).await {
+        Ok(_) => get_revision_from_db(group_id, number, &pool).await,
+        Err(e) => Err(e),
+    };
+
+    match revision {
+        Ok(revision) => HttpResponse::Ok().json(revision),
+        Err(e) => e.into(),
+    }
+}
+
+#[post("/group/{group_id}/revision/{number}/restore")]
+#[tracing::instrument(name = "Restoring curl group revision.", skip(pool, identity))]
+async fn restore_curl_group_revision(
+    params: web::Path<(i64, i64)>,
+    pool: web::Data<SqlitePool>,
+    identity: Identity,
+    request_id: RequestId,
+) -> impl Responder {
+    let (group_id, number) = params.into_inner();
+    match restore_revision_in_db(group_id, number, &pool, &identity, &request_id).await {
+        Ok(revision) => HttpResponse::Ok().json(revision),
+        Err(e) => e.into(),
+    }
+}
+
+/// Snapshots the group as it stands within the transaction, unless that is what its latest
+/// revision already holds. Called by everything that changes a group or its entries.
+pub(crate) async fn record_curl_group_revision(
+    group_id: i64,
+    user_id: i64,
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> Result<(), ProjectError> {
+    let content = sqlx::query!(
+        r#"SELECT json_object(
+            'name', name,
+            'description', description,
+            'labels', labels,
+            'curls', json((
+                SELECT json_group_array(json_object('id', id, 'name', name, 'description', description, 'rawQuery', raw_query))
+                FROM (SELECT * FROM curl_entry WHERE curl_entry.group_id = curl_group.id ORDER BY position)
+            ))
+        ) AS "content!: String"
+        FROM curl_group WHERE id = ?"#,
+        group_id
+    )
+    .fetch_one(&mut *transaction)
+    .await?
+    .content;
+
+    let latest = sqlx::query!(
+        r#"SELECT content FROM curl_group_revision WHERE group_id = ? ORDER BY number DESC LIMIT 1"#,
+        group_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await?;
+    if latest.is_some_and(|latest| latest.content == content) {
+        return Ok(());
+    }
+
+    sqlx::query!(
+        r#"INSERT INTO curl_group_revision (group_id, number, user_id, content)
+        VALUES (?, (SELECT COALESCE(MAX(number), 0) + 1 FROM curl_group_revision WHERE group_id = ?), ?, ?)"#,
+        group_id,
+        group_id,
+        user_id,
+        content
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    Ok(())
+}
+
+/// Newest first.
+async fn get_revisions_from_db(
+    group_id: i64,
+    pool: &SqlitePool,
+    identity: &Identity,
+) -> Result<Vec<RevisionSummary>, ProjectError> {
+    curl_group_check_user_permission(group_id, pool, identity).await?;
+
+    let revisions = sqlx::query_as!(
+        RevisionSummary,
+        r#"SELECT number, user.name AS "author?", created_at FROM curl_group_revision
+        LEFT JOIN user ON user.id = curl_group_revision.user_id
+        WHERE group_id = ? ORDER BY number DESC"#,
+        group_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(revisions)
+}
+
+async fn get_revision_from_db(
+    group_id: i64,
+    number: i64,
+    pool: &SqlitePool,
+) -> Result<Revision, ProjectError> {
+    let record = sqlx::query!(
+        r#"SELECT number, user.name AS "author?", created_at, content FROM curl_group_revision
+        LEFT JOIN user ON user.id = curl_group_revision.user_id
+        WHERE group_id = ? AND number = ?"#,
+        group_id,
+        number
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let content = serde_json::from_str(&record.content)
+        .map_err(|e| ProjectError::SqlxError(sqlx::Error::Decode(Box::new(e))))?;
+    Ok(Revision {
+        summary: RevisionSummary {
+            number: record.number,
+            author: record.author,
+            created_at: record.created_at,
+        },
+        content,
+    })
+}
+
+async fn diff_revisions_from_db(
+    group_id: i64,
+    from: i64,
+    to: i64,
+    pool: &SqlitePool,
+    identity: &Identity,
+) -> Result<RevisionDiff, ProjectError> {
+    curl_group_check_user_permission(group_id, pool, identity).await?;
+    let from_revision = get_revision_from_db(group_id, from, pool).await?;
+    let to_revision = get_revision_from_db(group_id, to, pool).await?;
+
+    Ok(diff_revisions(
+        from,
+        &from_revision.content,
+        to,
+        &to_revision.content,
+    ))
+}
+
+/// Puts the group back the way it was as a new revision, leaving the history in between intact.
+async fn restore_revision_in_db(
+    group_id: i64,
+    number: i64,
+    pool: &SqlitePool,
+    identity: &Identity,
+    request_id: &RequestId,
+) -> Result<RevisionSummary, ProjectError> {
+    let user_id = get_user_id(identity).await?;
+    let project_id = curl_group_check_user_permission(group_id, pool, identity).await?;
+    check_project_permission(identity, project_id, Permission::EditGroup, pool).await?;
+    let revision = get_revision_from_db(group_id, number, pool).await?;
+    let content = revision.content;
+
+    let mut transaction = pool.begin().await?;
+    let before = get_curl_group_summary(group_id, &mut transaction).await?;
+    sqlx::query!(
+        r#"UPDATE curl_group SET description = ?, labels = ?, name = ? WHERE id = ?"#,
+        content.description,
+        content.labels,
+        content.name,
+        group_id
+    )
+    .execute(&mut transaction)
+    .await?;
+    replace_curl_entries(group_id, &content.curls, &mut transaction).await?;
+    record_curl_group_revision(group_id, user_id, &mut transaction).await?;
+    let after = get_curl_group_summary(group_id, &mut transaction).await?;
+    record_audit_event(
+        AuditRecord {
+            project_id,
+            actor_id: user_id,
+            action: "curl_group.restore",
+            target: format!("curl_group:{}", group_id),
+            before: Some(before),
+            after: Some(after),
+        },
+        request_id,
+        &mut transaction,
+    )
+    .await?;
+
+    let latest = sqlx::query_as!(
+        RevisionSummary,
+        r#"SELECT number, user.name AS "author?", created_at FROM curl_group_revision
+        LEFT JOIN user ON user.id = curl_group_revision.user_id
+        WHERE group_id = ? ORDER BY number DESC LIMIT 1"#,
+        group_id
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+    transaction.commit().await?;
+
+    Ok(latest)
+}
+
+fn field_changes(fields: &[(&str, &str, &str)]) -> Vec<FieldChange> {
+    fields
+        .iter()
+        .filter(|(_, from, to)| from != to)
+        .map(|(field, from, to)| FieldChange {
+            field: field.to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+        })
+        .collect()
+}
+
+fn diff_revisions(
+    from: i64,
+    from_content: &RevisionContent,
+    to: i64,
+    to_content: &RevisionContent,
+) -> RevisionDiff {
+    let fields = field_changes(&[
+        ("name", &from_content.name, &to_content.name),
+        (
+            "description",
+            &from_content.description,
+            &to_content.description,
+        ),
+        ("labels", &from_content.labels, &to_content.labels),
+    ]);
+
+    let position = |curls: &[Curl], id: i64| curls.iter().position(|curl| curl.id == id);
+    // of the entries in both, those outside the longest run kept in order count as moved
+    let from_ids: Vec<i64> = from_content.curls.iter().map(|curl| curl.id).collect();
+    let to_ids: Vec<i64> = to_content.curls.iter().map(|curl| curl.id).collect();
+    let in_order: Vec<i64> = common_subsequence(&from_ids, &to_ids)
+        .into_iter()
+        .map(|(index, _)| from_ids[index])
+        .collect();
+
+    let mut entries = vec![];
+    for (to_position, curl) in to_content.curls.iter().enumerate() {
+        let Some(from_position) = position(&from_content.curls, curl.id) else {
+            entries.push(EntryDiff {
+                id: curl.id,
+                change: EntryChange::Added,
+                name: curl.name.clone(),
+                from_position: None,
+                to_position: Some(to_position),
+                fields: vec![],
+                raw_query: diff_lines("", &curl.raw_query),
+            });
+            continue;
+        };
+
+        let previous = &from_content.curls[from_position];
+        let fields = field_changes(&[
+            ("name", &previous.name, &curl.name),
+            ("description", &previous.description, &curl.description),
+        ]);
+        let raw_query = if previous.raw_query == curl.raw_query {
+            vec![]
+        } else {
+            diff_lines(&previous.raw_query, &curl.raw_query)
+        };
+        let change = if !fields.is_empty() || !raw_query.is_empty() {
+            EntryChange::Modified
+        } else if !in_order.contains(&curl.id) {
+            EntryChange::Moved
+        } else {
+            continue;
+        };
+        entries.push(EntryDiff {
+            id: curl.id,
+            change,
+            name: curl.name.clone(),
+            from_position: Some(from_position),
+            to_position: Some(to_position),
+            fields,
+            raw_query,
+        });
+    }
+
+    for (from_position, curl) in from_content.curls.iter().enumerate() {
+        if position(&to_content.curls, curl.id).is_none() {
+            entries.push(EntryDiff {
+                id: curl.id,
+                change: EntryChange::Removed,
+                name: curl.name.clone(),
+                from_position: Some(from_position),
+                to_position: None,
+                fields: vec![],
+                raw_query: diff_lines(&curl.raw_query, ""),
+            });
+        }
+    }
+
+    RevisionDiff {
+        from,
+        to,
+        fields,
+        entries,
+    }
+}
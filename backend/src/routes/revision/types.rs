@@ -0,0 +1,75 @@
+use crate::{diff::DiffLine, models::Curl};
+
+use serde::{Deserialize, Serialize};
+
+/// The group as it was saved. Curls keep the ids of their entries, which tell entries apart
+/// between revisions.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct RevisionContent {
+    pub name: String,
+    pub description: String,
+    pub labels: String,
+    pub curls: Vec<Curl>,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct RevisionSummary {
+    pub number: i64,
+    /// Name of the user who saved the revision, unknown for revisions older than the history.
+    pub author: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Revision {
+    #[serde(flatten)]
+    pub summary: RevisionSummary,
+    pub content: RevisionContent,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RevisionDiffParams {
+    pub from: i64,
+    pub to: i64,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct RevisionDiff {
+    pub from: i64,
+    pub to: i64,
+    /// Changes to the name, description and labels of the group.
+    pub fields: Vec<FieldChange>,
+    /// Entries that differ between the revisions, in the order of `to` followed by the removed
+    /// ones.
+    pub entries: Vec<EntryDiff>,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: String,
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryChange {
+    Added,
+    Removed,
+    Modified,
+    /// Unchanged, but in a different order relative to the other entries.
+    Moved,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct EntryDiff {
+    pub id: i64,
+    pub change: EntryChange,
+    pub name: String,
+    pub from_position: Option<usize>,
+    pub to_position: Option<usize>,
+    /// Changes to the name and description of the entry.
+    pub fields: Vec<FieldChange>,
+    /// The lines of the raw query, empty when the query is unchanged.
+    pub raw_query: Vec<DiffLine>,
+}
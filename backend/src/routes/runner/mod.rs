@@ -0,0 +1,11 @@
+mod routes;
+pub mod types;
+
+use actix_web::web;
+use routes::*;
+
+pub fn runner_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_runner_settings)
+        .service(update_runner_settings)
+        .service(run_curl_entry);
+}
@@ -0,0 +1,209 @@
+use crate::{
+    curl,
+    helpers::{get_user_id, Identity},
+    routes::{
+        environment::get_environment_variables,
+        project::{
+            check_project_permission, curl_group_check_user_permission,
+            project_check_user_permission,
+            types::{FieldError, Permission, ProjectError},
+        },
+    },
+    runner::{self, RunResult},
+    secrets::SecretCipher,
+    template,
+};
+
+use actix_web::{get, post, web, HttpResponse, Responder};
+use sqlx::SqlitePool;
+use std::collections::BTreeMap;
+
+use super::types::{RunRequest, RunnerError, RunnerSettings, MAX_RESPONSE_BYTES, MAX_TIMEOUT_MS};
+
+#[get("/project/{project_id}/runner")]
+#[tracing::instrument(name = "Getting runner settings.", skip(pool, identity))]
+async fn get_runner_settings(
+    params: web::Path<i64>,
+    pool: web::Data<SqlitePool>,
+    identity: Identity,
+) -> impl Responder {
+    let settings = match project_check_user_permission(*params, &pool, &identity).await {
+        Ok(_) => get_runner_settings_from_db(*params, &pool).await,
+        Err(e) => Err(e),
+    };
+
+    match settings {
+        Ok(settings) => HttpResponse::Ok().json(settings),
+        Err(e) => e.into(),
+    }
+}
+
+#[post("/project/{project_id}/runner")]
+#[tracing::instrument(name = "Updating runner settings.", skip(body, pool, identity))]
+async fn update_runner_settings(
+    params: web::Path<i64>,
+    body: web::Json<RunnerSettings>,
+    pool: web::Data<SqlitePool>,
+    identity: Identity,
+) -> impl Responder {
+    match update_runner_settings_in_db(*params, &body, &pool, &identity).await {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) => e.into(),
+    }
+}
+
+#[post("/group/{group_id}/entry/{entry_id}/run")]
+#[tracing::instrument(name = "Running curl entry.", skip(body, pool, cipher, identity))]
+async fn run_curl_entry(
+    params: web::Path<(i64, i64)>,
+    body: web::Json<RunRequest>,
+    pool: web::Data<SqlitePool>,
+    cipher: web::Data<SecretCipher>,
+    identity: Identity,
+) -> impl Responder {
+    let (group_id, entry_id) = params.into_inner();
+    match run_curl_entry_from_db(group_id, entry_id, &body, &pool, &cipher, &identity).await {
+        Ok(result) => HttpResponse::Ok().json(result),
+        Err(e) => e.into(),
+    }
+}
+
+async fn get_runner_settings_from_db(
+    project_id: i64,
+    pool: &SqlitePool,
+) -> Result<RunnerSettings, ProjectError> {
+    let settings = sqlx::query!(
+        r#"SELECT allowed_hosts, timeout_ms, max_response_bytes FROM project_runner_settings WHERE project_id = ?"#,
+        project_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .map(|record| RunnerSettings {
+        allowed_hosts: record
+            .allowed_hosts
+            .split(',')
+            .map(|host| host.trim().to_string())
+            .filter(|host| !host.is_empty())
+            .collect(),
+        timeout_ms: record.timeout_ms,
+        max_response_bytes: record.max_response_bytes,
+    })
+    .unwrap_or_default();
+
+    Ok(settings)
+}
+
+async fn update_runner_settings_in_db(
+    project_id: i64,
+    settings: &RunnerSettings,
+    pool: &SqlitePool,
+    identity: &Identity,
+) -> Result<(), ProjectError> {
+    check_project_permission(identity, project_id, Permission::ManageEnvironments, pool).await?;
+    validate_runner_settings(settings)?;
+
+    let allowed_hosts = settings.allowed_hosts.join(",");
+    sqlx::query!(
+        r#"INSERT INTO project_runner_settings (project_id, allowed_hosts, timeout_ms, max_response_bytes) VALUES (?, ?, ?, ?)
+        ON CONFLICT (project_id) DO UPDATE SET allowed_hosts = excluded.allowed_hosts, timeout_ms = excluded.timeout_ms, max_response_bytes = excluded.max_response_bytes"#,
+        project_id,
+        allowed_hosts,
+        settings.timeout_ms,
+        settings.max_response_bytes
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+async fn run_curl_entry_from_db(
+    group_id: i64,
+    entry_id: i64,
+    run_request: &RunRequest,
+    pool: &SqlitePool,
+    cipher: &SecretCipher,
+    identity: &Identity,
+) -> Result<RunResult, RunnerError> {
+    // requests are sent from the server, so anonymous visitors of public projects cannot run them
+    get_user_id(identity).await.map_err(ProjectError::from)?;
+    let project_id = curl_group_check_user_permission(group_id, pool, identity).await?;
+
+    let raw_query = sqlx::query!(
+        r#"SELECT raw_query FROM curl_entry WHERE id = ? AND group_id = ?"#,
+        entry_id,
+        group_id
+    )
+    .fetch_one(pool)
+    .await?
+    .raw_query;
+
+    let variables = match &run_request.environment {
+        Some(name) => {
+            let (mut variables, secrets) =
+                get_environment_variables(project_id, name, pool).await?;
+            // secrets are only used on behalf of members who may edit the project's groups
+            if check_project_permission(identity, project_id, Permission::EditGroup, pool)
+                .await
+                .is_ok()
+            {
+                for (name, value) in &secrets {
+                    variables.insert(
+                        name.clone(),
+                        cipher.decrypt(value).map_err(ProjectError::from)?,
+                    );
+                }
+            }
+            variables
+        }
+        None => BTreeMap::new(),
+    };
+
+    let rendered = template::render(&raw_query, &variables);
+    if !rendered.missing.is_empty() {
+        return Err(RunnerError::MissingVariables(rendered.missing));
+    }
+
+    let request = curl::parse(&rendered.text).map_err(RunnerError::InvalidCommand)?;
+    let settings = get_runner_settings_from_db(project_id, pool).await?;
+    let result = runner::run(&request, &settings.into()).await?;
+
+    Ok(result)
+}
+
+fn validate_runner_settings(settings: &RunnerSettings) -> Result<(), ProjectError> {
+    let mut errors = vec![];
+
+    for (index, host) in settings.allowed_hosts.iter().enumerate() {
+        let host = host.trim();
+        if host.is_empty() || host.contains("://") || host.contains('/') || host.contains(',') {
+            errors.push(FieldError::new(
+                format!("allowed_hosts[{}]", index),
+                "Hosts must be given without a scheme or path, e.g. api.example.com or *.example.com.",
+            ));
+        }
+    }
+
+    if !(1..=MAX_TIMEOUT_MS).contains(&settings.timeout_ms) {
+        errors.push(FieldError::new(
+            "timeout_ms",
+            format!("Timeout must be between 1 and {} ms.", MAX_TIMEOUT_MS),
+        ));
+    }
+
+    if !(1..=MAX_RESPONSE_BYTES).contains(&settings.max_response_bytes) {
+        errors.push(FieldError::new(
+            "max_response_bytes",
+            format!(
+                "Maximum response size must be between 1 and {} bytes.",
+                MAX_RESPONSE_BYTES
+            ),
+        ));
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ProjectError::ValidationError(errors))
+    }
+}
@@ -0,0 +1,109 @@
+use actix_web::HttpResponse;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::{
+    curl::ParseError, routes::project::types::ProjectError, runner::RunError, ssrf::SsrfGuard,
+};
+
+pub const DEFAULT_TIMEOUT_MS: i64 = 10_000;
+pub const MAX_TIMEOUT_MS: i64 = 60_000;
+pub const DEFAULT_MAX_RESPONSE_BYTES: i64 = 1024 * 1024;
+pub const MAX_RESPONSE_BYTES: i64 = 10 * 1024 * 1024;
+
+#[derive(Debug)]
+pub enum RunnerError {
+    InvalidCommand(ParseError),
+    MissingVariables(Vec<String>),
+    ProjectError(ProjectError),
+    RunError(RunError),
+}
+
+impl From<ProjectError> for RunnerError {
+    fn from(e: ProjectError) -> Self {
+        RunnerError::ProjectError(e)
+    }
+}
+
+impl From<sqlx::Error> for RunnerError {
+    fn from(e: sqlx::Error) -> Self {
+        RunnerError::ProjectError(e.into())
+    }
+}
+
+impl From<RunError> for RunnerError {
+    fn from(e: RunError) -> Self {
+        RunnerError::RunError(e)
+    }
+}
+
+impl From<RunnerError> for HttpResponse {
+    fn from(e: RunnerError) -> Self {
+        match e {
+            RunnerError::InvalidCommand(e) => HttpResponse::UnprocessableEntity().json(e),
+            RunnerError::MissingVariables(names) => HttpResponse::UnprocessableEntity().json(
+                ErrorMessage::new(format!("Missing variables: {}", names.join(", "))),
+            ),
+            RunnerError::ProjectError(e) => e.into(),
+            RunnerError::RunError(e) => {
+                let message = ErrorMessage::new(e.to_string());
+                match e {
+                    RunError::UnsupportedRequest(_) => {
+                        HttpResponse::UnprocessableEntity().json(message)
+                    }
+                    RunError::Blocked(_) => HttpResponse::Forbidden().json(message),
+                    RunError::Timeout => HttpResponse::GatewayTimeout().json(message),
+                    RunError::RequestFailed(_) => HttpResponse::BadGateway().json(message),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ErrorMessage {
+    pub message: String,
+}
+
+impl ErrorMessage {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct RunnerSettings {
+    /// Nothing can be run until hosts are added here.
+    pub allowed_hosts: Vec<String>,
+    pub timeout_ms: i64,
+    pub max_response_bytes: i64,
+}
+
+impl Default for RunnerSettings {
+    fn default() -> Self {
+        Self {
+            allowed_hosts: vec![],
+            timeout_ms: DEFAULT_TIMEOUT_MS,
+            max_response_bytes: DEFAULT_MAX_RESPONSE_BYTES,
+        }
+    }
+}
+
+impl From<RunnerSettings> for SsrfGuard {
+    fn from(settings: RunnerSettings) -> Self {
+        SsrfGuard::new(
+            settings.allowed_hosts,
+            Duration::from_millis(settings.timeout_ms as u64),
+            settings.max_response_bytes as usize,
+        )
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RunRequest {
+    /// Environment to render the command with before running it.
+    pub environment: Option<String>,
+}
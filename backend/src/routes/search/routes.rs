@@ -0,0 +1,88 @@
+use crate::{
+    helpers::{get_user_id, Identity},
+    routes::{project::types::ProjectError, user::types::GlobalRole},
+};
+
+use actix_web::{get, web, HttpResponse, Responder};
+use sqlx::SqlitePool;
+
+use super::types::{highlight, SearchParams, SearchResult, MATCH_END, MATCH_START};
+
+const MAX_SEARCH_RESULTS: i64 = 50;
+
+#[get("/search")]
+#[tracing::instrument(name = "Searching curl groups.", skip(pool, identity))]
+async fn search_curl_groups(
+    query: web::Query<SearchParams>,
+    pool: web::Data<SqlitePool>,
+    identity: Identity,
+) -> impl Responder {
+    match search_curl_groups_in_db(&query, &pool, &identity).await {
+        Ok(results) => HttpResponse::Ok().json(results),
+        Err(e) => e.into(),
+    }
+}
+
+async fn search_curl_groups_in_db(
+    params: &SearchParams,
+    pool: &SqlitePool,
+    identity: &Identity,
+) -> Result<Vec<SearchResult>, ProjectError> {
+    let match_expression = match params.to_match_expression() {
+        Some(match_expression) => match_expression,
+        None => return Ok(vec![]),
+    };
+    // same visibility rules as listing projects - anonymous users only see public projects
+    let user_id = get_user_id(identity).await.unwrap_or(-1);
+    let scope = identity.project_scope();
+    let global_admin = GlobalRole::Admin.name();
+
+    let (match_start, match_end) = (MATCH_START.to_string(), MATCH_END.to_string());
+
+    // bm25 column weights favour matches in the group name, then description/labels, then the curls
+    let results = sqlx::query_as!(
+        SearchResult,
+        r#"
+        SELECT
+            curl_group.id AS "group_id!",
+            curl_group.project_id AS "project_id!",
+            project.name AS "project_name!",
+            curl_group.name AS "name!",
+            curl_group.description AS "description!",
+            curl_group.labels AS "labels!",
+            snippet(curl_group_search, -1, ?, ?, '...', 16) AS "snippet!: String"
+        FROM curl_group_search
+        JOIN curl_group ON curl_group.id = curl_group_search.rowid
+        JOIN project ON project.id = curl_group.project_id
+        WHERE curl_group_search MATCH ? AND curl_group.deleted_at IS NULL
+        AND (
+            project.visibility = "Public"
+            OR EXISTS (
+                SELECT 1 FROM project_member WHERE project_member.project_id = project.id AND project_member.user_id = ?
+            )
+            OR EXISTS (SELECT 1 FROM user WHERE user.id = ? AND global_role = ?)
+        )
+        AND (? IS NULL OR project.id = ?)
+        ORDER BY bm25(curl_group_search, 10.0, 5.0, 5.0, 1.0)
+        LIMIT ?"#,
+        match_start,
+        match_end,
+        match_expression,
+        user_id,
+        user_id,
+        global_admin,
+        scope,
+        scope,
+        MAX_SEARCH_RESULTS
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|result| SearchResult {
+        snippet: highlight(&result.snippet),
+        ..result
+    })
+    .collect();
+
+    Ok(results)
+}
@@ -0,0 +1,101 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct SearchParams {
+    pub query: String,
+}
+
+impl SearchParams {
+    /// Converts the free-text query into an FTS5 match expression. Every whitespace separated term
+    /// is quoted so punctuation such as `/billing/refund` is matched as a phrase rather than being
+    /// interpreted as FTS syntax, and the last term is prefix matched to support search-as-you-type.
+    pub fn to_match_expression(&self) -> Option<String> {
+        let terms: Vec<String> = self
+            .query
+            .split_whitespace()
+            .map(|term| term.replace('"', ""))
+            .filter(|term| !term.is_empty())
+            .map(|term| format!("\"{}\"", term))
+            .collect();
+
+        if terms.is_empty() {
+            None
+        } else {
+            Some(format!("{}*", terms.join(" ")))
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct SearchResult {
+    pub group_id: i64,
+    pub project_id: i64,
+    pub project_name: String,
+    pub name: String,
+    pub description: String,
+    pub labels: String,
+    /// Best matching fragment as HTML, with matches wrapped in `<mark>` tags and everything else
+    /// escaped.
+    pub snippet: String,
+}
+
+/// What SQLite is asked to put around matches, as they cannot be confused with `<mark>` tags that
+/// were part of the text.
+pub const MATCH_START: char = '\u{2}';
+pub const MATCH_END: char = '\u{3}';
+
+/// Escapes a snippet for HTML and turns its match markers into `<mark>` tags.
+pub fn highlight(snippet: &str) -> String {
+    let mut html = String::with_capacity(snippet.len());
+    for c in snippet.chars() {
+        match c {
+            MATCH_START => html.push_str("<mark>"),
+            MATCH_END => html.push_str("</mark>"),
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c),
+        }
+    }
+    html
+}
+
+#[cfg(test)]
+mod search_params_test {
+    use super::{highlight, SearchParams};
+
+    fn match_expression(query: &str) -> Option<String> {
+        SearchParams {
+            query: query.to_string(),
+        }
+        .to_match_expression()
+    }
+
+    #[test]
+    fn quotes_each_term_and_prefix_matches_the_last() {
+        assert_eq!(
+            match_expression("POST /billing/refund"),
+            Some("\"POST\" \"/billing/refund\"*".to_string())
+        );
+    }
+
+    #[test]
+    fn strips_quotes_and_ignores_blank_queries() {
+        assert_eq!(
+            match_expression("\"token\" OR"),
+            Some("\"token\" \"OR\"*".to_string())
+        );
+        assert_eq!(match_expression("  \"\" "), None);
+    }
+
+    #[test]
+    fn highlight_escapes_everything_but_the_matches() {
+        assert_eq!(
+            highlight("-d '<script>\u{2}alert\u{3}(1)</script>'"),
+            "-d &#39;&lt;script&gt;<mark>alert</mark>(1)&lt;/script&gt;&#39;"
+        );
+    }
+}
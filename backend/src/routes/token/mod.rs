@@ -0,0 +1,12 @@
+mod routes;
+pub mod types;
+
+use actix_web::web;
+pub(crate) use routes::authenticate_api_token;
+use routes::*;
+
+pub fn token_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(create_api_token)
+        .service(get_api_tokens)
+        .service(revoke_api_token);
+}
@@ -0,0 +1,230 @@
+use crate::{
+    helpers::get_session_user_id,
+    routes::project::types::{FieldError, ProjectError},
+};
+
+use actix_session::Session;
+use actix_web::{delete, get, post, web, HttpResponse, Responder};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+
+use super::types::{
+    ApiToken, ApiTokenRequest, CreatedApiToken, TokenAccess, TokenScope, MAX_TOKEN_LIFETIME_DAYS,
+    MAX_TOKEN_NAME_LENGTH, TOKEN_PREFIX,
+};
+
+// tokens are managed from a logged in session only, so a leaked token cannot mint more of them
+
+#[post("/tokens")]
+#[tracing::instrument(name = "Creating API token.", skip(body, pool, session))]
+async fn create_api_token(
+    body: web::Json<ApiTokenRequest>,
+    pool: web::Data<SqlitePool>,
+    session: Session,
+) -> impl Responder {
+    match insert_api_token_into_db(&body, &pool, &session).await {
+        Ok(token) => HttpResponse::Ok().json(token),
+        Err(e) => e.into(),
+    }
+}
+
+#[get("/tokens")]
+#[tracing::instrument(name = "Getting API tokens.", skip(pool, session))]
+async fn get_api_tokens(pool: web::Data<SqlitePool>, session: Session) -> impl Responder {
+    match get_api_tokens_from_db(&pool, &session).await {
+        Ok(tokens) => HttpResponse::Ok().json(tokens),
+        Err(e) => e.into(),
+    }
+}
+
+#[delete("/tokens/{token_id}")]
+#[tracing::instrument(name = "Revoking API token.", skip(pool, session))]
+async fn revoke_api_token(
+    params: web::Path<i64>,
+    pool: web::Data<SqlitePool>,
+    session: Session,
+) -> impl Responder {
+    match revoke_api_token_in_db(*params, &pool, &session).await {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) => e.into(),
+    }
+}
+
+/// Looks up the token a request was made with, recording that it was used. Expired and revoked
+/// tokens are not found.
+pub(crate) async fn authenticate_api_token(
+    token: &str,
+    pool: &SqlitePool,
+) -> Result<Option<TokenAccess>, sqlx::Error> {
+    let token_hash = hash_token(token);
+    let record = sqlx::query!(
+        r#"UPDATE api_token SET last_used_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+        WHERE token_hash = ? AND (expires_at IS NULL OR expires_at > strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+        RETURNING user_id AS "user_id!", scope AS "scope!", project_id"#,
+        token_hash
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(record.and_then(|record| {
+        Some(TokenAccess {
+            user_id: record.user_id,
+            scope: TokenScope::from_name(&record.scope)?,
+            project_id: record.project_id,
+        })
+    }))
+}
+
+async fn insert_api_token_into_db(
+    request: &ApiTokenRequest,
+    pool: &SqlitePool,
+    session: &Session,
+) -> Result<CreatedApiToken, ProjectError> {
+    let user_id = get_session_user_id(session, pool).await?;
+    validate_api_token_request(user_id, request, pool).await?;
+
+    let mut secret = [0u8; 32];
+    OsRng.fill_bytes(&mut secret);
+    let token = format!("{}{}", TOKEN_PREFIX, URL_SAFE_NO_PAD.encode(secret));
+    let token_hash = hash_token(&token);
+    let name = request.name.trim();
+    let scope = request.scope.name();
+    let id = sqlx::query!(
+        r#"INSERT INTO api_token (user_id, name, token_hash, scope, project_id, expires_at)
+        VALUES (?, ?, ?, ?, ?, CASE WHEN ? IS NULL THEN NULL ELSE strftime('%Y-%m-%dT%H:%M:%fZ', 'now', '+' || ? || ' days') END)
+        RETURNING id"#,
+        user_id,
+        name,
+        token_hash,
+        scope,
+        request.project_id,
+        request.expires_in_days,
+        request.expires_in_days
+    )
+    .fetch_one(pool)
+    .await?
+    .id;
+
+    let info = get_api_tokens_for_user(user_id, pool)
+        .await?
+        .into_iter()
+        .find(|token| token.id == id)
+        .ok_or(sqlx::Error::RowNotFound)?;
+    Ok(CreatedApiToken { token, info })
+}
+
+async fn get_api_tokens_from_db(
+    pool: &SqlitePool,
+    session: &Session,
+) -> Result<Vec<ApiToken>, ProjectError> {
+    let user_id = get_session_user_id(session, pool).await?;
+    get_api_tokens_for_user(user_id, pool).await
+}
+
+/// Newest first, expired tokens included so they can be told apart from revoked ones.
+async fn get_api_tokens_for_user(
+    user_id: i64,
+    pool: &SqlitePool,
+) -> Result<Vec<ApiToken>, ProjectError> {
+    let records = sqlx::query!(
+        r#"SELECT id, name, scope, project_id, created_at, expires_at, last_used_at FROM api_token
+        WHERE user_id = ? ORDER BY id DESC"#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(records
+        .into_iter()
+        .filter_map(|record| {
+            Some(ApiToken {
+                id: record.id,
+                name: record.name,
+                scope: TokenScope::from_name(&record.scope)?,
+                project_id: record.project_id,
+                created_at: record.created_at,
+                expires_at: record.expires_at,
+                last_used_at: record.last_used_at,
+            })
+        })
+        .collect())
+}
+
+async fn revoke_api_token_in_db(
+    token_id: i64,
+    pool: &SqlitePool,
+    session: &Session,
+) -> Result<(), ProjectError> {
+    let user_id = get_session_user_id(session, pool).await?;
+    let revoked = sqlx::query!(
+        r#"DELETE FROM api_token WHERE id = ? AND user_id = ?"#,
+        token_id,
+        user_id
+    )
+    .execute(pool)
+    .await?
+    .rows_affected();
+
+    if revoked == 0 {
+        return Err(sqlx::Error::RowNotFound.into());
+    }
+    Ok(())
+}
+
+async fn validate_api_token_request(
+    user_id: i64,
+    request: &ApiTokenRequest,
+    pool: &SqlitePool,
+) -> Result<(), ProjectError> {
+    let mut errors = vec![];
+    let name = request.name.trim();
+    if name.is_empty() {
+        errors.push(FieldError::new("name", "Name must not be empty."));
+    } else if name.chars().count() > MAX_TOKEN_NAME_LENGTH {
+        errors.push(FieldError::new(
+            "name",
+            format!(
+                "Name must not be longer than {} characters.",
+                MAX_TOKEN_NAME_LENGTH
+            ),
+        ));
+    }
+    if let Some(days) = request.expires_in_days {
+        if !(1..=MAX_TOKEN_LIFETIME_DAYS).contains(&days) {
+            errors.push(FieldError::new(
+                "expires_in_days",
+                format!(
+                    "Tokens must expire within 1 to {} days.",
+                    MAX_TOKEN_LIFETIME_DAYS
+                ),
+            ));
+        }
+    }
+    if let Some(project_id) = request.project_id {
+        let is_member = sqlx::query!(
+            r#"SELECT EXISTS (SELECT 1 FROM project_member WHERE project_id = ? AND user_id = ?) AS "is_member!: bool""#,
+            project_id,
+            user_id
+        )
+        .fetch_one(pool)
+        .await?
+        .is_member;
+        if !is_member {
+            errors.push(FieldError::new(
+                "project_id",
+                "Tokens can only be limited to projects you are a member of.",
+            ));
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(ProjectError::ValidationError(errors));
+    }
+    Ok(())
+}
+
+fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+
+/// Tokens are prefixed so they are easy to recognise, e.g. by secret scanners.
+pub const TOKEN_PREFIX: &str = "cs_";
+pub const MAX_TOKEN_NAME_LENGTH: usize = 100;
+pub const MAX_TOKEN_LIFETIME_DAYS: i64 = 365;
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenScope {
+    /// Only `GET` requests.
+    Read,
+    Write,
+}
+
+impl TokenScope {
+    pub fn name(self) -> &'static str {
+        match self {
+            TokenScope::Read => "read",
+            TokenScope::Write => "write",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<TokenScope> {
+        [TokenScope::Read, TokenScope::Write]
+            .into_iter()
+            .find(|scope| scope.name() == name)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ApiTokenRequest {
+    pub name: String,
+    pub scope: TokenScope,
+    /// Limits the token to one of the user's projects.
+    #[serde(default)]
+    pub project_id: Option<i64>,
+    /// Tokens without an expiry last until they are revoked.
+    #[serde(default)]
+    pub expires_in_days: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ApiToken {
+    pub id: i64,
+    pub name: String,
+    pub scope: TokenScope,
+    pub project_id: Option<i64>,
+    pub created_at: String,
+    pub expires_at: Option<String>,
+    pub last_used_at: Option<String>,
+}
+
+/// Only returned when the token is created.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CreatedApiToken {
+    pub token: String,
+    #[serde(flatten)]
+    pub info: ApiToken,
+}
+
+/// What a request made with a token is allowed to do.
+#[derive(Debug, Clone)]
+pub struct TokenAccess {
+    pub user_id: i64,
+    pub scope: TokenScope,
+    pub project_id: Option<i64>,
+}
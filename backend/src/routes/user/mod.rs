@@ -5,9 +5,12 @@ use actix_web::web;
 use routes::*;
 
 pub fn user_routes(cfg: &mut web::ServiceConfig) {
-    cfg.service(delete_user)
+    cfg.service(change_password)
+        .service(delete_user)
         .service(login)
         .service(logout)
+        .service(oidc_callback)
+        .service(oidc_login)
         .service(signup)
         .service(user_status);
 }
@@ -1,20 +1,68 @@
 use actix_session::Session;
-use actix_web::{get, post, web, HttpResponse, HttpResponseBuilder, Responder};
+use actix_web::{
+    get, http::header, post, web, HttpRequest, HttpResponse, HttpResponseBuilder, Responder,
+};
 use argon2::{
     password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
     Argon2,
 };
-use secrecy::ExposeSecret;
-use sqlx::SqlitePool;
+use once_cell::sync::Lazy;
+use secrecy::{ExposeSecret, Secret};
+use serde_json::json;
+use sqlx::{Sqlite, SqlitePool, Transaction};
+use std::time::SystemTime;
+use tracing_actix_web::RequestId;
 
 use super::types::*;
-use crate::helpers::*;
+use crate::{
+    helpers::*,
+    ldap::{DirectoryUser, LdapAuthenticator, LdapError},
+    oidc::{OidcConfig, OidcError, OidcIdentity, PendingLogin},
+    password::PasswordPolicy,
+    rate_limit::{RateLimit, RateLimiter},
+    routes::{
+        audit::{record_audit_event, AuditRecord},
+        project::types::FieldError,
+    },
+};
+
+/// Checked against when there is no password to check, so that logging in as someone who does not
+/// exist takes as long as getting a real password wrong.
+static DUMMY_PASSWORD_HASH: Lazy<String> = Lazy::new(|| {
+    hash_password(&Secret::new("dummy-password".to_string()))
+        .expect("hashing a fixed password works")
+});
+
+#[post("/change-password")]
+#[tracing::instrument(name = "Changing password.", skip(body, pool, policy, session))]
+async fn change_password(
+    body: web::Json<ChangePasswordRequest>,
+    pool: web::Data<SqlitePool>,
+    policy: web::Data<PasswordPolicy>,
+    session: Session,
+) -> impl Responder {
+    let user_id = match get_session_user_id(&session, &pool).await {
+        Ok(user_id) => user_id,
+        Err(e) => return e.into(),
+    };
+    match change_user_password(user_id, &body, &policy, &pool).await {
+        Ok(_) => create_session(HttpResponse::NoContent(), &session, user_id, &pool).await,
+        Err(e) => {
+            tracing::error!("Error recieved changing password: {:?}", e);
+            e.into()
+        }
+    }
+}
 
 #[post("/delete-user")]
 #[tracing::instrument(name = "Deleting user.", skip(pool, session))]
-async fn delete_user(pool: web::Data<SqlitePool>, session: Session) -> impl Responder {
-    match get_user_id(&session).await {
-        Ok(id) => match delete_user_from_db(&pool, id).await {
+async fn delete_user(
+    pool: web::Data<SqlitePool>,
+    session: Session,
+    request_id: RequestId,
+) -> impl Responder {
+    match get_session_user_id(&session, &pool).await {
+        Ok(id) => match delete_user_from_db(&pool, id, &request_id).await {
             Ok(_) => {
                 session.purge();
                 HttpResponse::NoContent().finish()
@@ -25,10 +73,10 @@ async fn delete_user(pool: web::Data<SqlitePool>, session: Session) -> impl Resp
     }
 }
 
-#[post("/log-in")]
+#[post("/log-in", wrap = "RateLimit")]
 #[tracing::instrument(
     name = "Logging in user.",
-    skip(body, pool, session),
+    skip(body, pool, ldap, rate_limiter, session),
     fields(
         %body.username
     )
@@ -36,11 +84,19 @@ async fn delete_user(pool: web::Data<SqlitePool>, session: Session) -> impl Resp
 async fn login(
     body: web::Json<UserRequest>,
     pool: web::Data<SqlitePool>,
+    ldap: web::Data<Option<LdapAuthenticator>>,
+    rate_limiter: web::Data<RateLimiter>,
     session: Session,
 ) -> impl Responder {
-    match check_user_password(&body, &pool).await {
-        Ok(user_id) => create_session(HttpResponse::Ok(), &session, user_id),
+    match authenticate_user(&body, &pool, ldap.as_ref().as_ref()).await {
+        Ok(user_id) => {
+            rate_limiter.record_success(&body.username);
+            create_session(HttpResponse::Ok(), &session, user_id, &pool).await
+        }
         Err(e) => {
+            if let UserError::InvalidPassword(_) = e {
+                rate_limiter.record_failure(&body.username, SystemTime::now());
+            }
             tracing::error!("Error recieved during log in: {:?}", e);
             e.into()
         }
@@ -54,10 +110,57 @@ async fn logout(session: Session) -> impl Responder {
     HttpResponse::NoContent().finish()
 }
 
-#[post("/sign-up")]
+#[get("/oidc/login")]
+#[tracing::instrument(name = "Starting single sign-on.", skip(oidc, pool, request, session))]
+async fn oidc_login(
+    query: web::Query<OidcLoginParams>,
+    oidc: web::Data<Option<OidcConfig>>,
+    pool: web::Data<SqlitePool>,
+    request: HttpRequest,
+    session: Session,
+) -> impl Responder {
+    let Some(oidc) = oidc.as_ref() else {
+        return HttpResponse::NotFound().finish();
+    };
+    match start_oidc_login(query.link, oidc, &pool, &request, &session).await {
+        Ok(url) => HttpResponse::Found()
+            .insert_header((header::LOCATION, url))
+            .finish(),
+        Err(e) => {
+            tracing::error!("Error recieved starting single sign-on: {:?}", e);
+            e.into()
+        }
+    }
+}
+
+#[get("/oidc/callback", name = "oidc_callback")]
+#[tracing::instrument(name = "Finishing single sign-on.", skip(query, oidc, pool, session))]
+async fn oidc_callback(
+    query: web::Query<OidcCallbackParams>,
+    oidc: web::Data<Option<OidcConfig>>,
+    pool: web::Data<SqlitePool>,
+    session: Session,
+) -> impl Responder {
+    let Some(oidc) = oidc.as_ref() else {
+        return HttpResponse::NotFound().finish();
+    };
+    match finish_oidc_login(&query, oidc, &pool, &session).await {
+        Ok(user_id) => {
+            let mut response = HttpResponse::Found();
+            response.insert_header((header::LOCATION, oidc.post_login_redirect.as_str()));
+            create_session(response, &session, user_id, &pool).await
+        }
+        Err(e) => {
+            tracing::error!("Error recieved during single sign-on: {:?}", e);
+            e.into()
+        }
+    }
+}
+
+#[post("/sign-up", wrap = "RateLimit")]
 #[tracing::instrument(
     name = "Signing up new user.",
-    skip(body, pool, session),
+    skip(body, pool, policy, session),
     fields(
         %body.username
     )
@@ -65,10 +168,11 @@ async fn logout(session: Session) -> impl Responder {
 async fn signup(
     body: web::Json<UserRequest>,
     pool: web::Data<SqlitePool>,
+    policy: web::Data<PasswordPolicy>,
     session: Session,
 ) -> impl Responder {
-    match sign_up_user(&body, &pool).await {
-        Ok(user_id) => create_session(HttpResponse::Created(), &session, user_id),
+    match sign_up_user(&body, &policy, &pool).await {
+        Ok(user_id) => create_session(HttpResponse::Created(), &session, user_id, &pool).await,
         Err(e) => {
             tracing::error!("Error recieved during sign up: {:?}", e);
             e.into()
@@ -77,41 +181,354 @@ async fn signup(
 }
 
 #[get("/user-status")]
-#[tracing::instrument(name = "Checking user status.", skip(session))]
-async fn user_status(session: Session) -> impl Responder {
+#[tracing::instrument(name = "Checking user status.", skip(pool, session))]
+async fn user_status(pool: web::Data<SqlitePool>, session: Session) -> impl Responder {
     tracing::info!("Cookies recieved: {}", session.entries().capacity());
+    // the session also holds single sign-ons that were started but never finished
+    let user_id = get_session_user_id(&session, &pool).await.ok();
+    let global_role = match user_id {
+        Some(user_id) => match get_global_role(user_id, &pool).await {
+            Ok(global_role) => global_role,
+            Err(e) => return e.into(),
+        },
+        None => None,
+    };
     let body = UserStatus {
-        is_logged_in: !session.entries().is_empty(),
+        is_logged_in: user_id.is_some(),
+        global_role,
     };
     HttpResponse::Ok().json(body)
 }
 
-async fn delete_user_from_db(pool: &SqlitePool, user_id: i64) -> Result<(), UserError> {
+/// Users who are the last owner of a project have to hand it over or delete it first. Every
+/// project the user belonged to records their departure, while their name can still be looked up.
+async fn delete_user_from_db(
+    pool: &SqlitePool,
+    user_id: i64,
+    request_id: &RequestId,
+) -> Result<(), UserError> {
+    let mut transaction = pool.begin().await?;
+    let sole_owner_of: Vec<String> = sqlx::query!(
+        r#"SELECT project.name FROM project_member JOIN project ON project.id = project_member.project_id
+        WHERE project_member.user_id = ? AND project_member.role = 'owner' AND NOT EXISTS (
+            SELECT 1 FROM project_member AS other
+            WHERE other.project_id = project_member.project_id AND other.user_id != project_member.user_id
+            AND other.role = 'owner'
+        )"#,
+        user_id
+    )
+    .fetch_all(&mut transaction)
+    .await?
+    .into_iter()
+    .map(|record| record.name)
+    .collect();
+    if !sole_owner_of.is_empty() {
+        return Err(UserError::LastProjectOwner(sole_owner_of));
+    }
+
+    let memberships = sqlx::query!(
+        r#"SELECT user.name, project_id, role FROM project_member
+        JOIN user ON user.id = project_member.user_id WHERE user_id = ?"#,
+        user_id
+    )
+    .fetch_all(&mut transaction)
+    .await?;
+    for membership in memberships {
+        record_audit_event(
+            AuditRecord {
+                project_id: membership.project_id,
+                actor_id: user_id,
+                action: "user.delete",
+                target: format!("user:{}", membership.name),
+                before: Some(json!({ "role": membership.role })),
+                after: None,
+            },
+            request_id,
+            &mut transaction,
+        )
+        .await?;
+    }
+
     sqlx::query!(r#"DELETE FROM user WHERE id=?"#, user_id)
-        .execute(pool)
+        .execute(&mut transaction)
         .await?;
+    transaction.commit().await?;
     Ok(())
 }
 
+/// The directory is asked first when there is one. Local accounts are tried whenever it does not
+/// sign the user in, including while it cannot be reached, unless it turned them away for their
+/// groups.
+async fn authenticate_user(
+    body: &UserRequest,
+    pool: &SqlitePool,
+    ldap: Option<&LdapAuthenticator>,
+) -> Result<i64, UserError> {
+    if let Some(ldap) = ldap {
+        match ldap.authenticate(&body.username, &body.password).await {
+            Ok(Some(user)) => {
+                return sign_in_directory_user(&body.username, &user, ldap, pool).await
+            }
+            Ok(None) | Err(LdapError::InvalidCredentials) => {}
+            Err(LdapError::Unavailable(e)) => {
+                tracing::warn!("Falling back to local accounts: {}", e);
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+    check_user_password(body, pool).await
+}
+
+/// Directory users are known by their DN rather than their name, so a local account that happens
+/// to share the name stays separate.
+async fn sign_in_directory_user(
+    username: &str,
+    user: &DirectoryUser,
+    ldap: &LdapAuthenticator,
+    pool: &SqlitePool,
+) -> Result<i64, UserError> {
+    let user_id = sign_in_identity(&ldap.config().url, &user.dn, username, None, pool).await?;
+    let global_role = match user.is_admin {
+        true => GlobalRole::Admin,
+        false => GlobalRole::User,
+    }
+    .name();
+    sqlx::query!(
+        r#"UPDATE user SET global_role = ? WHERE id = ?"#,
+        global_role,
+        user_id
+    )
+    .execute(pool)
+    .await?;
+    Ok(user_id)
+}
+
+async fn get_global_role(user_id: i64, pool: &SqlitePool) -> Result<Option<GlobalRole>, UserError> {
+    let role = sqlx::query!(r#"SELECT global_role FROM user WHERE id = ?"#, user_id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(role.and_then(|role| GlobalRole::from_name(&role.global_role)))
+}
+
 async fn check_user_password(body: &UserRequest, pool: &SqlitePool) -> Result<i64, UserError> {
     let user = sqlx::query!(
         r#"SELECT id, password_hash FROM user WHERE name=?"#,
         body.username
     )
-    .fetch_one(pool)
+    .fetch_optional(pool)
+    .await?
+    .filter(|user| !user.password_hash.is_empty());
+
+    match user {
+        Some(user) => {
+            verify_password(&user.password_hash, &body.password)?;
+            Ok(user.id)
+        }
+        None => {
+            let _ = verify_password(&DUMMY_PASSWORD_HASH, &body.password);
+            Err(UserError::InvalidPassword(
+                argon2::password_hash::Error::Password,
+            ))
+        }
+    }
+}
+
+/// Signing in with the new password is left to this session, as every other one is signed out.
+async fn change_user_password(
+    user_id: i64,
+    body: &ChangePasswordRequest,
+    policy: &PasswordPolicy,
+    pool: &SqlitePool,
+) -> Result<(), UserError> {
+    let user = sqlx::query!(r#"SELECT password_hash FROM user WHERE id = ?"#, user_id)
+        .fetch_one(pool)
+        .await?;
+    verify_password(&user.password_hash, &body.old_password)?;
+    check_password_policy("new_password", &body.new_password, policy)?;
+
+    let password_hash = hash_password(&body.new_password)?;
+    sqlx::query!(
+        r#"UPDATE user SET password_hash = ?, session_version = session_version + 1 WHERE id = ?"#,
+        password_hash,
+        user_id
+    )
+    .execute(pool)
     .await?;
+    Ok(())
+}
 
-    let parsed_hash = PasswordHash::new(&user.password_hash)?;
-    Argon2::default().verify_password(body.password.expose_secret().as_bytes(), &parsed_hash)?;
-    Ok(user.id)
+fn verify_password(password_hash: &str, password: &Secret<String>) -> Result<(), UserError> {
+    // accounts created through single sign-on or the directory have no password
+    if password_hash.is_empty() {
+        return Err(UserError::InvalidPassword(
+            argon2::password_hash::Error::Password,
+        ));
+    }
+    let parsed_hash = PasswordHash::new(password_hash)?;
+    Argon2::default().verify_password(password.expose_secret().as_bytes(), &parsed_hash)?;
+    Ok(())
 }
 
-async fn sign_up_user(body: &UserRequest, pool: &SqlitePool) -> Result<i64, UserError> {
+fn hash_password(password: &Secret<String>) -> Result<String, UserError> {
     let salt = SaltString::generate(&mut OsRng);
-    let argon2 = Argon2::default();
-    let password_hash = argon2
-        .hash_password(body.password.expose_secret().as_bytes(), &salt)?
-        .to_string();
+    Ok(Argon2::default()
+        .hash_password(password.expose_secret().as_bytes(), &salt)?
+        .to_string())
+}
+
+fn check_password_policy(
+    field: &str,
+    password: &Secret<String>,
+    policy: &PasswordPolicy,
+) -> Result<(), UserError> {
+    let problems = policy
+        .check(password.expose_secret())
+        .map_err(UserError::BreachedListError)?;
+    if !problems.is_empty() {
+        return Err(UserError::ValidationError(
+            problems
+                .into_iter()
+                .map(|problem| FieldError::new(field, problem))
+                .collect(),
+        ));
+    }
+    Ok(())
+}
+
+async fn start_oidc_login(
+    link: bool,
+    oidc: &OidcConfig,
+    pool: &SqlitePool,
+    request: &HttpRequest,
+    session: &Session,
+) -> Result<String, UserError> {
+    let link_user_id = match link {
+        true => Some(get_session_user_id(session, pool).await?),
+        false => None,
+    };
+    let redirect_url = match &oidc.redirect_url {
+        Some(url) => url.clone(),
+        None => request
+            .url_for_static("oidc_callback")
+            .map_err(|e| OidcError::Provider(e.to_string()))?
+            .to_string(),
+    };
+
+    let mut authorization = oidc.authorization_request(&redirect_url).await?;
+    authorization.pending.link_user_id = link_user_id;
+    session.insert("oidc_login", &authorization.pending)?;
+    Ok(authorization.url)
+}
+
+async fn finish_oidc_login(
+    params: &OidcCallbackParams,
+    oidc: &OidcConfig,
+    pool: &SqlitePool,
+    session: &Session,
+) -> Result<i64, UserError> {
+    let rejected = |reason: &str| UserError::from(OidcError::InvalidToken(reason.to_string()));
+    let pending = session
+        .remove_as::<PendingLogin>("oidc_login")
+        .and_then(Result::ok)
+        .ok_or_else(|| rejected("no sign in was started"))?;
+    if let Some(error) = &params.error {
+        return Err(rejected(&format!("the provider returned {}", error)));
+    }
+    if params.state.as_deref() != Some(pending.state.as_str()) {
+        return Err(rejected("the state does not match"));
+    }
+    let code = params
+        .code
+        .as_deref()
+        .ok_or_else(|| rejected("no code was returned"))?;
+
+    let OidcIdentity {
+        issuer,
+        subject,
+        username,
+    } = oidc.exchange_code(code, &pending).await?;
+    sign_in_identity(&issuer, &subject, &username, pending.link_user_id, pool).await
+}
+
+/// Known identities sign in to the account they belong to. New ones are linked to the signed in
+/// account when asked to, and get an account named after `username` otherwise.
+async fn sign_in_identity(
+    issuer: &str,
+    subject: &str,
+    username: &str,
+    link_user_id: Option<i64>,
+    pool: &SqlitePool,
+) -> Result<i64, UserError> {
+    let mut transaction = pool.begin().await?;
+    let linked = sqlx::query!(
+        r#"SELECT user_id FROM user_identity WHERE issuer = ? AND subject = ?"#,
+        issuer,
+        subject
+    )
+    .fetch_optional(&mut transaction)
+    .await?;
+    if let Some(linked) = linked {
+        if link_user_id.is_some_and(|user_id| user_id != linked.user_id) {
+            return Err(UserError::IdentityAlreadyLinked);
+        }
+        return Ok(linked.user_id);
+    }
+
+    let user_id = match link_user_id {
+        Some(user_id) => user_id,
+        None => {
+            let name = available_username(username, &mut transaction).await?;
+            sqlx::query!(
+                r#"INSERT INTO user (name, password_hash) VALUES (?, '') RETURNING id"#,
+                name
+            )
+            .fetch_one(&mut transaction)
+            .await?
+            .id
+        }
+    };
+    sqlx::query!(
+        r#"INSERT INTO user_identity (issuer, subject, user_id) VALUES (?, ?, ?)"#,
+        issuer,
+        subject,
+        user_id
+    )
+    .execute(&mut transaction)
+    .await?;
+    transaction.commit().await?;
+
+    Ok(user_id)
+}
+
+/// The name itself when it is free, otherwise the name with the first free number added to it.
+async fn available_username(
+    name: &str,
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> Result<String, UserError> {
+    let mut candidate = name.to_string();
+    for number in 2.. {
+        let taken = sqlx::query!(
+            r#"SELECT EXISTS (SELECT 1 FROM user WHERE name = ?) AS "taken!: bool""#,
+            candidate
+        )
+        .fetch_one(&mut *transaction)
+        .await?
+        .taken;
+        if !taken {
+            break;
+        }
+        candidate = format!("{}-{}", name, number);
+    }
+    Ok(candidate)
+}
+
+async fn sign_up_user(
+    body: &UserRequest,
+    policy: &PasswordPolicy,
+    pool: &SqlitePool,
+) -> Result<i64, UserError> {
+    check_password_policy("password", &body.password, policy)?;
+    let password_hash = hash_password(&body.password)?;
 
     let user_id = sqlx::query!(
         r#"INSERT INTO user (name, password_hash) VALUES (?,?) RETURNING id"#,
@@ -125,11 +542,34 @@ async fn sign_up_user(body: &UserRequest, pool: &SqlitePool) -> Result<i64, User
     Ok(user_id)
 }
 
-fn create_session(code: HttpResponseBuilder, session: &Session, user_id: i64) -> HttpResponse {
-    session.renew();
-    match session.insert("user_id", user_id) {
+async fn create_session(
+    code: HttpResponseBuilder,
+    session: &Session,
+    user_id: i64,
+    pool: &SqlitePool,
+) -> HttpResponse {
+    match start_session(session, user_id, pool).await {
         Ok(_) => code,
-        Err(_) => HttpResponse::InternalServerError(),
+        Err(e) => {
+            tracing::error!("Error recieved starting session: {:?}", e);
+            HttpResponse::InternalServerError()
+        }
     }
     .finish()
 }
+
+/// Sessions are signed in at the user's current session version.
+async fn start_session(
+    session: &Session,
+    user_id: i64,
+    pool: &SqlitePool,
+) -> Result<(), UserError> {
+    let session_version = sqlx::query!(r#"SELECT session_version FROM user WHERE id = ?"#, user_id)
+        .fetch_one(pool)
+        .await?
+        .session_version;
+    session.renew();
+    session.insert("user_id", user_id)?;
+    session.insert("session_version", session_version)?;
+    Ok(())
+}
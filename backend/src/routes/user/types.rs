@@ -1,18 +1,34 @@
-use actix_session::SessionGetError;
+use actix_session::{SessionGetError, SessionInsertError};
 use actix_web::HttpResponse;
 use secrecy::Secret;
 use serde::{Deserialize, Serialize};
 use sqlx::error::DatabaseError;
 
+use crate::{
+    ldap::LdapError,
+    oidc::OidcError,
+    routes::project::types::{FieldError, ValidationErrors},
+};
+
 #[derive(Debug)]
 pub enum UserError {
+    /// The breached password list could not be read.
+    BreachedListError(std::io::Error),
+    /// The single sign-on identity already belongs to another account.
+    IdentityAlreadyLinked,
     InvalidPassword(argon2::password_hash::Error),
+    /// Names the projects that would be left without an owner.
+    LastProjectOwner(Vec<String>),
+    LdapError(LdapError),
+    OidcError(OidcError),
     PasswordHashError(argon2::password_hash::Error),
     SessionGetError(String),
+    SessionInsertError(String),
     SqlxDatabaseError(Box<dyn DatabaseError>),
     SqlxError(sqlx::Error),
     UserAlreadyExists,
     UserNotFound(sqlx::Error),
+    ValidationError(Vec<FieldError>),
 }
 
 impl From<argon2::password_hash::Error> for UserError {
@@ -24,12 +40,30 @@ impl From<argon2::password_hash::Error> for UserError {
     }
 }
 
+impl From<LdapError> for UserError {
+    fn from(e: LdapError) -> Self {
+        UserError::LdapError(e)
+    }
+}
+
+impl From<OidcError> for UserError {
+    fn from(e: OidcError) -> Self {
+        UserError::OidcError(e)
+    }
+}
+
 impl From<SessionGetError> for UserError {
     fn from(e: SessionGetError) -> Self {
         UserError::SessionGetError(e.to_string())
     }
 }
 
+impl From<SessionInsertError> for UserError {
+    fn from(e: SessionInsertError) -> Self {
+        UserError::SessionInsertError(e.to_string())
+    }
+}
+
 impl From<sqlx::Error> for UserError {
     fn from(e: sqlx::Error) -> Self {
         match e {
@@ -56,7 +90,22 @@ impl From<UserError> for HttpResponse {
                 HttpResponse::Unauthorized().finish()
             }
             UserError::UserAlreadyExists => HttpResponse::Conflict().finish(),
+            UserError::IdentityAlreadyLinked | UserError::LastProjectOwner(_) => {
+                HttpResponse::Conflict().finish()
+            }
+            UserError::LdapError(LdapError::InvalidCredentials) => {
+                HttpResponse::Unauthorized().finish()
+            }
+            UserError::LdapError(LdapError::NotPermitted) => HttpResponse::Forbidden().finish(),
+            UserError::LdapError(LdapError::Unavailable(_)) => HttpResponse::BadGateway().finish(),
+            UserError::OidcError(OidcError::Provider(_)) => HttpResponse::BadGateway().finish(),
+            UserError::OidcError(OidcError::InvalidToken(_)) => {
+                HttpResponse::Unauthorized().finish()
+            }
             UserError::UserNotFound(_) => HttpResponse::Unauthorized().finish(),
+            UserError::ValidationError(errors) => {
+                HttpResponse::UnprocessableEntity().json(ValidationErrors { errors })
+            }
             _ => HttpResponse::InternalServerError().finish(),
         }
     }
@@ -68,7 +117,55 @@ pub struct UserRequest {
     pub password: Secret<String>,
 }
 
+#[derive(Deserialize)]
+pub struct ChangePasswordRequest {
+    pub old_password: Secret<String>,
+    pub new_password: Secret<String>,
+}
+
 #[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct UserStatus {
     pub is_logged_in: bool,
+    /// `None` while logged out.
+    pub global_role: Option<GlobalRole>,
+}
+
+/// A user's role across the whole instance, as opposed to their role in a project. Admins have
+/// every permission in every project. Users who sign in through the directory get theirs from their
+/// groups.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum GlobalRole {
+    Admin,
+    User,
+}
+
+impl GlobalRole {
+    pub const ALL: [GlobalRole; 2] = [GlobalRole::Admin, GlobalRole::User];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GlobalRole::Admin => "admin",
+            GlobalRole::User => "user",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<GlobalRole> {
+        Self::ALL.into_iter().find(|role| role.name() == name)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OidcLoginParams {
+    /// Links the identity to the signed in account instead of signing in with it.
+    #[serde(default)]
+    pub link: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OidcCallbackParams {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    /// Set by the provider when the user did not sign in.
+    pub error: Option<String>,
 }
@@ -0,0 +1,191 @@
+use crate::{
+    curl::{CurlRequest, NameValue, RequestBody},
+    ssrf::{find_ssrf_error, SsrfError, SsrfGuard},
+};
+
+use reqwest::{
+    header::{HeaderName, HeaderValue, CONTENT_TYPE, COOKIE},
+    Method, Url,
+};
+use serde::{Deserialize, Serialize};
+use std::{fmt, time::Instant};
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct RunResult {
+    pub status: u16,
+    pub headers: Vec<NameValue>,
+    pub timing: Timing,
+    pub body: String,
+    /// Set when the body was cut off at the size limit.
+    pub truncated: bool,
+}
+
+/// Milliseconds until the response headers arrived, spent reading the body, and overall.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Timing {
+    pub headers_ms: u64,
+    pub body_ms: u64,
+    pub total_ms: u64,
+}
+
+#[derive(Debug)]
+pub enum RunError {
+    UnsupportedRequest(String),
+    Blocked(SsrfError),
+    Timeout,
+    RequestFailed(String),
+}
+
+impl fmt::Display for RunError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RunError::UnsupportedRequest(e) => write!(f, "{}", e),
+            RunError::Blocked(e) => write!(f, "{}", e),
+            RunError::Timeout => write!(f, "The request timed out"),
+            RunError::RequestFailed(e) => write!(f, "The request failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for RunError {}
+
+impl From<reqwest::Error> for RunError {
+    fn from(e: reqwest::Error) -> Self {
+        if let Some(blocked) = find_ssrf_error(&e) {
+            RunError::Blocked(blocked.clone())
+        } else if e.is_timeout() {
+            RunError::Timeout
+        } else {
+            RunError::RequestFailed(e.to_string())
+        }
+    }
+}
+
+/// Sends a parsed curl request through the guard, which decides where it may go and how much of
+/// the response is read.
+pub async fn run(request: &CurlRequest, guard: &SsrfGuard) -> Result<RunResult, RunError> {
+    let url = parse_url(&request.url)?;
+    guard.check_url(&url).map_err(RunError::Blocked)?;
+
+    let client = guard
+        .client_builder(request.follow_redirects)
+        .danger_accept_invalid_certs(request.insecure)
+        .build()?;
+
+    let method = Method::from_bytes(request.method.as_bytes()).map_err(|_| {
+        RunError::UnsupportedRequest(format!("\"{}\" is not a valid method", request.method))
+    })?;
+    let mut builder = client.request(method, url);
+
+    for header in &request.headers {
+        let name = HeaderName::from_bytes(header.name.as_bytes()).map_err(|_| {
+            RunError::UnsupportedRequest(format!("\"{}\" is not a valid header name", header.name))
+        })?;
+        let value = HeaderValue::from_str(&header.value).map_err(|_| {
+            RunError::UnsupportedRequest(format!("Header \"{}\" has an invalid value", header.name))
+        })?;
+        builder = builder.header(name, value);
+    }
+
+    if !request.cookies.is_empty() {
+        let cookies: Vec<String> = request
+            .cookies
+            .iter()
+            .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+            .collect();
+        builder = builder.header(COOKIE, cookies.join("; "));
+    }
+
+    if let Some(auth) = &request.basic_auth {
+        builder = builder.basic_auth(&auth.username, auth.password.as_ref());
+    }
+
+    if let Some(body) = &request.body {
+        let content = match body {
+            RequestBody::Raw { content } | RequestBody::Json { content } => content.clone(),
+            RequestBody::Multipart { .. } => {
+                return Err(RunError::UnsupportedRequest(
+                    "Requests with form data (-F) cannot be run".to_string(),
+                ))
+            }
+        };
+        if request.header(CONTENT_TYPE.as_str()).is_none() {
+            builder = builder.header(CONTENT_TYPE, body.default_content_type());
+        }
+        builder = builder.body(content);
+    }
+
+    let start = Instant::now();
+    let mut response = builder.send().await?;
+    let headers_at = Instant::now();
+
+    let status = response.status().as_u16();
+    let headers = response
+        .headers()
+        .iter()
+        .map(|(name, value)| {
+            NameValue::new(name.as_str(), String::from_utf8_lossy(value.as_bytes()))
+        })
+        .collect();
+
+    let (body, truncated) = guard.read_body(&mut response).await?;
+    let end = Instant::now();
+
+    Ok(RunResult {
+        status,
+        headers,
+        timing: Timing {
+            headers_ms: (headers_at - start).as_millis() as u64,
+            body_ms: (end - headers_at).as_millis() as u64,
+            total_ms: (end - start).as_millis() as u64,
+        },
+        body: String::from_utf8_lossy(&body).into_owned(),
+        truncated,
+    })
+}
+
+/// Like curl, URLs without a scheme are sent over plain http. Other protocols curl speaks are not
+/// supported.
+fn parse_url(url: &str) -> Result<Url, RunError> {
+    let url = if url.contains("://") {
+        url.to_string()
+    } else {
+        format!("http://{}", url)
+    };
+    let url = Url::parse(&url).map_err(|e| {
+        RunError::UnsupportedRequest(format!("\"{}\" is not a valid URL: {}", url, e))
+    })?;
+
+    match url.scheme() {
+        "http" | "https" if url.host_str().is_some() => Ok(url),
+        "http" | "https" => Err(RunError::UnsupportedRequest(format!(
+            "\"{}\" has no host",
+            url
+        ))),
+        scheme => Err(RunError::UnsupportedRequest(format!(
+            "The {} protocol is not supported",
+            scheme
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod runner_test {
+    use super::{parse_url, RunError};
+
+    #[test]
+    fn only_accepts_http_urls() {
+        assert_eq!(
+            parse_url("example.com/path").unwrap().as_str(),
+            "http://example.com/path"
+        );
+        assert!(matches!(
+            parse_url("ftp://example.com"),
+            Err(RunError::UnsupportedRequest(_))
+        ));
+        assert!(matches!(
+            parse_url("file:///etc/passwd"),
+            Err(RunError::UnsupportedRequest(_))
+        ));
+    }
+}
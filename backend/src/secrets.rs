@@ -0,0 +1,307 @@
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Nonce,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rand_core::RngCore;
+use secrecy::{ExposeSecret, Secret};
+use sqlx::SqlitePool;
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::{env, fmt, fs, io::Write, path::Path};
+
+const KEY_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 12;
+const CIPHERTEXT_PREFIX: &str = "v1:";
+
+#[derive(Debug)]
+pub enum SecretError {
+    InvalidKey(String),
+    KeyFileError(std::io::Error),
+    DecryptionFailed,
+    SqlxError(sqlx::Error),
+}
+
+impl fmt::Display for SecretError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecretError::InvalidKey(e) => write!(f, "Invalid secret key: {}", e),
+            SecretError::KeyFileError(e) => {
+                write!(f, "Could not read or write the key file: {}", e)
+            }
+            SecretError::DecryptionFailed => {
+                write!(f, "Secret could not be decrypted with any known key")
+            }
+            SecretError::SqlxError(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for SecretError {}
+
+impl From<sqlx::Error> for SecretError {
+    fn from(e: sqlx::Error) -> Self {
+        SecretError::SqlxError(e)
+    }
+}
+
+/// Encrypts secret environment variables with the server key. Values encrypted with one of the
+/// previous keys can still be read until [`rotate_secret_key`] has re-encrypted them.
+pub struct SecretCipher {
+    current: Aes256Gcm,
+    previous: Vec<Aes256Gcm>,
+}
+
+impl SecretCipher {
+    /// Reads the base64 encoded key from `SECRET_KEY`, falling back to `key_file` which is created
+    /// with a fresh key on first start. Retired keys go in the comma separated `PREVIOUS_SECRET_KEYS`.
+    pub fn from_env(key_file: impl AsRef<Path>) -> Result<Self, SecretError> {
+        let current = match env::var("SECRET_KEY") {
+            Ok(key) => Secret::new(key),
+            Err(_) => Secret::new(read_or_create_key_file(key_file.as_ref())?),
+        };
+        let previous = env::var("PREVIOUS_SECRET_KEYS").unwrap_or_default();
+        let previous: Vec<Secret<String>> = previous
+            .split(',')
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(|key| Secret::new(key.to_string()))
+            .collect();
+
+        Self::new(&current, &previous)
+    }
+
+    pub fn new(current: &Secret<String>, previous: &[Secret<String>]) -> Result<Self, SecretError> {
+        Ok(Self {
+            current: cipher_from_base64(current)?,
+            previous: previous
+                .iter()
+                .map(cipher_from_base64)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// A cipher with a throwaway key, for tests.
+    pub fn generate() -> Self {
+        Self {
+            current: Aes256Gcm::new(&Aes256Gcm::generate_key(&mut OsRng)),
+            previous: vec![],
+        }
+    }
+
+    pub fn encrypt(&self, plaintext: &str) -> String {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .current
+            .encrypt(&nonce, plaintext.as_bytes())
+            .expect("encrypting in memory does not fail");
+
+        let mut payload = nonce.to_vec();
+        payload.extend(ciphertext);
+        format!("{}{}", CIPHERTEXT_PREFIX, STANDARD.encode(payload))
+    }
+
+    pub fn decrypt(&self, encrypted: &str) -> Result<String, SecretError> {
+        self.decrypt_with_any_key(encrypted)
+            .map(|(plaintext, _)| plaintext)
+    }
+
+    /// Returns the plaintext along with whether the current key was the one that decrypted it.
+    fn decrypt_with_any_key(&self, encrypted: &str) -> Result<(String, bool), SecretError> {
+        let payload = encrypted
+            .strip_prefix(CIPHERTEXT_PREFIX)
+            .and_then(|payload| STANDARD.decode(payload).ok())
+            .filter(|payload| payload.len() > NONCE_LENGTH)
+            .ok_or(SecretError::DecryptionFailed)?;
+        let (nonce, ciphertext) = payload.split_at(NONCE_LENGTH);
+        let nonce = Nonce::from_slice(nonce);
+
+        std::iter::once(&self.current)
+            .chain(&self.previous)
+            .enumerate()
+            .find_map(|(index, cipher)| {
+                cipher
+                    .decrypt(nonce, ciphertext)
+                    .ok()
+                    .and_then(|plaintext| String::from_utf8(plaintext).ok())
+                    .map(|plaintext| (plaintext, index == 0))
+            })
+            .ok_or(SecretError::DecryptionFailed)
+    }
+}
+
+/// Re-encrypts every secret variable that is not yet encrypted with the current key, returning
+/// how many were updated. Run it after moving the old key to `PREVIOUS_SECRET_KEYS`.
+pub async fn rotate_secret_key(
+    cipher: &SecretCipher,
+    pool: &SqlitePool,
+) -> Result<usize, SecretError> {
+    let mut transaction = pool.begin().await?;
+    let secrets =
+        sqlx::query!(r#"SELECT id, value FROM project_environment_variable WHERE secret = 1"#)
+            .fetch_all(&mut transaction)
+            .await?;
+
+    let mut rotated = 0;
+    for secret in secrets {
+        let (plaintext, is_current) = cipher.decrypt_with_any_key(&secret.value)?;
+        if is_current {
+            continue;
+        }
+
+        let value = cipher.encrypt(&plaintext);
+        sqlx::query!(
+            r#"UPDATE project_environment_variable SET value = ? WHERE id = ?"#,
+            value,
+            secret.id
+        )
+        .execute(&mut transaction)
+        .await?;
+        rotated += 1;
+    }
+
+    transaction.commit().await?;
+    Ok(rotated)
+}
+
+fn cipher_from_base64(key: &Secret<String>) -> Result<Aes256Gcm, SecretError> {
+    let key = STANDARD
+        .decode(key.expose_secret().trim())
+        .map_err(|e| SecretError::InvalidKey(e.to_string()))?;
+    Aes256Gcm::new_from_slice(&key).map_err(|_| {
+        SecretError::InvalidKey(format!("expected {} bytes, got {}", KEY_LENGTH, key.len()))
+    })
+}
+
+fn read_or_create_key_file(path: &Path) -> Result<String, SecretError> {
+    if path.exists() {
+        return fs::read_to_string(path).map_err(SecretError::KeyFileError);
+    }
+
+    tracing::warn!(
+        "SECRET_KEY is not set, generating a new key at {}",
+        path.display()
+    );
+    let mut key = [0u8; KEY_LENGTH];
+    OsRng.fill_bytes(&mut key);
+    let key = STANDARD.encode(key);
+    // only the server's own user may read the key, and a key written meanwhile is never replaced
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    options
+        .open(path)
+        .and_then(|mut file| file.write_all(key.as_bytes()))
+        .map_err(SecretError::KeyFileError)?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod secrets_test {
+    use super::{read_or_create_key_file, rotate_secret_key, SecretCipher, SecretError};
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use secrecy::Secret;
+    use sqlx::SqlitePool;
+
+    fn key(byte: u8) -> Secret<String> {
+        Secret::new(STANDARD.encode([byte; 32]))
+    }
+
+    #[test]
+    fn round_trips_and_never_stores_plaintext() {
+        let cipher = SecretCipher::generate();
+        let encrypted = cipher.encrypt("client-secret");
+        assert!(encrypted.starts_with("v1:"));
+        assert!(!encrypted.contains("client-secret"));
+        // a fresh nonce is used every time
+        assert_ne!(encrypted, cipher.encrypt("client-secret"));
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), "client-secret");
+    }
+
+    #[test]
+    fn previous_keys_can_still_decrypt() {
+        let old = SecretCipher::new(&key(1), &[]).unwrap();
+        let encrypted = old.encrypt("token");
+
+        let rotated = SecretCipher::new(&key(2), &[key(1)]).unwrap();
+        assert_eq!(
+            rotated.decrypt_with_any_key(&encrypted).unwrap(),
+            ("token".to_string(), false)
+        );
+
+        let forgotten = SecretCipher::new(&key(2), &[]).unwrap();
+        assert!(matches!(
+            forgotten.decrypt(&encrypted),
+            Err(SecretError::DecryptionFailed)
+        ));
+        assert!(matches!(
+            forgotten.decrypt("plaintext"),
+            Err(SecretError::DecryptionFailed)
+        ));
+    }
+
+    #[test]
+    fn rejects_keys_of_the_wrong_length() {
+        let short = Secret::new(STANDARD.encode([0u8; 16]));
+        assert!(matches!(
+            SecretCipher::new(&short, &[]),
+            Err(SecretError::InvalidKey(_))
+        ));
+        assert!(SecretCipher::new(&Secret::new("not base64!".to_string()), &[]).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn generated_key_file_is_only_readable_by_its_owner() {
+        use std::{env, fs, os::unix::fs::PermissionsExt};
+
+        let path = env::temp_dir().join(format!("secret-{}.key", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let key = read_or_create_key_file(&path).unwrap();
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(read_or_create_key_file(&path).unwrap(), key);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn rotation_re_encrypts_values_under_the_current_key() {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+        sqlx::query(
+            "INSERT INTO project (environments, description, name, visibility) VALUES ('', '', 'p', 'Public');
+            INSERT INTO project_environment (project_id, name) VALUES (1, 'staging');",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let old = SecretCipher::new(&key(1), &[]).unwrap();
+        for (name, value) in [("a", "one"), ("b", "two")] {
+            sqlx::query("INSERT INTO project_environment_variable (environment_id, name, value, secret) VALUES (1, ?, ?, 1)")
+                .bind(name)
+                .bind(old.encrypt(value))
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+
+        let rotated = SecretCipher::new(&key(2), &[key(1)]).unwrap();
+        assert_eq!(rotate_secret_key(&rotated, &pool).await.unwrap(), 2);
+        assert_eq!(rotate_secret_key(&rotated, &pool).await.unwrap(), 0);
+
+        let only_new = SecretCipher::new(&key(2), &[]).unwrap();
+        let values: Vec<(String,)> =
+            sqlx::query_as("SELECT value FROM project_environment_variable ORDER BY name")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        let values: Vec<String> = values
+            .iter()
+            .map(|(value,)| only_new.decrypt(value).unwrap())
+            .collect();
+        assert_eq!(values, vec!["one", "two"]);
+    }
+}
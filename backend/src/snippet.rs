@@ -0,0 +1,855 @@
+//! Translating parsed curl requests into code for common HTTP clients. Every snippet sends what
+//! curl would: URLs without a scheme use http, the implicit content type of a body is spelled out
+//! and redirects are only followed with `-L`.
+
+use crate::curl::{self, CurlRequest, FormPart, NameValue, RequestBody};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    /// Python with requests.
+    Python,
+    /// The Fetch API.
+    JavaScript,
+    /// Node.js with axios.
+    Node,
+    /// Go with net/http.
+    Go,
+    /// Rust with reqwest.
+    Rust,
+    HTTPie,
+    /// PowerShell's Invoke-WebRequest.
+    PowerShell,
+}
+
+pub fn generate(request: &CurlRequest, language: Language) -> String {
+    match language {
+        Language::Python => python(request),
+        Language::JavaScript => javascript(request),
+        Language::Node => node(request),
+        Language::Go => go(request),
+        Language::Rust => rust(request),
+        Language::HTTPie => httpie(request),
+        Language::PowerShell => powershell(request),
+    }
+}
+
+fn url(request: &CurlRequest) -> String {
+    if request.url.contains("://") {
+        request.url.clone()
+    } else {
+        format!("http://{}", request.url)
+    }
+}
+
+/// The explicit headers and the content type curl implies for the body. A multipart content type
+/// is left to the client, which has to add the boundary.
+fn headers(request: &CurlRequest) -> Vec<NameValue> {
+    let mut headers: Vec<NameValue> = request
+        .headers
+        .iter()
+        .filter(|header| {
+            !matches!(request.body, Some(RequestBody::Multipart { .. }))
+                || !header.name.eq_ignore_ascii_case("content-type")
+        })
+        .cloned()
+        .collect();
+    match &request.body {
+        Some(body @ (RequestBody::Raw { .. } | RequestBody::Json { .. }))
+            if request.header("content-type").is_none() =>
+        {
+            headers.push(NameValue::new("Content-Type", body.default_content_type()))
+        }
+        _ => {}
+    }
+    headers
+}
+
+fn cookie_header(request: &CurlRequest) -> String {
+    request
+        .cookies
+        .iter()
+        .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+fn content(request: &CurlRequest) -> Option<&str> {
+    match &request.body {
+        Some(RequestBody::Raw { content } | RequestBody::Json { content }) => Some(content),
+        _ => None,
+    }
+}
+
+fn parts(request: &CurlRequest) -> &[FormPart] {
+    match &request.body {
+        Some(RequestBody::Multipart { parts }) => parts,
+        _ => &[],
+    }
+}
+
+fn user(request: &CurlRequest) -> Option<(&str, &str)> {
+    request.basic_auth.as_ref().map(|auth| {
+        (
+            auth.username.as_str(),
+            auth.password.as_deref().unwrap_or_default(),
+        )
+    })
+}
+
+/// The name a file part is uploaded under.
+fn file_name(part: &FormPart) -> &str {
+    part.filename
+        .as_deref()
+        .unwrap_or_else(|| part.value.rsplit('/').next().unwrap_or_default())
+}
+
+/// A double quoted string, which reads the same in Python, JavaScript and Go.
+fn string(value: &str) -> String {
+    serde_json::Value::from(value).to_string()
+}
+
+fn rust_string(value: &str) -> String {
+    let mut quoted = String::from('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn powershell_string(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// One item per line, each followed by a comma.
+fn items(items: &[String], indent: &str) -> String {
+    items
+        .iter()
+        .map(|item| format!("{}{},\n", indent, item))
+        .collect()
+}
+
+fn python(request: &CurlRequest) -> String {
+    let mut code = String::from("import requests\n\n");
+    let mut arguments = vec!["url".to_string()];
+    code.push_str(&format!("url = {}\n", string(&url(request))));
+
+    let headers: Vec<String> = headers(request)
+        .iter()
+        .map(|header| format!("{}: {}", string(&header.name), string(&header.value)))
+        .collect();
+    if !headers.is_empty() {
+        code.push_str(&format!("headers = {{\n{}}}\n", items(&headers, "    ")));
+        arguments.push("headers=headers".to_string());
+    }
+    let cookies: Vec<String> = request
+        .cookies
+        .iter()
+        .map(|cookie| format!("{}: {}", string(&cookie.name), string(&cookie.value)))
+        .collect();
+    if !cookies.is_empty() {
+        code.push_str(&format!("cookies = {{\n{}}}\n", items(&cookies, "    ")));
+        arguments.push("cookies=cookies".to_string());
+    }
+    if let Some(content) = content(request) {
+        code.push_str(&format!("data = {}\n", string(content)));
+        arguments.push("data=data".to_string());
+    }
+    // a list of tuples keeps repeated names, and sends text parts as multipart rather than a form
+    let files: Vec<String> = parts(request)
+        .iter()
+        .map(|part| {
+            let mut file = if part.is_file {
+                vec![
+                    string(file_name(part)),
+                    format!("open({}, \"rb\")", string(&part.value)),
+                ]
+            } else {
+                vec![
+                    part.filename
+                        .as_deref()
+                        .map(string)
+                        .unwrap_or_else(|| "None".to_string()),
+                    string(&part.value),
+                ]
+            };
+            if let Some(content_type) = &part.content_type {
+                file.push(string(content_type));
+            }
+            format!("({}, ({}))", string(&part.name), file.join(", "))
+        })
+        .collect();
+    if !files.is_empty() {
+        code.push_str(&format!("files = [\n{}]\n", items(&files, "    ")));
+        arguments.push("files=files".to_string());
+    }
+
+    if let Some((username, password)) = user(request) {
+        arguments.push(format!("auth=({}, {})", string(username), string(password)));
+    }
+    if request.insecure {
+        arguments.push("verify=False".to_string());
+    }
+    if !request.follow_redirects {
+        arguments.push("allow_redirects=False".to_string());
+    }
+
+    let function = match request.method.as_str() {
+        "GET" | "POST" | "PUT" | "PATCH" | "DELETE" | "HEAD" | "OPTIONS" => {
+            request.method.to_lowercase()
+        }
+        method => {
+            arguments.insert(0, string(method));
+            "request".to_string()
+        }
+    };
+    code.push_str(&format!(
+        "\nresponse = requests.{}(\n{})\n",
+        function,
+        items(&arguments, "    ")
+    ));
+    code.push_str("print(response.status_code)\nprint(response.text)\n");
+    code
+}
+
+/// Appends the parts to a `FormData`, with `file` giving the contents of a file part.
+fn form_data(parts: &[FormPart], file: impl Fn(&FormPart) -> String) -> String {
+    let mut code = String::from("const form = new FormData();\n");
+    for part in parts {
+        let value = match (&part.content_type, part.is_file) {
+            (_, true) => file(part),
+            (Some(content_type), false) => format!(
+                "new Blob([{}], {{ type: {} }})",
+                string(&part.value),
+                string(content_type)
+            ),
+            (None, false) => string(&part.value),
+        };
+        let file_name = match (part.is_file, &part.filename) {
+            (true, _) => format!(", {}", string(file_name(part))),
+            (false, Some(filename)) => format!(", {}", string(filename)),
+            (false, None) => String::new(),
+        };
+        code.push_str(&format!(
+            "form.append({}, {}{});\n",
+            string(&part.name),
+            value,
+            file_name
+        ));
+    }
+    code.push('\n');
+    code
+}
+
+fn blob_type(part: &FormPart) -> String {
+    part.content_type
+        .as_ref()
+        .map(|content_type| format!(", {{ type: {} }}", string(content_type)))
+        .unwrap_or_default()
+}
+
+fn javascript(request: &CurlRequest) -> String {
+    let mut code = String::new();
+    let mut options = vec![];
+    if request.method != "GET" {
+        options.push(format!("method: {}", string(&request.method)));
+    }
+
+    let mut headers: Vec<String> = headers(request)
+        .iter()
+        .map(|header| format!("{}: {}", string(&header.name), string(&header.value)))
+        .collect();
+    if !request.cookies.is_empty() {
+        headers.push(format!("\"Cookie\": {}", string(&cookie_header(request))));
+    }
+    if let Some((username, password)) = user(request) {
+        headers.push(format!(
+            "\"Authorization\": \"Basic \" + btoa({})",
+            string(&format!("{}:{}", username, password))
+        ));
+    }
+    if !headers.is_empty() {
+        options.push(format!("headers: {{\n{}  }}", items(&headers, "    ")));
+    }
+
+    if let Some(content) = content(request) {
+        options.push(format!("body: {}", string(content)));
+    }
+    let parts = parts(request);
+    if !parts.is_empty() {
+        code.push_str(&form_data(parts, |part| {
+            format!(
+                "new Blob([/* contents of {} */]{})",
+                part.value,
+                blob_type(part)
+            )
+        }));
+        options.push("body: form".to_string());
+    }
+    if !request.follow_redirects {
+        options.push("redirect: \"manual\"".to_string());
+    }
+
+    if options.is_empty() {
+        code.push_str(&format!(
+            "const response = await fetch({});\n",
+            string(&url(request))
+        ));
+    } else {
+        code.push_str(&format!(
+            "const response = await fetch({}, {{\n{}}});\n",
+            string(&url(request)),
+            items(&options, "  ")
+        ));
+    }
+    code.push_str("\nconsole.log(response.status);\nconsole.log(await response.text());\n");
+    code
+}
+
+fn node(request: &CurlRequest) -> String {
+    let parts = parts(request);
+    let mut code = String::from("const axios = require(\"axios\");\n");
+    if parts.iter().any(|part| part.is_file) {
+        code.push_str("const fs = require(\"fs\");\n");
+    }
+    if request.insecure {
+        code.push_str("const https = require(\"https\");\n");
+    }
+    code.push('\n');
+
+    let mut options = vec![
+        format!("method: {}", string(&request.method.to_lowercase())),
+        format!("url: {}", string(&url(request))),
+    ];
+    let mut headers: Vec<String> = headers(request)
+        .iter()
+        .map(|header| format!("{}: {}", string(&header.name), string(&header.value)))
+        .collect();
+    if !request.cookies.is_empty() {
+        headers.push(format!("\"Cookie\": {}", string(&cookie_header(request))));
+    }
+    if !headers.is_empty() {
+        options.push(format!("headers: {{\n{}  }}", items(&headers, "    ")));
+    }
+    if let Some((username, password)) = user(request) {
+        options.push(format!(
+            "auth: {{\n    username: {},\n    password: {},\n  }}",
+            string(username),
+            string(password)
+        ));
+    }
+
+    if let Some(content) = content(request) {
+        options.push(format!("data: {}", string(content)));
+    }
+    if !parts.is_empty() {
+        code.push_str(&form_data(parts, |part| {
+            format!(
+                "new Blob([fs.readFileSync({})]{})",
+                string(&part.value),
+                blob_type(part)
+            )
+        }));
+        options.push("data: form".to_string());
+    }
+    if !request.follow_redirects {
+        options.push("maxRedirects: 0".to_string());
+    }
+    if request.insecure {
+        options.push("httpsAgent: new https.Agent({ rejectUnauthorized: false })".to_string());
+    }
+
+    code.push_str(&format!(
+        "axios({{\n{}}}).then((response) => {{\n",
+        items(&options, "  ")
+    ));
+    code.push_str("  console.log(response.status);\n  console.log(response.data);\n});\n");
+    code
+}
+
+fn go(request: &CurlRequest) -> String {
+    let parts = parts(request);
+    let mut imports = vec!["fmt", "io", "net/http"];
+    let mut code = String::new();
+
+    let body = if let Some(content) = content(request) {
+        imports.push("strings");
+        format!("strings.NewReader({})", string(content))
+    } else if !parts.is_empty() {
+        imports.extend(["bytes", "mime/multipart"]);
+        code.push_str("\tbody := &bytes.Buffer{}\n\twriter := multipart.NewWriter(body)\n");
+        for part in parts {
+            if !part.is_file && part.content_type.is_none() && part.filename.is_none() {
+                code.push_str(&format!(
+                    "\tif err := writer.WriteField({}, {}); err != nil {{\n\t\tpanic(err)\n\t}}\n",
+                    string(&part.name),
+                    string(&part.value)
+                ));
+                continue;
+            }
+
+            imports.push("net/textproto");
+            let mut disposition = format!("form-data; name=\"{}\"", part.name);
+            if part.is_file || part.filename.is_some() {
+                disposition.push_str(&format!("; filename=\"{}\"", file_name(part)));
+            }
+            code.push_str("\t{\n\t\theader := make(textproto.MIMEHeader)\n");
+            code.push_str(&format!(
+                "\t\theader.Set(\"Content-Disposition\", {})\n",
+                string(&disposition)
+            ));
+            let content_type = match (&part.content_type, part.is_file) {
+                (Some(content_type), _) => Some(content_type.as_str()),
+                (None, true) => Some("application/octet-stream"),
+                (None, false) => None,
+            };
+            if let Some(content_type) = content_type {
+                code.push_str(&format!(
+                    "\t\theader.Set(\"Content-Type\", {})\n",
+                    string(content_type)
+                ));
+            }
+            code.push_str("\t\tpart, err := writer.CreatePart(header)\n\t\tif err != nil {\n\t\t\tpanic(err)\n\t\t}\n");
+            if part.is_file {
+                imports.push("os");
+                code.push_str(&format!(
+                    "\t\tfile, err := os.Open({})\n\t\tif err != nil {{\n\t\t\tpanic(err)\n\t\t}}\n\t\tdefer file.Close()\n\t\tif _, err := io.Copy(part, file); err != nil {{\n\t\t\tpanic(err)\n\t\t}}\n",
+                    string(&part.value)
+                ));
+            } else {
+                code.push_str(&format!(
+                    "\t\tif _, err := io.WriteString(part, {}); err != nil {{\n\t\t\tpanic(err)\n\t\t}}\n",
+                    string(&part.value)
+                ));
+            }
+            code.push_str("\t}\n");
+        }
+        code.push_str("\tif err := writer.Close(); err != nil {\n\t\tpanic(err)\n\t}\n\n");
+        "body".to_string()
+    } else {
+        "nil".to_string()
+    };
+
+    code.push_str(&format!(
+        "\treq, err := http.NewRequest({}, {}, {})\n\tif err != nil {{\n\t\tpanic(err)\n\t}}\n",
+        string(&request.method),
+        string(&url(request)),
+        body
+    ));
+    for header in headers(request) {
+        code.push_str(&format!(
+            "\treq.Header.Add({}, {})\n",
+            string(&header.name),
+            string(&header.value)
+        ));
+    }
+    if !parts.is_empty() {
+        code.push_str("\treq.Header.Set(\"Content-Type\", writer.FormDataContentType())\n");
+    }
+    for cookie in &request.cookies {
+        code.push_str(&format!(
+            "\treq.AddCookie(&http.Cookie{{Name: {}, Value: {}}})\n",
+            string(&cookie.name),
+            string(&cookie.value)
+        ));
+    }
+    if let Some((username, password)) = user(request) {
+        code.push_str(&format!(
+            "\treq.SetBasicAuth({}, {})\n",
+            string(username),
+            string(password)
+        ));
+    }
+
+    code.push_str("\n\tclient := &http.Client{");
+    if !request.follow_redirects || request.insecure {
+        code.push('\n');
+        if !request.follow_redirects {
+            code.push_str("\t\tCheckRedirect: func(req *http.Request, via []*http.Request) error {\n\t\t\treturn http.ErrUseLastResponse\n\t\t},\n");
+        }
+        if request.insecure {
+            imports.push("crypto/tls");
+            code.push_str("\t\tTransport: &http.Transport{\n\t\t\tTLSClientConfig: &tls.Config{InsecureSkipVerify: true},\n\t\t},\n");
+        }
+        code.push('\t');
+    }
+    code.push_str("}\n");
+    code.push_str("\tresp, err := client.Do(req)\n\tif err != nil {\n\t\tpanic(err)\n\t}\n\tdefer resp.Body.Close()\n\n");
+    code.push_str(
+        "\trespBody, err := io.ReadAll(resp.Body)\n\tif err != nil {\n\t\tpanic(err)\n\t}\n",
+    );
+    code.push_str("\tfmt.Println(resp.Status)\n\tfmt.Println(string(respBody))\n}\n");
+
+    imports.sort_unstable();
+    imports.dedup();
+    format!(
+        "package main\n\nimport (\n{})\n\nfunc main() {{\n{}",
+        imports
+            .iter()
+            .map(|import| format!("\t\"{}\"\n", import))
+            .collect::<String>(),
+        code
+    )
+}
+
+fn rust(request: &CurlRequest) -> String {
+    let mut code = String::from(
+        "#[tokio::main]\nasync fn main() -> Result<(), Box<dyn std::error::Error>> {\n",
+    );
+
+    code.push_str("    let client = reqwest::Client::builder()\n");
+    if !request.follow_redirects {
+        code.push_str("        .redirect(reqwest::redirect::Policy::none())\n");
+    }
+    if request.insecure {
+        code.push_str("        .danger_accept_invalid_certs(true)\n");
+    }
+    code.push_str("        .build()?;\n\n");
+
+    let parts = parts(request);
+    if !parts.is_empty() {
+        code.push_str("    let form = reqwest::multipart::Form::new()");
+        for part in parts {
+            if !part.is_file && part.content_type.is_none() && part.filename.is_none() {
+                code.push_str(&format!(
+                    "\n        .text({}, {})",
+                    rust_string(&part.name),
+                    rust_string(&part.value)
+                ));
+                continue;
+            }
+
+            let mut value = if part.is_file {
+                format!(
+                    "reqwest::multipart::Part::bytes(std::fs::read({})?)",
+                    rust_string(&part.value)
+                )
+            } else {
+                format!(
+                    "reqwest::multipart::Part::text({})",
+                    rust_string(&part.value)
+                )
+            };
+            if part.is_file || part.filename.is_some() {
+                value.push_str(&format!(
+                    "\n                .file_name({})",
+                    rust_string(file_name(part))
+                ));
+            }
+            if let Some(content_type) = &part.content_type {
+                value.push_str(&format!(
+                    "\n                .mime_str({})?",
+                    rust_string(content_type)
+                ));
+            }
+            code.push_str(&format!(
+                "\n        .part(\n            {},\n            {},\n        )",
+                rust_string(&part.name),
+                value
+            ));
+        }
+        code.push_str(";\n\n");
+    }
+
+    let method = match request.method.as_str() {
+        "GET" | "POST" | "PUT" | "PATCH" | "DELETE" | "HEAD" => format!(
+            ".{}({})",
+            request.method.to_lowercase(),
+            rust_string(&url(request))
+        ),
+        method => format!(
+            ".request(reqwest::Method::from_bytes(b{})?, {})",
+            rust_string(method),
+            rust_string(&url(request))
+        ),
+    };
+    code.push_str(&format!("    let response = client\n        {}\n", method));
+    for header in headers(request) {
+        code.push_str(&format!(
+            "        .header({}, {})\n",
+            rust_string(&header.name),
+            rust_string(&header.value)
+        ));
+    }
+    if !request.cookies.is_empty() {
+        code.push_str(&format!(
+            "        .header(\"Cookie\", {})\n",
+            rust_string(&cookie_header(request))
+        ));
+    }
+    if let Some((username, password)) = user(request) {
+        code.push_str(&format!(
+            "        .basic_auth({}, Some({}))\n",
+            rust_string(username),
+            rust_string(password)
+        ));
+    }
+    if let Some(content) = content(request) {
+        code.push_str(&format!("        .body({})\n", rust_string(content)));
+    }
+    if !parts.is_empty() {
+        code.push_str("        .multipart(form)\n");
+    }
+    code.push_str("        .send()\n        .await?;\n\n");
+    code.push_str("    println!(\"{}\", response.status());\n    println!(\"{}\", response.text().await?);\n    Ok(())\n}\n");
+    code
+}
+
+fn httpie(request: &CurlRequest) -> String {
+    let mut arguments = vec!["http".to_string()];
+    if request.follow_redirects {
+        arguments.push("--follow".to_string());
+    }
+    if request.insecure {
+        arguments.push("--verify=no".to_string());
+    }
+    if let Some(auth) = &request.basic_auth {
+        let user = match &auth.password {
+            Some(password) => format!("{}:{}", auth.username, password),
+            None => auth.username.clone(),
+        };
+        arguments.push(format!("-a {}", curl::quote(&user)));
+    }
+    if let Some(content) = content(request) {
+        arguments.push(format!("--raw {}", curl::quote(content)));
+    }
+    let parts = parts(request);
+    if !parts.is_empty() {
+        arguments.push("--multipart".to_string());
+    }
+
+    arguments.push(request.method.clone());
+    arguments.push(curl::quote(&url(request)));
+    for header in headers(request) {
+        let header = if header.value.is_empty() {
+            format!("{};", header.name)
+        } else {
+            format!("{}:{}", header.name, header.value)
+        };
+        arguments.push(curl::quote(&header));
+    }
+    if !request.cookies.is_empty() {
+        arguments.push(curl::quote(&format!("Cookie:{}", cookie_header(request))));
+    }
+    for part in parts {
+        let mut item = if part.is_file {
+            format!("{}@{}", part.name, part.value)
+        } else {
+            format!("{}={}", part.name, part.value)
+        };
+        if let (true, Some(content_type)) = (part.is_file, &part.content_type) {
+            item.push_str(&format!(";type={}", content_type));
+        }
+        arguments.push(curl::quote(&item));
+    }
+
+    format!("{}\n", arguments.join(" \\\n  "))
+}
+
+fn powershell(request: &CurlRequest) -> String {
+    let mut code = String::new();
+    let mut parameters = vec![format!("Uri = {}", powershell_string(&url(request)))];
+    match request.method.as_str() {
+        "GET" | "HEAD" | "POST" | "PUT" | "DELETE" | "TRACE" | "OPTIONS" | "PATCH" => {
+            parameters.push(format!("Method = {}", powershell_string(&request.method)))
+        }
+        method => parameters.push(format!("CustomMethod = {}", powershell_string(method))),
+    }
+
+    // the content type has a parameter of its own, setting it as a header fails
+    let (content_type, headers): (Vec<NameValue>, Vec<NameValue>) = headers(request)
+        .into_iter()
+        .partition(|header| header.name.eq_ignore_ascii_case("content-type"));
+    let mut headers: Vec<String> = headers
+        .iter()
+        .map(|header| {
+            format!(
+                "{} = {}",
+                powershell_string(&header.name),
+                powershell_string(&header.value)
+            )
+        })
+        .collect();
+    if !request.cookies.is_empty() {
+        headers.push(format!(
+            "'Cookie' = {}",
+            powershell_string(&cookie_header(request))
+        ));
+    }
+    if let Some((username, password)) = user(request) {
+        code.push_str(&format!(
+            "$credentials = [Convert]::ToBase64String([Text.Encoding]::UTF8.GetBytes({}))\n",
+            powershell_string(&format!("{}:{}", username, password))
+        ));
+        headers.push("'Authorization' = \"Basic $credentials\"".to_string());
+    }
+    if !headers.is_empty() {
+        parameters.push(format!(
+            "Headers = @{{\n{}    }}",
+            headers
+                .iter()
+                .map(|header| format!("        {}\n", header))
+                .collect::<String>()
+        ));
+    }
+    if let Some(content_type) = content_type.first() {
+        parameters.push(format!(
+            "ContentType = {}",
+            powershell_string(&content_type.value)
+        ));
+    }
+
+    if let Some(content) = content(request) {
+        parameters.push(format!("Body = {}", powershell_string(content)));
+    }
+    let parts = parts(request);
+    if !parts.is_empty() {
+        parameters.push(format!(
+            "Form = @{{\n{}    }}",
+            parts
+                .iter()
+                .map(|part| {
+                    let value = if part.is_file {
+                        format!("Get-Item -Path {}", powershell_string(&part.value))
+                    } else {
+                        powershell_string(&part.value)
+                    };
+                    format!("        {} = {}\n", powershell_string(&part.name), value)
+                })
+                .collect::<String>()
+        ));
+    }
+    if !request.follow_redirects {
+        parameters.push("MaximumRedirection = 0".to_string());
+    }
+    if request.insecure {
+        parameters.push("SkipCertificateCheck = $true".to_string());
+    }
+
+    code.push_str(&format!(
+        "$request = @{{\n{}}}\n",
+        parameters
+            .iter()
+            .map(|parameter| format!("    {}\n", parameter))
+            .collect::<String>()
+    ));
+    code.push_str(
+        "$response = Invoke-WebRequest @request\n$response.StatusCode\n$response.Content\n",
+    );
+    code
+}
+
+#[cfg(test)]
+mod snippet_test {
+    use super::{generate, Language};
+    use crate::curl::parse;
+
+    const LANGUAGES: [Language; 7] = [
+        Language::Python,
+        Language::JavaScript,
+        Language::Node,
+        Language::Go,
+        Language::Rust,
+        Language::HTTPie,
+        Language::PowerShell,
+    ];
+
+    #[test]
+    fn translates_a_json_post_into_python() {
+        let request = parse(
+            "curl -X POST 'https://api.example.com/users?notify=1' \
+            -H 'Content-Type: application/json' -b 'session=abc' \
+            -d '{\"name\": \"Ada\"}'",
+        )
+        .unwrap();
+        assert_eq!(
+            generate(&request, Language::Python),
+            r#"import requests
+
+url = "https://api.example.com/users?notify=1"
+headers = {
+    "Content-Type": "application/json",
+}
+cookies = {
+    "session": "abc",
+}
+data = "{\"name\": \"Ada\"}"
+
+response = requests.post(
+    url,
+    headers=headers,
+    cookies=cookies,
+    data=data,
+    allow_redirects=False,
+)
+print(response.status_code)
+print(response.text)
+"#
+        );
+    }
+
+    #[test]
+    fn spells_out_the_implied_content_type() {
+        let request = parse("curl example.com -d 'a=1'").unwrap();
+        assert_eq!(
+            generate(&request, Language::HTTPie),
+            "http \\\n  --raw a=1 \\\n  POST \\\n  http://example.com \\\n  \
+            Content-Type:application/x-www-form-urlencoded\n"
+        );
+
+        let request = parse("curl -L example.com --json '{}'").unwrap();
+        let fetch = generate(&request, Language::JavaScript);
+        assert!(fetch.contains("\"Content-Type\": \"application/json\""));
+        assert!(!fetch.contains("redirect"));
+    }
+
+    #[test]
+    fn uploads_files_in_every_language() {
+        let request = parse(
+            "curl https://example.com/upload -H 'Content-Type: multipart/form-data' \
+            -F 'title=Holiday' -F 'photo=@/tmp/me.png;type=image/png'",
+        )
+        .unwrap();
+        for language in LANGUAGES {
+            let code = generate(&request, language);
+            assert!(code.contains("Holiday"), "{:?}: {}", language, code);
+            assert!(code.contains("/tmp/me.png"), "{:?}: {}", language, code);
+            assert!(
+                !code.contains("multipart/form-data"),
+                "{:?}: {}",
+                language,
+                code
+            );
+        }
+        assert!(generate(&request, Language::Go).contains("writer.FormDataContentType()"));
+    }
+
+    #[test]
+    fn carries_credentials_and_tls_settings() {
+        let request = parse("curl -k -u 'admin:s3cr\"et' -X PURGE https://example.com").unwrap();
+        let code = generate(&request, Language::Rust);
+        assert!(code.contains(".basic_auth(\"admin\", Some(\"s3cr\\\"et\"))"));
+        assert!(code.contains(".danger_accept_invalid_certs(true)"));
+        assert!(code.contains("reqwest::Method::from_bytes(b\"PURGE\")?"));
+
+        let code = generate(&request, Language::Go);
+        assert!(code.contains("\t\"crypto/tls\"\n"));
+        assert!(code.contains("req.SetBasicAuth(\"admin\", \"s3cr\\\"et\")"));
+
+        let code = generate(&request, Language::PowerShell);
+        assert!(code.contains("GetBytes('admin:s3cr\"et')"));
+        assert!(code.contains("CustomMethod = 'PURGE'"));
+        assert!(code.contains("SkipCertificateCheck = $true"));
+    }
+}
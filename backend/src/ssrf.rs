@@ -0,0 +1,466 @@
+use hyper::client::connect::dns::Name;
+use reqwest::{
+    dns::{Addrs, Resolve, Resolving},
+    redirect::Policy,
+    ClientBuilder, Response, Url,
+};
+use std::{
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+pub const MAX_REDIRECTS: usize = 10;
+
+#[derive(Debug, Clone)]
+pub enum SsrfError {
+    HostNotAllowed(String),
+    AddressBlocked(String, IpAddr),
+}
+
+impl fmt::Display for SsrfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SsrfError::HostNotAllowed(host) => {
+                write!(f, "Requests to {} are not allowed for this project", host)
+            }
+            SsrfError::AddressBlocked(host, ip) => write!(
+                f,
+                "{} resolves to the internal address {}, add it to the allowed hosts to call it",
+                host, ip
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SsrfError {}
+
+/// Keeps requests sent on behalf of users away from the server's own network. Only allowed hosts
+/// are called, and loopback, private, link-local and metadata addresses are refused unless an
+/// allowed host names them exactly. Addresses are checked after every DNS lookup, so redirects
+/// and DNS rebinding cannot reach them either.
+pub struct SsrfGuard {
+    allowed_hosts: Arc<Vec<String>>,
+    timeout: Duration,
+    max_response_bytes: usize,
+}
+
+impl SsrfGuard {
+    /// Host patterns are `example.com`, `*.example.com` or `localhost:8080`.
+    pub fn new(allowed_hosts: Vec<String>, timeout: Duration, max_response_bytes: usize) -> Self {
+        Self {
+            allowed_hosts: Arc::new(allowed_hosts),
+            timeout,
+            max_response_bytes,
+        }
+    }
+
+    pub fn check_url(&self, url: &Url) -> Result<(), SsrfError> {
+        check_url(url, &self.allowed_hosts)
+    }
+
+    /// A client builder that resolves, redirects and times out through the guard. The url of the
+    /// first request still has to go through [`SsrfGuard::check_url`].
+    pub fn client_builder(&self, follow_redirects: bool) -> ClientBuilder {
+        let redirect_policy = if follow_redirects {
+            let allowed_hosts = self.allowed_hosts.clone();
+            Policy::custom(move |attempt| {
+                if attempt.previous().len() >= MAX_REDIRECTS {
+                    attempt.error("too many redirects")
+                } else if let Err(e) = check_url(attempt.url(), &allowed_hosts) {
+                    attempt.error(e)
+                } else {
+                    attempt.follow()
+                }
+            })
+        } else {
+            Policy::none()
+        };
+
+        ClientBuilder::new()
+            .timeout(self.timeout)
+            .redirect(redirect_policy)
+            // a proxy would do the DNS lookups the guard relies on
+            .no_proxy()
+            .dns_resolver(Arc::new(GuardedResolver::new(
+                self.allowed_hosts.clone(),
+                Arc::new(SystemResolver),
+            )))
+    }
+
+    /// Reads at most the configured number of bytes, returning whether the body was cut off.
+    pub async fn read_body(&self, response: &mut Response) -> reqwest::Result<(Vec<u8>, bool)> {
+        let mut body = vec![];
+        while let Some(chunk) = response.chunk().await? {
+            let remaining = self.max_response_bytes - body.len();
+            if chunk.len() > remaining {
+                body.extend_from_slice(&chunk[..remaining]);
+                return Ok((body, true));
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok((body, false))
+    }
+}
+
+/// Finds the guard's error behind a failed request, if the guard is what stopped it.
+pub fn find_ssrf_error(e: &reqwest::Error) -> Option<&SsrfError> {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(e);
+    while let Some(e) = source {
+        if let Some(e) = e.downcast_ref::<SsrfError>() {
+            return Some(e);
+        }
+        source = e.source();
+    }
+    None
+}
+
+fn check_url(url: &Url, allowed_hosts: &[String]) -> Result<(), SsrfError> {
+    let host = url.host_str().unwrap_or_default();
+    if !is_host_allowed(url, allowed_hosts) {
+        return Err(SsrfError::HostNotAllowed(host_with_port(url)));
+    }
+
+    // IP literals are connected to without a lookup; the url parser has already turned decimal,
+    // octal and hex forms into dotted quads
+    let Some(ip) = host_ip(url) else {
+        return Ok(());
+    };
+    if is_blocked_ip(ip) && !is_explicitly_allowed(host, allowed_hosts) {
+        return Err(SsrfError::AddressBlocked(host.to_string(), ip));
+    }
+    Ok(())
+}
+
+fn host_ip(url: &Url) -> Option<IpAddr> {
+    let host = url.host_str()?;
+    host.trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse()
+        .ok()
+}
+
+fn host_with_port(url: &Url) -> String {
+    match (url.host_str(), url.port()) {
+        (Some(host), Some(port)) => format!("{}:{}", host, port),
+        (Some(host), None) => host.to_string(),
+        (None, _) => url.to_string(),
+    }
+}
+
+fn split_pattern(pattern: &str) -> (String, Option<u16>) {
+    let pattern = pattern.trim().to_ascii_lowercase();
+    match pattern.rsplit_once(':') {
+        Some((host, port)) if !host.ends_with(':') => match port.parse::<u16>() {
+            Ok(port) => (host.to_string(), Some(port)),
+            Err(_) => (pattern.clone(), None),
+        },
+        _ => (pattern.clone(), None),
+    }
+}
+
+/// Patterns without a port allow any port on that host; `*.` allows any subdomain but not the
+/// domain itself, and never matches IP addresses.
+pub fn is_host_allowed(url: &Url, allowed_hosts: &[String]) -> bool {
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    let host = host.to_ascii_lowercase();
+    let is_domain = host_ip(url).is_none();
+    let port = url.port_or_known_default();
+
+    allowed_hosts.iter().any(|pattern| {
+        let (pattern_host, pattern_port) = split_pattern(pattern);
+        let host_matches = match pattern_host.strip_prefix("*.") {
+            Some(domain) => is_domain && host.ends_with(&format!(".{}", domain)),
+            None => host == pattern_host,
+        };
+        host_matches && (pattern_port.is_none() || pattern_port == port)
+    })
+}
+
+/// Whether an allowed host names `host` itself rather than through a wildcard.
+fn is_explicitly_allowed(host: &str, allowed_hosts: &[String]) -> bool {
+    let host = host.to_ascii_lowercase();
+    allowed_hosts
+        .iter()
+        .any(|pattern| split_pattern(pattern).0 == host)
+}
+
+pub fn is_blocked_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_blocked_ipv4(ip),
+        IpAddr::V6(ip) => match embedded_ipv4(ip) {
+            Some(ip) => is_blocked_ipv4(ip),
+            None => is_blocked_ipv6(ip),
+        },
+    }
+}
+
+fn is_blocked_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    // 0.0.0.0/8 reaches the local host on some systems
+    a == 0
+        || ip.is_loopback()
+        || ip.is_private()
+        // 169.254.0.0/16, which holds the cloud metadata endpoints
+        || ip.is_link_local()
+        // carrier-grade NAT
+        || (a == 100 && (64..128).contains(&b))
+        || (a == 192 && b == 0 && c == 0)
+        || (a == 198 && (b == 18 || b == 19))
+        || ip.is_multicast()
+        || a >= 240
+}
+
+fn is_blocked_ipv6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // unique local (fc00::/7, including fd00:ec2::254), link-local and site-local
+        || (first & 0xfe00) == 0xfc00
+        || (first & 0xffc0) == 0xfe80
+        || (first & 0xffc0) == 0xfec0
+}
+
+/// The IPv4 address an IPv6 address routes to: mapped (`::ffff:a.b.c.d`), compatible
+/// (`::a.b.c.d`), NAT64 (`64:ff9b::a.b.c.d`) or 6to4 (`2002:aabb:ccdd::`).
+fn embedded_ipv4(ip: Ipv6Addr) -> Option<Ipv4Addr> {
+    let segments = ip.segments();
+    let from_segments = |high: u16, low: u16| Ipv4Addr::from(((high as u32) << 16) | low as u32);
+
+    match segments {
+        [0x64, 0xff9b, 0, 0, 0, 0, high, low] => Some(from_segments(high, low)),
+        [0x2002, high, low, ..] => Some(from_segments(high, low)),
+        _ => ip.to_ipv4(),
+    }
+}
+
+/// Checks every address a host resolves to, on every connection the client makes.
+struct GuardedResolver {
+    allowed_hosts: Arc<Vec<String>>,
+    inner: Arc<dyn Resolve>,
+}
+
+impl GuardedResolver {
+    fn new(allowed_hosts: Arc<Vec<String>>, inner: Arc<dyn Resolve>) -> Self {
+        Self {
+            allowed_hosts,
+            inner,
+        }
+    }
+}
+
+impl Resolve for GuardedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let allowed_hosts = self.allowed_hosts.clone();
+        let resolving = self.inner.resolve(name.clone());
+
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = resolving.await?.collect();
+            if !is_explicitly_allowed(name.as_str(), &allowed_hosts) {
+                // a single internal answer is enough to refuse, rebinding services mix them in
+                if let Some(addr) = addrs.iter().find(|addr| is_blocked_ip(addr.ip())) {
+                    return Err(Box::new(SsrfError::AddressBlocked(
+                        name.as_str().to_string(),
+                        addr.ip(),
+                    )) as _);
+                }
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+struct SystemResolver;
+
+impl Resolve for SystemResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> =
+                tokio::net::lookup_host((name.as_str(), 0)).await?.collect();
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+#[cfg(test)]
+mod ssrf_test {
+    use super::{check_url, is_blocked_ip, is_host_allowed, GuardedResolver, SsrfError};
+    use hyper::client::connect::dns::Name;
+    use reqwest::{
+        dns::{Addrs, Resolve, Resolving},
+        Url,
+    };
+    use std::{
+        net::{IpAddr, SocketAddr},
+        str::FromStr,
+        sync::{Arc, Mutex},
+    };
+
+    fn patterns(patterns: &[&str]) -> Vec<String> {
+        patterns.iter().map(|pattern| pattern.to_string()).collect()
+    }
+
+    fn allowed(url: &str, allowed_hosts: &[&str]) -> bool {
+        is_host_allowed(&Url::parse(url).unwrap(), &patterns(allowed_hosts))
+    }
+
+    fn check(url: &str, allowed_hosts: &[&str]) -> Result<(), SsrfError> {
+        check_url(&Url::parse(url).unwrap(), &patterns(allowed_hosts))
+    }
+
+    fn blocked(ip: &str) -> bool {
+        is_blocked_ip(ip.parse().unwrap())
+    }
+
+    #[test]
+    fn matches_hosts_against_the_allowlist() {
+        assert!(allowed("https://api.example.com/x", &["api.example.com"]));
+        assert!(allowed("https://API.example.com", &["Api.Example.com"]));
+        assert!(!allowed("https://example.com", &["api.example.com"]));
+        assert!(!allowed(
+            "https://api.example.com.evil.com",
+            &["api.example.com"]
+        ));
+        assert!(!allowed("https://api.example.com", &[]));
+    }
+
+    #[test]
+    fn supports_wildcards_and_ports() {
+        assert!(allowed("https://a.b.example.com", &["*.example.com"]));
+        assert!(!allowed("https://example.com", &["*.example.com"]));
+        assert!(!allowed("https://badexample.com", &["*.example.com"]));
+        assert!(!allowed("http://127.0.0.1", &["*.0.1"]));
+
+        assert!(allowed("http://localhost:8080", &["localhost:8080"]));
+        assert!(!allowed("http://localhost:9090", &["localhost:8080"]));
+        assert!(allowed("http://localhost:9090", &["localhost"]));
+        assert!(allowed("https://example.com", &["example.com:443"]));
+        assert!(allowed("http://[::1]:8080", &["[::1]:8080"]));
+    }
+
+    #[test]
+    fn blocks_internal_addresses() {
+        for ip in [
+            "127.0.0.1",
+            "0.0.0.0",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.100.100.200",
+            "::1",
+            "::",
+            "fe80::1",
+            "fd00:ec2::254",
+        ] {
+            assert!(blocked(ip), "{} should be blocked", ip);
+        }
+        for ip in ["93.184.216.34", "8.8.8.8", "172.32.0.1", "2606:4700::1111"] {
+            assert!(!blocked(ip), "{} should be allowed", ip);
+        }
+    }
+
+    #[test]
+    fn blocks_ipv4_addresses_hidden_in_ipv6() {
+        assert!(blocked("::ffff:127.0.0.1"));
+        assert!(blocked("::ffff:a9fe:a9fe"));
+        assert!(blocked("::127.0.0.1"));
+        assert!(blocked("64:ff9b::10.0.0.1"));
+        assert!(blocked("2002:a9fe:a9fe::"));
+        assert!(!blocked("::ffff:8.8.8.8"));
+
+        assert!(matches!(
+            check("http://[::ffff:169.254.169.254]/", &["[::ffff:a9fe:a9fe]"]),
+            Ok(())
+        ));
+        assert!(matches!(
+            check("http://[::ffff:169.254.169.254]/", &["169.254.169.254"]),
+            Err(SsrfError::HostNotAllowed(_))
+        ));
+    }
+
+    #[test]
+    fn decimal_octal_and_hex_hosts_are_normalised_before_checking() {
+        for url in [
+            "http://2130706433/",
+            "http://0x7f000001/",
+            "http://0177.0.0.1/",
+            "http://127.1/",
+        ] {
+            assert_eq!(Url::parse(url).unwrap().host_str(), Some("127.0.0.1"));
+            assert!(matches!(
+                check(url, &["localhost", "*.example.com"]),
+                Err(SsrfError::HostNotAllowed(_))
+            ));
+            assert!(check(url, &["127.0.0.1"]).is_ok());
+        }
+    }
+
+    struct FakeResolver {
+        answers: Mutex<Vec<Vec<&'static str>>>,
+    }
+
+    impl Resolve for FakeResolver {
+        fn resolve(&self, _: Name) -> Resolving {
+            let answer = self.answers.lock().unwrap().remove(0);
+            let addrs: Vec<SocketAddr> = answer
+                .iter()
+                .map(|ip| SocketAddr::new(ip.parse().unwrap(), 0))
+                .collect();
+            Box::pin(async move { Ok(Box::new(addrs.into_iter()) as Addrs) })
+        }
+    }
+
+    fn resolver(allowed_hosts: &[&str], answers: Vec<Vec<&'static str>>) -> GuardedResolver {
+        GuardedResolver::new(
+            Arc::new(patterns(allowed_hosts)),
+            Arc::new(FakeResolver {
+                answers: Mutex::new(answers),
+            }),
+        )
+    }
+
+    async fn resolve(resolver: &GuardedResolver, host: &str) -> Result<Vec<IpAddr>, String> {
+        match resolver.resolve(Name::from_str(host).unwrap()).await {
+            Ok(addrs) => Ok(addrs.map(|addr| addr.ip()).collect()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn every_lookup_is_checked_against_rebinding() {
+        let resolver = resolver(
+            &["*.example.com"],
+            vec![
+                vec!["93.184.216.34"],
+                vec!["127.0.0.1"],
+                vec!["93.184.216.34", "169.254.169.254"],
+            ],
+        );
+
+        assert!(resolve(&resolver, "api.example.com").await.is_ok());
+        // the same name answering with an internal address on the next connection is refused
+        let error = resolve(&resolver, "api.example.com").await.unwrap_err();
+        assert!(error.contains("127.0.0.1"));
+        let error = resolve(&resolver, "api.example.com").await.unwrap_err();
+        assert!(error.contains("169.254.169.254"));
+    }
+
+    #[tokio::test]
+    async fn internal_hosts_named_in_the_allowlist_resolve() {
+        let resolver = resolver(
+            &["intranet.corp:8080", "*.corp"],
+            vec![vec!["10.0.0.5"], vec!["10.0.0.6"]],
+        );
+
+        assert_eq!(
+            resolve(&resolver, "intranet.corp").await.unwrap(),
+            vec!["10.0.0.5".parse::<IpAddr>().unwrap()]
+        );
+        assert!(resolve(&resolver, "wiki.corp").await.is_err());
+    }
+}
@@ -0,0 +1,187 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::BTreeMap;
+
+static PLACEHOLDER: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\{\{\s*([A-Za-z_][A-Za-z0-9_]*)\s*\}\}").expect("placeholder pattern is valid")
+});
+
+static VARIABLE_NAME: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*$").expect("variable pattern is valid"));
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Rendered {
+    pub text: String,
+    /// Placeholders with no matching variable, in order of first appearance. They are left as is.
+    pub missing: Vec<String>,
+}
+
+/// Whether `name` can be referenced as `{{name}}`.
+pub fn is_valid_variable_name(name: &str) -> bool {
+    VARIABLE_NAME.is_match(name)
+}
+
+/// Substitutes `{{name}}` placeholders in a shell command with the value of the matching
+/// variable, quoted for where the placeholder sits so the value cannot break out of its word.
+pub fn render(template: &str, variables: &BTreeMap<String, String>) -> Rendered {
+    let mut missing = vec![];
+    let mut text = String::with_capacity(template.len());
+    let mut quoting = Quoting::Unquoted;
+    let mut end = 0;
+
+    for captures in PLACEHOLDER.captures_iter(template) {
+        let placeholder = captures.get(0).expect("the whole match is always captured");
+        let before = &template[end..placeholder.start()];
+        quoting = quoting.after(before);
+        text.push_str(before);
+        end = placeholder.end();
+
+        let name = &captures[1];
+        match variables.get(name) {
+            Some(value) => text.push_str(&quoting.quote(value)),
+            None => {
+                if !missing.iter().any(|missing| missing == name) {
+                    missing.push(name.to_string());
+                }
+                text.push_str(placeholder.as_str());
+            }
+        }
+    }
+    text.push_str(&template[end..]);
+
+    Rendered { text, missing }
+}
+
+/// The kind of shell quoting in effect at some point of a command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Quoting {
+    Unquoted,
+    Single,
+    Double,
+    AnsiC,
+}
+
+impl Quoting {
+    /// The quoting in effect once `text` follows, read the way the curl parser reads commands.
+    fn after(self, text: &str) -> Quoting {
+        let mut quoting = self;
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            quoting = match (quoting, c) {
+                (Quoting::Unquoted | Quoting::Double | Quoting::AnsiC, '\\') => {
+                    chars.next();
+                    quoting
+                }
+                (Quoting::Unquoted, '\'') => Quoting::Single,
+                (Quoting::Unquoted, '"') => Quoting::Double,
+                (Quoting::Unquoted, '$') if chars.peek() == Some(&'\'') => {
+                    chars.next();
+                    Quoting::AnsiC
+                }
+                (Quoting::Single | Quoting::AnsiC, '\'') | (Quoting::Double, '"') => {
+                    Quoting::Unquoted
+                }
+                _ => quoting,
+            };
+        }
+        quoting
+    }
+
+    fn quote(self, value: &str) -> String {
+        match self {
+            Quoting::Unquoted if !value.is_empty() && value.chars().all(is_safe_unquoted) => {
+                value.to_string()
+            }
+            Quoting::Unquoted => format!("'{}'", value.replace('\'', r"'\''")),
+            Quoting::Single => value.replace('\'', r"'\''"),
+            Quoting::Double => escape(value, &['\\', '"', '$', '`']),
+            Quoting::AnsiC => escape(value, &['\\', '\'']),
+        }
+    }
+}
+
+fn is_safe_unquoted(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "-_./:=@%+,".contains(c)
+}
+
+fn escape(value: &str, special: &[char]) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if special.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod template_test {
+    use super::{is_valid_variable_name, render};
+    use crate::curl::parse;
+    use std::collections::BTreeMap;
+
+    fn variables(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn substitutes_known_variables() {
+        let rendered = render(
+            "curl {{base_url}}/v{{ api_version }}/tenants/{{tenant_id}}",
+            &variables(&[
+                ("base_url", "https://staging.example.com"),
+                ("api_version", "2"),
+                ("tenant_id", "42"),
+            ]),
+        );
+        assert_eq!(
+            rendered.text,
+            "curl https://staging.example.com/v2/tenants/42"
+        );
+        assert!(rendered.missing.is_empty());
+    }
+
+    #[test]
+    fn reports_and_keeps_missing_placeholders() {
+        let rendered = render(
+            "curl {{base_url}}/{{a}}/{{ a }} -H 'Authorization: Bearer {{REDACTED}}' -d '{}'",
+            &variables(&[("base_url", "localhost")]),
+        );
+        assert_eq!(
+            rendered.text,
+            "curl localhost/{{a}}/{{ a }} -H 'Authorization: Bearer {{REDACTED}}' -d '{}'"
+        );
+        assert_eq!(rendered.missing, vec!["a", "REDACTED"]);
+    }
+
+    #[test]
+    fn quotes_values_for_where_they_are_used() {
+        let value = "it's a \"test\" $HOME";
+        let rendered = render(
+            "curl {{v}}/path -H 'X-A: {{v}}' -H \"X-B: {{v}}\" -H $'X-C: {{v}}' -d {{n}}",
+            &variables(&[("v", value), ("n", "42")]),
+        );
+        assert_eq!(
+            rendered.text,
+            r#"curl 'it'\''s a "test" $HOME'/path -H 'X-A: it'\''s a "test" $HOME' -H "X-B: it's a \"test\" \$HOME" -H $'X-C: it\'s a "test" $HOME' -d 42"#
+        );
+
+        let request = parse(&rendered.text).unwrap();
+        assert_eq!(request.url, format!("{}/path", value));
+        let headers: Vec<&str> = request.headers.iter().map(|h| h.value.as_str()).collect();
+        assert_eq!(headers, vec![value, value, value]);
+    }
+
+    #[test]
+    fn validates_variable_names() {
+        assert!(is_valid_variable_name("base_url"));
+        assert!(is_valid_variable_name("_Tenant2"));
+        assert!(!is_valid_variable_name("2fa"));
+        assert!(!is_valid_variable_name("base-url"));
+        assert!(!is_valid_variable_name(""));
+    }
+}
@@ -0,0 +1,185 @@
+mod common;
+use crate::common::TestApplication;
+use backend::routes::audit::types::{AuditEvent, AuditPage};
+use backend::routes::entry::types::CurlEntryRequest;
+use reqwest::StatusCode;
+use serde_json::json;
+
+async fn create_project_with_group(app: &TestApplication) {
+    app.signup("integration-test").await;
+    let project = app.get_test_public_project();
+    app.create_project(&project.info).await;
+    app.create_curl_group(1, &app.get_test_curl_group()).await;
+}
+
+async fn get_page(app: &TestApplication, query: &[(&str, &str)]) -> AuditPage {
+    let response = app.get_audit_events(1, query).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    response.json().await.expect("Failed to serialise response")
+}
+
+fn actions(events: &[AuditEvent]) -> Vec<&str> {
+    events.iter().map(|event| event.action.as_str()).collect()
+}
+
+#[cfg(test)]
+mod get_audit_events {
+    use super::*;
+
+    #[tokio::test]
+    async fn changes_are_recorded_newest_first() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app).await;
+
+        let mut curl_group = app.get_test_curl_group();
+        curl_group.name = "Renamed".to_string();
+        app.update_curl_group(&curl_group).await;
+
+        let page = get_page(&app, &[]).await;
+        assert_eq!(
+            actions(&page.events),
+            vec!["curl_group.update", "curl_group.create", "project.create"]
+        );
+        assert_eq!(page.next, None);
+
+        let update = &page.events[0];
+        assert_eq!(update.actor, "integration-test");
+        assert_eq!(update.target, "curl_group:1");
+        assert_eq!(
+            update.before.as_ref().unwrap()["name"],
+            json!("CurlGroup 1")
+        );
+        assert_eq!(update.after.as_ref().unwrap()["name"], json!("Renamed"));
+        assert_eq!(page.events[1].before, None);
+
+        // every event carries the id of the request that caused it
+        assert!(!update.request_id.is_empty());
+        assert_ne!(update.request_id, page.events[1].request_id);
+    }
+
+    #[tokio::test]
+    async fn entry_changes_are_recorded_without_the_command() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app).await;
+
+        let entry = CurlEntryRequest {
+            name: "Authorised".to_string(),
+            description: String::new(),
+            raw_query: "curl -H 'Authorization: Basic dXNlcjpwYXNz' a.com".to_string(),
+        };
+        let response = app.create_curl_entry(1, &entry).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let page = get_page(&app, &[]).await;
+        let after = page.events[0].after.as_ref().unwrap();
+        assert_eq!(after["name"], json!("Authorised"));
+        assert_eq!(after["raw_query_length"], json!(entry.raw_query.len()));
+        assert!(!after.to_string().contains("dXNlcjpwYXNz"));
+    }
+
+    #[tokio::test]
+    async fn membership_changes_are_recorded_apart_from_settings() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app).await;
+        let mut project = app.get_test_public_project();
+        app.add_other_user_as_collaborator_and_login(&mut project)
+            .await;
+        app.logout().await;
+        app.login("integration-test", common::TEST_PASSWORD).await;
+
+        let page = get_page(&app, &[("action", "project.members.update")]).await;
+        assert_eq!(page.events.len(), 1);
+        assert_eq!(
+            page.events[0].before.as_ref().unwrap()["collaborators"],
+            json!([])
+        );
+        assert_eq!(
+            page.events[0].after.as_ref().unwrap()["collaborators"],
+            json!(["integration-test-other-user"])
+        );
+
+        // the project itself did not change
+        let page = get_page(&app, &[("action", "project.update")]).await;
+        assert!(page.events.is_empty());
+    }
+
+    #[tokio::test]
+    async fn events_can_be_filtered_by_actor() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app).await;
+        let mut project = app.get_test_public_project();
+        app.add_other_user_as_collaborator_and_login(&mut project)
+            .await;
+        app.create_curl_group(1, &app.get_test_curl_group()).await;
+        app.logout().await;
+        app.login("integration-test", common::TEST_PASSWORD).await;
+
+        let page = get_page(&app, &[("actor", "integration-test-other-user")]).await;
+        assert_eq!(actions(&page.events), vec!["curl_group.create"]);
+        assert_eq!(page.events[0].target, "curl_group:2");
+    }
+
+    #[tokio::test]
+    async fn events_are_paged() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app).await;
+        let mut curl_group = app.get_test_curl_group();
+        for name in ["First", "Second", "Third"] {
+            curl_group.name = name.to_string();
+            app.update_curl_group(&curl_group).await;
+        }
+
+        let first = get_page(&app, &[("limit", "2")]).await;
+        assert_eq!(first.events.len(), 2);
+        assert_eq!(
+            first.events[0].after.as_ref().unwrap()["name"],
+            json!("Third")
+        );
+
+        let before = first
+            .next
+            .expect("There should be another page")
+            .to_string();
+        let second = get_page(&app, &[("limit", "2"), ("before", &before)]).await;
+        assert_eq!(
+            second.events[0].after.as_ref().unwrap()["name"],
+            json!("First")
+        );
+
+        let before = second
+            .next
+            .expect("There should be a last page")
+            .to_string();
+        let last = get_page(&app, &[("limit", "2"), ("before", &before)]).await;
+        assert_eq!(actions(&last.events), vec!["project.create"]);
+        assert_eq!(last.next, None);
+    }
+
+    #[tokio::test]
+    async fn deleting_a_user_is_recorded_in_their_projects() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app).await;
+        let mut project = app.get_test_public_project();
+        app.add_other_user_as_collaborator_and_login(&mut project)
+            .await;
+        app.delete_user().await;
+        app.login("integration-test", common::TEST_PASSWORD).await;
+
+        let page = get_page(&app, &[("action", "user.delete")]).await;
+        assert_eq!(page.events.len(), 1);
+        assert_eq!(page.events[0].actor, "integration-test-other-user");
+        assert_eq!(page.events[0].target, "user:integration-test-other-user");
+    }
+
+    #[tokio::test]
+    async fn only_admins_can_read_the_audit_log() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app).await;
+        let mut project = app.get_test_public_project();
+        app.add_other_user_as_collaborator_and_login(&mut project)
+            .await;
+
+        let response = app.get_audit_events(1, &[]).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+}
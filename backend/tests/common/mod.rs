@@ -1,14 +1,47 @@
+#![allow(dead_code)]
+
+use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
 use backend::{
-    application::{Application, ApplicationPoolSettings},
-    models::{CurlGroup, Project, ProjectInfo},
+    application::{Application, ApplicationPoolSettings, AuthSettings},
+    ldap::{
+        protocol::{
+            read_message, write_message, LdapMessage, LdapResult, ProtocolOp, INVALID_CREDENTIALS,
+            SUCCESS,
+        },
+        LdapConfig,
+    },
+    models::{Curl, CurlGroup, Project, ProjectInfo},
     observability::{get_subscriber, init_subscriber},
+    oidc::OidcConfig,
+    routes::{
+        entry::types::{CurlEntryPosition, CurlEntryRequest},
+        environment::types::EnvironmentRequest,
+        import::types::{HarFile, HarImportRequest, ImportRequest, OpenApiImportRequest},
+        member::types::{Member, MemberRoleRequest},
+        project::types::{Id, Role},
+        runner::types::{RunRequest, RunnerSettings},
+        token::types::{ApiTokenRequest, TokenScope},
+    },
 };
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use once_cell::sync::Lazy;
-use reqwest::{
-    cookie::{Cookie, Jar},
-    Client, Url,
+use reqwest::{header, Client, Url};
+use secrecy::Secret;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, HashMap},
+    net::TcpListener,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
+/// Good enough for the default password policy.
+pub const TEST_PASSWORD: &str = "Integration-Test-1";
+
 static LOGS: Lazy<()> = Lazy::new(|| {
     let subscriber_name = "integration";
     let default_filter_level = "info";
@@ -38,15 +71,47 @@ impl TestApplication {
         self.signup("integration-test-other-user").await;
 
         self.logout().await;
-        self.login("integration-test", "test").await;
+        self.login("integration-test", TEST_PASSWORD).await;
 
         project
             .collaborators
             .push("integration-test-other-user".to_string());
-        self.update_project(&project).await;
+        self.update_project(project).await;
 
         self.logout().await;
-        self.login("integration-test-other-user", "test").await;
+        self.login("integration-test-other-user", TEST_PASSWORD)
+            .await;
+    }
+
+    /// The same app, called with an API token instead of the session cookie.
+    pub fn with_token(&self, token: &str) -> TestApplication {
+        let mut headers = header::HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+        let client = Client::builder()
+            .default_headers(headers)
+            .build()
+            .expect("Failed to construct reqwest client");
+
+        TestApplication {
+            client,
+            url: self.url.clone(),
+        }
+    }
+
+    /// The same app, called from another device with a session of its own.
+    pub fn with_new_session(&self) -> TestApplication {
+        let client = Client::builder()
+            .cookie_store(true)
+            .build()
+            .expect("Failed to construct reqwest client");
+
+        TestApplication {
+            client,
+            url: self.url.clone(),
+        }
     }
 
     pub async fn health_check(&self) -> reqwest::Response {
@@ -58,10 +123,24 @@ impl TestApplication {
             .expect("Failed to send health check request")
     }
 
+    pub fn get_test_curls(&self, raw_queries: &[&str]) -> String {
+        let curls: Vec<Curl> = raw_queries
+            .iter()
+            .enumerate()
+            .map(|(index, raw_query)| Curl {
+                id: index as i64 + 1,
+                name: format!("cURL #{}", index + 1),
+                description: "A test curl".to_string(),
+                raw_query: raw_query.to_string(),
+            })
+            .collect();
+        serde_json::to_string(&curls).expect("Failed to serialise curls")
+    }
+
     pub fn get_test_curl_group(&self) -> CurlGroup {
         CurlGroup {
             id: 1,
-            curls: "some-curl".to_string(),
+            curls: self.get_test_curls(&["curl https://example.com/some-curl"]),
             description: "This is a test curl group".to_string(),
             labels: "test1,test2".to_string(),
             name: "CurlGroup 1".to_string(),
@@ -79,6 +158,8 @@ impl TestApplication {
                 environments: "localhost:8080,https://coolurl.com".to_string(),
                 description: "A test public project".to_string(),
                 visibility: "Public".to_string(),
+                redaction_policy: Some("Redact".to_string()),
+                collaborators_can_delete_groups: Some(false),
             },
             groups: vec![],
         }
@@ -94,6 +175,8 @@ impl TestApplication {
                 environments: "localhost:1800,https://coolurl.co.uk".to_string(),
                 description: "A test private project".to_string(),
                 visibility: "Private".to_string(),
+                redaction_policy: Some("Redact".to_string()),
+                collaborators_can_delete_groups: Some(false),
             },
             groups: vec![],
         }
@@ -146,6 +229,16 @@ impl TestApplication {
             .expect("Failed to send get project request")
     }
 
+    pub async fn get_projects_with_query(&self, search: &str, labels: &str) -> reqwest::Response {
+        let url = self.generate_url("project".to_string());
+        self.client
+            .get(url)
+            .query(&[("search", search), ("labels", labels)])
+            .send()
+            .await
+            .expect("Failed to send get project request")
+    }
+
     pub async fn update_project(&self, project: &Project) -> reqwest::Response {
         let url = self.generate_url(format!("project/{}", project.info.id));
 
@@ -203,7 +296,614 @@ impl TestApplication {
             .expect("Failed to send update curl group request")
     }
 
+    pub async fn delete_curl_group(&self, id: i64) -> reqwest::Response {
+        let url = self.generate_url(format!("group/{}", id));
+
+        self.client
+            .delete(url)
+            .send()
+            .await
+            .expect("Failed to send delete curl group request")
+    }
+
+    pub async fn get_trashed_curl_groups(&self, project_id: i64) -> reqwest::Response {
+        let url = self.generate_url(format!("project/{}/trash", project_id));
+
+        self.client
+            .get(url)
+            .send()
+            .await
+            .expect("Failed to send get trashed curl groups request")
+    }
+
+    pub async fn restore_trashed_curl_group(
+        &self,
+        project_id: i64,
+        group_id: i64,
+    ) -> reqwest::Response {
+        let url = self.generate_url(format!("project/{}/trash/{}/restore", project_id, group_id));
+
+        self.client
+            .post(url)
+            .send()
+            .await
+            .expect("Failed to send restore trashed curl group request")
+    }
+
+    pub async fn purge_trashed_curl_group(
+        &self,
+        project_id: i64,
+        group_id: i64,
+    ) -> reqwest::Response {
+        let url = self.generate_url(format!("project/{}/trash/{}", project_id, group_id));
+
+        self.client
+            .delete(url)
+            .send()
+            .await
+            .expect("Failed to send purge trashed curl group request")
+    }
+
+    // curl
+    pub async fn parse_curl(&self, command: &str) -> reqwest::Response {
+        let url = self.generate_url("curl/parse".to_string());
+        let data = serde_json::json!({ "command": command });
+
+        self.client
+            .post(url)
+            .json(&data)
+            .send()
+            .await
+            .expect("Failed to send parse curl request")
+    }
+
+    // entry
+    pub async fn get_curl_entries(&self, group_id: i64) -> reqwest::Response {
+        let url = self.generate_url(format!("group/{}/entry", group_id));
+
+        self.client
+            .get(url)
+            .send()
+            .await
+            .expect("Failed to send get curl entries request")
+    }
+
+    pub async fn create_curl_entry(
+        &self,
+        group_id: i64,
+        entry: &CurlEntryRequest,
+    ) -> reqwest::Response {
+        let url = self.generate_url(format!("group/{}/entry", group_id));
+
+        self.client
+            .post(url)
+            .json(entry)
+            .send()
+            .await
+            .expect("Failed to send create curl entry request")
+    }
+
+    pub async fn get_curl_entry(&self, group_id: i64, entry_id: i64) -> reqwest::Response {
+        let url = self.generate_url(format!("group/{}/entry/{}", group_id, entry_id));
+
+        self.client
+            .get(url)
+            .send()
+            .await
+            .expect("Failed to send get curl entry request")
+    }
+
+    pub async fn get_curl_entry_snippet(
+        &self,
+        group_id: i64,
+        entry_id: i64,
+        lang: &str,
+    ) -> reqwest::Response {
+        let url = self.generate_url(format!(
+            "group/{}/entry/{}/snippet?lang={}",
+            group_id, entry_id, lang
+        ));
+
+        self.client
+            .get(url)
+            .send()
+            .await
+            .expect("Failed to send get curl entry snippet request")
+    }
+
+    pub async fn update_curl_entry(
+        &self,
+        group_id: i64,
+        entry_id: i64,
+        entry: &CurlEntryRequest,
+    ) -> reqwest::Response {
+        let url = self.generate_url(format!("group/{}/entry/{}", group_id, entry_id));
+
+        self.client
+            .post(url)
+            .json(entry)
+            .send()
+            .await
+            .expect("Failed to send update curl entry request")
+    }
+
+    pub async fn move_curl_entry(
+        &self,
+        group_id: i64,
+        entry_id: i64,
+        position: i64,
+    ) -> reqwest::Response {
+        let url = self.generate_url(format!("group/{}/entry/{}/move", group_id, entry_id));
+
+        self.client
+            .post(url)
+            .json(&CurlEntryPosition { position })
+            .send()
+            .await
+            .expect("Failed to send move curl entry request")
+    }
+
+    pub async fn delete_curl_entry(&self, group_id: i64, entry_id: i64) -> reqwest::Response {
+        let url = self.generate_url(format!("group/{}/entry/{}", group_id, entry_id));
+
+        self.client
+            .delete(url)
+            .send()
+            .await
+            .expect("Failed to send delete curl entry request")
+    }
+
+    // environment
+    pub fn get_test_environment(
+        &self,
+        name: &str,
+        variables: &[(&str, &str)],
+    ) -> EnvironmentRequest {
+        EnvironmentRequest {
+            name: name.to_string(),
+            variables: variables
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            secrets: BTreeMap::new(),
+        }
+    }
+
+    pub async fn get_environments(&self, project_id: i64) -> reqwest::Response {
+        let url = self.generate_url(format!("project/{}/environment", project_id));
+
+        self.client
+            .get(url)
+            .send()
+            .await
+            .expect("Failed to send get environments request")
+    }
+
+    pub async fn create_environment(
+        &self,
+        project_id: i64,
+        environment: &EnvironmentRequest,
+    ) -> reqwest::Response {
+        let url = self.generate_url(format!("project/{}/environment", project_id));
+
+        self.client
+            .post(url)
+            .json(environment)
+            .send()
+            .await
+            .expect("Failed to send create environment request")
+    }
+
+    pub async fn update_environment(
+        &self,
+        project_id: i64,
+        environment_id: i64,
+        environment: &EnvironmentRequest,
+    ) -> reqwest::Response {
+        let url = self.generate_url(format!(
+            "project/{}/environment/{}",
+            project_id, environment_id
+        ));
+
+        self.client
+            .post(url)
+            .json(environment)
+            .send()
+            .await
+            .expect("Failed to send update environment request")
+    }
+
+    pub async fn delete_environment(
+        &self,
+        project_id: i64,
+        environment_id: i64,
+    ) -> reqwest::Response {
+        let url = self.generate_url(format!(
+            "project/{}/environment/{}",
+            project_id, environment_id
+        ));
+
+        self.client
+            .delete(url)
+            .send()
+            .await
+            .expect("Failed to send delete environment request")
+    }
+
+    pub async fn render_curl_group(
+        &self,
+        group_id: i64,
+        environment: Option<&str>,
+    ) -> reqwest::Response {
+        let url = self.generate_url(format!("group/{}/render", group_id));
+        let query: Vec<(&str, &str)> = environment
+            .map(|environment| vec![("environment", environment)])
+            .unwrap_or_default();
+
+        self.client
+            .get(url)
+            .query(&query)
+            .send()
+            .await
+            .expect("Failed to send render curl group request")
+    }
+
+    pub async fn render_curl_group_with_secrets(
+        &self,
+        group_id: i64,
+        environment: &str,
+    ) -> reqwest::Response {
+        let url = self.generate_url(format!("group/{}/render", group_id));
+        let query = [("environment", environment), ("include_secrets", "true")];
+
+        self.client
+            .get(url)
+            .query(&query)
+            .send()
+            .await
+            .expect("Failed to send render curl group request")
+    }
+
+    // export
+    pub async fn export_curl_group(&self, group_id: i64, format: &str) -> reqwest::Response {
+        let url = self.generate_url(format!("group/{}/export", group_id));
+
+        self.client
+            .get(url)
+            .query(&[("format", format)])
+            .send()
+            .await
+            .expect("Failed to send export curl group request")
+    }
+
+    pub async fn export_curl_group_as_har(
+        &self,
+        group_id: i64,
+        environment: Option<&str>,
+    ) -> reqwest::Response {
+        let url = self.generate_url(format!("group/{}/export", group_id));
+        let mut query = vec![("format", "har")];
+        if let Some(environment) = environment {
+            query.push(("environment", environment));
+        }
+
+        self.client
+            .get(url)
+            .query(&query)
+            .send()
+            .await
+            .expect("Failed to send export curl group request")
+    }
+
+    // import
+    pub async fn import_collection(
+        &self,
+        project_id: i64,
+        content: &serde_json::Value,
+        environments: &[serde_json::Value],
+    ) -> reqwest::Response {
+        let url = self.generate_url(format!("project/{}/import", project_id));
+
+        self.client
+            .post(url)
+            .json(&ImportRequest {
+                content: content.clone(),
+                environments: environments.to_vec(),
+            })
+            .send()
+            .await
+            .expect("Failed to send import collection request")
+    }
+
+    pub async fn get_har_entries(
+        &self,
+        project_id: i64,
+        har: &serde_json::Value,
+    ) -> reqwest::Response {
+        let url = self.generate_url(format!("project/{}/import/har/entries", project_id));
+
+        self.client
+            .post(url)
+            .json(&HarFile { har: har.clone() })
+            .send()
+            .await
+            .expect("Failed to send get HAR entries request")
+    }
+
+    pub async fn import_har(
+        &self,
+        project_id: i64,
+        request: &HarImportRequest,
+    ) -> reqwest::Response {
+        let url = self.generate_url(format!("project/{}/import/har", project_id));
+
+        self.client
+            .post(url)
+            .json(request)
+            .send()
+            .await
+            .expect("Failed to send import HAR request")
+    }
+
+    pub async fn import_openapi(&self, project_id: i64, spec: &str) -> reqwest::Response {
+        let url = self.generate_url(format!("project/{}/import/openapi", project_id));
+
+        self.client
+            .post(url)
+            .json(&OpenApiImportRequest {
+                spec: spec.to_string(),
+            })
+            .send()
+            .await
+            .expect("Failed to send import OpenAPI request")
+    }
+
+    // audit
+    pub async fn get_audit_events(
+        &self,
+        project_id: i64,
+        query: &[(&str, &str)],
+    ) -> reqwest::Response {
+        let url = self.generate_url(format!("project/{}/audit", project_id));
+
+        self.client
+            .get(url)
+            .query(query)
+            .send()
+            .await
+            .expect("Failed to send get audit events request")
+    }
+
+    // member
+    pub async fn get_roles(&self) -> reqwest::Response {
+        let url = self.generate_url("roles".to_string());
+
+        self.client
+            .get(url)
+            .send()
+            .await
+            .expect("Failed to send get roles request")
+    }
+
+    pub async fn get_project_members(&self, project_id: i64) -> reqwest::Response {
+        let url = self.generate_url(format!("project/{}/members", project_id));
+
+        self.client
+            .get(url)
+            .send()
+            .await
+            .expect("Failed to send get project members request")
+    }
+
+    pub async fn get_project_member(&self, project_id: i64, username: &str) -> reqwest::Response {
+        let url = self.generate_url(format!("project/{}/members/{}", project_id, username));
+
+        self.client
+            .get(url)
+            .send()
+            .await
+            .expect("Failed to send get project member request")
+    }
+
+    pub async fn set_project_member_role(
+        &self,
+        project_id: i64,
+        username: &str,
+        role: Role,
+    ) -> reqwest::Response {
+        let url = self.generate_url(format!("project/{}/members/{}", project_id, username));
+
+        self.client
+            .put(url)
+            .json(&MemberRoleRequest { role })
+            .send()
+            .await
+            .expect("Failed to send set project member role request")
+    }
+
+    pub async fn remove_project_member(
+        &self,
+        project_id: i64,
+        username: &str,
+    ) -> reqwest::Response {
+        let url = self.generate_url(format!("project/{}/members/{}", project_id, username));
+
+        self.client
+            .delete(url)
+            .send()
+            .await
+            .expect("Failed to send remove project member request")
+    }
+
+    // revision
+    pub async fn get_curl_group_revisions(&self, group_id: i64) -> reqwest::Response {
+        let url = self.generate_url(format!("group/{}/revision", group_id));
+
+        self.client
+            .get(url)
+            .send()
+            .await
+            .expect("Failed to send get curl group revisions request")
+    }
+
+    pub async fn get_curl_group_revision(&self, group_id: i64, number: i64) -> reqwest::Response {
+        let url = self.generate_url(format!("group/{}/revision/{}", group_id, number));
+
+        self.client
+            .get(url)
+            .send()
+            .await
+            .expect("Failed to send get curl group revision request")
+    }
+
+    pub async fn get_curl_group_revision_diff(
+        &self,
+        group_id: i64,
+        from: i64,
+        to: i64,
+    ) -> reqwest::Response {
+        let url = self.generate_url(format!(
+            "group/{}/revision/diff?from={}&to={}",
+            group_id, from, to
+        ));
+
+        self.client
+            .get(url)
+            .send()
+            .await
+            .expect("Failed to send get curl group revision diff request")
+    }
+
+    pub async fn restore_curl_group_revision(
+        &self,
+        group_id: i64,
+        number: i64,
+    ) -> reqwest::Response {
+        let url = self.generate_url(format!("group/{}/revision/{}/restore", group_id, number));
+
+        self.client
+            .post(url)
+            .send()
+            .await
+            .expect("Failed to send restore curl group revision request")
+    }
+
+    // runner
+    pub async fn get_runner_settings(&self, project_id: i64) -> reqwest::Response {
+        let url = self.generate_url(format!("project/{}/runner", project_id));
+
+        self.client
+            .get(url)
+            .send()
+            .await
+            .expect("Failed to send get runner settings request")
+    }
+
+    pub async fn update_runner_settings(
+        &self,
+        project_id: i64,
+        settings: &RunnerSettings,
+    ) -> reqwest::Response {
+        let url = self.generate_url(format!("project/{}/runner", project_id));
+
+        self.client
+            .post(url)
+            .json(settings)
+            .send()
+            .await
+            .expect("Failed to send update runner settings request")
+    }
+
+    pub async fn run_curl_entry(
+        &self,
+        group_id: i64,
+        entry_id: i64,
+        environment: Option<&str>,
+    ) -> reqwest::Response {
+        let url = self.generate_url(format!("group/{}/entry/{}/run", group_id, entry_id));
+        let run_request = RunRequest {
+            environment: environment.map(str::to_string),
+        };
+
+        self.client
+            .post(url)
+            .json(&run_request)
+            .send()
+            .await
+            .expect("Failed to send run curl entry request")
+    }
+
+    // search
+    pub async fn search(&self, query: &str) -> reqwest::Response {
+        let url = self.generate_url("search".to_string());
+
+        self.client
+            .get(url)
+            .query(&[("query", query)])
+            .send()
+            .await
+            .expect("Failed to send search request")
+    }
+
+    // token
+    pub async fn create_api_token(
+        &self,
+        name: &str,
+        scope: TokenScope,
+        project_id: Option<i64>,
+    ) -> reqwest::Response {
+        let url = self.generate_url("tokens".to_string());
+
+        self.client
+            .post(url)
+            .json(&ApiTokenRequest {
+                name: name.to_string(),
+                scope,
+                project_id,
+                expires_in_days: Some(30),
+            })
+            .send()
+            .await
+            .expect("Failed to send create API token request")
+    }
+
+    pub async fn get_api_tokens(&self) -> reqwest::Response {
+        let url = self.generate_url("tokens".to_string());
+
+        self.client
+            .get(url)
+            .send()
+            .await
+            .expect("Failed to send get API tokens request")
+    }
+
+    pub async fn revoke_api_token(&self, token_id: i64) -> reqwest::Response {
+        let url = self.generate_url(format!("tokens/{}", token_id));
+
+        self.client
+            .delete(url)
+            .send()
+            .await
+            .expect("Failed to send revoke API token request")
+    }
+
     // user
+    pub async fn change_password(
+        &self,
+        old_password: &str,
+        new_password: &str,
+    ) -> reqwest::Response {
+        let url = self.generate_url("change-password".to_string());
+        let data = serde_json::json!({"old_password": old_password, "new_password": new_password});
+
+        self.client
+            .post(url)
+            .json(&data)
+            .send()
+            .await
+            .expect("Failed to send change password request")
+    }
+
     pub async fn delete_user(&self) -> reqwest::Response {
         let url = self.generate_url("delete-user".to_string());
         self.client
@@ -236,8 +936,12 @@ impl TestApplication {
     }
 
     pub async fn signup(&self, username: &'static str) -> reqwest::Response {
+        self.signup_with_password(username, TEST_PASSWORD).await
+    }
+
+    pub async fn signup_with_password(&self, username: &str, password: &str) -> reqwest::Response {
         let url = self.generate_url("sign-up".to_string());
-        let data = serde_json::json!({"username": username.to_string(), "password": "test"});
+        let data = serde_json::json!({"username": username, "password": password});
 
         self.client
             .post(url)
@@ -247,6 +951,37 @@ impl TestApplication {
             .expect("Failed to send sign up request")
     }
 
+    pub async fn oidc_login(&self, link: bool) -> reqwest::Response {
+        let url = self.generate_url("oidc/login".to_string());
+
+        self.client
+            .get(url)
+            .query(&[("link", link)])
+            .send()
+            .await
+            .expect("Failed to send single sign-on request")
+    }
+
+    pub async fn oidc_callback(&self, query: &[(&str, &str)]) -> reqwest::Response {
+        let url = self.generate_url("oidc/callback".to_string());
+
+        self.client
+            .get(url)
+            .query(query)
+            .send()
+            .await
+            .expect("Failed to send single sign-on callback request")
+    }
+
+    /// Named after the project created to find out, which has to be new each time.
+    pub async fn signed_in_username(&self, project_name: &str) -> String {
+        let mut info: ProjectInfo = self.get_test_public_project().info;
+        info.name = project_name.to_string();
+        let id: Id = self.create_project(&info).await.json().await.unwrap();
+        let members: Vec<Member> = self.get_project_members(id.id).await.json().await.unwrap();
+        members[0].username.clone()
+    }
+
     pub async fn user_status(&self) -> reqwest::Response {
         let url = self.generate_url("user-status".to_string());
 
@@ -259,9 +994,29 @@ impl TestApplication {
 }
 
 pub async fn spawn_test_app() -> TestApplication {
+    spawn_test_app_with_auth(AuthSettings::default()).await
+}
+
+pub async fn spawn_test_app_with_oidc(oidc: Option<OidcConfig>) -> TestApplication {
+    spawn_test_app_with_auth(AuthSettings {
+        oidc,
+        ..AuthSettings::default()
+    })
+    .await
+}
+
+pub async fn spawn_test_app_with_ldap(ldap: LdapConfig) -> TestApplication {
+    spawn_test_app_with_auth(AuthSettings {
+        ldap: Some(ldap),
+        ..AuthSettings::default()
+    })
+    .await
+}
+
+pub async fn spawn_test_app_with_auth(auth: AuthSettings) -> TestApplication {
     Lazy::force(&LOGS);
 
-    let application = Application::build(0, ApplicationPoolSettings::Test)
+    let application = Application::build_with_auth(0, ApplicationPoolSettings::Test, auth)
         .await
         .expect("Failed to build test application");
 
@@ -271,7 +1026,345 @@ pub async fn spawn_test_app() -> TestApplication {
         .expect("Failed to construct reqwest client");
     let url = format!("http://localhost:{}", application.port());
 
-    let _ = tokio::spawn(application.run_until_stopped());
+    tokio::spawn(application.run_until_stopped());
 
     TestApplication { client, url }
 }
+
+/// A local HTTP server for the runner to call. Returns its `host:port`.
+pub async fn spawn_mock_server() -> String {
+    async fn echo(request: HttpRequest, body: String) -> HttpResponse {
+        let header = |name: &str| {
+            request
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default()
+                .to_string()
+        };
+        HttpResponse::Ok().json(serde_json::json!({
+            "method": request.method().as_str(),
+            "body": body,
+            "content_type": header("content-type"),
+            "authorization": header("authorization"),
+        }))
+    }
+
+    async fn redirect(query: web::Query<BTreeMap<String, String>>) -> HttpResponse {
+        HttpResponse::Found()
+            .insert_header(("location", query["to"].as_str()))
+            .finish()
+    }
+
+    async fn slow() -> HttpResponse {
+        actix_web::rt::time::sleep(Duration::from_secs(5)).await;
+        HttpResponse::Ok().finish()
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind mock server");
+    let address = listener.local_addr().unwrap().to_string();
+    let server = HttpServer::new(|| {
+        App::new()
+            .route(
+                "/hello",
+                web::get().to(|| async { HttpResponse::Ok().body("hello") }),
+            )
+            .route("/echo", web::to(echo))
+            .route("/redirect", web::get().to(redirect))
+            .route("/slow", web::get().to(slow))
+            .route(
+                "/large",
+                web::get().to(|| async { HttpResponse::Ok().body("x".repeat(4096)) }),
+            )
+    })
+    .workers(1)
+    .listen(listener)
+    .expect("Failed to listen on mock server")
+    .run();
+
+    tokio::spawn(server);
+    address
+}
+
+pub const MOCK_OIDC_CLIENT_ID: &str = "curl-share";
+pub const MOCK_OIDC_CLIENT_SECRET: &str = "test-secret";
+
+/// A local OpenID Connect provider that signs everyone in straight away, as whoever `claims`
+/// describes at the time.
+pub struct MockOidcProvider {
+    pub issuer: String,
+    pub claims: Arc<Mutex<Value>>,
+}
+
+impl MockOidcProvider {
+    /// Sends users to the user status once they are signed in, so where they end up tells
+    /// whether it worked.
+    pub fn config(&self) -> OidcConfig {
+        let mut config = OidcConfig::new(
+            &self.issuer,
+            MOCK_OIDC_CLIENT_ID,
+            Secret::new(MOCK_OIDC_CLIENT_SECRET.to_string()),
+        );
+        config.post_login_redirect = "/api/v1/user-status".to_string();
+        config
+    }
+}
+
+struct MockOidcState {
+    issuer: String,
+    claims: Arc<Mutex<Value>>,
+    /// What each code handed out was issued for: its nonce, PKCE challenge and redirect URL.
+    codes: Mutex<HashMap<String, (String, String, String)>>,
+}
+
+pub async fn spawn_mock_oidc_provider(claims: Value) -> MockOidcProvider {
+    async fn discovery(state: web::Data<MockOidcState>) -> HttpResponse {
+        HttpResponse::Ok().json(json!({
+            "issuer": state.issuer,
+            "authorization_endpoint": format!("{}/authorize", state.issuer),
+            "token_endpoint": format!("{}/token", state.issuer),
+        }))
+    }
+
+    async fn authorize(
+        query: web::Query<HashMap<String, String>>,
+        state: web::Data<MockOidcState>,
+    ) -> HttpResponse {
+        if query.get("response_type").map(String::as_str) != Some("code")
+            || query.get("client_id").map(String::as_str) != Some(MOCK_OIDC_CLIENT_ID)
+            || query.get("code_challenge_method").map(String::as_str) != Some("S256")
+        {
+            return HttpResponse::BadRequest().finish();
+        }
+
+        let mut codes = state.codes.lock().unwrap();
+        let code = format!("code-{}", codes.len());
+        codes.insert(
+            code.clone(),
+            (
+                query["nonce"].clone(),
+                query["code_challenge"].clone(),
+                query["redirect_uri"].clone(),
+            ),
+        );
+        let location = Url::parse_with_params(
+            &query["redirect_uri"],
+            &[("code", code.as_str()), ("state", query["state"].as_str())],
+        )
+        .unwrap();
+        HttpResponse::Found()
+            .insert_header(("location", location.to_string()))
+            .finish()
+    }
+
+    async fn token(
+        form: web::Form<HashMap<String, String>>,
+        state: web::Data<MockOidcState>,
+    ) -> HttpResponse {
+        if form.get("client_secret").map(String::as_str) != Some(MOCK_OIDC_CLIENT_SECRET) {
+            return HttpResponse::Unauthorized().finish();
+        }
+        let Some((nonce, challenge, redirect_uri)) =
+            state.codes.lock().unwrap().remove(&form["code"])
+        else {
+            return HttpResponse::BadRequest().finish();
+        };
+        let verifier_challenge =
+            URL_SAFE_NO_PAD.encode(Sha256::digest(form["code_verifier"].as_bytes()));
+        if verifier_challenge != challenge || form["redirect_uri"] != redirect_uri {
+            return HttpResponse::BadRequest().finish();
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let mut claims = state.claims.lock().unwrap().clone();
+        claims["iss"] = json!(state.issuer);
+        claims["aud"] = json!(MOCK_OIDC_CLIENT_ID);
+        claims["iat"] = json!(now);
+        claims["exp"] = json!(now + 300);
+        claims["nonce"] = json!(nonce);
+        let id_token = format!(
+            "{}.{}.signature",
+            URL_SAFE_NO_PAD.encode(r#"{"alg":"RS256"}"#),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+        HttpResponse::Ok().json(json!({
+            "access_token": "access-token",
+            "token_type": "Bearer",
+            "id_token": id_token,
+        }))
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind mock OIDC provider");
+    let issuer = format!("http://{}", listener.local_addr().unwrap());
+    let claims = Arc::new(Mutex::new(claims));
+    let state = web::Data::new(MockOidcState {
+        issuer: issuer.clone(),
+        claims: claims.clone(),
+        codes: Mutex::new(HashMap::new()),
+    });
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(state.clone())
+            .route(
+                "/.well-known/openid-configuration",
+                web::get().to(discovery),
+            )
+            .route("/authorize", web::get().to(authorize))
+            .route("/token", web::post().to(token))
+    })
+    .workers(1)
+    .listen(listener)
+    .expect("Failed to listen on mock OIDC provider")
+    .run();
+
+    tokio::spawn(server);
+    MockOidcProvider { issuer, claims }
+}
+
+pub const MOCK_LDAP_BASE_DN: &str = "dc=example,dc=com";
+pub const MOCK_LDAP_BIND_DN: &str = "cn=curl-share,dc=example,dc=com";
+pub const MOCK_LDAP_BIND_PASSWORD: &str = "service-secret";
+pub const MOCK_LDAP_ADMIN_GROUP: &str = "cn=admins,ou=groups,dc=example,dc=com";
+pub const MOCK_LDAP_USER_GROUP: &str = "cn=developers,ou=groups,dc=example,dc=com";
+
+struct MockLdapUser {
+    dn: String,
+    password: String,
+    groups: Vec<String>,
+}
+
+/// A local stand-in for a directory, whose users are looked up by `uid` and list their groups in
+/// `memberOf`. Searching takes the service account.
+pub struct MockLdapServer {
+    pub url: String,
+    users: Arc<Mutex<HashMap<String, MockLdapUser>>>,
+    /// How many times users, rather than the service account, have bound.
+    user_binds: Arc<AtomicUsize>,
+}
+
+impl MockLdapServer {
+    pub fn config(&self) -> LdapConfig {
+        let mut config = LdapConfig::new(&self.url, MOCK_LDAP_BASE_DN);
+        config.bind_dn = Some(MOCK_LDAP_BIND_DN.to_string());
+        config.bind_password = Secret::new(MOCK_LDAP_BIND_PASSWORD.to_string());
+        config.admin_groups = vec![MOCK_LDAP_ADMIN_GROUP.to_string()];
+        config
+    }
+
+    /// Adds the user, or replaces them when they are already there.
+    pub fn add_user(&self, uid: &str, password: &str, groups: &[&str]) {
+        self.users.lock().unwrap().insert(
+            uid.to_string(),
+            MockLdapUser {
+                dn: format!("uid={},ou=people,{}", uid, MOCK_LDAP_BASE_DN),
+                password: password.to_string(),
+                groups: groups.iter().map(|group| group.to_string()).collect(),
+            },
+        );
+    }
+
+    pub fn user_binds(&self) -> usize {
+        self.user_binds.load(Ordering::SeqCst)
+    }
+}
+
+pub async fn spawn_mock_ldap_server() -> MockLdapServer {
+    fn respond(
+        op: ProtocolOp,
+        bound_dn: &mut String,
+        users: &Mutex<HashMap<String, MockLdapUser>>,
+        user_binds: &AtomicUsize,
+    ) -> Vec<ProtocolOp> {
+        let users = users.lock().unwrap();
+        match op {
+            ProtocolOp::BindRequest { name, password } => {
+                let user = users
+                    .values()
+                    .find(|user| user.dn == name && user.password == password);
+                if user.is_some() {
+                    user_binds.fetch_add(1, Ordering::SeqCst);
+                }
+                if user.is_some()
+                    || (name == MOCK_LDAP_BIND_DN && password == MOCK_LDAP_BIND_PASSWORD)
+                {
+                    *bound_dn = name;
+                    vec![ProtocolOp::BindResponse(LdapResult::new(SUCCESS, ""))]
+                } else {
+                    vec![ProtocolOp::BindResponse(LdapResult::new(
+                        INVALID_CREDENTIALS,
+                        "invalid credentials",
+                    ))]
+                }
+            }
+            ProtocolOp::SearchRequest {
+                base,
+                attribute,
+                value,
+                attributes,
+                ..
+            } => {
+                if bound_dn != MOCK_LDAP_BIND_DN {
+                    let insufficient_access_rights = 50;
+                    return vec![ProtocolOp::SearchResultDone(LdapResult::new(
+                        insufficient_access_rights,
+                        "bind first",
+                    ))];
+                }
+                let mut responses: Vec<ProtocolOp> = users
+                    .iter()
+                    .filter(|(uid, user)| {
+                        attribute == "uid" && **uid == value && user.dn.ends_with(&base)
+                    })
+                    .map(|(_, user)| ProtocolOp::SearchResultEntry {
+                        dn: user.dn.clone(),
+                        attributes: attributes
+                            .iter()
+                            .filter(|attribute| *attribute == "memberOf")
+                            .map(|attribute| (attribute.clone(), user.groups.clone()))
+                            .collect(),
+                    })
+                    .collect();
+                responses.push(ProtocolOp::SearchResultDone(LdapResult::new(SUCCESS, "")));
+                responses
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Failed to bind mock LDAP server");
+    let url = format!("ldap://{}", listener.local_addr().unwrap());
+    let users = Arc::new(Mutex::new(HashMap::new()));
+    let user_binds = Arc::new(AtomicUsize::new(0));
+
+    let (server_users, server_user_binds) = (users.clone(), user_binds.clone());
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let (users, user_binds) = (server_users.clone(), server_user_binds.clone());
+            tokio::spawn(async move {
+                let mut bound_dn = String::new();
+                while let Ok(Some(message)) = read_message(&mut stream).await {
+                    if message.op == ProtocolOp::UnbindRequest {
+                        break;
+                    }
+                    for op in respond(message.op, &mut bound_dn, &users, &user_binds) {
+                        let response = LdapMessage::new(message.id, op);
+                        if write_message(&mut stream, &response).await.is_err() {
+                            return;
+                        }
+                    }
+                }
+            });
+        }
+    });
+
+    MockLdapServer {
+        url,
+        users,
+        user_binds,
+    }
+}
@@ -0,0 +1,35 @@
+mod common;
+use backend::curl::{CurlRequest, NameValue, ParseError};
+use reqwest::StatusCode;
+
+#[cfg(test)]
+mod parse_curl {
+    use super::*;
+
+    #[tokio::test]
+    async fn parsing_valid_command_returns_200_with_breakdown() {
+        let app = common::spawn_test_app().await;
+
+        let response = app
+            .parse_curl("curl -X DELETE 'https://example.com/item?id=4' -H 'X-Trace: 1'")
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let request: CurlRequest = response.json().await.unwrap();
+        assert_eq!(request.method, "DELETE");
+        assert_eq!(request.query, vec![NameValue::new("id", "4")]);
+        assert_eq!(request.header("x-trace"), Some("1"));
+    }
+
+    #[tokio::test]
+    async fn parsing_invalid_command_returns_422_with_position() {
+        let app = common::spawn_test_app().await;
+
+        let response = app.parse_curl("curl 'https://example.com").await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let error: ParseError = response.json().await.unwrap();
+        assert_eq!(error.message, "Unterminated single quote");
+        assert_eq!((error.offset, error.line, error.column), (5, 1, 6));
+    }
+}
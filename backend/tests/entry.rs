@@ -0,0 +1,389 @@
+mod common;
+use crate::common::TestApplication;
+use backend::models::*;
+use backend::redaction::{SecretKind, REDACTED};
+use backend::routes::entry::types::{CurlEntryRequest, Snippet};
+use backend::routes::project::types::{Id, SavedWithRedactions, ValidationErrors};
+use backend::snippet::Language;
+use reqwest::StatusCode;
+
+fn get_test_entry_request(raw_query: &str) -> CurlEntryRequest {
+    CurlEntryRequest {
+        name: "Test entry".to_string(),
+        description: "A test entry".to_string(),
+        raw_query: raw_query.to_string(),
+    }
+}
+
+async fn create_project_with_group(app: &TestApplication, raw_queries: &[&str]) {
+    app.signup("integration-test").await;
+    let project = app.get_test_public_project();
+    app.create_project(&project.info).await;
+
+    let mut curl_group = app.get_test_curl_group();
+    curl_group.curls = app.get_test_curls(raw_queries);
+    app.create_curl_group(1, &curl_group).await;
+}
+
+/// Public projects can be read by anyone, but only their members may change them.
+async fn sign_in_as_non_member(app: &TestApplication) {
+    app.logout().await;
+    app.signup("integration-test-other-user").await;
+}
+
+async fn get_raw_queries(app: &TestApplication, group_id: i64) -> Vec<String> {
+    let entries: Vec<CurlEntry> = app
+        .get_curl_entries(group_id)
+        .await
+        .json()
+        .await
+        .expect("Failed to serialise response");
+    entries.into_iter().map(|entry| entry.raw_query).collect()
+}
+
+#[cfg(test)]
+mod get_curl_entries {
+    use super::*;
+
+    #[tokio::test]
+    async fn entries_are_split_out_of_the_group_in_order() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app, &["curl a.com", "curl b.com"]).await;
+
+        let response = app.get_curl_entries(1).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let entries: Vec<CurlEntry> = response.json().await.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].group_id, 1);
+        assert_eq!(entries[0].position, 0);
+        assert_eq!(entries[0].name, "cURL #1");
+        assert_eq!(entries[1].raw_query, "curl b.com");
+        assert_eq!(entries[1].position, 1);
+    }
+
+    #[tokio::test]
+    async fn getting_entries_of_non_existent_group_returns_404() {
+        let app = common::spawn_test_app().await;
+
+        let response = app.get_curl_entries(1).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn getting_private_entries_when_not_permitted_returns_403() {
+        let app = common::spawn_test_app().await;
+        app.signup("integration-test").await;
+        let mut project = app.get_test_private_project();
+        project.info.id = 1;
+        app.create_project(&project.info).await;
+        app.create_curl_group(1, &app.get_test_curl_group()).await;
+
+        app.logout().await;
+        app.signup("integration-test-other-user").await;
+
+        let response = app.get_curl_entries(1).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app.get_curl_entry(1, 1).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn getting_entry_from_another_group_returns_404() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app, &["curl a.com"]).await;
+        app.create_curl_group(1, &app.get_test_curl_group()).await;
+
+        let response = app.get_curl_entry(1, 1).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.get_curl_entry(2, 1).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
+
+#[cfg(test)]
+mod get_curl_entry_snippet {
+    use super::*;
+
+    #[tokio::test]
+    async fn translates_the_entry_into_the_requested_language() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(
+            &app,
+            &["curl -X PUT https://api.example.com/users/1 -H 'Accept: application/json' -d 'name=Ada'"],
+        )
+        .await;
+
+        let response = app.get_curl_entry_snippet(1, 1, "python").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let snippet: Snippet = response.json().await.unwrap();
+        assert_eq!(snippet.language, Language::Python);
+        assert!(snippet.code.contains("requests.put("));
+        assert!(snippet
+            .code
+            .contains("\"Content-Type\": \"application/x-www-form-urlencoded\""));
+
+        for lang in ["javascript", "node", "go", "rust", "httpie", "powershell"] {
+            let response = app.get_curl_entry_snippet(1, 1, lang).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            let snippet: Snippet = response.json().await.unwrap();
+            assert!(snippet.code.contains("https://api.example.com/users/1"));
+        }
+    }
+
+    #[tokio::test]
+    async fn unknown_languages_and_entries_are_rejected() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app, &["curl a.com"]).await;
+
+        let response = app.get_curl_entry_snippet(1, 1, "cobol").await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = app.get_curl_entry_snippet(1, 2, "go").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
+
+#[cfg(test)]
+mod create_curl_entry {
+    use super::*;
+
+    #[tokio::test]
+    async fn creating_entry_appends_it_to_the_group() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app, &["curl a.com"]).await;
+
+        let response = app
+            .create_curl_entry(1, &get_test_entry_request("curl new.com"))
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let response_id: Id = response.json().await.unwrap();
+        assert_eq!(response_id.id, 2);
+
+        let entry: CurlEntry = app.get_curl_entry(1, 2).await.json().await.unwrap();
+        assert_eq!(entry.position, 1);
+        assert_eq!(entry.name, "Test entry");
+
+        let curl_group: CurlGroup = app.get_curl_group(1).await.json().await.unwrap();
+        let curls: Vec<Curl> = serde_json::from_str(&curl_group.curls).unwrap();
+        assert_eq!(curls[1].raw_query, "curl new.com");
+    }
+
+    #[tokio::test]
+    async fn creating_entry_with_a_token_redacts_it() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app, &["curl a.com"]).await;
+
+        let response = app
+            .create_curl_entry(
+                1,
+                &get_test_entry_request("curl -H 'Authorization: token ghp_abc' a.com"),
+            )
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let saved: SavedWithRedactions = response.json().await.unwrap();
+        assert_eq!(saved.redactions[0].field, "raw_query");
+        assert_eq!(saved.redactions[0].secrets, vec![SecretKind::BearerToken]);
+
+        assert_eq!(
+            get_raw_queries(&app, 1).await[1],
+            format!("curl -H 'Authorization: token {}' a.com", REDACTED)
+        );
+    }
+
+    #[tokio::test]
+    async fn creating_entry_when_not_logged_in_returns_401() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app, &["curl a.com"]).await;
+        app.logout().await;
+
+        let response = app
+            .create_curl_entry(1, &get_test_entry_request("curl new.com"))
+            .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn creating_public_entry_as_non_member_returns_403() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app, &["curl a.com"]).await;
+        sign_in_as_non_member(&app).await;
+
+        let response = app
+            .create_curl_entry(1, &get_test_entry_request("curl new.com"))
+            .await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(get_raw_queries(&app, 1).await, vec!["curl a.com"]);
+    }
+}
+
+#[cfg(test)]
+mod update_curl_entry {
+    use super::*;
+
+    #[tokio::test]
+    async fn updating_entry_only_changes_that_entry() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app, &["curl a.com", "curl b.com"]).await;
+
+        let response = app
+            .update_curl_entry(1, 2, &get_test_entry_request("curl updated.com"))
+            .await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        assert_eq!(
+            get_raw_queries(&app, 1).await,
+            vec!["curl a.com", "curl updated.com"]
+        );
+    }
+
+    #[tokio::test]
+    async fn updating_entry_with_invalid_curl_returns_422() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app, &["curl a.com"]).await;
+
+        let response = app
+            .update_curl_entry(1, 1, &get_test_entry_request("curl -H 'Accept' a.com"))
+            .await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let validation_errors: ValidationErrors = response.json().await.unwrap();
+        assert_eq!(validation_errors.errors[0].field, "raw_query");
+
+        assert_eq!(get_raw_queries(&app, 1).await, vec!["curl a.com"]);
+    }
+
+    #[tokio::test]
+    async fn updating_entry_with_unsupported_curl_options_saves_it() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app, &["curl a.com"]).await;
+
+        let response = app
+            .update_curl_entry(
+                1,
+                1,
+                &get_test_entry_request("curl --oauth2-bearer {{TOKEN}} a.com"),
+            )
+            .await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(
+            get_raw_queries(&app, 1).await,
+            vec!["curl --oauth2-bearer {{TOKEN}} a.com"]
+        );
+    }
+
+    #[tokio::test]
+    async fn updating_non_existent_entry_returns_404() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app, &["curl a.com"]).await;
+
+        let response = app
+            .update_curl_entry(1, 5, &get_test_entry_request("curl updated.com"))
+            .await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn updating_public_entry_as_non_member_returns_403() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app, &["curl a.com"]).await;
+        sign_in_as_non_member(&app).await;
+
+        let response = app
+            .update_curl_entry(1, 1, &get_test_entry_request("curl evil.com"))
+            .await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(get_raw_queries(&app, 1).await, vec!["curl a.com"]);
+    }
+}
+
+#[cfg(test)]
+mod move_curl_entry {
+    use super::*;
+
+    #[tokio::test]
+    async fn moving_entry_reorders_the_group() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app, &["curl a.com", "curl b.com", "curl c.com"]).await;
+
+        let response = app.move_curl_entry(1, 3, 0).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(
+            get_raw_queries(&app, 1).await,
+            vec!["curl c.com", "curl a.com", "curl b.com"]
+        );
+
+        app.move_curl_entry(1, 3, 10).await;
+        assert_eq!(
+            get_raw_queries(&app, 1).await,
+            vec!["curl a.com", "curl b.com", "curl c.com"]
+        );
+
+        app.move_curl_entry(1, 1, 1).await;
+        assert_eq!(
+            get_raw_queries(&app, 1).await,
+            vec!["curl b.com", "curl a.com", "curl c.com"]
+        );
+    }
+
+    #[tokio::test]
+    async fn moving_public_entry_as_non_member_returns_403() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app, &["curl a.com", "curl b.com"]).await;
+        sign_in_as_non_member(&app).await;
+
+        let response = app.move_curl_entry(1, 2, 0).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(
+            get_raw_queries(&app, 1).await,
+            vec!["curl a.com", "curl b.com"]
+        );
+    }
+}
+
+#[cfg(test)]
+mod delete_curl_entry {
+    use super::*;
+
+    #[tokio::test]
+    async fn deleting_entry_closes_the_gap() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app, &["curl a.com", "curl b.com", "curl c.com"]).await;
+
+        let response = app.delete_curl_entry(1, 2).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        let entries: Vec<CurlEntry> = app.get_curl_entries(1).await.json().await.unwrap();
+        let positions: Vec<i64> = entries.iter().map(|entry| entry.position).collect();
+        assert_eq!(positions, vec![0, 1]);
+        assert_eq!(entries[1].raw_query, "curl c.com");
+
+        let response = app.delete_curl_entry(1, 2).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn deleting_private_entry_when_not_permitted_returns_403() {
+        let app = common::spawn_test_app().await;
+        app.signup("integration-test").await;
+        let mut project = app.get_test_private_project();
+        project.info.id = 1;
+        app.create_project(&project.info).await;
+        app.create_curl_group(1, &app.get_test_curl_group()).await;
+
+        app.logout().await;
+        app.signup("integration-test-other-user").await;
+
+        let response = app.delete_curl_entry(1, 1).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn deleting_public_entry_as_non_member_returns_403() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app, &["curl a.com"]).await;
+        sign_in_as_non_member(&app).await;
+
+        let response = app.delete_curl_entry(1, 1).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(get_raw_queries(&app, 1).await, vec!["curl a.com"]);
+    }
+}
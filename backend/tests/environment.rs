@@ -0,0 +1,329 @@
+mod common;
+use crate::common::TestApplication;
+use backend::models::*;
+use backend::routes::environment::types::RenderedCurl;
+use backend::routes::project::types::{Id, ValidationErrors};
+use reqwest::StatusCode;
+
+/// The project's base URLs are dropped, so the only environments are the ones a test creates.
+async fn create_project_with_group(
+    app: &TestApplication,
+    project: &mut Project,
+    raw_queries: &[&str],
+) {
+    app.signup("integration-test").await;
+    project.info.environments.clear();
+    app.create_project(&project.info).await;
+
+    let mut curl_group = app.get_test_curl_group();
+    curl_group.curls = app.get_test_curls(raw_queries);
+    app.create_curl_group(project.info.id, &curl_group).await;
+}
+
+#[cfg(test)]
+mod environments {
+    use super::*;
+
+    #[tokio::test]
+    async fn created_environments_are_listed_with_their_variables() {
+        let app = common::spawn_test_app().await;
+        let mut project = app.get_test_public_project();
+        create_project_with_group(&app, &mut project, &["curl a.com"]).await;
+
+        let staging = app.get_test_environment(
+            "staging",
+            &[("base_url", "https://staging.com"), ("tenant_id", "7")],
+        );
+        let response = app.create_environment(1, &staging).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let response_id: Id = response.json().await.unwrap();
+
+        let response = app.get_environments(1).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let environments: Vec<Environment> = response.json().await.unwrap();
+        assert_eq!(
+            environments,
+            vec![Environment {
+                id: response_id.id,
+                project_id: 1,
+                name: "staging".to_string(),
+                variables: staging.variables,
+                secrets: vec![],
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn project_base_urls_are_kept_as_environments() {
+        let app = common::spawn_test_app().await;
+        let mut project = app.get_test_public_project();
+        app.signup("integration-test").await;
+        app.create_project(&project.info).await;
+
+        let environments: Vec<Environment> = app.get_environments(1).await.json().await.unwrap();
+        let names: Vec<&str> = environments.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["https://coolurl.com", "localhost:8080"]);
+        assert_eq!(
+            environments[1].variables.get("base_url").unwrap(),
+            "localhost:8080"
+        );
+
+        app.create_environment(1, &app.get_test_environment("dev", &[("a", "1")]))
+            .await;
+        project.info.environments = "localhost:8080, https://other.com".to_string();
+        let response = app.update_project(&project).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        let environments: Vec<Environment> = app.get_environments(1).await.json().await.unwrap();
+        let names: Vec<&str> = environments.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["dev", "https://other.com", "localhost:8080"]);
+    }
+
+    #[tokio::test]
+    async fn updating_and_deleting_environments() {
+        let app = common::spawn_test_app().await;
+        let mut project = app.get_test_public_project();
+        create_project_with_group(&app, &mut project, &["curl a.com"]).await;
+        app.create_environment(1, &app.get_test_environment("dev", &[("a", "1")]))
+            .await;
+
+        let prod = app.get_test_environment("prod", &[("b", "2")]);
+        let response = app.update_environment(1, 1, &prod).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        let environments: Vec<Environment> = app.get_environments(1).await.json().await.unwrap();
+        assert_eq!(environments[0].name, "prod");
+        assert_eq!(environments[0].variables, prod.variables);
+
+        let response = app.delete_environment(1, 1).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let response = app.delete_environment(1, 1).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let environments: Vec<Environment> = app.get_environments(1).await.json().await.unwrap();
+        assert!(environments.is_empty());
+    }
+
+    #[tokio::test]
+    async fn invalid_environments_return_422() {
+        let app = common::spawn_test_app().await;
+        let mut project = app.get_test_public_project();
+        create_project_with_group(&app, &mut project, &["curl a.com"]).await;
+        app.create_environment(1, &app.get_test_environment("dev", &[]))
+            .await;
+
+        let response = app
+            .create_environment(1, &app.get_test_environment("dev", &[("base-url", "x")]))
+            .await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let validation_errors: ValidationErrors = response.json().await.unwrap();
+        let fields: Vec<&str> = validation_errors
+            .errors
+            .iter()
+            .map(|error| error.field.as_str())
+            .collect();
+        assert_eq!(fields, vec!["name", "variables.base-url"]);
+
+        // keeping its own name is not a conflict
+        let response = app
+            .update_environment(1, 1, &app.get_test_environment("dev", &[("a", "1")]))
+            .await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    }
+
+    #[tokio::test]
+    async fn changing_environments_requires_project_permission() {
+        let app = common::spawn_test_app().await;
+        let mut project = app.get_test_public_project();
+        create_project_with_group(&app, &mut project, &["curl a.com"]).await;
+
+        app.logout().await;
+        let response = app
+            .create_environment(1, &app.get_test_environment("dev", &[]))
+            .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        app.signup("integration-test-other-user").await;
+        let response = app
+            .create_environment(1, &app.get_test_environment("dev", &[]))
+            .await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        // public projects can still be read
+        let response = app.get_environments(1).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn getting_private_environments_when_not_permitted_returns_403() {
+        let app = common::spawn_test_app().await;
+        let mut project = app.get_test_private_project();
+        project.info.id = 1;
+        create_project_with_group(&app, &mut project, &["curl a.com"]).await;
+
+        app.logout().await;
+        app.signup("integration-test-other-user").await;
+
+        let response = app.get_environments(1).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+}
+
+#[cfg(test)]
+mod render_curl_group {
+    use super::*;
+
+    #[tokio::test]
+    async fn renders_curls_for_the_chosen_environment() {
+        let app = common::spawn_test_app().await;
+        let mut project = app.get_test_public_project();
+        create_project_with_group(
+            &app,
+            &mut project,
+            &[
+                "curl {{base_url}}/tenants/{{tenant_id}}",
+                "curl {{base_url}}/v{{api_version}}",
+            ],
+        )
+        .await;
+        app.create_environment(
+            1,
+            &app.get_test_environment(
+                "staging",
+                &[("base_url", "https://staging.com"), ("tenant_id", "7")],
+            ),
+        )
+        .await;
+
+        let response = app.render_curl_group(1, Some("staging")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let rendered: Vec<RenderedCurl> = response.json().await.unwrap();
+        assert_eq!(rendered.len(), 2);
+        assert_eq!(rendered[0].raw_query, "curl https://staging.com/tenants/7");
+        assert!(rendered[0].missing_variables.is_empty());
+        assert_eq!(
+            rendered[1].raw_query,
+            "curl https://staging.com/v{{api_version}}"
+        );
+        assert_eq!(rendered[1].missing_variables, vec!["api_version"]);
+
+        let rendered: Vec<RenderedCurl> =
+            app.render_curl_group(1, None).await.json().await.unwrap();
+        assert_eq!(rendered[0].missing_variables, vec!["base_url", "tenant_id"]);
+    }
+
+    #[tokio::test]
+    async fn rendering_with_unknown_environment_returns_404() {
+        let app = common::spawn_test_app().await;
+        let mut project = app.get_test_public_project();
+        create_project_with_group(&app, &mut project, &["curl {{base_url}}"]).await;
+
+        let response = app.render_curl_group(1, Some("prod")).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
+
+#[cfg(test)]
+mod secret_variables {
+    use super::*;
+
+    async fn create_environment_with_secret(app: &TestApplication) {
+        let mut staging =
+            app.get_test_environment("staging", &[("base_url", "https://staging.com")]);
+        staging
+            .secrets
+            .insert("token".to_string(), Some("staging-token".to_string()));
+        let response = app.create_environment(1, &staging).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn secret_values_are_never_returned() {
+        let app = common::spawn_test_app().await;
+        let mut project = app.get_test_public_project();
+        create_project_with_group(&app, &mut project, &["curl {{base_url}}"]).await;
+        create_environment_with_secret(&app).await;
+
+        let body = app.get_environments(1).await.text().await.unwrap();
+        assert!(!body.contains("staging-token"));
+        let environments: Vec<Environment> = serde_json::from_str(&body).unwrap();
+        assert_eq!(environments[0].secrets, vec!["token"]);
+        assert!(!environments[0].variables.contains_key("token"));
+
+        let body = app.get_project(&project, None).await.text().await.unwrap();
+        assert!(!body.contains("staging-token"));
+    }
+
+    #[tokio::test]
+    async fn secrets_are_only_rendered_for_collaborators_who_ask() {
+        let app = common::spawn_test_app().await;
+        let mut project = app.get_test_public_project();
+        create_project_with_group(
+            &app,
+            &mut project,
+            &["curl -H 'Authorization: Bearer {{token}}' {{base_url}}"],
+        )
+        .await;
+        create_environment_with_secret(&app).await;
+
+        let rendered: Vec<RenderedCurl> = app
+            .render_curl_group(1, Some("staging"))
+            .await
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(
+            rendered[0].raw_query,
+            "curl -H 'Authorization: Bearer {{token}}' https://staging.com"
+        );
+        assert_eq!(rendered[0].withheld_variables, vec!["token"]);
+        assert!(rendered[0].missing_variables.is_empty());
+
+        let response = app.render_curl_group_with_secrets(1, "staging").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let rendered: Vec<RenderedCurl> = response.json().await.unwrap();
+        assert_eq!(
+            rendered[0].raw_query,
+            "curl -H 'Authorization: Bearer staging-token' https://staging.com"
+        );
+        assert!(rendered[0].withheld_variables.is_empty());
+
+        // readers of a public project can render it, but not with its secrets
+        app.logout().await;
+        app.signup("integration-test-other-user").await;
+        let response = app.render_curl_group(1, Some("staging")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.render_curl_group_with_secrets(1, "staging").await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn secrets_without_a_value_keep_the_stored_one() {
+        let app = common::spawn_test_app().await;
+        let mut project = app.get_test_public_project();
+        create_project_with_group(&app, &mut project, &["curl {{base_url}}?key={{token}}"]).await;
+        create_environment_with_secret(&app).await;
+
+        let mut staging = app.get_test_environment("staging", &[("base_url", "https://new.com")]);
+        staging.secrets.insert("token".to_string(), None);
+        let response = app.update_environment(1, 1, &staging).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        let rendered: Vec<RenderedCurl> = app
+            .render_curl_group_with_secrets(1, "staging")
+            .await
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(
+            rendered[0].raw_query,
+            "curl https://new.com?key=staging-token"
+        );
+
+        staging.secrets.insert("other".to_string(), None);
+        let response = app.update_environment(1, 1, &staging).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let validation_errors: ValidationErrors = response.json().await.unwrap();
+        assert_eq!(validation_errors.errors[0].field, "secrets.other");
+    }
+}
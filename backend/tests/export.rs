@@ -0,0 +1,218 @@
+mod common;
+use crate::common::TestApplication;
+use backend::har::Har;
+use backend::postman::{Collection, PostmanEnvironment};
+use backend::routes::{export::types::PostmanExport, import::types::HarImportRequest};
+use reqwest::StatusCode;
+
+const RAW_QUERIES: [&str; 4] = [
+    "curl -H 'Accept: application/json' -H 'Authorization: Bearer {{token}}' '{{base_url}}/users?page=2&sort=name%20asc'",
+    "curl -X PUT --json '{\"name\": \"Ada\"}' -u '{{user}}:{{password}}' {{base_url}}/users/{{id}}",
+    "curl -L -k -d 'grant_type=client_credentials&scope=read%20write' https://auth.example.com/token",
+    "curl -F 'name=avatar' -F 'file=@photo.png;type=image/png' -b 'session={{session_id}}' https://api.example.com:8443/upload",
+];
+
+fn read_fixture(name: &str) -> serde_json::Value {
+    let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+    let fixture = std::fs::read_to_string(path).expect("Failed to read fixture");
+    serde_json::from_str(&fixture).expect("Fixture is not valid JSON")
+}
+
+async fn create_project_with_group(app: &TestApplication, raw_queries: &[&str]) {
+    app.signup("integration-test").await;
+    let mut project = app.get_test_public_project();
+    // base URLs would otherwise become environments of their own
+    project.info.environments.clear();
+    app.create_project(&project.info).await;
+
+    let mut curl_group = app.get_test_curl_group();
+    curl_group.curls = app.get_test_curls(raw_queries);
+    app.create_curl_group(1, &curl_group).await;
+}
+
+#[cfg(test)]
+mod postman {
+    use super::*;
+
+    #[tokio::test]
+    async fn exports_the_group_as_a_collection_with_environments() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app, &RAW_QUERIES).await;
+        let mut staging =
+            app.get_test_environment("staging", &[("base_url", "https://staging.example.com")]);
+        staging
+            .secrets
+            .insert("token".to_string(), Some("staging-token".to_string()));
+        app.create_environment(1, &staging).await;
+
+        let response = app.export_curl_group(1, "postman").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let export: serde_json::Value = response.json().await.unwrap();
+
+        assert_eq!(
+            export["collection"],
+            read_fixture("postman/collection.json")
+        );
+        assert_eq!(
+            export["environments"][0],
+            read_fixture("postman/environment.json")
+        );
+        assert!(!export.to_string().contains("staging-token"));
+    }
+
+    #[tokio::test]
+    async fn fixtures_round_trip_through_the_postman_types() {
+        let fixture = read_fixture("postman/collection.json");
+        let collection: Collection = serde_json::from_value(fixture.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&collection).unwrap(), fixture);
+
+        let fixture = read_fixture("postman/environment.json");
+        let environment: PostmanEnvironment = serde_json::from_value(fixture.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&environment).unwrap(), fixture);
+    }
+
+    #[tokio::test]
+    async fn entries_are_exported_in_order() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app, &["curl b.com", "curl a.com", "curl c.com"]).await;
+
+        let export: PostmanExport = app
+            .export_curl_group(1, "postman")
+            .await
+            .json()
+            .await
+            .unwrap();
+        let names: Vec<&str> = export
+            .collection
+            .item
+            .iter()
+            .map(|item| item.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["cURL #1", "cURL #2", "cURL #3"]);
+        assert!(export.environments.is_empty());
+    }
+
+    #[tokio::test]
+    async fn exporting_requires_read_permission_and_a_known_format() {
+        let app = common::spawn_test_app().await;
+        let mut project = app.get_test_private_project();
+        project.info.id = 1;
+        app.signup("integration-test").await;
+        app.create_project(&project.info).await;
+        app.create_curl_group(1, &app.get_test_curl_group()).await;
+
+        let response = app.export_curl_group(1, "insomnia").await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = app.export_curl_group(2, "postman").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        app.logout().await;
+        app.signup("integration-test-other-user").await;
+        let response = app.export_curl_group(1, "postman").await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+}
+
+#[cfg(test)]
+mod har {
+    use super::*;
+
+    #[tokio::test]
+    async fn exports_the_group_as_a_har_file() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app, &RAW_QUERIES).await;
+
+        let response = app.export_curl_group_as_har(1, None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let har: Har = response.json().await.unwrap();
+        assert_eq!(har.log.version, "1.2");
+        assert_eq!(har.log.entries.len(), 4);
+
+        let list_users = &har.log.entries[0];
+        assert_eq!(list_users.comment.as_deref(), Some("cURL #1"));
+        assert_eq!(
+            list_users.request.url,
+            "{{base_url}}/users?page=2&sort=name%20asc"
+        );
+        assert_eq!(list_users.request.query_string.len(), 2);
+
+        let update_user = &har.log.entries[1].request;
+        assert_eq!(update_user.method, "PUT");
+        let post_data = update_user.post_data.as_ref().unwrap();
+        assert_eq!(post_data.mime_type, "application/json");
+        assert_eq!(post_data.text, "{\"name\": \"Ada\"}");
+        assert!(update_user
+            .headers
+            .iter()
+            .any(|header| header.name == "Authorization" && header.value.starts_with("Basic ")));
+
+        let upload = &har.log.entries[3].request;
+        assert_eq!(upload.cookies[0].name, "session");
+        let params = &upload.post_data.as_ref().unwrap().params;
+        assert_eq!(params[1].file_name.as_deref(), Some("photo.png"));
+    }
+
+    #[tokio::test]
+    async fn fills_in_the_variables_of_an_environment() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app, &RAW_QUERIES).await;
+        let mut staging =
+            app.get_test_environment("staging", &[("base_url", "https://staging.example.com")]);
+        staging
+            .secrets
+            .insert("token".to_string(), Some("staging-token".to_string()));
+        app.create_environment(1, &staging).await;
+
+        let response = app.export_curl_group_as_har(1, Some("staging")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let har: Har = response.json().await.unwrap();
+        let list_users = &har.log.entries[0].request;
+        assert_eq!(
+            list_users.url,
+            "https://staging.example.com/users?page=2&sort=name%20asc"
+        );
+        assert!(list_users
+            .headers
+            .iter()
+            .any(|header| header.value == "Bearer {{token}}"));
+
+        let response = app.export_curl_group_as_har(1, Some("production")).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn exported_files_import_into_an_equal_group() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app, &RAW_QUERIES[..2]).await;
+        let har: serde_json::Value = app
+            .export_curl_group_as_har(1, None)
+            .await
+            .json()
+            .await
+            .unwrap();
+
+        let response = app
+            .import_har(
+                1,
+                &HarImportRequest {
+                    name: "Round trip".to_string(),
+                    description: "".to_string(),
+                    har,
+                    entries: None,
+                    include_static: false,
+                },
+            )
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let original: Vec<serde_json::Value> = app.get_curl_entries(1).await.json().await.unwrap();
+        let imported: Vec<serde_json::Value> = app.get_curl_entries(2).await.json().await.unwrap();
+        assert_eq!(imported.len(), 2);
+        assert_eq!(imported[0]["name"], original[0]["name"]);
+        assert_eq!(
+            backend::curl::parse(imported[0]["raw_query"].as_str().unwrap()).unwrap(),
+            backend::curl::parse(original[0]["raw_query"].as_str().unwrap()).unwrap()
+        );
+    }
+}
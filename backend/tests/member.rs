@@ -0,0 +1,385 @@
+mod common;
+use crate::common::TestApplication;
+use backend::models::*;
+use backend::routes::member::types::{Member, RoleInfo};
+use backend::routes::project::types::{FieldError, Permission, Role, ValidationErrors};
+use reqwest::StatusCode;
+
+/// A public project owned by `integration-test`, with `integration-test-other-user` as an editor.
+/// Leaves the owner logged in.
+async fn create_project_with_collaborator(app: &TestApplication) -> Project {
+    app.signup("integration-test").await;
+    let mut project = app.get_test_public_project();
+    app.create_project(&project.info).await;
+    app.add_other_user_as_collaborator_and_login(&mut project)
+        .await;
+    app.logout().await;
+    app.login("integration-test", common::TEST_PASSWORD).await;
+    project
+}
+
+async fn get_members(app: &TestApplication) -> Vec<Member> {
+    let response = app.get_project_members(1).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    response.json().await.unwrap()
+}
+
+fn member(username: &str, role: Role) -> Member {
+    Member {
+        username: username.to_string(),
+        role,
+    }
+}
+
+async fn validation_errors(response: reqwest::Response) -> Vec<FieldError> {
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let errors: ValidationErrors = response.json().await.unwrap();
+    errors.errors
+}
+
+#[cfg(test)]
+mod get_members {
+    use super::*;
+
+    #[tokio::test]
+    async fn members_are_listed_with_their_roles() {
+        let app = common::spawn_test_app().await;
+        create_project_with_collaborator(&app).await;
+
+        assert_eq!(
+            get_members(&app).await,
+            vec![
+                member("integration-test", Role::Owner),
+                member("integration-test-other-user", Role::Editor),
+            ]
+        );
+
+        let response = app
+            .get_project_member(1, "integration-test-other-user")
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app.get_project_member(1, "nobody").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
+
+#[cfg(test)]
+mod set_member_role {
+    use super::*;
+
+    #[tokio::test]
+    async fn owners_can_change_roles() {
+        let app = common::spawn_test_app().await;
+        let project = create_project_with_collaborator(&app).await;
+
+        let response = app
+            .set_project_member_role(1, "integration-test-other-user", Role::Owner)
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let project_from_server: Project =
+            app.get_project(&project, None).await.json().await.unwrap();
+        assert_eq!(
+            project_from_server.admins,
+            vec!["integration-test", "integration-test-other-user"]
+        );
+        assert!(project_from_server.collaborators.is_empty());
+
+        // with another owner in place, the creator can step down
+        let response = app
+            .set_project_member_role(1, "integration-test", Role::Viewer)
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            get_members(&app).await,
+            vec![
+                member("integration-test-other-user", Role::Owner),
+                member("integration-test", Role::Viewer),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn only_owners_can_grant_or_change_ownership() {
+        let app = common::spawn_test_app().await;
+        create_project_with_collaborator(&app).await;
+        app.set_project_member_role(1, "integration-test-other-user", Role::Admin)
+            .await;
+        app.logout().await;
+        app.login("integration-test-other-user", common::TEST_PASSWORD)
+            .await;
+
+        let response = app
+            .set_project_member_role(1, "integration-test-other-user", Role::Owner)
+            .await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = app
+            .set_project_member_role(1, "integration-test", Role::Viewer)
+            .await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = app.remove_project_member(1, "integration-test").await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn adding_unknown_user_returns_422() {
+        let app = common::spawn_test_app().await;
+        create_project_with_collaborator(&app).await;
+
+        let response = app.set_project_member_role(1, "nobody", Role::Editor).await;
+        assert_eq!(
+            validation_errors(response).await,
+            vec![FieldError::new("username", "No user is named \"nobody\".")]
+        );
+    }
+
+    #[tokio::test]
+    async fn demoting_the_last_owner_returns_422() {
+        let app = common::spawn_test_app().await;
+        create_project_with_collaborator(&app).await;
+
+        let response = app
+            .set_project_member_role(1, "integration-test", Role::Admin)
+            .await;
+        assert_eq!(
+            validation_errors(response).await,
+            vec![FieldError::new(
+                "role",
+                "A project must keep at least one owner."
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn changing_roles_as_editor_returns_403() {
+        let app = common::spawn_test_app().await;
+        create_project_with_collaborator(&app).await;
+        app.logout().await;
+        app.login("integration-test-other-user", common::TEST_PASSWORD)
+            .await;
+
+        let response = app
+            .set_project_member_role(1, "integration-test-other-user", Role::Admin)
+            .await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+}
+
+#[cfg(test)]
+mod remove_member {
+    use super::*;
+
+    #[tokio::test]
+    async fn owners_can_remove_members() {
+        let app = common::spawn_test_app().await;
+        create_project_with_collaborator(&app).await;
+
+        let response = app
+            .remove_project_member(1, "integration-test-other-user")
+            .await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(
+            get_members(&app).await,
+            vec![member("integration-test", Role::Owner)]
+        );
+
+        let response = app
+            .remove_project_member(1, "integration-test-other-user")
+            .await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn removing_the_last_owner_returns_422() {
+        let app = common::spawn_test_app().await;
+        create_project_with_collaborator(&app).await;
+
+        let response = app.remove_project_member(1, "integration-test").await;
+        assert_eq!(
+            validation_errors(response).await,
+            vec![FieldError::new(
+                "username",
+                "A project must keep at least one owner."
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn editors_can_only_remove_themselves() {
+        let app = common::spawn_test_app().await;
+        create_project_with_collaborator(&app).await;
+        app.logout().await;
+        app.login("integration-test-other-user", common::TEST_PASSWORD)
+            .await;
+
+        let response = app.remove_project_member(1, "integration-test").await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = app
+            .remove_project_member(1, "integration-test-other-user")
+            .await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    }
+}
+
+#[cfg(test)]
+mod update_project_members {
+    use super::*;
+
+    #[tokio::test]
+    async fn saving_a_project_replaces_its_members_without_duplicates() {
+        let app = common::spawn_test_app().await;
+        let mut project = create_project_with_collaborator(&app).await;
+
+        // saving again keeps a single row per member
+        app.update_project(&project).await;
+        app.update_project(&project).await;
+        assert_eq!(get_members(&app).await.len(), 2);
+
+        project.collaborators = vec![];
+        let response = app.update_project(&project).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(
+            get_members(&app).await,
+            vec![member("integration-test", Role::Owner)]
+        );
+    }
+
+    #[tokio::test]
+    async fn saving_a_project_keeps_the_roles_of_listed_members() {
+        let app = common::spawn_test_app().await;
+        let project = create_project_with_collaborator(&app).await;
+        app.set_project_member_role(1, "integration-test-other-user", Role::Viewer)
+            .await;
+
+        let response = app.update_project(&project).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(
+            get_members(&app).await,
+            vec![
+                member("integration-test", Role::Owner),
+                member("integration-test-other-user", Role::Viewer),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn saving_a_project_with_invalid_members_returns_422_and_changes_nothing() {
+        let app = common::spawn_test_app().await;
+        let mut project = create_project_with_collaborator(&app).await;
+
+        project.info.description = "Updated".to_string();
+        project.admins = vec![];
+        project.collaborators = vec![
+            "nobody".to_string(),
+            "integration-test-other-user".to_string(),
+        ];
+        let response = app.update_project(&project).await;
+        assert_eq!(
+            validation_errors(response).await,
+            vec![
+                FieldError::new("admins", "A project must keep at least one owner."),
+                FieldError::new("collaborators", "No user is named \"nobody\"."),
+            ]
+        );
+
+        let project_from_server: Project =
+            app.get_project(&project, None).await.json().await.unwrap();
+        assert_eq!(
+            project_from_server.info.description,
+            "A test public project"
+        );
+        assert_eq!(get_members(&app).await.len(), 2);
+    }
+}
+
+#[cfg(test)]
+mod roles {
+    use super::*;
+
+    #[tokio::test]
+    async fn roles_are_listed_with_their_permissions() {
+        let app = common::spawn_test_app().await;
+
+        let response = app.get_roles().await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let roles: Vec<RoleInfo> = response.json().await.unwrap();
+        assert_eq!(
+            roles.iter().map(|role| role.name).collect::<Vec<_>>(),
+            Role::ALL
+        );
+        assert_eq!(roles[0].permissions, Permission::ALL);
+        assert_eq!(
+            roles[2].permissions,
+            vec![
+                Permission::Read,
+                Permission::CreateGroup,
+                Permission::EditGroup
+            ]
+        );
+        assert_eq!(roles[4].permissions, vec![Permission::Read]);
+    }
+
+    #[tokio::test]
+    async fn viewers_can_read_private_projects_without_editing_them() {
+        let app = common::spawn_test_app().await;
+        app.signup("integration-test").await;
+        app.create_project(&app.get_test_public_project().info)
+            .await;
+        let mut project = app.get_test_private_project();
+        app.create_project(&project.info).await;
+        app.add_other_user_as_collaborator_and_login(&mut project)
+            .await;
+        app.logout().await;
+        app.login("integration-test", common::TEST_PASSWORD).await;
+        app.set_project_member_role(2, "integration-test-other-user", Role::Viewer)
+            .await;
+        app.logout().await;
+        app.login("integration-test-other-user", common::TEST_PASSWORD)
+            .await;
+
+        let response = app.get_project(&project, None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app.create_curl_group(2, &app.get_test_curl_group()).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn editors_cannot_manage_environments() {
+        let app = common::spawn_test_app().await;
+        create_project_with_collaborator(&app).await;
+        app.logout().await;
+        app.login("integration-test-other-user", common::TEST_PASSWORD)
+            .await;
+
+        let response = app.create_curl_group(1, &app.get_test_curl_group()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let environment = app.get_test_environment("staging", &[("host", "example.com")]);
+        let response = app.create_environment(1, &environment).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn only_owners_can_delete_projects() {
+        let app = common::spawn_test_app().await;
+        let project = create_project_with_collaborator(&app).await;
+        app.set_project_member_role(1, "integration-test-other-user", Role::Admin)
+            .await;
+        app.logout().await;
+        app.login("integration-test-other-user", common::TEST_PASSWORD)
+            .await;
+
+        let response = app.delete_project(&project.info).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        app.logout().await;
+        app.login("integration-test", common::TEST_PASSWORD).await;
+        let response = app.delete_project(&project.info).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    }
+}
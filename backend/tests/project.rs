@@ -1,6 +1,7 @@
 mod common;
 use crate::common::TestApplication;
 use backend::models::*;
+use backend::redaction::{SecretKind, REDACTED};
 use backend::routes::project::types::*;
 use reqwest::StatusCode;
 
@@ -146,6 +147,114 @@ mod get_projects {
     }
 }
 
+#[cfg(test)]
+mod search_projects {
+    use super::*;
+
+    async fn search(app: &TestApplication, search: &str, labels: &str) -> Vec<ProjectInfo> {
+        app.get_projects_with_query(search, labels)
+            .await
+            .json()
+            .await
+            .expect("Failed to serialise response")
+    }
+
+    #[tokio::test]
+    async fn search_matches_project_name_and_description() {
+        let app = common::spawn_test_app().await;
+        app.signup("integration-test").await;
+        let public_project = app.get_test_public_project();
+        let private_project = app.get_test_private_project();
+        app.create_project(&public_project.info).await;
+        app.create_project(&private_project.info).await;
+
+        let projects = search(&app, "public", "").await;
+        assert_eq!(projects, vec![public_project.info]);
+
+        let projects = search(&app, "test private", "").await;
+        assert_eq!(projects, vec![private_project.info]);
+
+        let projects = search(&app, "project", "").await;
+        assert_eq!(projects.len(), 2);
+
+        let projects = search(&app, "100%", "").await;
+        assert!(projects.is_empty());
+    }
+
+    #[tokio::test]
+    async fn search_respects_project_visibility() {
+        let app = common::spawn_test_app().await;
+        app.signup("integration-test").await;
+        let public_project = app.get_test_public_project();
+        let private_project = app.get_test_private_project();
+        app.create_project(&public_project.info).await;
+        app.create_project(&private_project.info).await;
+
+        app.logout().await;
+
+        let projects = search(&app, "project", "").await;
+        assert_eq!(projects, vec![public_project.info]);
+
+        let projects = search(&app, "private", "").await;
+        assert!(projects.is_empty());
+    }
+
+    #[tokio::test]
+    async fn label_filter_matches_projects_with_labelled_curl_groups() {
+        let app = common::spawn_test_app().await;
+        app.signup("integration-test").await;
+        let public_project = app.get_test_public_project();
+        let private_project = app.get_test_private_project();
+        app.create_project(&public_project.info).await;
+        app.create_project(&private_project.info).await;
+
+        let mut curl_group = app.get_test_curl_group();
+        app.create_curl_group(1, &curl_group).await;
+        curl_group.labels = "test2, other".to_string();
+        app.create_curl_group(2, &curl_group).await;
+
+        let projects = search(&app, "", "test1").await;
+        assert_eq!(projects, vec![app.get_test_public_project().info]);
+
+        let projects = search(&app, "", "test2").await;
+        assert_eq!(projects.len(), 2);
+
+        let projects = search(&app, "", "test2,other").await;
+        assert_eq!(projects, vec![private_project.info]);
+
+        let projects = search(&app, "public", "test2").await;
+        assert_eq!(projects, vec![public_project.info]);
+
+        let projects = search(&app, "", "test").await;
+        assert!(projects.is_empty());
+
+        for wildcard in ["%", "_", "test_"] {
+            let projects = search(&app, "", wildcard).await;
+            assert!(projects.is_empty(), "{} matched", wildcard);
+        }
+    }
+
+    #[tokio::test]
+    async fn label_filter_ignores_spacing_around_stored_labels() {
+        let app = common::spawn_test_app().await;
+        app.signup("integration-test").await;
+        let public_project = app.get_test_public_project();
+        app.create_project(&public_project.info).await;
+
+        let mut curl_group = app.get_test_curl_group();
+        curl_group.labels = "auth ,billing,  Refunds ".to_string();
+        app.create_curl_group(1, &curl_group).await;
+
+        for labels in ["auth", "billing", "refunds", "auth, billing ,refunds"] {
+            let projects = search(&app, "", labels).await;
+            let ids: Vec<i64> = projects.iter().map(|project| project.id).collect();
+            assert_eq!(ids, vec![public_project.info.id], "{}", labels);
+        }
+        let projects = search(&app, "", "bill").await;
+        assert!(projects.is_empty());
+    }
+}
+
 #[cfg(test)]
 mod delete_project {
     use super::*;
@@ -349,6 +458,33 @@ mod update_project {
         );
     }
 
+    #[tokio::test]
+    async fn updating_project_without_redaction_policy_keeps_the_current_one() {
+        let app = common::spawn_test_app().await;
+        app.signup("integration-test").await;
+
+        let mut project = app.get_test_public_project();
+        project.info.redaction_policy = Some("Reject".to_string());
+        app.create_project(&project.info).await;
+
+        project.info.redaction_policy = None;
+        let response = app.update_project(&project).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        let project_from_server: Project =
+            app.get_project(&project, None).await.json().await.unwrap();
+        assert_eq!(
+            project_from_server.info.redaction_policy,
+            Some("Reject".to_string())
+        );
+
+        project.info.redaction_policy = Some("Ignore".to_string());
+        let response = app.update_project(&project).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let validation_errors: ValidationErrors = response.json().await.unwrap();
+        assert_eq!(validation_errors.errors[0].field, "redaction_policy");
+    }
+
     #[tokio::test]
     async fn updating_project_not_as_project_admin_returns_403() {
         let app = common::spawn_test_app().await;
@@ -393,6 +529,7 @@ mod update_project {
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod is_user_admin {
     use super::*;
 
@@ -511,6 +648,99 @@ mod create_curl_group {
         assert_eq!(response_id.id, 1);
     }
 
+    #[tokio::test]
+    async fn creating_group_with_malformed_curls_returns_422_with_field_errors() {
+        let app = common::spawn_test_app().await;
+        app.signup("integration-test").await;
+
+        let project = app.get_test_public_project();
+        app.create_project(&project.info).await;
+
+        let mut curl_group = app.get_test_curl_group();
+        curl_group.curls = "some-curl".to_string();
+        let response = app.create_curl_group(1, &curl_group).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let validation_errors: ValidationErrors = response.json().await.unwrap();
+        assert_eq!(validation_errors.errors.len(), 1);
+        assert_eq!(validation_errors.errors[0].field, "curls");
+
+        curl_group.name = "".to_string();
+        curl_group.curls = app.get_test_curls(&["curl https://example.com", "curl 'unclosed"]);
+        let response = app.create_curl_group(1, &curl_group).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let validation_errors: ValidationErrors = response.json().await.unwrap();
+        let fields: Vec<&str> = validation_errors
+            .errors
+            .iter()
+            .map(|error| error.field.as_str())
+            .collect();
+        assert_eq!(fields, vec!["name", "curls[1].rawQuery"]);
+        assert!(validation_errors.errors[1]
+            .message
+            .contains("Unterminated single quote"));
+
+        let project: Project = app.get_project(&project, None).await.json().await.unwrap();
+        assert!(project.groups.is_empty());
+    }
+
+    #[tokio::test]
+    async fn creating_group_with_secrets_redacts_them_and_reports_fields() {
+        let app = common::spawn_test_app().await;
+        app.signup("integration-test").await;
+
+        let project = app.get_test_public_project();
+        app.create_project(&project.info).await;
+
+        let mut curl_group = app.get_test_curl_group();
+        curl_group.curls = app.get_test_curls(&[
+            "curl https://example.com",
+            "curl -H 'Authorization: Bearer staging-token' -b 'session=abc' https://example.com",
+        ]);
+        let response = app.create_curl_group(1, &curl_group).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let saved: SavedWithRedactions = response.json().await.unwrap();
+        assert_eq!(saved.id, 1);
+        assert_eq!(
+            saved.redactions,
+            vec![Redaction {
+                field: "curls[1].rawQuery".to_string(),
+                secrets: vec![SecretKind::BearerToken, SecretKind::Cookie],
+            }]
+        );
+
+        let curl_group: CurlGroup = app.get_curl_group(1).await.json().await.unwrap();
+        let curls: Vec<Curl> = serde_json::from_str(&curl_group.curls).unwrap();
+        assert_eq!(curls[0].raw_query, "curl https://example.com");
+        assert_eq!(
+            curls[1].raw_query,
+            format!(
+                "curl -H 'Authorization: Bearer {0}' -b 'session={0}' https://example.com",
+                REDACTED
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn creating_group_with_secrets_when_policy_rejects_returns_422() {
+        let app = common::spawn_test_app().await;
+        app.signup("integration-test").await;
+
+        let mut project = app.get_test_public_project();
+        project.info.redaction_policy = Some("Reject".to_string());
+        app.create_project(&project.info).await;
+
+        let mut curl_group = app.get_test_curl_group();
+        curl_group.curls = app.get_test_curls(&["curl -u admin:hunter2 https://example.com"]);
+        let response = app.create_curl_group(1, &curl_group).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let validation_errors: ValidationErrors = response.json().await.unwrap();
+        assert_eq!(validation_errors.errors[0].field, "curls[0].rawQuery");
+
+        let project: Project = app.get_project(&project, None).await.json().await.unwrap();
+        assert!(project.groups.is_empty());
+    }
+
     #[tokio::test]
     async fn creating_group_for_private_project_while_not_permitted_returns_403() {
         let app = common::spawn_test_app().await;
@@ -667,10 +897,10 @@ mod update_curl_group {
         curl_group: &mut CurlGroup,
     ) -> reqwest::Response {
         curl_group.description = "I have been updated!".to_string();
-        curl_group.curls = "updated.com".to_string();
+        curl_group.curls = app.get_test_curls(&["curl https://updated.com"]);
         curl_group.labels = "1,2,3".to_string();
         curl_group.name = "Updated curl group".to_string();
-        app.update_curl_group(&curl_group).await
+        app.update_curl_group(curl_group).await
     }
 
     #[tokio::test]
@@ -751,7 +981,51 @@ mod update_curl_group {
     }
 
     #[tokio::test]
-    async fn updating_public_project_curl_group_when_logged_in_returns_204() {
+    async fn updating_curl_group_with_too_many_labels_returns_422() {
+        let app = common::spawn_test_app().await;
+        app.signup("integration-test").await;
+
+        let project = app.get_test_public_project();
+        app.create_project(&project.info).await;
+
+        let mut curl_group = app.get_test_curl_group();
+        app.create_curl_group(project.info.id, &curl_group).await;
+
+        curl_group.labels = "a,b,c,d".to_string();
+        let response = app.update_curl_group(&curl_group).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let validation_errors: ValidationErrors = response.json().await.unwrap();
+        assert_eq!(
+            validation_errors.errors,
+            vec![FieldError::new("labels", "At most 3 labels are allowed.")]
+        );
+    }
+
+    #[tokio::test]
+    async fn updating_curl_group_with_secrets_returns_200_with_redactions() {
+        let app = common::spawn_test_app().await;
+        app.signup("integration-test").await;
+
+        let project = app.get_test_public_project();
+        app.create_project(&project.info).await;
+
+        let mut curl_group = app.get_test_curl_group();
+        app.create_curl_group(1, &curl_group).await;
+
+        curl_group.curls = app.get_test_curls(&["curl -H 'X-Api-Key: 123' https://example.com"]);
+        let response = app.update_curl_group(&curl_group).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let saved: SavedWithRedactions = response.json().await.unwrap();
+        assert_eq!(saved.redactions[0].secrets, vec![SecretKind::ApiKey]);
+
+        // saving the redacted curls again changes nothing further
+        let curl_group: CurlGroup = app.get_curl_group(1).await.json().await.unwrap();
+        let response = app.update_curl_group(&curl_group).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    }
+
+    #[tokio::test]
+    async fn updating_public_project_curl_group_as_non_member_returns_403() {
         let app = common::spawn_test_app().await;
         app.signup("integration-test").await;
 
@@ -766,7 +1040,7 @@ mod update_curl_group {
 
         curl_group.description = "Updated".to_string();
         let response = app.update_curl_group(&curl_group).await;
-        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
     }
 
     #[tokio::test]
@@ -799,3 +1073,165 @@ mod update_curl_group {
         assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
     }
 }
+
+#[cfg(test)]
+mod delete_curl_group {
+    use super::*;
+
+    async fn create_project_with_group(app: &TestApplication) -> Project {
+        app.signup("integration-test").await;
+        let project = app.get_test_public_project();
+        app.create_project(&project.info).await;
+        app.create_curl_group(project.info.id, &app.get_test_curl_group())
+            .await;
+        project
+    }
+
+    async fn get_trash(app: &TestApplication) -> Vec<TrashedCurlGroup> {
+        let response = app.get_trashed_curl_groups(1).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        response.json().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn deleting_group_as_admin_moves_it_to_the_trash() {
+        let app = common::spawn_test_app().await;
+        let project = create_project_with_group(&app).await;
+
+        let response = app.delete_curl_group(1).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        assert_eq!(app.get_curl_group(1).await.status(), StatusCode::NOT_FOUND);
+        let project_from_server: Project =
+            app.get_project(&project, None).await.json().await.unwrap();
+        assert!(project_from_server.groups.is_empty());
+
+        let trash = get_trash(&app).await;
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].name, "CurlGroup 1");
+        assert_eq!(trash[0].deleted_by.as_deref(), Some("integration-test"));
+        assert!(trash[0].purge_at > trash[0].deleted_at);
+    }
+
+    #[tokio::test]
+    async fn deleting_non_existent_group_returns_404() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app).await;
+
+        let response = app.delete_curl_group(2).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        app.delete_curl_group(1).await;
+        let response = app.delete_curl_group(1).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn deleting_group_as_collaborator_depends_on_project_setting() {
+        let app = common::spawn_test_app().await;
+        let mut project = create_project_with_group(&app).await;
+        app.add_other_user_as_collaborator_and_login(&mut project)
+            .await;
+
+        let response = app.delete_curl_group(1).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        app.logout().await;
+        app.login("integration-test", common::TEST_PASSWORD).await;
+        project.info.collaborators_can_delete_groups = Some(true);
+        app.update_project(&project).await;
+        app.logout().await;
+        app.login("integration-test-other-user", common::TEST_PASSWORD)
+            .await;
+
+        let response = app.delete_curl_group(1).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    }
+
+    #[tokio::test]
+    async fn deleting_public_group_when_not_member_returns_403() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app).await;
+        app.logout().await;
+        app.signup("integration-test-other-user").await;
+
+        let response = app.delete_curl_group(1).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        app.logout().await;
+        let response = app.delete_curl_group(1).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn restoring_trashed_group_brings_it_back() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app).await;
+        app.delete_curl_group(1).await;
+
+        let response = app.restore_trashed_curl_group(1, 1).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        let curl_group: CurlGroup = app.get_curl_group(1).await.json().await.unwrap();
+        assert_eq!(curl_group.name, "CurlGroup 1");
+        assert!(get_trash(&app).await.is_empty());
+
+        // only groups in the trash can be restored
+        let response = app.restore_trashed_curl_group(1, 1).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn purging_trashed_group_removes_it_for_good() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app).await;
+
+        // groups still in use cannot be purged
+        let response = app.purge_trashed_curl_group(1, 1).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        app.delete_curl_group(1).await;
+        let response = app.purge_trashed_curl_group(1, 1).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        assert!(get_trash(&app).await.is_empty());
+        let response = app.restore_trashed_curl_group(1, 1).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn managing_trash_as_collaborator_returns_403() {
+        let app = common::spawn_test_app().await;
+        let mut project = create_project_with_group(&app).await;
+        app.delete_curl_group(1).await;
+        app.add_other_user_as_collaborator_and_login(&mut project)
+            .await;
+
+        let response = app.get_trashed_curl_groups(1).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app.restore_trashed_curl_group(1, 1).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app.purge_trashed_curl_group(1, 1).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn managing_trash_as_collaborator_who_can_delete_groups_returns_403() {
+        let app = common::spawn_test_app().await;
+        let mut project = create_project_with_group(&app).await;
+        project.info.collaborators_can_delete_groups = Some(true);
+        app.update_project(&project).await;
+        app.add_other_user_as_collaborator_and_login(&mut project)
+            .await;
+
+        let response = app.delete_curl_group(1).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        let response = app.get_trashed_curl_groups(1).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app.restore_trashed_curl_group(1, 1).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app.purge_trashed_curl_group(1, 1).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+}
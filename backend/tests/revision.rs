@@ -0,0 +1,259 @@
+mod common;
+use crate::common::TestApplication;
+use backend::diff::{DiffLine, LineChange};
+use backend::models::*;
+use backend::routes::entry::types::CurlEntryRequest;
+use backend::routes::revision::types::{EntryChange, Revision, RevisionDiff, RevisionSummary};
+use reqwest::StatusCode;
+
+async fn create_project_with_group(app: &TestApplication, raw_queries: &[&str]) {
+    app.signup("integration-test").await;
+    let project = app.get_test_public_project();
+    app.create_project(&project.info).await;
+
+    let mut curl_group = app.get_test_curl_group();
+    curl_group.curls = app.get_test_curls(raw_queries);
+    app.create_curl_group(1, &curl_group).await;
+}
+
+async fn get_revisions(app: &TestApplication, group_id: i64) -> Vec<RevisionSummary> {
+    app.get_curl_group_revisions(group_id)
+        .await
+        .json()
+        .await
+        .expect("Failed to serialise response")
+}
+
+/// The group as currently saved, with the ids its entries were given.
+async fn get_saved_group(app: &TestApplication, group_id: i64) -> CurlGroup {
+    app.get_curl_group(group_id).await.json().await.unwrap()
+}
+
+#[cfg(test)]
+mod get_curl_group_revisions {
+    use super::*;
+
+    #[tokio::test]
+    async fn saving_a_group_records_a_revision_by_its_author() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app, &["curl a.com"]).await;
+
+        let mut curl_group = get_saved_group(&app, 1).await;
+        curl_group.name = "Renamed".to_string();
+        app.update_curl_group(&curl_group).await;
+        // saving without changes adds nothing to the history
+        app.update_curl_group(&curl_group).await;
+
+        let revisions = get_revisions(&app, 1).await;
+        let numbers: Vec<i64> = revisions.iter().map(|revision| revision.number).collect();
+        assert_eq!(numbers, vec![2, 1]);
+        assert_eq!(revisions[0].author.as_deref(), Some("integration-test"));
+
+        let response = app.get_curl_group_revision(1, 1).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let revision: Revision = response.json().await.unwrap();
+        assert_eq!(revision.content.name, "CurlGroup 1");
+        assert_eq!(revision.content.curls[0].raw_query, "curl a.com");
+    }
+
+    #[tokio::test]
+    async fn changing_entries_records_a_revision() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app, &["curl a.com"]).await;
+
+        app.create_curl_entry(
+            1,
+            &CurlEntryRequest {
+                name: "New".to_string(),
+                description: "".to_string(),
+                raw_query: "curl b.com".to_string(),
+            },
+        )
+        .await;
+        app.delete_curl_entry(1, 1).await;
+
+        let revisions = get_revisions(&app, 1).await;
+        assert_eq!(revisions.len(), 3);
+        let revision: Revision = app
+            .get_curl_group_revision(1, 3)
+            .await
+            .json()
+            .await
+            .unwrap();
+        let raw_queries: Vec<&str> = revision
+            .content
+            .curls
+            .iter()
+            .map(|curl| curl.raw_query.as_str())
+            .collect();
+        assert_eq!(raw_queries, vec!["curl b.com"]);
+    }
+
+    #[tokio::test]
+    async fn private_history_is_hidden_from_other_users() {
+        let app = common::spawn_test_app().await;
+        app.signup("integration-test").await;
+        let mut project = app.get_test_private_project();
+        project.info.id = 1;
+        app.create_project(&project.info).await;
+        app.create_curl_group(1, &app.get_test_curl_group()).await;
+
+        app.logout().await;
+        app.signup("integration-test-other-user").await;
+
+        let response = app.get_curl_group_revisions(1).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app.get_curl_group_revision(1, 1).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app.get_curl_group_revision_diff(1, 1, 1).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn getting_missing_revision_returns_404() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app, &["curl a.com"]).await;
+
+        let response = app.get_curl_group_revision(1, 2).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let response = app.get_curl_group_revision_diff(1, 1, 2).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
+
+#[cfg(test)]
+mod get_curl_group_revision_diff {
+    use super::*;
+
+    #[tokio::test]
+    async fn diff_lists_changed_fields_and_entries() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(
+            &app,
+            &[
+                "curl a.com",
+                "curl b.com \\\n  -H 'Accept: */*'",
+                "curl c.com",
+            ],
+        )
+        .await;
+
+        let mut curl_group = get_saved_group(&app, 1).await;
+        let mut curls: Vec<Curl> = serde_json::from_str(&curl_group.curls).unwrap();
+        curls[1].raw_query = "curl b.com \\\n  -H 'Accept: application/json'".to_string();
+        curls.remove(0);
+        curls.push(Curl {
+            id: 0,
+            name: "Added".to_string(),
+            description: "".to_string(),
+            raw_query: "curl d.com".to_string(),
+        });
+        curl_group.curls = serde_json::to_string(&curls).unwrap();
+        curl_group.labels = "test1".to_string();
+        app.update_curl_group(&curl_group).await;
+
+        let response = app.get_curl_group_revision_diff(1, 1, 2).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let diff: RevisionDiff = response.json().await.unwrap();
+        assert_eq!(diff.fields.len(), 1);
+        assert_eq!(diff.fields[0].field, "labels");
+        assert_eq!(diff.fields[0].to, "test1");
+
+        let changes: Vec<(EntryChange, &str)> = diff
+            .entries
+            .iter()
+            .map(|entry| (entry.change, entry.name.as_str()))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                (EntryChange::Modified, "cURL #2"),
+                (EntryChange::Added, "Added"),
+                (EntryChange::Removed, "cURL #1")
+            ]
+        );
+        assert_eq!(
+            diff.entries[0].raw_query,
+            vec![
+                DiffLine {
+                    change: LineChange::Unchanged,
+                    text: "curl b.com \\".to_string()
+                },
+                DiffLine {
+                    change: LineChange::Removed,
+                    text: "  -H 'Accept: */*'".to_string()
+                },
+                DiffLine {
+                    change: LineChange::Added,
+                    text: "  -H 'Accept: application/json'".to_string()
+                },
+            ]
+        );
+        assert_eq!(
+            (diff.entries[0].from_position, diff.entries[0].to_position),
+            (Some(1), Some(0))
+        );
+    }
+
+    #[tokio::test]
+    async fn reordered_entries_are_marked_as_moved() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app, &["curl a.com", "curl b.com", "curl c.com"]).await;
+        let entries: Vec<CurlEntry> = app.get_curl_entries(1).await.json().await.unwrap();
+        app.move_curl_entry(1, entries[2].id, 0).await;
+
+        let diff: RevisionDiff = app
+            .get_curl_group_revision_diff(1, 1, 2)
+            .await
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(diff.entries.len(), 1);
+        assert_eq!(diff.entries[0].change, EntryChange::Moved);
+        assert_eq!(diff.entries[0].id, entries[2].id);
+    }
+}
+
+#[cfg(test)]
+mod restore_curl_group_revision {
+    use super::*;
+
+    #[tokio::test]
+    async fn restoring_brings_back_the_old_content_as_a_new_revision() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app, &["curl a.com", "curl b.com"]).await;
+
+        let mut curl_group = get_saved_group(&app, 1).await;
+        curl_group.name = "Broken".to_string();
+        curl_group.curls = app.get_test_curls(&["curl wrong.com"]);
+        app.update_curl_group(&curl_group).await;
+
+        let response = app.restore_curl_group_revision(1, 1).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let restored: RevisionSummary = response.json().await.unwrap();
+        assert_eq!(restored.number, 3);
+
+        let curl_group = get_saved_group(&app, 1).await;
+        assert_eq!(curl_group.name, "CurlGroup 1");
+        let curls: Vec<Curl> = serde_json::from_str(&curl_group.curls).unwrap();
+        let raw_queries: Vec<&str> = curls.iter().map(|curl| curl.raw_query.as_str()).collect();
+        assert_eq!(raw_queries, vec!["curl a.com", "curl b.com"]);
+
+        let revisions = get_revisions(&app, 1).await;
+        assert_eq!(revisions.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn restoring_requires_project_membership() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app, &["curl a.com"]).await;
+
+        app.logout().await;
+        let response = app.restore_curl_group_revision(1, 1).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        app.signup("integration-test-other-user").await;
+        let response = app.restore_curl_group_revision(1, 1).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+}
@@ -0,0 +1,259 @@
+mod common;
+use crate::common::TestApplication;
+use backend::routes::project::types::ValidationErrors;
+use backend::routes::runner::types::RunnerSettings;
+use backend::runner::RunResult;
+use reqwest::StatusCode;
+
+async fn create_project_with_group(app: &TestApplication, raw_queries: &[&str]) {
+    app.signup("integration-test").await;
+    let project = app.get_test_public_project();
+    app.create_project(&project.info).await;
+
+    let mut curl_group = app.get_test_curl_group();
+    curl_group.curls = app.get_test_curls(raw_queries);
+    app.create_curl_group(1, &curl_group).await;
+}
+
+async fn allow_hosts(app: &TestApplication, allowed_hosts: &[&str]) {
+    let settings = RunnerSettings {
+        allowed_hosts: allowed_hosts.iter().map(|host| host.to_string()).collect(),
+        ..Default::default()
+    };
+    let response = app.update_runner_settings(1, &settings).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+}
+
+#[cfg(test)]
+mod run_curl_entry {
+    use super::*;
+
+    #[tokio::test]
+    async fn runs_the_entry_and_returns_the_response() {
+        let app = common::spawn_test_app().await;
+        let server = common::spawn_mock_server().await;
+        create_project_with_group(
+            &app,
+            &[&format!(
+                "curl -X PUT -d 'name=test' http://{}/echo",
+                server
+            )],
+        )
+        .await;
+        allow_hosts(&app, &[&server]).await;
+
+        let response = app.run_curl_entry(1, 1, None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let result: RunResult = response.json().await.unwrap();
+        assert_eq!(result.status, 200);
+        assert!(!result.truncated);
+        assert!(result.timing.total_ms >= result.timing.headers_ms);
+        assert!(result
+            .headers
+            .iter()
+            .any(|header| header.name == "content-type" && header.value == "application/json"));
+
+        let body: serde_json::Value = serde_json::from_str(&result.body).unwrap();
+        assert_eq!(body["method"], "PUT");
+        assert_eq!(body["body"], "name=test");
+        assert_eq!(body["content_type"], "application/x-www-form-urlencoded");
+    }
+
+    #[tokio::test]
+    async fn renders_the_entry_with_environment_secrets() {
+        let app = common::spawn_test_app().await;
+        let server = common::spawn_mock_server().await;
+        create_project_with_group(
+            &app,
+            &["curl -H 'Authorization: Bearer {{token}}' {{base_url}}/echo"],
+        )
+        .await;
+        allow_hosts(&app, &[&server]).await;
+
+        let mut staging =
+            app.get_test_environment("staging", &[("base_url", &format!("http://{}", server))]);
+        staging
+            .secrets
+            .insert("token".to_string(), Some("staging-token".to_string()));
+        app.create_environment(1, &staging).await;
+
+        let response = app.run_curl_entry(1, 1, None).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let response = app.run_curl_entry(1, 1, Some("staging")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let result: RunResult = response.json().await.unwrap();
+        let body: serde_json::Value = serde_json::from_str(&result.body).unwrap();
+        assert_eq!(body["authorization"], "Bearer staging-token");
+
+        let response = app.run_curl_entry(1, 1, Some("prod")).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn hosts_outside_the_allowlist_return_403() {
+        let app = common::spawn_test_app().await;
+        let server = common::spawn_mock_server().await;
+        create_project_with_group(&app, &[&format!("curl http://{}/hello", server)]).await;
+
+        // nothing is allowed until the project opts in
+        let response = app.run_curl_entry(1, 1, None).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        allow_hosts(&app, &["example.com"]).await;
+        let response = app.run_curl_entry(1, 1, None).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        allow_hosts(&app, &["127.0.0.1"]).await;
+        let response = app.run_curl_entry(1, 1, None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let result: RunResult = response.json().await.unwrap();
+        assert_eq!(result.body, "hello");
+    }
+
+    #[tokio::test]
+    async fn redirects_are_checked_on_every_hop() {
+        let app = common::spawn_test_app().await;
+        let server = common::spawn_mock_server().await;
+        create_project_with_group(
+            &app,
+            &[
+                &format!("curl -L 'http://{0}/redirect?to=http://{0}/hello'", server),
+                &format!(
+                    "curl -L 'http://{}/redirect?to=http://169.254.169.254/latest/meta-data'",
+                    server
+                ),
+                &format!("curl 'http://{}/redirect?to=/hello'", server),
+            ],
+        )
+        .await;
+        allow_hosts(&app, &[&server, "*.254.169.254"]).await;
+
+        let result: RunResult = app.run_curl_entry(1, 1, None).await.json().await.unwrap();
+        assert_eq!(result.body, "hello");
+
+        let response = app.run_curl_entry(1, 2, None).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        // without -L the redirect itself is returned
+        let result: RunResult = app.run_curl_entry(1, 3, None).await.json().await.unwrap();
+        assert_eq!(result.status, 302);
+    }
+
+    #[tokio::test]
+    async fn slow_responses_return_504_and_large_ones_are_truncated() {
+        let app = common::spawn_test_app().await;
+        let server = common::spawn_mock_server().await;
+        create_project_with_group(
+            &app,
+            &[
+                &format!("curl http://{}/slow", server),
+                &format!("curl http://{}/large", server),
+            ],
+        )
+        .await;
+        let settings = RunnerSettings {
+            allowed_hosts: vec![server],
+            timeout_ms: 200,
+            max_response_bytes: 1000,
+        };
+        app.update_runner_settings(1, &settings).await;
+
+        let response = app.run_curl_entry(1, 1, None).await;
+        assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+
+        let response = app.run_curl_entry(1, 2, None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let result: RunResult = response.json().await.unwrap();
+        assert!(result.truncated);
+        assert_eq!(result.body.len(), 1000);
+    }
+
+    #[tokio::test]
+    async fn running_requires_login_and_an_existing_entry() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app, &["curl example.com"]).await;
+
+        let response = app.run_curl_entry(1, 2, None).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        app.logout().await;
+        let response = app.run_curl_entry(1, 1, None).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+}
+
+#[cfg(test)]
+mod runner_settings {
+    use super::*;
+
+    #[tokio::test]
+    async fn settings_default_until_updated() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app, &["curl example.com"]).await;
+
+        let settings: RunnerSettings = app.get_runner_settings(1).await.json().await.unwrap();
+        assert_eq!(settings, RunnerSettings::default());
+
+        let updated = RunnerSettings {
+            allowed_hosts: vec![
+                "api.example.com".to_string(),
+                "*.internal.io:8443".to_string(),
+            ],
+            timeout_ms: 2000,
+            max_response_bytes: 4096,
+        };
+        app.update_runner_settings(1, &updated).await;
+
+        let settings: RunnerSettings = app.get_runner_settings(1).await.json().await.unwrap();
+        assert_eq!(settings, updated);
+    }
+
+    #[tokio::test]
+    async fn invalid_settings_return_422() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app, &["curl example.com"]).await;
+
+        let settings = RunnerSettings {
+            allowed_hosts: vec!["https://example.com".to_string(), "".to_string()],
+            timeout_ms: 0,
+            max_response_bytes: 1024 * 1024 * 1024,
+        };
+        let response = app.update_runner_settings(1, &settings).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let validation_errors: ValidationErrors = response.json().await.unwrap();
+        let fields: Vec<&str> = validation_errors
+            .errors
+            .iter()
+            .map(|error| error.field.as_str())
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                "allowed_hosts[0]",
+                "allowed_hosts[1]",
+                "timeout_ms",
+                "max_response_bytes"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn only_admins_can_update_settings() {
+        let app = common::spawn_test_app().await;
+        create_project_with_group(&app, &["curl example.com"]).await;
+        let mut project = app.get_test_public_project();
+        app.add_other_user_as_collaborator_and_login(&mut project)
+            .await;
+
+        let response = app
+            .update_runner_settings(1, &RunnerSettings::default())
+            .await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = app.get_runner_settings(1).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,131 @@
+mod common;
+use crate::common::TestApplication;
+use backend::routes::search::types::SearchResult;
+use reqwest::StatusCode;
+
+async fn search(app: &TestApplication, query: &str) -> Vec<SearchResult> {
+    let response = app.search(query).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    response.json().await.expect("Failed to serialise response")
+}
+
+#[cfg(test)]
+mod search_curl_groups {
+    use super::*;
+
+    #[tokio::test]
+    async fn search_finds_groups_by_raw_curl_text_with_highlights() {
+        let app = common::spawn_test_app().await;
+        app.signup("integration-test").await;
+        let project = app.get_test_public_project();
+        app.create_project(&project.info).await;
+
+        let mut curl_group = app.get_test_curl_group();
+        curl_group.curls =
+            app.get_test_curls(&["curl -X POST https://api.example.com/billing/refund"]);
+        app.create_curl_group(1, &curl_group).await;
+        app.create_curl_group(1, &app.get_test_curl_group()).await;
+
+        let results = search(&app, "/billing/refund").await;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].group_id, 1);
+        assert_eq!(results[0].project_name, "Public Project");
+        assert!(results[0].snippet.contains("<mark>billing/refund</mark>"));
+        assert!(!results[0].snippet.contains("rawQuery"));
+    }
+
+    #[tokio::test]
+    async fn search_highlights_are_html_escaped() {
+        let app = common::spawn_test_app().await;
+        app.signup("integration-test").await;
+        let project = app.get_test_public_project();
+        app.create_project(&project.info).await;
+
+        let mut curl_group = app.get_test_curl_group();
+        curl_group.curls = app.get_test_curls(&[
+            "curl -d '<script>alert(1)</script>' https://api.example.com/billing",
+        ]);
+        app.create_curl_group(1, &curl_group).await;
+
+        let results = search(&app, "billing").await;
+        assert_eq!(results.len(), 1);
+        assert!(results[0].snippet.contains("<mark>billing</mark>"));
+        assert!(results[0].snippet.contains("&lt;script&gt;"));
+        assert!(!results[0].snippet.contains("<script>"));
+    }
+
+    #[tokio::test]
+    async fn search_ranks_name_matches_above_curl_matches() {
+        let app = common::spawn_test_app().await;
+        app.signup("integration-test").await;
+        let project = app.get_test_public_project();
+        app.create_project(&project.info).await;
+
+        let mut curl_group = app.get_test_curl_group();
+        curl_group.curls = app.get_test_curls(&["curl https://example.com/orders"]);
+        app.create_curl_group(1, &curl_group).await;
+
+        let mut curl_group = app.get_test_curl_group();
+        curl_group.name = "Orders".to_string();
+        app.create_curl_group(1, &curl_group).await;
+
+        let results = search(&app, "orders").await;
+        let ids: Vec<i64> = results.iter().map(|result| result.group_id).collect();
+        assert_eq!(ids, vec![2, 1]);
+    }
+
+    #[tokio::test]
+    async fn search_index_follows_updates_and_deletes() {
+        let app = common::spawn_test_app().await;
+        app.signup("integration-test").await;
+        let project = app.get_test_public_project();
+        app.create_project(&project.info).await;
+
+        let mut curl_group = app.get_test_curl_group();
+        app.create_curl_group(1, &curl_group).await;
+        assert_eq!(search(&app, "some-curl").await.len(), 1);
+
+        curl_group.curls = app.get_test_curls(&["curl https://example.com/invoices"]);
+        app.update_curl_group(&curl_group).await;
+        assert!(search(&app, "some-curl").await.is_empty());
+        assert_eq!(search(&app, "invoices").await.len(), 1);
+
+        app.delete_project(&project.info).await;
+        assert!(search(&app, "invoices").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn search_respects_project_visibility() {
+        let app = common::spawn_test_app().await;
+        app.signup("integration-test").await;
+        let mut project = app.get_test_private_project();
+        project.info.id = 1;
+        app.create_project(&project.info).await;
+        app.create_curl_group(1, &app.get_test_curl_group()).await;
+
+        assert_eq!(search(&app, "test1").await.len(), 1);
+
+        app.logout().await;
+        assert!(search(&app, "test1").await.is_empty());
+
+        app.signup("integration-test-other-user").await;
+        assert!(search(&app, "test1").await.is_empty());
+
+        app.logout().await;
+        app.login("integration-test", common::TEST_PASSWORD).await;
+        app.add_other_user_as_collaborator_and_login(&mut project)
+            .await;
+        assert_eq!(search(&app, "test1").await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn blank_search_returns_no_results() {
+        let app = common::spawn_test_app().await;
+        app.signup("integration-test").await;
+        let project = app.get_test_public_project();
+        app.create_project(&project.info).await;
+        app.create_curl_group(1, &app.get_test_curl_group()).await;
+
+        assert!(search(&app, "  ").await.is_empty());
+    }
+}
@@ -0,0 +1,221 @@
+mod common;
+use crate::common::TestApplication;
+use backend::models::*;
+use backend::routes::project::types::{FieldError, ValidationErrors};
+use backend::routes::token::types::{ApiToken, CreatedApiToken, TokenScope};
+use reqwest::StatusCode;
+
+/// Signs up `integration-test` with a public project and a private one, and creates a token.
+/// Leaves the user logged in.
+async fn create_projects_and_token(
+    app: &TestApplication,
+    scope: TokenScope,
+    project_id: Option<i64>,
+) -> CreatedApiToken {
+    app.signup("integration-test").await;
+    app.create_project(&app.get_test_public_project().info)
+        .await;
+    app.create_project(&app.get_test_private_project().info)
+        .await;
+
+    let response = app.create_api_token("ci", scope, project_id).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    response.json().await.unwrap()
+}
+
+async fn get_tokens(app: &TestApplication) -> Vec<ApiToken> {
+    let response = app.get_api_tokens().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    response.json().await.unwrap()
+}
+
+#[cfg(test)]
+mod manage_tokens {
+    use super::*;
+
+    #[tokio::test]
+    async fn created_tokens_are_listed_without_their_secret() {
+        let app = common::spawn_test_app().await;
+        let created = create_projects_and_token(&app, TokenScope::Write, Some(2)).await;
+        assert!(created.token.starts_with("cs_"));
+        assert!(created.info.expires_at.is_some());
+
+        let response = app.get_api_tokens().await;
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert!(body[0].get("token").is_none());
+        assert_eq!(get_tokens(&app).await, vec![created.info]);
+    }
+
+    #[tokio::test]
+    async fn using_a_token_records_when_it_was_last_used() {
+        let app = common::spawn_test_app().await;
+        let created = create_projects_and_token(&app, TokenScope::Read, None).await;
+        assert_eq!(get_tokens(&app).await[0].last_used_at, None);
+
+        app.with_token(&created.token).get_projects().await;
+        assert!(get_tokens(&app).await[0].last_used_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn invalid_token_requests_return_422() {
+        let app = common::spawn_test_app().await;
+        create_projects_and_token(&app, TokenScope::Read, None).await;
+        app.logout().await;
+        app.signup("integration-test-other-user").await;
+
+        let response = app.create_api_token(" ", TokenScope::Read, Some(1)).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let errors: ValidationErrors = response.json().await.unwrap();
+        assert_eq!(
+            errors.errors,
+            vec![
+                FieldError::new("name", "Name must not be empty."),
+                FieldError::new(
+                    "project_id",
+                    "Tokens can only be limited to projects you are a member of."
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn tokens_cannot_manage_tokens() {
+        let app = common::spawn_test_app().await;
+        let created = create_projects_and_token(&app, TokenScope::Write, None).await;
+        app.logout().await;
+
+        let client = app.with_token(&created.token);
+        let response = client
+            .create_api_token("more", TokenScope::Write, None)
+            .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = client.get_api_tokens().await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn revoked_tokens_stop_working() {
+        let app = common::spawn_test_app().await;
+        let created = create_projects_and_token(&app, TokenScope::Read, None).await;
+
+        let response = app.revoke_api_token(created.info.id).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert!(get_tokens(&app).await.is_empty());
+
+        let response = app.with_token(&created.token).get_projects().await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = app.revoke_api_token(created.info.id).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn unknown_tokens_return_401() {
+        let app = common::spawn_test_app().await;
+
+        let response = app.with_token("cs_unknown").get_projects().await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+}
+
+#[cfg(test)]
+mod use_tokens {
+    use super::*;
+
+    #[tokio::test]
+    async fn write_tokens_can_sync_curl_groups_without_a_session() {
+        let app = common::spawn_test_app().await;
+        let created = create_projects_and_token(&app, TokenScope::Write, None).await;
+        app.logout().await;
+        let client = app.with_token(&created.token);
+
+        let private_project = app.get_test_private_project();
+        let response = client.get_project(&private_project, None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let mut curl_group = app.get_test_curl_group();
+        curl_group.project_id = 2;
+        let response = client.create_curl_group(2, &curl_group).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        curl_group.description = "Synced from CI".to_string();
+        let response = client.update_curl_group(&curl_group).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    }
+
+    #[tokio::test]
+    async fn read_only_tokens_cannot_change_anything() {
+        let app = common::spawn_test_app().await;
+        let created = create_projects_and_token(&app, TokenScope::Read, None).await;
+        app.logout().await;
+        let client = app.with_token(&created.token);
+
+        let response = client
+            .get_project(&app.get_test_private_project(), None)
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = client
+            .create_curl_group(1, &app.get_test_curl_group())
+            .await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn project_tokens_only_reach_their_project() {
+        let app = common::spawn_test_app().await;
+        let created = create_projects_and_token(&app, TokenScope::Write, Some(1)).await;
+        app.logout().await;
+        let client = app.with_token(&created.token);
+
+        let response = client
+            .create_curl_group(1, &app.get_test_curl_group())
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = client
+            .get_project(&app.get_test_private_project(), None)
+            .await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = client
+            .create_curl_group(2, &app.get_test_curl_group())
+            .await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let projects: Vec<ProjectInfo> = client.get_projects().await.json().await.unwrap();
+        assert_eq!(projects.len(), 1);
+
+        let mut project = app.get_test_public_project();
+        project.info.name = "Another Project".to_string();
+        let response = client.create_project(&project.info).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn project_tokens_cannot_reach_other_public_projects() {
+        let app = common::spawn_test_app().await;
+        let created = create_projects_and_token(&app, TokenScope::Write, Some(2)).await;
+        app.logout().await;
+        let client = app.with_token(&created.token);
+
+        let response = client
+            .get_project(&app.get_test_public_project(), None)
+            .await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = client
+            .create_curl_group(1, &app.get_test_curl_group())
+            .await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let projects: Vec<ProjectInfo> = client.get_projects().await.json().await.unwrap();
+        assert_eq!(
+            projects
+                .iter()
+                .map(|project| project.id)
+                .collect::<Vec<_>>(),
+            [2]
+        );
+    }
+}
@@ -6,6 +6,11 @@ use reqwest::StatusCode;
 
 #[cfg(test)]
 mod sign_up {
+    use backend::application::AuthSettings;
+    use backend::password::PasswordPolicy;
+    use backend::routes::project::types::{FieldError, ValidationErrors};
+    use sha1::{Digest, Sha1};
+
     use super::*;
 
     async fn signup_and_assert(
@@ -32,7 +37,55 @@ mod sign_up {
         assert!(response.cookies().count() == 0);
     }
 
-    // TODO: sign up requirements
+    #[tokio::test]
+    async fn signup_with_insufficient_password_returns_422() {
+        let app = common::spawn_test_app().await;
+
+        let response = app.signup_with_password("integration-test", "short").await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: ValidationErrors = response.json().await.unwrap();
+        assert_eq!(
+            body.errors,
+            [
+                FieldError::new("password", "Password must be at least 8 characters long."),
+                FieldError::new("password", "Password must contain an uppercase letter."),
+            ]
+        );
+        let response = app.login("integration-test", "short").await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn signup_with_breached_password_returns_422() {
+        let list = std::env::temp_dir().join(format!("breached-{}.txt", std::process::id()));
+        std::fs::write(
+            &list,
+            format!("{:X}:3\n", Sha1::digest(common::TEST_PASSWORD.as_bytes())),
+        )
+        .unwrap();
+        let app = common::spawn_test_app_with_auth(AuthSettings {
+            password_policy: PasswordPolicy {
+                breached_list: Some(list),
+                ..PasswordPolicy::default()
+            },
+            ..AuthSettings::default()
+        })
+        .await;
+
+        let response = signup_and_assert(&app, StatusCode::UNPROCESSABLE_ENTITY).await;
+        let body: ValidationErrors = response.json().await.unwrap();
+        assert_eq!(
+            body.errors,
+            [FieldError::new(
+                "password",
+                "Password has appeared in a data breach."
+            )]
+        );
+        let response = app
+            .signup_with_password("integration-test", "Unbreached-password")
+            .await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
 }
 
 #[cfg(test)]
@@ -44,7 +97,7 @@ mod log_in {
         let app = common::spawn_test_app().await;
         app.signup("integration-test").await;
 
-        let response = app.login("integration-test", "test").await;
+        let response = app.login("integration-test", common::TEST_PASSWORD).await;
         assert_eq!(response.status(), StatusCode::OK);
         assert!(response.cookies().count() == 1);
     }
@@ -85,6 +138,98 @@ mod log_out {
     }
 }
 
+#[cfg(test)]
+mod change_password {
+    use backend::routes::{
+        project::types::{FieldError, ValidationErrors},
+        user::types::UserStatus,
+    };
+
+    use super::*;
+
+    const NEW_PASSWORD: &str = "Changed-Password-2";
+
+    #[tokio::test]
+    async fn changed_password_replaces_the_old_one() {
+        let app = common::spawn_test_app().await;
+        app.signup("integration-test").await;
+
+        let response = app
+            .change_password(common::TEST_PASSWORD, NEW_PASSWORD)
+            .await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        app.logout().await;
+
+        let response = app.login("integration-test", common::TEST_PASSWORD).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = app.login("integration-test", NEW_PASSWORD).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn wrong_old_password_returns_401() {
+        let app = common::spawn_test_app().await;
+        app.signup("integration-test").await;
+
+        let response = app.change_password("Wrong-password", NEW_PASSWORD).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = app.user_status().await;
+        let status: UserStatus = response.json().await.unwrap();
+        assert!(status.is_logged_in);
+    }
+
+    #[tokio::test]
+    async fn insufficient_new_password_returns_422() {
+        let app = common::spawn_test_app().await;
+        app.signup("integration-test").await;
+
+        let response = app
+            .change_password(common::TEST_PASSWORD, "lowercase-only")
+            .await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: ValidationErrors = response.json().await.unwrap();
+        assert_eq!(
+            body.errors,
+            [FieldError::new(
+                "new_password",
+                "Password must contain an uppercase letter."
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn logged_out_change_returns_401() {
+        let app = common::spawn_test_app().await;
+
+        let response = app
+            .change_password(common::TEST_PASSWORD, NEW_PASSWORD)
+            .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn changing_password_signs_out_other_sessions_only() {
+        let app = common::spawn_test_app().await;
+        app.signup("integration-test").await;
+        let other_device = app.with_new_session();
+        other_device
+            .login("integration-test", common::TEST_PASSWORD)
+            .await;
+
+        app.change_password(common::TEST_PASSWORD, NEW_PASSWORD)
+            .await;
+
+        let status: UserStatus = app.user_status().await.json().await.unwrap();
+        assert!(status.is_logged_in);
+        let status: UserStatus = other_device.user_status().await.json().await.unwrap();
+        assert!(!status.is_logged_in);
+        let response = other_device
+            .create_project(&app.get_test_public_project().info)
+            .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+}
+
 #[cfg(test)]
 mod delete_user {
     use super::*;
@@ -100,10 +245,25 @@ mod delete_user {
         let response_cookies: Vec<Cookie> = response.cookies().collect();
         assert!(response_cookies[0].value() == "");
 
-        let response = app.login("integration-test", "test").await;
+        let response = app.login("integration-test", common::TEST_PASSWORD).await;
         assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
     }
 
+    #[tokio::test]
+    async fn delete_user_who_is_last_owner_of_a_project_returns_409() {
+        let app = common::spawn_test_app().await;
+        app.signup("integration-test").await;
+        let project = app.get_test_public_project();
+        app.create_project(&project.info).await;
+
+        let response = app.delete_user().await;
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        app.delete_project(&project.info).await;
+        let response = app.delete_user().await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    }
+
     #[tokio::test]
     async fn delete_user_without_session_returns_something_401() {
         let app = common::spawn_test_app().await;
@@ -115,7 +275,7 @@ mod delete_user {
 
 #[cfg(test)]
 mod user_status {
-    use backend::routes::user::types::UserStatus;
+    use backend::routes::user::types::{GlobalRole, UserStatus};
 
     use super::*;
 
@@ -131,7 +291,10 @@ mod user_status {
                 .json::<UserStatus>()
                 .await
                 .expect("Failed to convert user response to UserStatus object"),
-            UserStatus { is_logged_in: true }
+            UserStatus {
+                is_logged_in: true,
+                global_role: Some(GlobalRole::User)
+            }
         );
     }
 
@@ -149,7 +312,8 @@ mod user_status {
                 .await
                 .expect("Failed to convert user response to UserStatus object"),
             UserStatus {
-                is_logged_in: false
+                is_logged_in: false,
+                global_role: None
             }
         );
     }
@@ -166,8 +330,448 @@ mod user_status {
                 .await
                 .expect("Failed to convert user response to UserStatus object"),
             UserStatus {
-                is_logged_in: false
+                is_logged_in: false,
+                global_role: None
             }
         );
     }
 }
+
+#[cfg(test)]
+mod oidc_login {
+    use backend::routes::user::types::UserStatus;
+    use serde_json::json;
+
+    use super::*;
+    use crate::common::MockOidcProvider;
+
+    async fn spawn_app_with_provider() -> (TestApplication, MockOidcProvider) {
+        let provider = common::spawn_mock_oidc_provider(json!({
+            "sub": "1234",
+            "preferred_username": "sso-user",
+        }))
+        .await;
+        let app = common::spawn_test_app_with_oidc(Some(provider.config())).await;
+        (app, provider)
+    }
+
+    /// Signing in ends on the user status, by way of the provider.
+    async fn sign_in_and_assert(app: &TestApplication, link: bool) {
+        let response = app.oidc_login(link).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let status: UserStatus = response.json().await.unwrap();
+        assert!(status.is_logged_in);
+    }
+
+    #[tokio::test]
+    async fn oidc_login_without_a_provider_returns_404() {
+        let app = common::spawn_test_app().await;
+
+        let response = app.oidc_login(false).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn first_sign_in_creates_an_account_named_after_the_claim() {
+        let (app, _provider) = spawn_app_with_provider().await;
+
+        sign_in_and_assert(&app, false).await;
+        assert_eq!(app.signed_in_username("First").await, "sso-user");
+    }
+
+    #[tokio::test]
+    async fn signing_in_again_uses_the_same_account_whatever_its_name() {
+        let (app, provider) = spawn_app_with_provider().await;
+        sign_in_and_assert(&app, false).await;
+        app.logout().await;
+
+        provider.claims.lock().unwrap()["preferred_username"] = json!("renamed");
+        sign_in_and_assert(&app, false).await;
+        assert_eq!(app.signed_in_username("Second").await, "sso-user");
+    }
+
+    #[tokio::test]
+    async fn new_identities_never_take_over_local_accounts() {
+        let (app, _provider) = spawn_app_with_provider().await;
+        app.signup("sso-user").await;
+        app.logout().await;
+
+        sign_in_and_assert(&app, false).await;
+        assert_eq!(app.signed_in_username("Taken").await, "sso-user-2");
+    }
+
+    #[tokio::test]
+    async fn signed_in_users_can_link_their_account() {
+        let (app, _provider) = spawn_app_with_provider().await;
+        app.signup("integration-test").await;
+        sign_in_and_assert(&app, true).await;
+        app.logout().await;
+
+        sign_in_and_assert(&app, false).await;
+        assert_eq!(app.signed_in_username("Linked").await, "integration-test");
+
+        // the identity cannot be linked to a second account
+        app.logout().await;
+        app.signup("integration-test-other-user").await;
+        let response = app.oidc_login(true).await;
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn linking_without_a_session_returns_401() {
+        let (app, _provider) = spawn_app_with_provider().await;
+
+        let response = app.oidc_login(true).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn accounts_created_through_sso_have_no_password() {
+        let (app, _provider) = spawn_app_with_provider().await;
+        sign_in_and_assert(&app, false).await;
+        app.logout().await;
+
+        let response = app.login("sso-user", "").await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn callbacks_that_were_not_started_here_return_401() {
+        let (app, _provider) = spawn_app_with_provider().await;
+
+        let response = app
+            .oidc_callback(&[("code", "code-0"), ("state", "forged")])
+            .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = app.user_status().await;
+        let status: UserStatus = response.json().await.unwrap();
+        assert!(!status.is_logged_in);
+    }
+
+    #[tokio::test]
+    async fn provider_rejecting_the_client_returns_502() {
+        let provider = common::spawn_mock_oidc_provider(json!({ "sub": "1234" })).await;
+        let mut config = provider.config();
+        config.client_secret = secrecy::Secret::new("wrong".to_string());
+        let app = common::spawn_test_app_with_oidc(Some(config)).await;
+
+        let response = app.oidc_login(false).await;
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    }
+}
+
+#[cfg(test)]
+mod ldap_login {
+    use backend::models::ProjectInfo;
+    use backend::routes::user::types::{GlobalRole, UserStatus};
+    use std::time::Duration;
+
+    use super::*;
+    use crate::common::{MockLdapServer, MOCK_LDAP_ADMIN_GROUP, MOCK_LDAP_USER_GROUP};
+
+    async fn spawn_app_with_directory() -> (TestApplication, MockLdapServer) {
+        let directory = common::spawn_mock_ldap_server().await;
+        directory.add_user("alice", "directory-password", &[MOCK_LDAP_USER_GROUP]);
+        let app = common::spawn_test_app_with_ldap(directory.config()).await;
+        (app, directory)
+    }
+
+    async fn global_role(app: &TestApplication) -> Option<GlobalRole> {
+        let status: UserStatus = app.user_status().await.json().await.unwrap();
+        status.global_role
+    }
+
+    #[tokio::test]
+    async fn first_sign_in_creates_an_account_for_the_directory_user() {
+        let (app, _directory) = spawn_app_with_directory().await;
+
+        let response = app.login("alice", "directory-password").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(global_role(&app).await, Some(GlobalRole::User));
+        assert_eq!(app.signed_in_username("First").await, "alice");
+    }
+
+    #[tokio::test]
+    async fn wrong_directory_password_returns_401() {
+        let (app, _directory) = spawn_app_with_directory().await;
+
+        let response = app.login("alice", "wrong").await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = app.login("alice", "").await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn global_role_follows_the_admin_groups() {
+        let directory = common::spawn_mock_ldap_server().await;
+        directory.add_user("alice", "directory-password", &[MOCK_LDAP_ADMIN_GROUP]);
+        let mut config = directory.config();
+        config.cache_ttl = Duration::ZERO;
+        let app = common::spawn_test_app_with_ldap(config).await;
+
+        app.login("alice", "directory-password").await;
+        assert_eq!(global_role(&app).await, Some(GlobalRole::Admin));
+
+        directory.add_user("alice", "directory-password", &[MOCK_LDAP_USER_GROUP]);
+        app.logout().await;
+        app.login("alice", "directory-password").await;
+        assert_eq!(global_role(&app).await, Some(GlobalRole::User));
+        assert_eq!(app.signed_in_username("Demoted").await, "alice");
+    }
+
+    #[tokio::test]
+    async fn directory_admins_can_reach_every_project() {
+        let directory = common::spawn_mock_ldap_server().await;
+        directory.add_user("alice", "directory-password", &[MOCK_LDAP_ADMIN_GROUP]);
+        directory.add_user("bob", "directory-password", &[MOCK_LDAP_USER_GROUP]);
+        let app = common::spawn_test_app_with_ldap(directory.config()).await;
+
+        app.signup("integration-test").await;
+        let mut project = app.get_test_private_project();
+        project.info.id = 1;
+        app.create_project(&project.info).await;
+        app.logout().await;
+
+        app.login("bob", "directory-password").await;
+        let response = app.get_project(&project, None).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        app.logout().await;
+
+        app.login("alice", "directory-password").await;
+        let response = app.get_project(&project, None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let projects: Vec<ProjectInfo> = app.get_projects().await.json().await.unwrap();
+        assert_eq!(projects, vec![project.info]);
+        let response = app.create_curl_group(1, &app.get_test_curl_group()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn users_outside_the_user_groups_return_403() {
+        let directory = common::spawn_mock_ldap_server().await;
+        directory.add_user("alice", "directory-password", &[]);
+        directory.add_user("bob", "directory-password", &[MOCK_LDAP_ADMIN_GROUP]);
+        let mut config = directory.config();
+        config.user_groups = vec![MOCK_LDAP_USER_GROUP.to_string()];
+        let app = common::spawn_test_app_with_ldap(config).await;
+
+        let response = app.login("alice", "directory-password").await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app.login("bob", "directory-password").await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn successful_sign_ins_are_cached() {
+        let (app, directory) = spawn_app_with_directory().await;
+
+        app.login("alice", "directory-password").await;
+        app.logout().await;
+        let response = app.login("alice", "directory-password").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(directory.user_binds(), 1);
+
+        let response = app.login("alice", "wrong").await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn users_the_directory_does_not_know_use_their_local_account() {
+        let (app, _directory) = spawn_app_with_directory().await;
+        app.signup("integration-test").await;
+        app.logout().await;
+
+        let response = app.login("integration-test", common::TEST_PASSWORD).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(app.signed_in_username("Local").await, "integration-test");
+    }
+
+    #[tokio::test]
+    async fn local_account_sharing_a_directory_name_stays_separate() {
+        let (app, _directory) = spawn_app_with_directory().await;
+        app.signup("alice").await;
+        app.logout().await;
+
+        app.login("alice", "directory-password").await;
+        assert_eq!(app.signed_in_username("Directory").await, "alice-2");
+        app.logout().await;
+
+        let response = app.login("alice", common::TEST_PASSWORD).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(app.signed_in_username("Local").await, "alice");
+    }
+
+    #[tokio::test]
+    async fn unreachable_directory_falls_back_to_local_accounts() {
+        let closed_port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let config = backend::ldap::LdapConfig::new(
+            &format!("ldap://127.0.0.1:{}", closed_port),
+            common::MOCK_LDAP_BASE_DN,
+        );
+        let app = common::spawn_test_app_with_ldap(config).await;
+        app.signup("integration-test").await;
+        app.logout().await;
+
+        let response = app.login("integration-test", common::TEST_PASSWORD).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
+
+#[cfg(test)]
+mod rate_limiting {
+    use backend::application::AuthSettings;
+    use backend::rate_limit::{RateLimitConfig, RateLimiter};
+    use reqwest::header::RETRY_AFTER;
+    use std::time::Duration;
+
+    use super::*;
+
+    async fn spawn_app_with_limits(config: RateLimitConfig) -> TestApplication {
+        let app = common::spawn_test_app_with_auth(AuthSettings {
+            rate_limiter: RateLimiter::new(config),
+            ..AuthSettings::default()
+        })
+        .await;
+        app.signup("integration-test").await;
+        app.logout().await;
+        app
+    }
+
+    fn assert_retry_after(response: &Response, seconds: u64) {
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: u64 = response.headers()[RETRY_AFTER]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(
+            (1..=seconds).contains(&retry_after),
+            "Retry-After was {}",
+            retry_after
+        );
+    }
+
+    #[tokio::test]
+    async fn too_many_attempts_from_one_ip_return_429_with_retry_after() {
+        let app = spawn_app_with_limits(RateLimitConfig {
+            ip_burst: 3,
+            ip_refill: Duration::from_secs(60),
+            ..RateLimitConfig::default()
+        })
+        .await;
+
+        // signing up took the first attempt
+        for username in ["first", "second"] {
+            let response = app.login(username, common::TEST_PASSWORD).await;
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+        let response = app.login("integration-test", common::TEST_PASSWORD).await;
+        assert_retry_after(&response, 60);
+        let response = app.signup("other-user").await;
+        assert_retry_after(&response, 60);
+    }
+
+    #[tokio::test]
+    async fn too_many_attempts_for_one_username_return_429() {
+        let app = spawn_app_with_limits(RateLimitConfig {
+            username_burst: 2,
+            username_refill: Duration::from_secs(30),
+            ..RateLimitConfig::default()
+        })
+        .await;
+
+        // signing up took the first attempt
+        let response = app.login("integration-test", common::TEST_PASSWORD).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.login("integration-test", common::TEST_PASSWORD).await;
+        assert_retry_after(&response, 30);
+
+        let response = app.signup("integration-test-other-user").await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn repeated_wrong_passwords_delay_further_attempts() {
+        let app = spawn_app_with_limits(RateLimitConfig {
+            free_failures: 2,
+            base_delay: Duration::from_secs(30),
+            ..RateLimitConfig::default()
+        })
+        .await;
+
+        for _ in 0..3 {
+            let response = app.login("integration-test", "Wrong-password").await;
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+        let response = app.login("integration-test", common::TEST_PASSWORD).await;
+        assert_retry_after(&response, 30);
+    }
+
+    #[tokio::test]
+    async fn repeated_wrong_passwords_lock_out_the_username_only() {
+        let app = spawn_app_with_limits(RateLimitConfig {
+            free_failures: 3,
+            lockout_failures: 3,
+            lockout_duration: Duration::from_secs(900),
+            ..RateLimitConfig::default()
+        })
+        .await;
+        app.signup("integration-test-other-user").await;
+        app.logout().await;
+
+        for _ in 0..3 {
+            let response = app.login("integration-test", "Wrong-password").await;
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+        let response = app.login("integration-test", common::TEST_PASSWORD).await;
+        assert_retry_after(&response, 900);
+
+        let response = app
+            .login("integration-test-other-user", common::TEST_PASSWORD)
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn logging_in_as_unknown_users_counts_towards_the_lockout() {
+        let app = spawn_app_with_limits(RateLimitConfig {
+            free_failures: 2,
+            lockout_failures: 2,
+            lockout_duration: Duration::from_secs(900),
+            ..RateLimitConfig::default()
+        })
+        .await;
+
+        for _ in 0..2 {
+            let response = app.login("integration-test-nobody", "Wrong-password").await;
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+        let response = app.login("integration-test-nobody", "Wrong-password").await;
+        assert_retry_after(&response, 900);
+    }
+
+    #[tokio::test]
+    async fn successful_log_in_forgives_earlier_wrong_passwords() {
+        let app = spawn_app_with_limits(RateLimitConfig {
+            free_failures: 1,
+            base_delay: Duration::from_secs(30),
+            ..RateLimitConfig::default()
+        })
+        .await;
+
+        app.login("integration-test", "Wrong-password").await;
+        let response = app.login("integration-test", common::TEST_PASSWORD).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        app.logout().await;
+
+        let response = app.login("integration-test", "Wrong-password").await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = app.login("integration-test", common::TEST_PASSWORD).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}